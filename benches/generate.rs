@@ -0,0 +1,60 @@
+//! Benchmarks of generating and running large lsystems.
+//!
+//! Compare the symbol keyed lookups with and without the `fxhash` feature:
+//! `cargo bench` and `cargo bench --features fxhash`.
+//! The memoized benchmark compares rewriting every generation with `GenerateOptions::memoize`.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use scebpl_system::{
+    Action, ActionParam, ActionResolver, DefaultAlphabetSymbolDefiner, GenerateOptions, LSystem,
+};
+
+fn fractal_plant() -> LSystem {
+    let mut lsystem = LSystem::new("X", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('X', "F+[[X]-X]-F[-FX]+X");
+    lsystem.add_rule('F', "FF");
+    lsystem
+}
+
+fn koch_curve() -> LSystem {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "F+F-F-F+F");
+    lsystem
+}
+
+fn generate(c: &mut Criterion) {
+    let plant = fractal_plant();
+    c.bench_function("generate fractal plant 7", |b| {
+        b.iter(|| plant.generate(black_box(7)))
+    });
+    let memoized = GenerateOptions::new().with_memoization();
+    c.bench_function("generate fractal plant 7 memoized", |b| {
+        b.iter(|| plant.generate_with(black_box(7), &memoized).unwrap())
+    });
+
+    let koch = koch_curve();
+    c.bench_function("generate koch curve 6", |b| {
+        b.iter(|| koch.generate(black_box(6)))
+    });
+}
+
+fn run(c: &mut Criterion) {
+    let mut plant = fractal_plant();
+    let action = |name: &str, params| Action::new(name.to_string(), params);
+    plant.add_action_rule("+", action("RotateZAction", vec![ActionParam::Number(0.4)]));
+    plant.add_action_rule(
+        "-",
+        action("RotateZAction", vec![ActionParam::Number(-0.4)]),
+    );
+    plant.add_action_rule("[", action("PushTranformToStackAction", vec![]));
+    plant.add_action_rule("]", action("PopTransformFromStackAction", vec![]));
+
+    let resolver = ActionResolver::with_default_actions();
+
+    let alphabet = plant.generate(5);
+    c.bench_function("run fractal plant 5", |b| {
+        b.iter(|| plant.run(&resolver, black_box(&alphabet)).unwrap())
+    });
+}
+
+criterion_group!(benches, generate, run);
+criterion_main!(benches);
@@ -0,0 +1,270 @@
+//! `#[derive(LSystemAction)]` for the actions of `scebpl-system`, enable it with the `derive` feature,
+//! and `lsystem!` compiling scripts at build time, enable it with the `macros` feature.
+mod script;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Data, DeriveInput, Expr, Fields, Ident, LitChar, LitStr, Path, Result, Type,
+};
+
+/// Implements `LSystemAction` for a struct whose fields are the numbered parameters of the action.
+///
+/// ```ignore
+/// #[derive(LSystemAction)]
+/// #[action(name = "RotateZ", trigger = '+', execute = Self::rotate)]
+/// struct RotateZ {
+///     angle: f32,
+///     #[action(default = 1.0)]
+///     scale: f32,
+/// }
+/// ```
+///
+/// Attributes of the struct:
+/// - `execute = path`: required, the function executing the action, `fn(&Self, &Symbol, &mut ExecuteContext<E>)`.
+/// - `name = "..."`: the name of the action in interpret statements, the struct name by default.
+/// - `trigger = 'c'`: the symbol returned by `trigger`, a constant.
+/// - `element = Type`: implements the action for contexts collecting `Type` only instead of every element type.
+///
+/// Attributes of the fields:
+/// - `trigger`: the field of type `Symbol` receiving the symbol the action is created for, instead of `trigger = 'c'`.
+/// - `default = expr`: the value of a parameter that is not given, otherwise the parameter counts towards `arity`.
+///
+/// The remaining fields receive the parameters in declaration order, converted from `f32` with `From`.
+#[proc_macro_derive(LSystemAction, attributes(action))]
+pub fn derive_lsystem_action(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Parses a script at compile time and evaluates to its `LSystem`, a syntax error or an unknown variable fails the build.
+///
+/// ```ignore
+/// let koch = lsystem! {
+///     lsystem Koch {
+///         axiom F;
+///         replace F by F+F-F-F+F;
+///         interpret F as DrawForwardAction(1.0);
+///     }
+/// };
+/// ```
+///
+/// The lsystem equals the one `LSystemParser::parse` reads from the same script, with the `DefaultAlphabetSymbolDefiner`.
+/// The body must consist of Rust tokens, write symbols that are none as char literals, e.g. `'['` for an unclosed bracket,
+/// `'\''` or `'\\'`. Numbers are written as `f32`, `F1.50` is read as `F1.5`.
+#[proc_macro]
+pub fn lsystem(input: TokenStream) -> TokenStream {
+    script::expand(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct ActionAttributes {
+    name: Option<LitStr>,
+    trigger: Option<LitChar>,
+    execute: Option<Path>,
+    element: Option<Type>,
+}
+
+impl ActionAttributes {
+    fn parse(input: &DeriveInput) -> Result<Self> {
+        let mut attributes = Self::default();
+        for attr in input
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("action"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    attributes.name = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("trigger") {
+                    attributes.trigger = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("execute") {
+                    attributes.execute = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("element") {
+                    attributes.element = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("expected `name`, `trigger`, `execute` or `element`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(attributes)
+    }
+}
+
+/// How a field of the action is initialized by `from_params`.
+enum FieldKind {
+    Trigger,
+    Param { index: usize, default: Option<Expr> },
+}
+
+fn field_kind(field: &syn::Field, index: &mut usize) -> Result<FieldKind> {
+    let mut trigger = false;
+    let mut default = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("action"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("trigger") {
+                trigger = true;
+            } else if meta.path.is_ident("default") {
+                default = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("expected `trigger` or `default`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    if trigger {
+        return Ok(FieldKind::Trigger);
+    }
+    let kind = FieldKind::Param {
+        index: *index,
+        default,
+    };
+    *index += 1;
+    Ok(kind)
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let ident = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "`LSystemAction` can not be derived for generic structs",
+        ));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "`LSystemAction` can only be derived for structs",
+        ));
+    };
+
+    let attributes = ActionAttributes::parse(input)?;
+    let Some(execute) = attributes.execute else {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "missing `#[action(execute = path)]`",
+        ));
+    };
+    let name = attributes
+        .name
+        .unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+
+    let mut param_index = 0;
+    let mut arity = 0usize;
+    let mut trigger_field = None;
+    let mut values = Vec::new();
+    for (position, field) in data.fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = syn::Index::from(position);
+                quote!(#index)
+            }
+        };
+        let value = match field_kind(field, &mut param_index)? {
+            FieldKind::Trigger => {
+                trigger_field = Some(member.clone());
+                quote!(symbol)
+            }
+            FieldKind::Param { index, default } => {
+                let param = match default {
+                    Some(default) => quote!(params.get(#index).unwrap_or(#default)),
+                    None => {
+                        arity = arity.max(index + 1);
+                        quote!(params.require(#index)?)
+                    }
+                };
+                quote!(::core::convert::From::from(#param))
+            }
+        };
+        values.push((member, value));
+    }
+
+    let construct = match &data.fields {
+        Fields::Named(_) => {
+            let fields = values.iter().map(|(member, value)| quote!(#member: #value));
+            quote!(Self { #(#fields),* })
+        }
+        Fields::Unnamed(_) => {
+            let fields = values.iter().map(|(_, value)| value);
+            quote!(Self(#(#fields),*))
+        }
+        Fields::Unit => quote!(Self),
+    };
+
+    let trigger = match (trigger_field.as_ref(), attributes.trigger) {
+        (Some(member), _) => quote!(::core::clone::Clone::clone(&self.#member)),
+        (None, Some(trigger)) => quote!(::scebpl_system::Symbol::Constant(#trigger)),
+        (None, None) => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "missing `#[action(trigger = 'c')]` or a field marked `#[action(trigger)]`",
+            ))
+        }
+    };
+    // Unused arguments are prefixed to not warn in the deriving crate.
+    let symbol: Ident = if trigger_field.is_some() {
+        format_ident!("symbol")
+    } else {
+        format_ident!("_symbol")
+    };
+    let params: Ident = if param_index > 0 {
+        format_ident!("params")
+    } else {
+        format_ident!("_params")
+    };
+
+    let (generics, element) = match attributes.element {
+        Some(element) => (quote!(), quote!(#element)),
+        None => (quote!(<E>), quote!(E)),
+    };
+
+    Ok(quote! {
+        impl #generics ::scebpl_system::LSystemAction<#element> for #ident {
+            fn from_params(
+                symbol: ::scebpl_system::Symbol,
+                params: &::scebpl_system::ParamsResolver,
+            ) -> ::core::option::Option<Self> {
+                <Self as ::scebpl_system::LSystemAction<#element>>::try_from_params(symbol, params).ok()
+            }
+
+            fn try_from_params(
+                #symbol: ::scebpl_system::Symbol,
+                #params: &::scebpl_system::ParamsResolver,
+            ) -> ::core::result::Result<Self, ::scebpl_system::ParamError> {
+                ::core::result::Result::Ok(#construct)
+            }
+
+            fn name() -> &'static str {
+                #name
+            }
+
+            fn arity() -> usize {
+                #arity
+            }
+
+            fn trigger(&self) -> ::scebpl_system::Symbol {
+                #trigger
+            }
+
+            fn execute(
+                &self,
+                symbol: &::scebpl_system::Symbol,
+                context: &mut ::scebpl_system::ExecuteContext<#element>,
+            ) {
+                #execute(self, symbol, context)
+            }
+        }
+    })
+}
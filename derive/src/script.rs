@@ -0,0 +1,644 @@
+//! `lsystem!`, parsing a script while compiling and expanding it into the code building its lsystem.
+//!
+//! The statements are read like `LSystemParser` reads them, thus the macro and the parser build equal lsystems.
+use std::collections::HashMap;
+
+use proc_macro2::{Delimiter, Literal, Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::{Error, Lit, Result};
+
+/// A token of the script language, see `Token` of the lexer.
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f32),
+    Range(f32, f32),
+    Symbol(char),
+    Param(char),
+}
+
+/// A parsed action parameter, see `ActionParam`.
+#[derive(Clone, Debug, PartialEq)]
+enum Param {
+    None,
+    Number(f32),
+    Constant(String),
+    Binary(&'static str, Box<Param>, Box<Param>),
+    Named(String, Box<Param>),
+    Random(f32, f32),
+}
+
+enum Statement {
+    Axiom(String),
+    Let(String, Param, Span),
+    Replace(String, String),
+    Override(String, String),
+    Alias(char, char),
+    Interpret(String, String, Vec<Param>),
+    Taper(Vec<Param>, Span),
+}
+
+/// Expands `lsystem Name { statements }` into a block evaluating to the `LSystem`.
+pub(crate) fn expand(input: TokenStream) -> Result<TokenStream> {
+    let mut tokens = input.into_iter();
+
+    match tokens.next() {
+        Some(TokenTree::Ident(keyword)) if keyword == "lsystem" => {}
+        token => return Err(error(token.as_ref(), "expected `lsystem Name { .. }`")),
+    }
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(name)) => name.to_string(),
+        token => return Err(error(token.as_ref(), "expected the name of the lsystem")),
+    };
+    let body = match tokens.next() {
+        Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body,
+        token => return Err(error(token.as_ref(), "expected `{` after the name")),
+    };
+    if let Some(token) = tokens.next() {
+        return Err(error(Some(&token), "unexpected token after the lsystem"));
+    }
+
+    let mut statements = vec![];
+    let mut statement = vec![];
+    for token in body.stream() {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == ';' => {
+                statements.push(parse_statement(&statement, punct.span())?);
+                statement.clear();
+            }
+            _ => statement.push(token),
+        }
+    }
+    if let Some(token) = statement.first() {
+        return Err(error(Some(token), "expected `;` after the statement"));
+    }
+
+    build(name, statements, body.span())
+}
+
+fn parse_statement(tokens: &[TokenTree], end: Span) -> Result<Statement> {
+    let Some(TokenTree::Ident(keyword)) = tokens.first() else {
+        return Err(error(tokens.first(), "expected a statement"));
+    };
+    let span = keyword.span();
+    let rest = &tokens[1..];
+
+    match keyword.to_string().as_str() {
+        "axiom" => Ok(Statement::Axiom(symbols(rest)?)),
+        "let" => {
+            let Some(TokenTree::Ident(name)) = rest.first() else {
+                return Err(Error::new(span, "expected `let x = 1.0;`"));
+            };
+            if !is_punct(rest.get(1), '=') {
+                return Err(Error::new(span, "expected `=` after the variable"));
+            }
+            let mut params = parameters(&rest[2..]).map_err(|message| Error::new(span, message))?;
+            if params.len() != 1 {
+                return Err(Error::new(span, "expected a single value, `let x = 1.0;`"));
+            }
+            Ok(Statement::Let(name.to_string(), params.remove(0), span))
+        }
+        "replace" | "override" => {
+            let Some(by) = position_of_ident(rest, "by") else {
+                return Err(Error::new(end, "expected `by`, `replace X by Y;`"));
+            };
+            let replace = symbols(&rest[..by])?;
+            if replace.is_empty() {
+                return Err(Error::new(span, "expected the symbol to replace"));
+            }
+            let by = symbols(&rest[by + 1..])?;
+
+            Ok(if keyword == "replace" {
+                Statement::Replace(replace, by)
+            } else {
+                Statement::Override(replace, by)
+            })
+        }
+        "alias" => match rest {
+            [alias, equals, symbol] if is_punct(Some(equals), '=') => Ok(Statement::Alias(
+                single_symbol(alias)?,
+                single_symbol(symbol)?,
+            )),
+            _ => Err(Error::new(span, "expected `alias X = F;`")),
+        },
+        "interpret" => {
+            let Some(as_) = position_of_ident(rest, "as") else {
+                return Err(Error::new(end, "expected `as`, `interpret X as Y(Z);`"));
+            };
+            let [symbol] = &rest[..as_] else {
+                return Err(Error::new(span, "expected a single symbol to interpret"));
+            };
+            let symbol = match symbol {
+                TokenTree::Ident(ident) => ident.to_string(),
+                symbol => single_symbol(symbol)?.to_string(),
+            };
+
+            let [TokenTree::Ident(action), TokenTree::Group(params)] = &rest[as_ + 1..] else {
+                return Err(Error::new(
+                    end,
+                    "expected an action, `interpret X as Y(Z);`",
+                ));
+            };
+            if params.delimiter() != Delimiter::Parenthesis {
+                return Err(Error::new(params.span(), "expected `(` after the action"));
+            }
+            let inner = params.stream().into_iter().collect::<Vec<_>>();
+            let params =
+                parameters(&inner).map_err(|message| Error::new(params.span(), message))?;
+
+            Ok(Statement::Interpret(symbol, action.to_string(), params))
+        }
+        "taper" => {
+            let params = parameters(rest).map_err(|message| Error::new(span, message))?;
+            if !(1..=2).contains(&params.len()) {
+                return Err(Error::new(
+                    span,
+                    "expected one or two factors, `taper 0.8;` or `taper 0.9, 0.7;`",
+                ));
+            }
+            Ok(Statement::Taper(params, span))
+        }
+        _ => Err(Error::new(
+            span,
+            "expected `axiom`, `let`, `replace`, `override`, `alias`, `interpret` or `taper`",
+        )),
+    }
+}
+
+/// Writes the symbols of a statement like the lexer reads them, numbers are written as `f32`.
+///
+/// Char literals are read as the symbol they contain, for symbols that are no Rust tokens like `'['`.
+fn symbols(tokens: &[TokenTree]) -> Result<String> {
+    let mut symbols = String::new();
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => symbols.push_str(&ident.to_string()),
+            TokenTree::Punct(punct) => symbols.push(punct.as_char()),
+            TokenTree::Literal(literal) => symbols.push_str(&literal_symbols(literal)?),
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+                let inner = group.stream().into_iter().collect::<Vec<_>>();
+                symbols.push_str(open);
+                symbols.push_str(&self::symbols(&inner)?);
+                symbols.push_str(close);
+            }
+        }
+    }
+    Ok(symbols)
+}
+
+fn literal_symbols(literal: &Literal) -> Result<String> {
+    match Lit::new(literal.clone()) {
+        Lit::Char(symbol) => Ok(symbol.value().to_string()),
+        Lit::Int(number) => Ok(number.base10_parse::<f32>()?.to_string()),
+        Lit::Float(number) => Ok(number.base10_parse::<f32>()?.to_string()),
+        _ => Err(Error::new(literal.span(), "expected a symbol or a number")),
+    }
+}
+
+fn single_symbol(token: &TokenTree) -> Result<char> {
+    let symbols = symbols(std::slice::from_ref(token))?;
+    let mut chars = symbols.chars();
+    match (chars.next(), chars.next()) {
+        (Some(symbol), None) => Ok(symbol),
+        _ => Err(Error::new(token.span(), "expected a single symbol")),
+    }
+}
+
+fn is_punct(token: Option<&TokenTree>, symbol: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(punct)) if punct.as_char() == symbol)
+}
+
+fn position_of_ident(tokens: &[TokenTree], name: &str) -> Option<usize> {
+    tokens
+        .iter()
+        .position(|token| matches!(token, TokenTree::Ident(ident) if ident == name))
+}
+
+fn error(token: Option<&TokenTree>, message: &str) -> Error {
+    let span = token.map_or_else(Span::call_site, TokenTree::span);
+    Error::new(span, message)
+}
+
+/// Reads the tokens of parameters like the lexer, `0..1` is a range.
+fn lex(tokens: &[TokenTree], lexed: &mut Vec<Token>) -> std::result::Result<(), String> {
+    let mut index = 0;
+    while let Some(token) = tokens.get(index) {
+        match token {
+            TokenTree::Ident(ident) => lexed.push(Token::Ident(ident.to_string())),
+            TokenTree::Punct(punct) => lexed.push(Token::Symbol(punct.as_char())),
+            TokenTree::Literal(literal) => {
+                let number = number(literal)?;
+                match (
+                    tokens.get(index + 1),
+                    tokens.get(index + 2),
+                    tokens.get(index + 3),
+                ) {
+                    (Some(dot), Some(second), Some(TokenTree::Literal(end)))
+                        if is_punct(Some(dot), '.') && is_punct(Some(second), '.') =>
+                    {
+                        lexed.push(Token::Range(number, self::number(end)?));
+                        index += 3;
+                    }
+                    _ => lexed.push(Token::Number(number)),
+                }
+            }
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+                lexed.push(Token::Param('('));
+                lex(&group.stream().into_iter().collect::<Vec<_>>(), lexed)?;
+                lexed.push(Token::Param(')'));
+            }
+            TokenTree::Group(_) => return Err("unexpected brackets in the parameters".to_string()),
+        }
+        index += 1;
+    }
+    Ok(())
+}
+
+fn number(literal: &Literal) -> std::result::Result<f32, String> {
+    let number = match Lit::new(literal.clone()) {
+        Lit::Int(number) => number.base10_parse::<f32>(),
+        Lit::Float(number) => number.base10_parse::<f32>(),
+        _ => return Err(format!("expected a number, found `{literal}`")),
+    };
+    number.map_err(|error| error.to_string())
+}
+
+/// Parses the parameters of an action or the value of a `let` statement as `(tokens)`, see `parse_module_parameters`.
+fn parameters(tokens: &[TokenTree]) -> std::result::Result<Vec<Param>, String> {
+    let mut lexed = vec![Token::Param('(')];
+    lex(tokens, &mut lexed)?;
+    lexed.push(Token::Param(')'));
+
+    // Skips the parenthesis opening the list.
+    let mut tokens = Tokens {
+        tokens: lexed,
+        index: 1,
+    };
+    let mut params = vec![];
+    while !tokens.finished() {
+        let param = tokens.parameter()?;
+        if param != Param::None {
+            params.push(param);
+        }
+        if matches!(tokens.peek(), Some(Token::Symbol(',') | Token::Param(')'))) {
+            tokens.index += 1;
+        }
+    }
+    Ok(params)
+}
+
+/// How tight the operator binds, the table of `BinOpKind::precedence` which the macro can not depend on.
+fn precedence(op: &str) -> u8 {
+    match op {
+        "Mul" | "Div" | "Rem" => 7,
+        "Add" | "Sub" => 6,
+        "BitAnd" => 5,
+        "BitXor" => 4,
+        "BitOr" => 3,
+        "Lt" | "Le" | "Ne" | "Ge" | "Gt" | "Eq" => 2,
+        "And" => 1,
+        _ => 0,
+    }
+}
+
+struct Tokens {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Tokens {
+    fn finished(&self) -> bool {
+        self.index >= self.tokens.len()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    /// Parses a parameter, an expression or a named parameter, see `parse_parameter`.
+    fn parameter(&mut self) -> std::result::Result<Param, String> {
+        let param = self.expression(0)?;
+        if self.peek() != Some(&Token::Symbol('=')) {
+            return Ok(param);
+        }
+        self.index += 1;
+
+        let Param::Constant(name) = param else {
+            return Err(format!(
+                "expected a parameter name before `=`, found {param:?}"
+            ));
+        };
+        let value = self.expression(0)?;
+        Ok(Param::Named(name, Box::new(value)))
+    }
+
+    /// Parses the binary operations binding at least as tight as `min_precedence`, see `parse_expression`.
+    fn expression(&mut self, min_precedence: u8) -> std::result::Result<Param, String> {
+        let mut lh = self.operand()?;
+        while let Some((op, length)) = self.binary() {
+            let precedence = precedence(op);
+            if precedence < min_precedence {
+                break;
+            }
+            self.index += length;
+
+            let rh = self.expression(precedence + 1)?;
+            lh = Param::Binary(op, Box::new(lh), Box::new(rh));
+        }
+        Ok(lh)
+    }
+
+    /// Parses an operand, see `parse_operand`.
+    fn operand(&mut self) -> std::result::Result<Param, String> {
+        let Some(token) = self.peek().cloned() else {
+            return Err("incomplete parameters".to_string());
+        };
+
+        match token {
+            Token::Number(number) => {
+                self.index += 1;
+                Ok(Param::Number(number))
+            }
+            Token::Ident(ident)
+                if ident == "r" && self.tokens.get(self.index + 1) == Some(&Token::Param('(')) =>
+            {
+                self.index += 1;
+                self.operand()
+            }
+            Token::Ident(ident) => {
+                self.index += 1;
+                Ok(Param::Constant(ident))
+            }
+            Token::Range(start, end) => {
+                self.index += 1;
+                Ok(Param::Random(start, end))
+            }
+            Token::Param('(') => {
+                self.index += 1;
+                let param = self.expression(0)?;
+                if self.peek() != Some(&Token::Param(')')) {
+                    return Err("expected `)` after the expression in parentheses".to_string());
+                }
+                self.index += 1;
+                Ok(param)
+            }
+            Token::Symbol('-') => {
+                self.index += 1;
+                let param = self.operand()?;
+                if param == Param::None {
+                    return Err("expected an operand after `-`".to_string());
+                }
+
+                // The negation `-x` is read as `0 - x`.
+                Ok(Param::Binary(
+                    "Sub",
+                    Box::new(Param::Number(0.0)),
+                    Box::new(param),
+                ))
+            }
+            Token::Symbol(',') | Token::Param(_) => Ok(Param::None),
+            Token::Symbol(symbol) => Err(format!("unexpected symbol `{symbol}` in the parameters")),
+        }
+    }
+
+    /// Returns the binary operator at the current token and its number of tokens, see `peek_binary`.
+    fn binary(&self) -> Option<(&'static str, usize)> {
+        let Some(Token::Symbol(symbol)) = self.peek() else {
+            return None;
+        };
+        let followed_by =
+            |next: char| self.tokens.get(self.index + 1) == Some(&Token::Symbol(next));
+
+        Some(match symbol {
+            '*' => ("Mul", 1),
+            '+' => ("Add", 1),
+            '-' => ("Sub", 1),
+            '/' => ("Div", 1),
+            '%' => ("Rem", 1),
+            '^' => ("BitXor", 1),
+            '&' if followed_by('&') => ("And", 2),
+            '&' => ("BitAnd", 1),
+            '|' if followed_by('|') => ("Or", 2),
+            '|' => ("BitOr", 1),
+            '<' if followed_by('=') => ("Le", 2),
+            '<' => ("Lt", 1),
+            '>' if followed_by('=') => ("Ge", 2),
+            '>' => ("Gt", 1),
+            '!' if followed_by('=') => ("Ne", 2),
+            '=' if followed_by('=') => ("Eq", 2),
+            _ => return None,
+        })
+    }
+}
+
+/// Splits the predecessor of a stochastic rule `replace F(0.5) by ..;` into the symbol and its probability.
+fn stochastic_predecessor(replace: &str) -> Option<(&str, f32)> {
+    let (predecessor, probability) = replace.strip_suffix(')')?.split_once('(')?;
+    Some((predecessor, probability.parse().ok()?))
+}
+
+/// Resolves the aliases of `symbols`, parameter lists are kept as they are, see `SymbolAliases::resolve_str`.
+fn resolve(aliases: &HashMap<char, char>, symbols: &str) -> String {
+    let mut depth = 0usize;
+    symbols
+        .chars()
+        .map(|symbol| match symbol {
+            '(' => {
+                depth += 1;
+                symbol
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                symbol
+            }
+            _ if depth > 0 => symbol,
+            _ => aliases.get(&symbol).copied().unwrap_or(symbol),
+        })
+        .collect()
+}
+
+/// Builds the lsystem like `LSystemParser::parse`: the actions, the variables, the taper and then the rules.
+fn build(name: String, statements: Vec<Statement>, span: Span) -> Result<TokenStream> {
+    let aliases = statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Alias(alias, symbol) => Some((*alias, *symbol)),
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+
+    let Some(axiom) = statements.iter().find_map(|statement| match statement {
+        Statement::Axiom(axiom) => Some(resolve(&aliases, axiom)),
+        _ => None,
+    }) else {
+        return Err(Error::new(span, "expected an `axiom` statement"));
+    };
+    check_variables(&statements)?;
+
+    let mut actions = vec![];
+    let mut variables = vec![];
+    let mut taper = None;
+    let mut rules = vec![];
+    for statement in statements {
+        match statement {
+            Statement::Interpret(symbol, action, params) => {
+                let symbol = resolve(&aliases, &symbol);
+                let params = params.iter().map(param);
+                actions.push(quote! {
+                    lsystem.add_action_rule(
+                        #symbol,
+                        ::scebpl_system::Action::new(
+                            ::core::convert::From::from(#action),
+                            ::core::iter::Iterator::collect(::core::iter::IntoIterator::into_iter([#(#params),*])),
+                        ),
+                    );
+                });
+            }
+            Statement::Let(name, value, _) => {
+                let value = param(&value);
+                let message = format!("Could not evaluate the value of variable '{name}'.");
+                variables.push(quote! {
+                    let value = ::scebpl_system::ParamsResolver {
+                        params: ::core::iter::Iterator::collect(::core::iter::IntoIterator::into_iter([#value])),
+                    }
+                    .get_with(0, lsystem.variables())
+                    .expect(#message);
+                    lsystem.set_variable(#name, value);
+                });
+            }
+            Statement::Replace(replace, by) => {
+                let replace = resolve(&aliases, &replace);
+                let by = resolve(&aliases, &by);
+                rules.push(match stochastic_predecessor(&replace) {
+                    Some((predecessor, probability)) => {
+                        quote!(lsystem.add_stochastic_rule(#predecessor, #probability, #by);)
+                    }
+                    None => quote!(lsystem.add_rule(#replace, #by);),
+                });
+            }
+            Statement::Override(replace, by) => {
+                let replace = resolve(&aliases, &replace);
+                let by = resolve(&aliases, &by);
+                rules.push(quote!(lsystem.override_rule(#replace, #by);));
+            }
+            Statement::Taper(factors, _) => {
+                let width = (factors.len() == 2).then(|| quote!(.with_width(factor(1))));
+                let factors = factors.iter().map(param);
+                // The last statement replaces the earlier ones.
+                taper = Some(quote! {{
+                    let factors = ::scebpl_system::ParamsResolver {
+                        params: ::core::iter::Iterator::collect(::core::iter::IntoIterator::into_iter([#(#factors),*])),
+                    };
+                    let factor = |index| {
+                        factors
+                            .get_with(index, lsystem.variables())
+                            .expect("Could not evaluate the taper factor.")
+                    };
+                    let taper = ::scebpl_system::Taper::new(factor(0))#width;
+                    lsystem.set_taper(::core::option::Option::Some(taper));
+                }});
+            }
+            Statement::Axiom(_) | Statement::Alias(..) => {}
+        }
+    }
+
+    Ok(quote! {
+        {
+            let mut lsystem: ::scebpl_system::LSystem = ::scebpl_system::LSystem::new(
+                #axiom,
+                ::scebpl_system::DefaultAlphabetSymbolDefiner,
+            );
+            lsystem.name = ::core::convert::From::from(#name);
+            #(#actions)*
+            #(#variables)*
+            #taper
+            #(#rules)*
+            lsystem
+        }
+    })
+}
+
+/// Fails on the variables the `let` and `taper` statements use before they are defined,
+/// thus the expanded code evaluates every value.
+///
+/// A value can use the variables defined before it, a taper every variable, see `LSystemParser::variables`.
+fn check_variables(statements: &[Statement]) -> Result<()> {
+    let defined = statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Let(name, ..) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut known = 0;
+    for statement in statements {
+        let (params, span, variables) = match statement {
+            Statement::Let(_, value, span) => {
+                known += 1;
+                (std::slice::from_ref(value), span, &defined[..known - 1])
+            }
+            Statement::Taper(factors, span) => (&factors[..], span, &defined[..]),
+            _ => continue,
+        };
+        if let Some(name) = params
+            .iter()
+            .find_map(|param| unknown_variable(param, variables))
+        {
+            return Err(Error::new(*span, format!("unknown variable `{name}`")));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the first variable of `param` that is not in `variables`.
+fn unknown_variable<'a>(param: &'a Param, variables: &[&str]) -> Option<&'a str> {
+    match param {
+        Param::Constant(name) if !variables.contains(&name.as_str()) => Some(name),
+        Param::Binary(_, lh, rh) => {
+            unknown_variable(lh, variables).or_else(|| unknown_variable(rh, variables))
+        }
+        Param::Named(_, value) => unknown_variable(value, variables),
+        _ => None,
+    }
+}
+
+fn param(param: &Param) -> TokenStream {
+    match param {
+        Param::None => quote!(::scebpl_system::ActionParam::None),
+        Param::Number(number) => quote!(::scebpl_system::ActionParam::Number(#number)),
+        Param::Constant(constant) => {
+            quote!(::scebpl_system::ActionParam::Constant(::core::convert::From::from(#constant)))
+        }
+        Param::Binary(op, lh, rh) => {
+            let op = syn::Ident::new(op, Span::call_site());
+            let lh = self::param(lh);
+            let rh = self::param(rh);
+            quote! {
+                ::scebpl_system::ActionParam::Expression(::scebpl_system::ExprKind::Binary(
+                    ::scebpl_system::BinOpKind::#op,
+                    ::scebpl_system::P::new(#lh),
+                    ::scebpl_system::P::new(#rh),
+                ))
+            }
+        }
+        Param::Named(name, value) => {
+            let value = self::param(value);
+            quote! {
+                ::scebpl_system::ActionParam::Named(
+                    ::core::convert::From::from(#name),
+                    ::scebpl_system::P::new(#value),
+                )
+            }
+        }
+        Param::Random(start, end) => quote! {
+            ::scebpl_system::ActionParam::Expression(::scebpl_system::ExprKind::Random(#start..#end))
+        },
+    }
+}
@@ -3,13 +3,8 @@
 
 use eframe::egui;
 
-use egui::{mutex::Mutex, panel::Side};
-use glow::{HasContext, NativeBuffer, NativeProgram, NativeVertexArray};
-
-use macaw::BoundingBox;
-use regex::Regex;
-use scebpl_system::*;
-use std::{f32::consts::PI, fs, path::PathBuf, sync::Arc};
+use scebpl_system::{ui::LSystemEditorWidget, *};
+use std::time::Duration;
 
 /// L systems commonly saves transforms while generating actions.
 /// This action saves the current turret transform.
@@ -48,7 +43,10 @@ impl LSystemAction for PopStack {
     }
 
     fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext) {
-        context.turtle = context.pop();
+        match context.pop() {
+            Ok(turtle) => context.turtle = turtle,
+            Err(error) => return context.fail(error),
+        }
         context.turtle.rotate_z(self.1)
     }
 
@@ -191,34 +189,11 @@ fn main() -> Result<(), eframe::Error> {
     )
 }
 
-struct LScriptInstance {
-    script: String,
-    path: PathBuf,
-}
-
-impl LScriptInstance {
-    pub fn load(path: PathBuf) -> Self {
-        let script = fs::read_to_string(path.clone()).unwrap();
-
-        Self { script, path }
-    }
-
-    pub fn safe(&self) {
-        fs::write(&self.path, self.script.clone());
-    }
-}
-
 struct MyApp {
-    /// Behind an `Arc<Mutex<…>>` so we can pass it to [`egui::PaintCallback`] and paint later.
-    lsystem_renderer: Arc<Mutex<Option<LSystemRenderer>>>,
-    forward_len: f32,
-    rotate_left: f32,
-    rotate_right: f32,
-    generations: u8,
-    lsystem_script: LScriptInstance,
-    gl: Arc<glow::Context>,
-    angle: f32,
-    alphabet: String,
+    editor: LSystemEditorWidget,
+    /// Reloads the script when it is changed by another editor.
+    host: ScriptHost,
+    save_error: Option<String>,
 }
 
 impl MyApp {
@@ -229,40 +204,10 @@ impl MyApp {
             .expect("You need to run eframe with the glow backend")
             .clone();
 
-        let mut app = Self {
-            lsystem_renderer: Arc::new(Mutex::new(None)),
-            lsystem_script: LScriptInstance::load(PathBuf::from(
-                "./examples/scripts/koch.ls",
-            )),
-            forward_len: 1.0,
-            rotate_left: PI / 2.0,
-            rotate_right: PI / 2.0,
-            generations: 3,
-            gl,
-            angle: 0.0,
-            alphabet: "".to_string(),
-        };
-
-        app.recompile_lsystem();
-        app
-    }
-
-    fn recompile_lsystem(&mut self) {
-        let instantiated_script = self.lsystem_script.script.clone();
-
-        let lexer = Lexer::new();
-
-        let lex = lexer.lex(instantiated_script);
-        let tokens = LexedTokens::new(lex);
-
-        let item = parse(tokens);
-
-        let mut lsystem = LSystemParser::parse(item);
-        let alphabet = lsystem.generate(self.generations);
+        let host = ScriptHost::watch("./examples/scripts/koch.ls").expect("The script exists.");
+        let script = host.read().expect("The script is readable.");
 
-        let mut resolver = ActionResolver {
-            actions: Default::default(),
-        };
+        let mut resolver = ActionResolver::new();
         resolver.add_action_resolver::<RotateLeft>('+'.into());
         resolver.add_action_resolver::<RotateRight>('-'.into());
         resolver.add_action_resolver::<DrawLeaf>('0'.into());
@@ -274,331 +219,60 @@ impl MyApp {
         resolver.add_action_resolver::<PushStack>('['.into());
         resolver.add_action_resolver::<PopStack>(']'.into());
 
-        let context = lsystem.run(&resolver, &alphabet);
+        let editor = LSystemEditorWidget::new(gl, script)
+            .with_resolver(resolver)
+            .with_generate_options(
+                GenerateOptions::new()
+                    .with_max_symbols(1_000_000)
+                    .with_timeout(Duration::from_secs(2)),
+            )
+            .with_editor_width(WINDOW_X / 3.0);
 
-        self.alphabet = alphabet.to_string();
-        self.lsystem_renderer = Arc::new(Mutex::new(Some(LSystemRenderer::new(
-            &self.gl,
-            context,
-            alphabet.to_string(),
-        ))))
+        Self {
+            editor,
+            host,
+            save_error: None,
+        }
     }
 }
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::SidePanel::new(Side::Left, "canvas-painter")
-            .exact_width((WINDOW_X / 3.0) * 2.0)
-            .show(ctx, |ui| {
-                egui::Frame::canvas(ui.style()).show(ui, |ui| {
-                    self.custom_painting(ui);
-                });
-            });
-
-        egui::SidePanel::new(Side::Right, "code_editor")
-            .exact_width((WINDOW_X / 3.0) * 1.0)
-            .show(ctx, |ui| {
-                ui.vertical(|ui| {
-                    ui.spacing();
-                    ui.separator();
-                    ui.text_edit_multiline(&mut self.lsystem_script.script);
-                    let gen = self.generations;
-                    ui.add(egui::Slider::new(&mut self.generations, 0..=5));
-
-                    ui.horizontal(|ui| {
-                        if ui.button("Recomile").clicked() {
-                            self.recompile_lsystem()
-                        }
-
-                        if ui.button("Safe").clicked() {
-                            self.lsystem_script.safe();
-                        }
-                    });
-
-                    ui.separator();
-
-                    ui.text_edit_multiline(&mut self.alphabet)
-                })
-            });
-    }
-
-    fn on_exit(&mut self, gl: Option<&glow::Context>) {
-        let lock = self.lsystem_renderer.lock();
-        if let (Some(gl), Some(render)) = (gl, lock.as_ref()) {
-            render.destroy(gl);
-        }
-    }
-}
-
-impl MyApp {
-    fn custom_painting(&mut self, ui: &mut egui::Ui) {
-        let (rect, response) = ui.allocate_exact_size(
-            egui::Vec2::new((WINDOW_X / 3.0) * 2.0, WINDOW_Y * 0.8),
-            egui::Sense::drag(),
-        );
-
-        self.angle += response.drag_delta().x * 0.01;
-
-        let renderer = self.lsystem_renderer.clone();
-
-        let callback = egui::PaintCallback {
-            rect,
-            callback: std::sync::Arc::new(egui_glow::CallbackFn::new(move |_info, painter| {
-                let mut lock = renderer.lock();
-                if let Some(render) = lock.as_mut() {
-                    render.run_compute_shader(painter.gl());
-                    render.paint(painter.gl());
+        // The editor compiles the script itself and shows its errors, thus only the source is taken.
+        while let Some(event) = self.host.try_next() {
+            if let ScriptEvent::Reloaded(_) = event {
+                match self.host.read() {
+                    Ok(script) => self.editor.set_script(script),
+                    Err(error) => self.save_error = Some(error.to_string()),
                 }
-            })),
-        };
-        ui.painter().add(callback);
-    }
-}
-
-struct LSystemRenderer {
-    compute_program: glow::Program,
-    render_program: glow::Program,
-
-    vbo_pos: glow::NativeBuffer,
-    rectangle_vbo: glow::NativeBuffer,
-
-    render_vbo: glow::NativeBuffer,
-    render_vao: glow::NativeVertexArray,
-    triangles_verts: Vec<f32>,
-    triangle_verts_indicies: usize,
-    should_run_compute: bool,
-    alphabet: String,
-}
+            }
+        }
 
-impl LSystemRenderer {
-    fn new(gl: &glow::Context, lcontext: ExecuteContext, alphabet: String) -> Self {
-        let mut bounds = BoundingBox::ZERO;
-
-        let positions = lcontext
-            .snapshot
-            .iter()
-            .zip(lcontext.snapshot.iter())
-            .flat_map(|(turtle, _)| {
-                let x = turtle.turtle.origin()[0];
-                let y = turtle.turtle.origin()[1];
-                let z = turtle.turtle.origin()[2];
-
-                if x < bounds.min.x {
-                    bounds.min.x = x;
-                }
-                if y < bounds.min.y {
-                    bounds.min.y = y;
+        egui::TopBottomPanel::top("menu").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    self.save_error = self
+                        .host
+                        .save(self.editor.script())
+                        .err()
+                        .map(|error| error.to_string());
                 }
-                if x > bounds.max.x {
-                    bounds.min.x = x;
+                if let Some(error) = &self.save_error {
+                    ui.colored_label(egui::Color32::RED, error);
                 }
-                if y > bounds.max.y {
-                    bounds.min.y = y;
-                }
-
-                vec![
-                    turtle.turtle.origin()[0],
-                    turtle.turtle.origin()[1],
-                    turtle.turtle.origin()[2],
-                    f32::from(turtle.is_leave)
-                ]
-            })
-            .collect::<Vec<f32>>();
-
-            println!("Positions: {}", positions.len());
-
-        let verticies = 4;
-
-        let path_count = (positions.len() / verticies) - 1;
-        let triangles_per_path = 2;
-        let triangle_indicies_per_path = triangles_per_path * 3;
-        let total_indicies = triangle_indicies_per_path * path_count;
-        let total_floats = total_indicies * verticies;
-
-        let triangles_verts = vec![0.0; total_floats];
-
-        assert_eq!(triangles_verts.len(), total_floats);
-        assert_eq!(triangles_verts.capacity(), total_floats);
-
-        let (compute_program, render_program, vbo_pos, rectangle_vbo,  (render_vbo, render_vao)) = unsafe {
-            (
-                Self::create_compute_program(gl),
-                Self::create_render_program(gl),
-                Self::create_storeage_buf(gl, to_bytes(positions.as_slice()), 0),
-                Self::create_storeage_buf(gl, to_bytes(triangles_verts.as_slice()), 1),
-                Self::create_vao_buf(gl, to_bytes(triangles_verts.as_slice()), 0),
-            )
-        };
-
-        Self {
-            compute_program,
-            render_program,
-            vbo_pos,
-            rectangle_vbo,
-            triangles_verts,
-            triangle_verts_indicies: total_indicies,
-            render_vbo,
-            render_vao,
-            should_run_compute: true,
-            alphabet,
-        }
-    }
-
-    unsafe fn create_render_program(gl: &glow::Context) -> NativeProgram {
-        let shader_sources = [
-            (glow::VERTEX_SHADER, include_str!("./shader.vert")),
-            (glow::FRAGMENT_SHADER, include_str!("./shader.frag")),
-        ];
-
-        Self::compile_shaders(gl, &shader_sources)
-    }
-
-    unsafe fn create_compute_program(gl: &glow::Context) -> NativeProgram {
-        let shader_sources = [(glow::COMPUTE_SHADER, include_str!("./shader.comp"))];
-
-        Self::compile_shaders(gl, &shader_sources)
-    }
-
-    unsafe fn compile_shaders(gl: &glow::Context, shader_sources: &[(u32, &str)]) -> NativeProgram {
-        let program = gl.create_program().expect("Cannot create program");
-
-        let shaders: Vec<_> = shader_sources
-            .iter()
-            .map(|(shader_type, shader_source)| {
-                let shader = gl
-                    .create_shader(*shader_type)
-                    .expect("Cannot create shader");
-                gl.shader_source(shader, shader_source);
-                gl.compile_shader(shader);
-                assert!(
-                    gl.get_shader_compile_status(shader),
-                    "Failed to compile {shader_type}: {}",
-                    gl.get_shader_info_log(shader)
-                );
-                gl.attach_shader(program, shader);
-                shader
-            })
-            .collect();
-
-        gl.link_program(program);
-        if !gl.get_program_link_status(program) {
-            panic!("{}", gl.get_program_info_log(program));
-        }
-
-        for shader in shaders {
-            gl.detach_shader(program, shader);
-            gl.delete_shader(shader);
-        }
-
-        program
-    }
-
-    unsafe fn create_storeage_buf(gl: &glow::Context, data: &[u8], index: u32) -> NativeBuffer {
-        let vbo = gl.create_buffer().unwrap();
-        gl.bind_buffer(glow::SHADER_STORAGE_BUFFER, Some(vbo));
-        gl.buffer_data_u8_slice(glow::SHADER_STORAGE_BUFFER, data, glow::DYNAMIC_DRAW);
-        gl.bind_buffer_base(glow::SHADER_STORAGE_BUFFER, index, Some(vbo));
-
-        vbo
-    }
-
-    unsafe fn create_vao_buf(
-        gl: &glow::Context,
-        data: &[u8],
-        index: u32,
-    ) -> (NativeBuffer, NativeVertexArray) {
-        let vbo = gl.create_buffer().unwrap();
-        gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
-        gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, data, glow::STATIC_DRAW);
-
-        let vao = gl.create_vertex_array().unwrap();
-        gl.bind_vertex_array(Some(vao));
-        gl.vertex_attrib_pointer_f32(index, 4, glow::FLOAT, false, 16, 0);
-        gl.enable_vertex_attrib_array(index);
-
-        gl.bind_vertex_array(None);
-        gl.bind_buffer(glow::ARRAY_BUFFER, None);
-
-        (vbo, vao)
-    }
-
-    fn destroy(&self, gl: &glow::Context) {
-        use glow::HasContext as _;
-        unsafe {
-            gl.delete_program(self.compute_program);
-        }
-    }
-
-    fn run_compute_shader(&mut self, gl: &glow::Context) {
-        use glow::HasContext as _;
-
-        if !self.should_run_compute {
-            return;
-        }
-
-        unsafe {
-            gl.use_program(Some(self.compute_program));
-
-            gl.bind_buffer(glow::SHADER_STORAGE_BUFFER, Some(self.vbo_pos));
-            gl.bind_buffer(glow::SHADER_STORAGE_BUFFER, Some(self.rectangle_vbo));
-            
-            gl.dispatch_compute(1, 1, 1);
-            gl.memory_barrier(glow::SHADER_STORAGE_BARRIER_BIT);
-
-            gl.get_buffer_sub_data(
-                glow::SHADER_STORAGE_BUFFER,
-                0,
-                to_bytes_mut(&mut self.triangles_verts),
-            );
-
-            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.render_vbo));
-            gl.buffer_data_u8_slice(
-                glow::ARRAY_BUFFER,
-                to_bytes(self.triangles_verts.as_slice()),
-                glow::STATIC_DRAW,
-            );
-
-            gl.bind_buffer(glow::SHADER_STORAGE_BUFFER, None);
-            gl.bind_buffer(glow::ARRAY_BUFFER, None);
-        }
+            });
+        });
 
-        self.should_run_compute = false;
+        egui::CentralPanel::default().show(ctx, |ui| self.editor.show(ui));
     }
 
-    fn paint(&mut self, gl: &glow::Context) {
-        use glow::HasContext as _;
-
-        unsafe {
-            gl.use_program(Some(self.render_program));
-
-            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.render_vbo));
-            gl.bind_vertex_array(Some(self.render_vao));
-            gl.enable_vertex_attrib_array(0);
-            gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
-
-            gl.draw_arrays(glow::TRIANGLES, 0, self.triangle_verts_indicies as i32);
+    fn on_exit(&mut self, gl: Option<&glow::Context>) {
+        if let Some(gl) = gl {
+            self.editor.destroy(gl);
         }
     }
 }
 
-fn to_bytes<'a>(elements: &'a [f32]) -> &'a [u8] {
-    unsafe {
-        core::slice::from_raw_parts(
-            elements.as_ptr() as *const u8,
-            elements.len() * core::mem::size_of::<f32>(),
-        )
-    }
-}
-
-fn to_bytes_mut<'a>(elements: &'a mut [f32]) -> &'a mut [u8] {
-    unsafe {
-        core::slice::from_raw_parts_mut(
-            elements.as_ptr() as *mut u8,
-            elements.len() * core::mem::size_of::<f32>(),
-        )
-    }
-}
-
 // Debug purposes
 #[allow(unused)]
 fn print_verts(verts: Vec<f32>) {
@@ -1,7 +0,0 @@
-#version 450
-
-out vec4 out_color;
-
-void main() {
-    out_color = vec4(0.0, 1.0, 0.0, 1.0);
-}
\ No newline at end of file
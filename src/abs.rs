@@ -1,6 +1,6 @@
-use std::ops::{Deref, Range};
+use core::ops::{Deref, Range};
 
-use crate::{action::ParamsResolver, Symbol};
+use crate::{action::ParamsResolver, prelude::*, Symbol};
 
 #[derive(PartialEq, Clone, Debug)]
 pub struct Item {
@@ -15,12 +15,20 @@ pub enum ItemKind {
 #[derive(PartialEq, Clone, Debug)]
 pub enum StatementKind {
     Axiom(String),
-    DefineVariable,
+    /// `let x = 1.0;` defines the variable `x` used by the parameters of actions.
+    DefineVariable(String, ActionParam),
     Replace(String, String),
+    /// Same as `Replace` but intentionally replaces an earlier rule for the same predecessor.
+    Override(String, String),
+    /// `alias X = F;` reads the symbol `X` as `F` everywhere in the script.
+    Alias(char, char),
     Interpret(Constant, Action),
+    /// `taper 0.8;` scales the branches by their depth, `taper 0.9, 0.7;` their length and width separately.
+    Taper(Vec<ActionParam>),
 }
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Action {
     pub name: String,
     pub params: ParamsResolver,
@@ -36,10 +44,13 @@ impl Action {
 }
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ActionParam {
     Number(Number),
     Constant(Constant),
     Expression(ExprKind),
+    /// A parameter given by name, `length = 1.0`, it is also found at its position.
+    Named(String, P<ActionParam>),
     None,
 }
 
@@ -49,6 +60,7 @@ impl ActionParam {
             ActionParam::Number(number) => number.to_string(),
             ActionParam::Constant(c) => c.to_owned(),
             ActionParam::Expression(e) => e.to_string(),
+            ActionParam::Named(name, value) => format!("{name} = {}", value.ptr.to_string()),
             ActionParam::None => todo!(),
         }
     }
@@ -58,6 +70,7 @@ pub type Constant = String;
 pub type Number = f32;
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExprKind {
     Binary(BinOpKind, P<ActionParam>, P<ActionParam>),
     Random(Range<f32>),
@@ -82,6 +95,7 @@ impl ExprKind {
 }
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BinOpKind {
     Add,
     Sub,
@@ -96,6 +110,11 @@ pub enum BinOpKind {
     Ne,
     Ge,
     Gt,
+    Eq,
+    /// Logical and `&&`, non-zero values are true.
+    And,
+    /// Logical or `||`, non-zero values are true.
+    Or,
 }
 
 impl BinOpKind {
@@ -114,12 +133,38 @@ impl BinOpKind {
             BinOpKind::Ne => "!=",
             BinOpKind::Ge => ">=",
             BinOpKind::Gt => ">",
+            BinOpKind::Eq => "==",
+            BinOpKind::And => "&&",
+            BinOpKind::Or => "||",
         }
         .to_string()
     }
+
+    /// How tight the operator binds, higher binds tighter, `1 + 2 * 3` is `1 + (2 * 3)`.
+    ///
+    /// Arithmetic binds tighter than the bitwise operators, those tighter than the comparisons,
+    /// then `&&` and `||` binds the weakest, thus `0 && 1 || 1` is `(0 && 1) || 1`.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinOpKind::Mul | BinOpKind::Div | BinOpKind::Rem => 7,
+            BinOpKind::Add | BinOpKind::Sub => 6,
+            BinOpKind::BitAnd => 5,
+            BinOpKind::BitXor => 4,
+            BinOpKind::BitOr => 3,
+            BinOpKind::Lt
+            | BinOpKind::Le
+            | BinOpKind::Ne
+            | BinOpKind::Ge
+            | BinOpKind::Gt
+            | BinOpKind::Eq => 2,
+            BinOpKind::And => 1,
+            BinOpKind::Or => 0,
+        }
+    }
 }
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct P<T: ?Sized + PartialEq + Clone> {
     ptr: Box<T>,
 }
@@ -1,40 +1,272 @@
-use std::collections::HashMap;
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+};
 
 use crate::{
-    abs::{Action, ActionParam},
-    ExecuteContext, Symbol,
+    abs::{Action, ActionParam, ExprKind, P},
+    prelude::*,
+    seeded_rng, AddVertexAction, BeginPolygonAction, DrawForwardAction, EndPolygonAction,
+    ExecuteContext, ExecutionHook, GeometryElement, IncColorAction, MoveForwardAction,
+    MultiplyWidthAction, PitchDownAction, PitchUpAction, PopTransformFromStackAction,
+    PushTranformToStackAction, Rng, RollLeftAction, RollRightAction, RotateXAction, RotateYAction,
+    RotateZAction, Symbol, SymbolId, SymbolMap, TurnAroundAction, Variables, DEFAULT_SEED,
 };
 
-pub struct ActionResolver {
-    pub actions: HashMap<(String, char), Box<dyn Fn(&Action) -> Option<Box<dyn LSystemAction>>>>,
+/// Creates an action from the parameters of an interpret binding, see `ActionResolver::add_action_resolver`.
+pub type ResolveAction<E> = Box<dyn Fn(&Action) -> Result<Box<dyn LSystemAction<E>>, ParamError>>;
+
+/// Resolves the actions of an lsystem whose context collects elements of type `E`, see `ExecuteContext::elements`.
+pub struct ActionResolver<E = GeometryElement> {
+    pub actions: SymbolMap<(String, SymbolId), ResolveAction<E>>,
+    /// Called around every executed symbol, in the order they were added.
+    hooks: Vec<Box<dyn ExecutionHook<E>>>,
 }
 
 impl ActionResolver {
-    pub fn add_action_resolver<A: LSystemAction + 'static>(&mut self, trigger: Symbol) {
-        let trigger_move = trigger.clone();
-        let resolver: Box<dyn Fn(&Action) -> Option<Box<dyn LSystemAction>>> = Box::new(move |action| {
-            let resolver_action = A::from_params(trigger_move.clone(),&action.params);
-            let result = resolver_action.map(|a| Box::new(a) as Box<dyn LSystemAction>);
+    /// Creates a resolver without actions for the default `GeometryElement` context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a resolver with the `default_actions` registered, see `ActionResolver::add_default_actions`.
+    pub fn with_default_actions() -> Self {
+        Self::builder().with_defaults().build()
+    }
+}
 
-            result
+impl<E> Default for ActionResolver<E> {
+    fn default() -> Self {
+        Self {
+            actions: SymbolMap::default(),
+            hooks: vec![],
+        }
+    }
+}
+
+impl<E> ActionResolver<E> {
+    /// Registers the action `A` for `trigger`.
+    ///
+    /// The parameters are checked against `LSystemAction::arity` before the action is created with `LSystemAction::try_from_params`.
+    pub fn add_action_resolver<A: LSystemAction<E> + 'static>(&mut self, trigger: Symbol) {
+        let trigger_move = trigger.clone();
+        let resolver: ResolveAction<E> = Box::new(move |action| {
+            action
+                .params
+                .validate_arity(A::arity())
+                .and_then(|()| A::try_from_params(trigger_move.clone(), &action.params))
+                .map(|a| Box::new(a) as Box<dyn LSystemAction<E>>)
+                .map_err(|error| error.in_action(A::name()))
         });
 
-        if let Symbol::Constant(char) | Symbol::Variable(char) = trigger {
-            self.actions.insert((A::name().to_owned(), char), resolver);
+        self.actions
+            .insert((A::name().to_owned(), trigger.id()), resolver);
+    }
+
+    /// Adds a hook called before and after the actions of every executed symbol.
+    pub fn add_hook(&mut self, hook: impl ExecutionHook<E> + 'static) {
+        self.hooks.push(Box::new(hook));
+    }
+
+    pub(crate) fn before(&self, symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        for hook in &self.hooks {
+            hook.before(symbol, context);
+        }
+    }
+
+    pub(crate) fn after(&self, symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        for hook in &self.hooks {
+            hook.after(symbol, context);
+        }
+    }
+
+    /// Starts building a resolver, e.g. `ActionResolver::builder().with_defaults().with::<MyAction>('F'.into()).build()`.
+    pub fn builder() -> ActionResolverBuilder<E> {
+        ActionResolverBuilder {
+            resolver: Self::default(),
+        }
+    }
+
+    /// Registers the `default_actions` under their conventional symbols:
+    /// `+ -` rotate around z, `& ^` around x, `/ \` around y, `F G` draw forward, `f g` move forward,
+    /// `'` selects the next color, `!` multiplies the width, `[ ]` push and pop the turtle
+    /// and `{ . }` open a polygon, add a vertex and close the polygon.
+    ///
+    /// The 3D turtle symbols are also bound to their directed actions: `&` pitch down, `^` pitch up,
+    /// `\` roll left, `/` roll right and `|` turns around.
+    pub fn add_default_actions(&mut self) {
+        for symbol in ['+', '-'] {
+            self.add_action_resolver::<RotateZAction>(Symbol::Constant(symbol));
+        }
+        for symbol in ['&', '^'] {
+            self.add_action_resolver::<RotateXAction>(Symbol::Constant(symbol));
+        }
+        for symbol in ['/', '\\'] {
+            self.add_action_resolver::<RotateYAction>(Symbol::Constant(symbol));
+        }
+        self.add_action_resolver::<PitchDownAction>(Symbol::Constant('&'));
+        self.add_action_resolver::<PitchUpAction>(Symbol::Constant('^'));
+        self.add_action_resolver::<RollLeftAction>(Symbol::Constant('\\'));
+        self.add_action_resolver::<RollRightAction>(Symbol::Constant('/'));
+        self.add_action_resolver::<TurnAroundAction>(Symbol::Constant('|'));
+        for symbol in ['F', 'G'] {
+            self.add_action_resolver::<DrawForwardAction>(Symbol::Constant(symbol));
+        }
+        for symbol in ['f', 'g'] {
+            self.add_action_resolver::<MoveForwardAction>(Symbol::Constant(symbol));
+        }
+        self.add_action_resolver::<IncColorAction>(Symbol::Constant('\''));
+        self.add_action_resolver::<MultiplyWidthAction>(Symbol::Constant('!'));
+        self.add_action_resolver::<BeginPolygonAction>(Symbol::Constant('{'));
+        self.add_action_resolver::<AddVertexAction>(Symbol::Constant('.'));
+        self.add_action_resolver::<EndPolygonAction>(Symbol::Constant('}'));
+        self.add_action_resolver::<PushTranformToStackAction>(Symbol::Constant('['));
+        self.add_action_resolver::<PopTransformFromStackAction>(Symbol::Constant(']'));
+    }
+
+    /// Returns `true` if an action named `name` is registered for `trigger`.
+    pub fn contains(&self, trigger: &Symbol, name: &str) -> bool {
+        self.actions.contains_key(&(name.to_owned(), trigger.id()))
+    }
+
+    pub fn resolve(&self, trigger: &Symbol, action: &Action) -> Option<Box<dyn LSystemAction<E>>> {
+        self.try_resolve(trigger, action)?.ok()
+    }
+
+    /// Same as `resolve` but reports why the action could not be created from its parameters.
+    /// Returns `None` if no action named like `action` is registered for `trigger`.
+    pub fn try_resolve(
+        &self,
+        trigger: &Symbol,
+        action: &Action,
+    ) -> Option<Result<Box<dyn LSystemAction<E>>, ParamError>> {
+        Some(self.resolver(trigger, &action.name)?(action))
+    }
+
+    pub(crate) fn resolver(&self, trigger: &Symbol, name: &str) -> Option<&ResolveAction<E>> {
+        self.actions.get(&(name.to_owned(), trigger.id()))
+    }
+
+    /// Same as `resolve` but draws the random parameters of `action` from `rng` instead of the global generator.
+    pub fn resolve_with_rng(
+        &self,
+        trigger: &Symbol,
+        action: &Action,
+        rng: &mut dyn Rng,
+    ) -> Option<Box<dyn LSystemAction<E>>> {
+        let cb = self.resolver(trigger, &action.name)?;
+
+        cb(&Action {
+            name: action.name.clone(),
+            params: action.params.sample(rng),
+        })
+        .ok()
+    }
+}
+
+/// Builds an `ActionResolver`, see `ActionResolver::builder`.
+pub struct ActionResolverBuilder<E = GeometryElement> {
+    resolver: ActionResolver<E>,
+}
+
+impl<E> ActionResolverBuilder<E> {
+    /// Registers the `default_actions`, see `ActionResolver::add_default_actions`.
+    pub fn with_defaults(mut self) -> Self {
+        self.resolver.add_default_actions();
+        self
+    }
+
+    /// Registers the action `A` for `trigger`, replacing an action with the same name registered before.
+    pub fn with<A: LSystemAction<E> + 'static>(mut self, trigger: Symbol) -> Self {
+        self.resolver.add_action_resolver::<A>(trigger);
+        self
+    }
+
+    /// Adds a hook, see `ActionResolver::add_hook`.
+    pub fn with_hook(mut self, hook: impl ExecutionHook<E> + 'static) -> Self {
+        self.resolver.add_hook(hook);
+        self
+    }
+
+    pub fn build(self) -> ActionResolver<E> {
+        self.resolver
+    }
+}
+
+/// The actions resolved during a single run, keyed by action name, trigger and a hash of the parameters.
+///
+/// Resolving an action allocates, thus symbols with equal actions and parameters share the resolved action.
+pub(crate) struct ActionCache<E> {
+    actions: HashMap<(String, SymbolId, u64), CachedActions<E>>,
+}
+
+/// The actions whose parameters have the same hash.
+type CachedActions<E> = Vec<(ParamsResolver, Box<dyn LSystemAction<E>>)>;
+
+impl<E> ActionCache<E> {
+    pub(crate) fn new() -> Self {
+        Self {
+            actions: HashMap::new(),
         }
     }
 
-    pub fn resolve(&self, trigger: &Symbol, action: &Action) -> Option<Box<dyn LSystemAction>> {
-        if let Symbol::Constant(char) | Symbol::Variable(char) = trigger {
-            self.actions.get(&(action.name.clone(), *char)).and_then(|cb| cb(action))
-        } else {
-            None
+    /// Returns the cached action for `trigger` and `action`, resolving it with `resolve` if it is not cached yet.
+    /// Actions with random parameters must not be cached, every symbol draws other values.
+    pub(crate) fn get_or_resolve(
+        &mut self,
+        trigger: &Symbol,
+        action: &Action,
+        resolve: impl FnOnce() -> Result<Box<dyn LSystemAction<E>>, ParamError>,
+    ) -> Result<&dyn LSystemAction<E>, ParamError> {
+        debug_assert!(!action.params.is_random());
+
+        let mut hasher = DefaultHasher::new();
+        for param in &action.params.params {
+            hash_param(param, &mut hasher);
+        }
+        let key = (action.name.clone(), trigger.id(), hasher.finish());
+
+        // Hashes can collide, thus the parameters are compared as well.
+        let cached = self.actions.entry(key).or_default();
+        let cached_index = cached
+            .iter()
+            .position(|(params, _)| *params == action.params);
+        let index = match cached_index {
+            Some(index) => index,
+            None => {
+                cached.push((action.params.clone(), resolve()?));
+                cached.len() - 1
+            }
+        };
+
+        Ok(cached[index].1.as_ref())
+    }
+}
+
+pub(crate) fn hash_param(param: &ActionParam, state: &mut impl Hasher) {
+    match param {
+        ActionParam::Number(number) => number.to_bits().hash(state),
+        ActionParam::Constant(constant) => constant.hash(state),
+        ActionParam::Expression(ExprKind::Binary(op, lh, rh)) => {
+            op.to_string().hash(state);
+            hash_param(lh, state);
+            hash_param(rh, state);
+        }
+        ActionParam::Expression(ExprKind::Random(range)) => {
+            range.start.to_bits().hash(state);
+            range.end.to_bits().hash(state);
+        }
+        ActionParam::Named(name, value) => {
+            name.hash(state);
+            hash_param(value, state);
         }
+        ActionParam::None => {}
     }
 }
 
-pub trait LSystemAction {
-    fn from_params(symbol: Symbol,params: &ParamsResolver) -> Option<Self>
+/// An action executed for a symbol, pushing elements of type `E` onto the context, see `ExecuteContext::elements`.
+pub trait LSystemAction<E = GeometryElement> {
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self>
     where
         Self: Sized;
 
@@ -42,14 +274,37 @@ pub trait LSystemAction {
     where
         Self: Sized;
 
+    /// Same as `from_params` but reports which parameter is wrong, e.g. with `ParamsResolver::require`.
+    /// The resolver creates actions with this method. By default it reports the first parameter that is not a number
+    /// if `from_params` fails, or the parameter past the given ones if all of them are numbers.
+    fn try_from_params(symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError>
+    where
+        Self: Sized,
+    {
+        Self::from_params(symbol, params).ok_or_else(|| {
+            (0..params.len())
+                .find_map(|index| params.require(index).err())
+                .unwrap_or_else(|| ParamError::missing(params.len()))
+        })
+    }
+
+    /// The number of parameters the action requires, the resolver checks them before creating the action.
+    fn arity() -> usize
+    where
+        Self: Sized,
+    {
+        0
+    }
+
     /// Returns the trigger letter for this action.
     fn trigger(&self) -> Symbol;
 
     /// Executes the given action.
-    fn execute(&self, symbol: &Symbol, context: &mut ExecuteContext);
+    fn execute(&self, symbol: &Symbol, context: &mut ExecuteContext<E>);
 }
 
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParamsResolver {
     pub params: Vec<ActionParam>,
 }
@@ -74,42 +329,275 @@ impl ParamsResolver {
         }
     }
 
+    /// Returns these parameters followed by the parameters of `defaults` past their length.
+    ///
+    /// A parameter replacing a named parameter of `defaults` keeps its name.
+    pub fn or(self, defaults: &ParamsResolver) -> ParamsResolver {
+        let mut params = self
+            .params
+            .into_iter()
+            .enumerate()
+            .map(|(index, param)| match (param, defaults.params.get(index)) {
+                (param @ ActionParam::Named(..), _) => param,
+                (param, Some(ActionParam::Named(name, _))) => {
+                    ActionParam::Named(name.clone(), P::new(param))
+                }
+                (param, _) => param,
+            })
+            .collect::<Vec<_>>();
+        params.extend(defaults.params.iter().skip(params.len()).cloned());
+        Self { params }
+    }
+
+    /// Returns `true` if a parameter is drawn at random, `r(a..b)`.
+    pub fn is_random(&self) -> bool {
+        fn is_random(param: &ActionParam) -> bool {
+            match param {
+                ActionParam::Expression(ExprKind::Random(_)) => true,
+                ActionParam::Expression(ExprKind::Binary(_, lh, rh)) => {
+                    is_random(lh) || is_random(rh)
+                }
+                ActionParam::Named(_, value) => is_random(value),
+                _ => false,
+            }
+        }
+
+        self.params.iter().any(is_random)
+    }
+
+    /// Returns a copy with every random parameter `r(a..b)` replaced by a number drawn from `rng`.
+    pub fn sample(&self, rng: &mut dyn Rng) -> ParamsResolver {
+        Self {
+            params: self
+                .params
+                .iter()
+                .map(|param| Self::sample_param(param, rng))
+                .collect(),
+        }
+    }
+
+    fn sample_param(param: &ActionParam, rng: &mut dyn Rng) -> ActionParam {
+        match param {
+            ActionParam::Expression(ExprKind::Random(range)) => {
+                ActionParam::Number(rng.range_f32(range.clone()))
+            }
+            ActionParam::Expression(ExprKind::Binary(op, lh, rh)) => {
+                ActionParam::Expression(ExprKind::Binary(
+                    op.clone(),
+                    P::new(Self::sample_param(lh, rng)),
+                    P::new(Self::sample_param(rh, rng)),
+                ))
+            }
+            ActionParam::Named(name, value) => {
+                ActionParam::Named(name.clone(), P::new(Self::sample_param(value, rng)))
+            }
+            param => param.clone(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Returns the parameter at `index` or an error telling whether it is missing or not a number.
+    ///
+    /// Random parameters are drawn like by `get`.
+    pub fn require(&self, index: usize) -> Result<f32, ParamError> {
+        match self.params.get(index) {
+            None | Some(ActionParam::None) => Err(ParamError::missing(index)),
+            Some(param) => {
+                Self::evaluate(param, &Variables::default(), &mut seeded_rng(DEFAULT_SEED))
+                    .ok_or_else(|| ParamError {
+                        action: String::new(),
+                        index,
+                        expected: "number",
+                        found: Some(param.to_string()),
+                    })
+            }
+        }
+    }
+
+    /// Fails with the first missing parameter if there are less than `arity` parameters.
+    pub fn validate_arity(&self, arity: usize) -> Result<(), ParamError> {
+        if self.len() < arity {
+            return Err(ParamError::missing(self.len()));
+        }
+        Ok(())
+    }
+
+    /// Returns the parameter at `index`, `None` if it is missing or uses a variable, see `get_with`.
+    ///
+    /// Random parameters `r(a..b)` are drawn from a generator seeded with `DEFAULT_SEED`, thus every call returns the same number.
+    /// Use `get_with_rng` or `sample` to draw them from the generator of a run.
     pub fn get(&self, index: usize) -> Option<f32> {
-        if let Some(param) = self.params.get(index) {
-            self.action_param(param)
-        } else {
-            None
+        self.get_with(index, &Variables::default())
+    }
+
+    /// Same as `get` but reads the variables the parameter uses from `variables`.
+    pub fn get_with(&self, index: usize, variables: &Variables) -> Option<f32> {
+        self.get_with_rng(index, variables, &mut seeded_rng(DEFAULT_SEED))
+    }
+
+    /// Same as `get_with` but draws the random parameters from `rng`.
+    pub fn get_with_rng(
+        &self,
+        index: usize,
+        variables: &Variables,
+        rng: &mut dyn Rng,
+    ) -> Option<f32> {
+        Self::evaluate(self.params.get(index)?, variables, rng)
+    }
+
+    /// Returns the parameter given by `name`, e.g. `length` of `DrawForward(length = 1.0)`.
+    pub fn get_named(&self, name: &str) -> Option<f32> {
+        self.params.iter().find_map(|param| match param {
+            ActionParam::Named(param_name, value) if param_name == name => {
+                Self::evaluate(value, &Variables::default(), &mut seeded_rng(DEFAULT_SEED))
+            }
+            _ => None,
+        })
+    }
+
+    /// Returns a copy with every variable bound in `variables` replaced by its value.
+    pub fn bind(&self, variables: &Variables) -> ParamsResolver {
+        Self {
+            params: self
+                .params
+                .iter()
+                .map(|param| Self::bind_param(param, variables))
+                .collect(),
         }
     }
 
-    fn action_param(&self, param: &ActionParam) -> Option<f32> {
+    fn bind_param(param: &ActionParam, variables: &Variables) -> ActionParam {
         match param {
-            ActionParam::Number(number) => Some(*number),
-            ActionParam::Constant(_constant) => {
-                panic!("The usage of constants/variables is not yet supported.")
+            ActionParam::Constant(name) => match variables.get(name) {
+                Some(value) => ActionParam::Number(value),
+                None => param.clone(),
+            },
+            ActionParam::Expression(ExprKind::Binary(op, lh, rh)) => {
+                ActionParam::Expression(ExprKind::Binary(
+                    op.clone(),
+                    P::new(Self::bind_param(lh, variables)),
+                    P::new(Self::bind_param(rh, variables)),
+                ))
+            }
+            ActionParam::Named(name, value) => {
+                ActionParam::Named(name.clone(), P::new(Self::bind_param(value, variables)))
             }
+            param => param.clone(),
+        }
+    }
+
+    /// Returns the name of the first variable used by the parameters, e.g. one that `bind` did not know.
+    pub fn unbound(&self) -> Option<&str> {
+        fn unbound(param: &ActionParam) -> Option<&str> {
+            match param {
+                ActionParam::Constant(name) => Some(name),
+                ActionParam::Expression(ExprKind::Binary(_, lh, rh)) => {
+                    unbound(lh).or_else(|| unbound(rh))
+                }
+                ActionParam::Named(_, value) => unbound(value),
+                _ => None,
+            }
+        }
+
+        self.params.iter().find_map(unbound)
+    }
+
+    fn evaluate(param: &ActionParam, variables: &Variables, rng: &mut dyn Rng) -> Option<f32> {
+        match param {
+            ActionParam::Number(number) => Some(*number),
+            ActionParam::Constant(name) => variables.get(name),
             ActionParam::Expression(kind) => match kind {
                 crate::ExprKind::Binary(opt, lh, rh) => {
-                    let lh = self.action_param(lh)?;
-                    let rh = self.action_param(rh)?;
+                    let lh = Self::evaluate(lh, variables, rng)?;
+                    let rh = Self::evaluate(rh, variables, rng)?;
+
+                    // Comparisons and logical operations return `1.0` for true and `0.0` for false,
+                    // bitwise operations work on the integer part.
+                    let truth = |value: bool| f32::from(u8::from(value));
+                    let integers = |op: fn(i64, i64) -> i64| op(lh as i64, rh as i64) as f32;
 
                     Some(match opt {
                         crate::BinOpKind::Add => lh + rh,
                         crate::BinOpKind::Sub => lh - rh,
                         crate::BinOpKind::Mul => lh * rh,
                         crate::BinOpKind::Div => lh / rh,
-                        _ => {
-                            panic!("The binary operation '{}' is not supported yet as action parameter.", opt.to_string());
-                        }
+                        crate::BinOpKind::Rem => lh % rh,
+                        crate::BinOpKind::BitXor => integers(|lh, rh| lh ^ rh),
+                        crate::BinOpKind::BitAnd => integers(|lh, rh| lh & rh),
+                        crate::BinOpKind::BitOr => integers(|lh, rh| lh | rh),
+                        crate::BinOpKind::Lt => truth(lh < rh),
+                        crate::BinOpKind::Le => truth(lh <= rh),
+                        crate::BinOpKind::Ne => truth(lh != rh),
+                        crate::BinOpKind::Ge => truth(lh >= rh),
+                        crate::BinOpKind::Gt => truth(lh > rh),
+                        crate::BinOpKind::Eq => truth(lh == rh),
+                        crate::BinOpKind::And => truth(lh != 0.0 && rh != 0.0),
+                        crate::BinOpKind::Or => truth(lh != 0.0 || rh != 0.0),
                     })
                 }
-                crate::ExprKind::Random(range) => {
-                    let mut rng = perchance::global();
-                    let rand = rng.uniform_range_f32(range.clone());
-                    Some(rand)
-                }
+                crate::ExprKind::Random(range) => Some(rng.range_f32(range.clone())),
             },
+            ActionParam::Named(_, value) => Self::evaluate(value, variables, rng),
             ActionParam::None => None,
         }
     }
 }
+
+/// A parameter an action could not be created from, see `ParamsResolver::require`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParamError {
+    /// The name of the action, set by the `ActionResolver`.
+    pub action: String,
+    /// The index of the parameter.
+    pub index: usize,
+    /// The expected type of the parameter.
+    pub expected: &'static str,
+    /// The parameter that was given, `None` if it is missing.
+    pub found: Option<String>,
+}
+
+impl ParamError {
+    pub fn missing(index: usize) -> Self {
+        Self {
+            action: String::new(),
+            index,
+            expected: "number",
+            found: None,
+        }
+    }
+
+    fn in_action(mut self, action: &str) -> Self {
+        self.action = action.to_owned();
+        self
+    }
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ParamError {
+            action,
+            index,
+            expected,
+            found,
+        } = self;
+        match found {
+            Some(found) => write!(
+                f,
+                "action `{action}` expected a {expected} as parameter {index}, found `{found}`"
+            ),
+            None => write!(
+                f,
+                "action `{action}` is missing parameter {index}, expected a {expected}"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for ParamError {}
@@ -0,0 +1,281 @@
+//! The `lsys` command line tool.
+//!
+//! `lsys watch plant.ls --out out/` exports the script whenever it changes,
+//! thus scripts can be written in any editor while a viewer shows the exported files.
+//! `lsys render plant.ls` prints the drawing of the script to the terminal, see `render::ascii`.
+//! `lsys repl` derives a grammar that is entered line by line, see `Repl`.
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use scebpl_system::{
+    export::ExporterRegistry, render::ascii, ActionResolver, CompileError, CompiledLSystem,
+    GenerateOptions, LSystem, Repl, ScriptEvent, ScriptHost,
+};
+
+const USAGE: &str = "\
+Usage: lsys watch <script> [options]
+       lsys render <script> [options]
+       lsys repl
+
+watch exports the script whenever it changes.
+render prints the drawing of the script with braille characters.
+repl derives a grammar entered line by line, type `help` for its commands.
+
+Options of watch:
+  --out <dir>          Directory the exported files are written to, `.` by default
+  --generations <n>    Number of generations to generate, 4 by default
+  --format <format>    Format to export, may be repeated, `svg` by default
+  --help               Prints this message
+
+Options of render:
+  --generations <n>    Number of generations to generate, 4 by default
+  --columns <n>        Width of the drawing in characters, 80 by default
+  --rows <n>           Height of the drawing in characters, 40 by default
+  --blocks             Draws with block characters instead of braille";
+
+struct WatchOptions {
+    script: PathBuf,
+    out: PathBuf,
+    generations: u8,
+    formats: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let result = match args.first().map(String::as_str) {
+        Some("watch") => parse_watch_options(&args[1..]).and_then(|options| watch(&options)),
+        Some("render") => render(&args[1..]),
+        Some("repl") => return repl(),
+        Some("--help") | None => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Some(command) => Err(format!("unknown command `{command}`")),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_watch_options(args: &[String]) -> Result<WatchOptions, String> {
+    let mut script = None;
+    let mut out = PathBuf::from(".");
+    let mut generations = 4;
+    let mut formats = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("`{arg}` expects a value"))
+        };
+        match arg.as_str() {
+            "--out" => out = PathBuf::from(value()?),
+            "--generations" => {
+                let value = value()?;
+                generations = value
+                    .parse()
+                    .map_err(|_| format!("`{value}` is no number of generations"))?;
+            }
+            "--format" => formats.push(value()?.clone()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{arg}`")),
+            _ if script.is_none() => script = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument `{arg}`")),
+        }
+    }
+
+    let registry = ExporterRegistry::with_defaults();
+    if formats.is_empty() {
+        formats.push("svg".to_string());
+    }
+    if let Some(format) = formats.iter().find(|format| registry.get(format).is_none()) {
+        let available = registry.formats().collect::<Vec<_>>().join(", ");
+        return Err(format!("unknown format `{format}`, available: {available}"));
+    }
+
+    Ok(WatchOptions {
+        script: script.ok_or("missing script to watch")?,
+        out,
+        generations,
+        formats,
+    })
+}
+
+fn watch(options: &WatchOptions) -> Result<(), String> {
+    fs::create_dir_all(&options.out)
+        .map_err(|error| format!("could not create `{}`: {error}", options.out.display()))?;
+    let host = ScriptHost::watch(&options.script)
+        .map_err(|error| format!("could not watch `{}`: {error}", options.script.display()))?;
+
+    println!(
+        "watching {}, press ctrl-c to stop",
+        options.script.display()
+    );
+    for event in host.events() {
+        match event {
+            ScriptEvent::Reloaded(lsystem) => rebuild(lsystem, options),
+            ScriptEvent::WatchFailed(error) => eprintln!("error: watching failed: {error}"),
+        }
+    }
+    Ok(())
+}
+
+/// Prints the drawing of the script given in `args` with its options.
+fn render(args: &[String]) -> Result<(), String> {
+    let mut script = None;
+    let mut generations = 4;
+    let mut columns = 80;
+    let mut rows = 40;
+    let mut charset = ascii::Charset::Braille;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut number = |what: &str| {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("`{arg}` expects a value"))?;
+            value
+                .parse::<usize>()
+                .map_err(|_| format!("`{value}` is no number of {what}"))
+        };
+        match arg.as_str() {
+            "--generations" => {
+                generations = u8::try_from(number("generations")?)
+                    .map_err(|_| "the number of generations is too large".to_string())?
+            }
+            "--columns" => columns = number("columns")?,
+            "--rows" => rows = number("rows")?,
+            "--blocks" => charset = ascii::Charset::Blocks,
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{arg}`")),
+            _ if script.is_none() => script = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument `{arg}`")),
+        }
+    }
+    let script = script.ok_or("missing script to render")?;
+
+    let source = fs::read_to_string(&script)
+        .map_err(|error| format!("could not read `{}`: {error}", script.display()))?;
+    let lsystem = CompiledLSystem::try_compile(&source)
+        .map_err(|error| format!("{}: {error}", script.display()))?
+        .lsystem;
+    print_warnings(&script, &lsystem);
+
+    let generate_options = GenerateOptions::new()
+        .with_max_symbols(10_000_000)
+        .with_timeout(Duration::from_secs(10));
+    let alphabet = lsystem
+        .generate_with(generations, &generate_options)
+        .map_err(|error| error.to_string())?;
+    let resolver: ActionResolver = ActionResolver::with_default_actions();
+    let context = lsystem
+        .run(&resolver, &alphabet)
+        .map_err(|error| error.to_string())?;
+
+    println!(
+        "{}",
+        ascii::render_with(&context.geometry(), columns, rows, charset)
+    );
+    Ok(())
+}
+
+fn repl() -> ExitCode {
+    let mut repl = Repl::new();
+    let mut stdin = io::stdin().lock();
+    println!("type `help` for the commands, `quit` or ctrl-d to exit");
+
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
+
+        let mut line = String::new();
+        match stdin.read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) if matches!(line.trim(), "quit" | "exit") => break,
+            Ok(_) => {}
+        }
+
+        match repl.eval(&line) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => println!("{output}"),
+            Err(error) => eprintln!("error: {error}"),
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Generates and exports the reloaded script, errors are printed and the previous exports are kept.
+fn rebuild(lsystem: Result<LSystem, CompileError>, options: &WatchOptions) {
+    let start = Instant::now();
+    match lsystem
+        .map_err(|error| error.to_string())
+        .and_then(|lsystem| {
+            print_warnings(&options.script, &lsystem);
+            export(&lsystem, options)
+        }) {
+        Ok(files) => {
+            let files = files
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect::<Vec<_>>();
+            println!(
+                "exported {} in {:.0?}: {}",
+                options.script.display(),
+                start.elapsed(),
+                files.join(", ")
+            );
+        }
+        Err(error) => eprintln!("error: {}: {error}", options.script.display()),
+    }
+}
+
+fn print_warnings(script: &Path, lsystem: &LSystem) {
+    for warning in lsystem.warnings() {
+        eprintln!("warning: {}: {warning}", script.display());
+    }
+}
+
+fn export(lsystem: &LSystem, options: &WatchOptions) -> Result<Vec<PathBuf>, String> {
+    let generate_options = GenerateOptions::new()
+        .with_max_symbols(10_000_000)
+        .with_timeout(Duration::from_secs(10));
+    let alphabet = lsystem
+        .generate_with(options.generations, &generate_options)
+        .map_err(|error| error.to_string())?;
+
+    let resolver: ActionResolver = ActionResolver::with_default_actions();
+    let context = lsystem
+        .run(&resolver, &alphabet)
+        .map_err(|error| error.to_string())?;
+
+    let stem = options
+        .script
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let registry = ExporterRegistry::with_defaults();
+    let mut files = Vec::new();
+    for format in &options.formats {
+        let exporter = registry.get(format).expect("Formats are validated.");
+        let path = options.out.join(format!("{stem}.{}", exporter.extension()));
+        write_file(&path, |writer| exporter.export(&context, writer))
+            .map_err(|error| format!("could not write `{}`: {error}", path.display()))?;
+        files.push(path);
+    }
+    Ok(files)
+}
+
+fn write_file(path: &Path, write: impl FnOnce(&mut dyn Write) -> io::Result<()>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer)?;
+    writer.flush()
+}
@@ -0,0 +1,87 @@
+//! The geometry drawn by the turtle as a tree of branches, see `ExecuteContext::branch_tree`.
+use crate::{prelude::*, ExecuteContext, Segment, TurtleEvent};
+
+/// A branch of the drawing, the segments drawn between opening and closing it and the branches opened inside it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Branch {
+    /// The segments of this branch, in the order they were drawn.
+    pub segments: Vec<Segment>,
+    /// The branches opened inside this branch, in the order they were opened.
+    pub children: Vec<Branch>,
+    /// The branch depth, `0` for the main branch.
+    pub depth: usize,
+}
+
+impl Branch {
+    fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            ..Default::default()
+        }
+    }
+
+    /// Returns this branch and all branches below it, depth first.
+    pub fn iter(&self) -> impl Iterator<Item = &Branch> {
+        let mut stack = vec![self];
+        core::iter::from_fn(move || {
+            let branch = stack.pop()?;
+            stack.extend(branch.children.iter().rev());
+            Some(branch)
+        })
+    }
+
+    /// Returns the number of segments of this branch and all branches below it.
+    pub fn segment_count(&self) -> usize {
+        self.iter().map(|branch| branch.segments.len()).sum()
+    }
+}
+
+impl<E> ExecuteContext<E> {
+    /// Builds the tree of branches from the recorded `events`, the returned branch is the main branch.
+    ///
+    /// Branches that are still open at the end are closed.
+    pub fn branch_tree(&self) -> Branch {
+        let mut open = vec![Branch::new(0)];
+
+        for event in &self.events {
+            match *event {
+                TurtleEvent::Line {
+                    from,
+                    to,
+                    width,
+                    color,
+                    depth,
+                    ref material,
+                    ..
+                } => {
+                    let branch = open.last_mut().expect("The main branch is never closed");
+                    branch.segments.push(Segment {
+                        width,
+                        color,
+                        depth,
+                        material: material.clone(),
+                        ..Segment::new(from, to)
+                    });
+                }
+                TurtleEvent::Branch { depth, .. } => open.push(Branch::new(depth)),
+                TurtleEvent::BranchEnd { .. } => close_branch(&mut open),
+            }
+        }
+
+        while open.len() > 1 {
+            close_branch(&mut open);
+        }
+        open.pop().expect("The main branch is never closed")
+    }
+}
+
+/// Moves the innermost open branch into the children of its parent, the main branch is never closed.
+fn close_branch(open: &mut Vec<Branch>) {
+    if open.len() > 1 {
+        let branch = open.pop().expect("An open branch");
+        open.last_mut()
+            .expect("The main branch")
+            .children
+            .push(branch);
+    }
+}
@@ -0,0 +1,210 @@
+//! Building lsystems in code, validated before they are generated.
+use core::fmt;
+
+use crate::{
+    prelude::*, Action, ContextSensitiveRuleCB, DefaultAlphabetSymbolDefiner, GrammarWarning,
+    LSystem, ParametricRuleCB, SymbolDefiner, Taper,
+};
+
+/// The probabilities of the stochastic rules of a predecessor may exceed `1.0` by this much due to rounding.
+const PROBABILITY_TOLERANCE: f32 = 1e-4;
+
+/// An lsystem the `LSystemBuilder` refused to build.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError {
+    /// The axiom is empty, thus every generation is empty.
+    EmptyAxiom,
+    /// A stochastic rule with a probability outside of `0.0..=1.0`.
+    InvalidProbability {
+        predecessor: String,
+        probability: f32,
+    },
+    /// The probabilities of the stochastic rules of a predecessor add up to more than `1.0`.
+    ProbabilitiesExceedOne { predecessor: String, total: f32 },
+    /// The grammar has warnings that are errors, see `GrammarWarning::is_error`.
+    Grammar(Vec<GrammarWarning>),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::EmptyAxiom => write!(f, "the axiom is empty"),
+            ValidationError::InvalidProbability {
+                predecessor,
+                probability,
+            } => write!(
+                f,
+                "stochastic rule of `{predecessor}` has probability {probability}, which is not between 0 and 1"
+            ),
+            ValidationError::ProbabilitiesExceedOne { predecessor, total } => write!(
+                f,
+                "probabilities of the stochastic rules of `{predecessor}` add up to {total}, which is more than 1"
+            ),
+            ValidationError::Grammar(warnings) => {
+                for (index, warning) in warnings.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{warning}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl core::error::Error for ValidationError {}
+
+/// Builds an lsystem in code, e.g. `LSystemBuilder::new().axiom("F").rule("F", "F+F").interpret("F", action).build()`.
+///
+/// `build` validates the grammar, warnings that are no errors are still reported by `LSystem::validate`.
+pub struct LSystemBuilder<A: SymbolDefiner = DefaultAlphabetSymbolDefiner> {
+    lsystem: LSystem<A>,
+    /// The predecessors and probabilities of the stochastic rules, in the order they were added.
+    probabilities: Vec<(String, f32)>,
+}
+
+impl LSystemBuilder {
+    /// Creates a builder of an lsystem with the `DefaultAlphabetSymbolDefiner` and an empty axiom.
+    pub fn new() -> Self {
+        Self::with_definer(DefaultAlphabetSymbolDefiner)
+    }
+}
+
+impl<A: SymbolDefiner> LSystemBuilder<A> {
+    /// Creates a builder of an lsystem with the given symbol definer and an empty axiom.
+    pub fn with_definer(alphabet_definer: A) -> Self {
+        Self {
+            lsystem: LSystem::new("", alphabet_definer),
+            probabilities: vec![],
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.lsystem.name = name.into();
+        self
+    }
+
+    pub fn axiom(mut self, axiom: impl ToString) -> Self {
+        self.lsystem.axiom = axiom.to_string();
+        self
+    }
+
+    /// Adds a rule replacing `predecessor` by `successor`, see `LSystem::add_rule`.
+    pub fn rule(mut self, predecessor: impl Into<String>, successor: impl Into<String>) -> Self {
+        self.lsystem.add_rule(predecessor, successor);
+        self
+    }
+
+    /// Adds a rule replacing `predecessor` by `successor` with the given `probability`, see `LSystem::add_stochastic_rule`.
+    pub fn stochastic_rule(
+        mut self,
+        predecessor: impl Into<String>,
+        probability: f32,
+        successor: impl Into<String>,
+    ) -> Self {
+        let predecessor = predecessor.into();
+        self.probabilities.push((predecessor.clone(), probability));
+        self.lsystem
+            .add_stochastic_rule(predecessor, probability, successor);
+        self
+    }
+
+    /// Adds a rule for `predecessor` depending on its neighbors, see `LSystem::add_context_sensitive_rule`.
+    pub fn context_sensitive_rule(
+        mut self,
+        predecessor: impl Into<String>,
+        rule_cb: ContextSensitiveRuleCB,
+    ) -> Self {
+        self.lsystem
+            .add_context_sensitive_rule(predecessor, rule_cb);
+        self
+    }
+
+    /// Adds a rule for the modules of `predecessor` depending on their parameters, see `LSystem::add_parametic_production_rule`.
+    pub fn parametric_rule(
+        mut self,
+        predecessor: impl Into<String>,
+        rule_cb: ParametricRuleCB,
+    ) -> Self {
+        self.lsystem
+            .add_parametic_production_rule(predecessor, rule_cb);
+        self
+    }
+
+    /// Interprets `symbol` as `action` when running the lsystem, see `LSystem::add_action_rule`.
+    pub fn interpret(mut self, symbol: impl Into<String>, action: Action) -> Self {
+        self.lsystem.add_action_rule(symbol, action);
+        self
+    }
+
+    /// Sets the variable `name` used by the parameters of the actions.
+    pub fn variable(mut self, name: impl Into<String>, value: f32) -> Self {
+        self.lsystem.set_variable(name, value);
+        self
+    }
+
+    /// Sets the seed of the generator used by stochastic rules and random action parameters.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.lsystem.set_seed(seed);
+        self
+    }
+
+    /// Scales the branches by their depth when running, see `Taper`.
+    pub fn taper(mut self, taper: Taper) -> Self {
+        self.lsystem.set_taper(Some(taper));
+        self
+    }
+
+    /// Validates the lsystem and returns it if it has no errors.
+    ///
+    /// Fails on an empty axiom, invalid probabilities of stochastic rules
+    /// and grammar warnings that are errors, which are all reported at once.
+    pub fn build(self) -> Result<LSystem<A>, ValidationError> {
+        if self.lsystem.axiom.is_empty() {
+            return Err(ValidationError::EmptyAxiom);
+        }
+
+        let mut totals: Vec<(&str, f32)> = vec![];
+        for (predecessor, probability) in &self.probabilities {
+            if !(0.0..=1.0).contains(probability) {
+                return Err(ValidationError::InvalidProbability {
+                    predecessor: predecessor.clone(),
+                    probability: *probability,
+                });
+            }
+
+            match totals.iter_mut().find(|(other, _)| other == predecessor) {
+                Some((_, total)) => *total += probability,
+                None => totals.push((predecessor, *probability)),
+            }
+        }
+        if let Some((predecessor, total)) = totals
+            .into_iter()
+            .find(|(_, total)| *total > 1.0 + PROBABILITY_TOLERANCE)
+        {
+            return Err(ValidationError::ProbabilitiesExceedOne {
+                predecessor: predecessor.to_string(),
+                total,
+            });
+        }
+
+        let errors = self
+            .lsystem
+            .validate()
+            .into_iter()
+            .filter(GrammarWarning::is_error)
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return Err(ValidationError::Grammar(errors));
+        }
+
+        Ok(self.lsystem)
+    }
+}
+
+impl Default for LSystemBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
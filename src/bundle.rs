@@ -0,0 +1,347 @@
+//! Bundles of everything that makes up a procedural species, shareable as a single directory.
+//!
+//! A bundle directory has the following layout, every file but the script is optional:
+//!
+//! ```text
+//! script.ls          the lsystem script
+//! palette.txt        one `#rrggbb` color per line
+//! settings.txt       `key = value` lines of the `ExportSettings`
+//! leaves/<name>.obj  leaf meshes instanced by name
+//! preview.pam        RGBA preview image
+//! ```
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use macaw::Vec3;
+
+use crate::{
+    export::{
+        obj::ObjExporter,
+        raster::{Raster, RasterStyle},
+    },
+    ActionResolver, Color, CompiledLSystem, Geometry, Polygon, RuntimeError, Segment,
+};
+
+const SCRIPT_FILE: &str = "script.ls";
+const PALETTE_FILE: &str = "palette.txt";
+const SETTINGS_FILE: &str = "settings.txt";
+const LEAVES_DIR: &str = "leaves";
+const PREVIEW_FILE: &str = "preview.pam";
+
+/// How a species is exported, stored in the `settings.txt` of a bundle.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExportSettings {
+    /// Number of generations the script is generated for.
+    pub generations: u8,
+    /// Formats of the `ExporterRegistry` the species is exported to.
+    pub formats: Vec<String>,
+    /// Style of the preview image.
+    pub preview: RasterStyle,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            generations: 4,
+            formats: vec!["svg".to_string()],
+            preview: RasterStyle::default(),
+        }
+    }
+}
+
+/// A procedural species: its script, palette, leaf meshes, export settings and preview image.
+///
+/// Colors are stored without alpha, thus the palette and the preview background are loaded opaque.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeciesBundle {
+    pub script: String,
+    pub palette: Vec<Color>,
+    /// Leaf meshes keyed by the name they are instanced with.
+    pub leaves: BTreeMap<String, Geometry>,
+    pub settings: ExportSettings,
+    pub preview: Option<Raster>,
+}
+
+impl SpeciesBundle {
+    /// Creates a bundle of `script` with the default settings.
+    pub fn new(script: impl Into<String>) -> Self {
+        Self {
+            script: script.into(),
+            palette: Vec::new(),
+            leaves: BTreeMap::new(),
+            settings: ExportSettings::default(),
+            preview: None,
+        }
+    }
+
+    /// Lexes and parses the script of the bundle.
+    pub fn compile(&self) -> CompiledLSystem {
+        CompiledLSystem::compile(&self.script)
+    }
+
+    /// Generates and runs the script with `action_resolver` and renders the result as preview image.
+    pub fn render_preview(&mut self, action_resolver: &ActionResolver) -> Result<(), RuntimeError> {
+        let lsystem = self.compile().lsystem;
+        let alphabet = lsystem.generate(self.settings.generations);
+        let context = lsystem.run(action_resolver, &alphabet)?;
+        self.preview = Some(Raster::render(&context, &self.settings.preview));
+        Ok(())
+    }
+
+    /// Writes the bundle into the directory `path`, creating it if it does not exist.
+    ///
+    /// Fails with `io::ErrorKind::InvalidInput` before writing anything if a leaf name is empty,
+    /// `.` or `..`, or contains a path separator, thus leaves are never written outside `leaves/`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if let Some(name) = self.leaves.keys().find(|name| !is_valid_leaf_name(name)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid leaf name `{name}`"),
+            ));
+        }
+
+        let path = path.as_ref();
+        fs::create_dir_all(path)?;
+
+        fs::write(path.join(SCRIPT_FILE), &self.script)?;
+
+        let palette = self
+            .palette
+            .iter()
+            .map(|color| color.to_hex() + "\n")
+            .collect::<String>();
+        fs::write(path.join(PALETTE_FILE), palette)?;
+
+        fs::write(path.join(SETTINGS_FILE), write_settings(&self.settings))?;
+
+        if !self.leaves.is_empty() {
+            let leaves = path.join(LEAVES_DIR);
+            fs::create_dir_all(&leaves)?;
+            for (name, geometry) in &self.leaves {
+                let mut file = fs::File::create(leaves.join(format!("{name}.obj")))?;
+                ObjExporter.export_geometry(geometry, &mut file)?;
+            }
+        }
+
+        if let Some(preview) = &self.preview {
+            let mut file = fs::File::create(path.join(PREVIEW_FILE))?;
+            write_pam(preview, &mut file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the bundle from the directory `path`, missing optional files are left at their default.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut bundle = SpeciesBundle::new(fs::read_to_string(path.join(SCRIPT_FILE))?);
+
+        if let Some(palette) = read_optional(&path.join(PALETTE_FILE))? {
+            bundle.palette = palette
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| parse_color(line.trim()))
+                .collect::<io::Result<_>>()?;
+        }
+
+        if let Some(settings) = read_optional(&path.join(SETTINGS_FILE))? {
+            bundle.settings = parse_settings(&settings)?;
+        }
+
+        let leaves = path.join(LEAVES_DIR);
+        if leaves.is_dir() {
+            for entry in fs::read_dir(leaves)? {
+                let leaf = entry?.path();
+                if leaf.extension() != Some("obj".as_ref()) {
+                    continue;
+                }
+
+                let name = leaf.file_stem().unwrap_or_default().to_string_lossy();
+                let geometry = parse_obj(&fs::read_to_string(&leaf)?)?;
+                bundle.leaves.insert(name.into_owned(), geometry);
+            }
+        }
+
+        let preview = path.join(PREVIEW_FILE);
+        if preview.is_file() {
+            bundle.preview = Some(parse_pam(&fs::read(preview)?)?);
+        }
+
+        Ok(bundle)
+    }
+}
+
+/// Returns whether `name` is a single file name, e.g. not `../leaf`.
+fn is_valid_leaf_name(name: &str) -> bool {
+    !matches!(name, "" | "." | "..") && !name.contains(['/', '\\', std::path::MAIN_SEPARATOR])
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_optional(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn parse_color(hex: &str) -> io::Result<Color> {
+    Color::from_hex(hex).ok_or_else(|| invalid_data(format!("Invalid color `{hex}`")))
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> io::Result<T> {
+    value
+        .parse()
+        .map_err(|_| invalid_data(format!("Invalid value `{value}` of setting `{key}`")))
+}
+
+fn write_settings(settings: &ExportSettings) -> String {
+    let preview = &settings.preview;
+    format!(
+        "generations = {}\nformats = {}\nwidth = {}\nheight = {}\nbackground = {}\nline_width = {}\nmargin = {}\n",
+        settings.generations,
+        settings.formats.join(" "),
+        preview.width,
+        preview.height,
+        preview.background.to_hex(),
+        preview.line_width,
+        preview.margin
+    )
+}
+
+fn parse_settings(source: &str) -> io::Result<ExportSettings> {
+    let mut settings = ExportSettings::default();
+
+    for line in source.lines().filter(|line| !line.trim().is_empty()) {
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid_data(format!("Expected `key = value`, found `{line}`")))?;
+        let (key, value) = (key.trim(), value.trim());
+
+        match key {
+            "generations" => settings.generations = parse_number(key, value)?,
+            "formats" => settings.formats = value.split_whitespace().map(str::to_string).collect(),
+            "width" => settings.preview.width = parse_number(key, value)?,
+            "height" => settings.preview.height = parse_number(key, value)?,
+            "background" => settings.preview.background = parse_color(value)?,
+            "line_width" => settings.preview.line_width = parse_number(key, value)?,
+            "margin" => settings.preview.margin = parse_number(key, value)?,
+            _ => return Err(invalid_data(format!("Unknown setting `{key}`"))),
+        }
+    }
+
+    Ok(settings)
+}
+
+/// Reads the `v`, `l`, `f` and `usemtl` elements written by `ObjExporter`, texture coordinates and normals are skipped.
+fn parse_obj(source: &str) -> io::Result<Geometry> {
+    let mut vertices = Vec::new();
+    let mut geometry = Geometry::new();
+    let mut material = None;
+
+    for line in source.lines() {
+        let mut elements = line.split_whitespace();
+        let kind = elements.next();
+        // Faces refer to their vertex first, e.g. `f 1/1/1 2/2/2 3/3/3`.
+        let mut values = elements.map(|value| {
+            value
+                .split('/')
+                .next()
+                .unwrap_or_default()
+                .parse::<f32>()
+                .map_err(|_| invalid_data(format!("Invalid OBJ element `{line}`")))
+        });
+
+        match kind {
+            Some("usemtl") => material = line.split_whitespace().nth(1).map(str::to_string),
+            Some("v") => {
+                let mut coordinate = || values.next().unwrap_or(Ok(0.0));
+                vertices.push(Vec3::new(coordinate()?, coordinate()?, coordinate()?));
+            }
+            Some("l" | "f") => {
+                let points = values
+                    .map(|index| {
+                        // OBJ indices start at 1.
+                        let index = index? as usize;
+                        index
+                            .checked_sub(1)
+                            .and_then(|index| vertices.get(index).copied())
+                            .ok_or_else(|| invalid_data(format!("Invalid OBJ index in `{line}`")))
+                    })
+                    .collect::<io::Result<Vec<_>>>()?;
+
+                if kind == Some("l") {
+                    let segments = points.windows(2).map(|line| Segment {
+                        material: material.clone(),
+                        ..Segment::new(line[0], line[1])
+                    });
+                    geometry.segments.extend(segments);
+                } else {
+                    geometry.polygons.push(Polygon {
+                        material: material.clone(),
+                        ..Polygon::new(points)
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(geometry)
+}
+
+fn write_pam(raster: &Raster, writer: &mut impl Write) -> io::Result<()> {
+    write!(
+        writer,
+        "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+        raster.width, raster.height
+    )?;
+    writer.write_all(&raster.pixels)
+}
+
+fn parse_pam(bytes: &[u8]) -> io::Result<Raster> {
+    const END_OF_HEADER: &[u8] = b"ENDHDR\n";
+
+    let header_end = bytes
+        .windows(END_OF_HEADER.len())
+        .position(|window| window == END_OF_HEADER)
+        .ok_or_else(|| invalid_data("PAM header is not terminated".to_string()))?;
+    let header = String::from_utf8_lossy(&bytes[..header_end]);
+
+    let mut width = 0;
+    let mut height = 0;
+    for line in header.lines() {
+        match line.split_once(' ') {
+            Some(("WIDTH", value)) => width = parse_number("WIDTH", value)?,
+            Some(("HEIGHT", value)) => height = parse_number("HEIGHT", value)?,
+            Some(("DEPTH", depth)) if depth != "4" => {
+                return Err(invalid_data(format!("Unsupported PAM depth `{depth}`")))
+            }
+            _ => {}
+        }
+    }
+
+    let pixels = bytes[header_end + END_OF_HEADER.len()..].to_vec();
+    let size = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|size| size.checked_mul(4));
+    if size != Some(pixels.len()) {
+        return Err(invalid_data(format!(
+            "Expected {width}x{height} RGBA pixels, found {} bytes",
+            pixels.len()
+        )));
+    }
+
+    Ok(Raster {
+        width,
+        height,
+        pixels,
+    })
+}
@@ -0,0 +1,283 @@
+//! A bounding volume hierarchy over the segments of a geometry, see `Bvh`.
+//!
+//! Finds the segments hit by a ray or close to a point without testing every segment,
+//! e.g. to pick a branch in an editor or to query the occupied space around a turtle.
+use macaw::Vec3;
+
+use crate::{prelude::*, Geometry, Segment};
+
+/// Number of segments below which a node is not split any further.
+const LEAF_SIZE: usize = 4;
+
+/// The segment a ray hit, see `Bvh::raycast`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    /// Index of the segment in `Geometry::segments`.
+    pub segment: usize,
+    /// The distance along the ray to `point`, in lengths of the ray direction.
+    pub distance: f32,
+    /// The point of the ray closest to the segment.
+    pub point: Vec3,
+}
+
+/// A node of the hierarchy bounding the segments below it.
+#[derive(Clone, Debug)]
+struct Node {
+    min: Vec3,
+    max: Vec3,
+    kind: NodeKind,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum NodeKind {
+    /// The segments at `order[start..end]`.
+    Leaf { start: usize, end: usize },
+    /// The indices of the two child nodes.
+    Inner { left: usize, right: usize },
+}
+
+/// A bounding volume hierarchy over the segments of a `Geometry`, built with `Geometry::build_bvh`.
+///
+/// Segments are treated as capsules of `radius` around their line, thus a ray passing a segment
+/// closer than `radius` hits it. The hierarchy holds copies of the segments, it is not updated
+/// when the geometry changes. Polygons, meshes and instances are not part of it.
+#[derive(Clone, Debug)]
+pub struct Bvh {
+    segments: Vec<Segment>,
+    /// The indices of the segments, ordered such that the segments of a leaf are adjacent.
+    order: Vec<usize>,
+    nodes: Vec<Node>,
+    /// The distance from a segment within which rays hit it.
+    pub radius: f32,
+}
+
+impl Geometry {
+    /// Builds a `Bvh` over the segments, its `radius` is 1% of the diagonal of their bounds.
+    pub fn build_bvh(&self) -> Bvh {
+        Bvh::new(self.segments.clone())
+    }
+}
+
+impl Bvh {
+    /// Builds the hierarchy over `segments`, see `Geometry::build_bvh`.
+    pub fn new(segments: Vec<Segment>) -> Self {
+        let mut bvh = Self {
+            order: (0..segments.len()).collect(),
+            segments,
+            nodes: Vec::new(),
+            radius: 0.0,
+        };
+        if !bvh.segments.is_empty() {
+            bvh.build(0, bvh.segments.len());
+            bvh.radius = (bvh.nodes[0].max - bvh.nodes[0].min).length() * 0.01;
+        }
+        bvh
+    }
+
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the first segment hit by the ray starting at `origin` along `direction`, `None` if it hits none.
+    ///
+    /// The segment whose closest point to the ray comes first along the ray is hit,
+    /// segments behind `origin` are not.
+    pub fn raycast(&self, origin: Vec3, direction: Vec3) -> Option<RayHit> {
+        if self.nodes.is_empty() || direction == Vec3::ZERO {
+            return None;
+        }
+
+        let inverse = direction.recip();
+        let mut hit: Option<RayHit> = None;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let Some(entry) = self.enter(node, origin, inverse) else {
+                continue;
+            };
+            if hit.is_some_and(|hit| hit.distance < entry) {
+                continue;
+            }
+
+            match node.kind {
+                NodeKind::Leaf { start, end } => {
+                    for &segment in &self.order[start..end] {
+                        let (distance, point) =
+                            closest_on_ray(&self.segments[segment], origin, direction);
+                        let closer = hit.is_none_or(|hit| distance < hit.distance);
+                        if closer && self.segments[segment].distance_to(point) <= self.radius {
+                            hit = Some(RayHit {
+                                segment,
+                                distance,
+                                point,
+                            });
+                        }
+                    }
+                }
+                NodeKind::Inner { left, right } => stack.extend([right, left]),
+            }
+        }
+        hit
+    }
+
+    /// Returns the indices of the segments closer than `distance` to `point`, in ascending order.
+    pub fn segments_within(&self, point: Vec3, distance: f32) -> Vec<usize> {
+        let mut found = Vec::new();
+        self.visit_within(point, distance, |segment| {
+            found.push(segment);
+            false
+        });
+        found.sort_unstable();
+        found
+    }
+
+    /// Returns whether a segment is closer than `distance` to `point`, e.g. to test the space
+    /// a branch would grow into.
+    pub fn is_occupied(&self, point: Vec3, distance: f32) -> bool {
+        self.visit_within(point, distance, |_| true)
+    }
+
+    /// Calls `on_segment` with every segment closer than `distance` to `point` until it returns `true`,
+    /// returns whether it did.
+    fn visit_within(
+        &self,
+        point: Vec3,
+        distance: f32,
+        mut on_segment: impl FnMut(usize) -> bool,
+    ) -> bool {
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let closest = point.clamp(node.min, node.max);
+            if closest.distance(point) > distance {
+                continue;
+            }
+
+            match node.kind {
+                NodeKind::Leaf { start, end } => {
+                    for &segment in &self.order[start..end] {
+                        if self.segments[segment].distance_to(point) < distance
+                            && on_segment(segment)
+                        {
+                            return true;
+                        }
+                    }
+                }
+                NodeKind::Inner { left, right } => stack.extend([right, left]),
+            }
+        }
+        false
+    }
+
+    /// Adds the node over the segments at `order[start..end]` and its children, returns the index of the node.
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let (min, max) = self.order[start..end]
+            .iter()
+            .map(|&segment| &self.segments[segment])
+            .fold(
+                (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+                |(min, max), segment| {
+                    (
+                        min.min(segment.a.min(segment.b)),
+                        max.max(segment.a.max(segment.b)),
+                    )
+                },
+            );
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            min,
+            max,
+            kind: NodeKind::Leaf { start, end },
+        });
+        if end - start <= LEAF_SIZE {
+            return index;
+        }
+
+        // Split at the median center along the axis the centers spread the most.
+        let centers = self.order[start..end]
+            .iter()
+            .map(|&segment| (self.segments[segment].a + self.segments[segment].b) * 0.5);
+        let (low, high) = centers.fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(low, high), center| (low.min(center), high.max(center)),
+        );
+        let spread = high - low;
+        let axis = if spread.x >= spread.y && spread.x >= spread.z {
+            0
+        } else if spread.y >= spread.z {
+            1
+        } else {
+            2
+        };
+
+        let middle = (start + end) / 2;
+        let segments = &self.segments;
+        let center = |segment: &usize| segments[*segment].a[axis] + segments[*segment].b[axis];
+        self.order[start..end]
+            .select_nth_unstable_by(middle - start, |a, b| center(a).total_cmp(&center(b)));
+
+        let left = self.build(start, middle);
+        let right = self.build(middle, end);
+        self.nodes[index].kind = NodeKind::Inner { left, right };
+        index
+    }
+
+    /// Returns the distance along the ray at which it enters the bounds of `node` grown by `radius`,
+    /// `None` if it misses them.
+    fn enter(&self, node: &Node, origin: Vec3, inverse: Vec3) -> Option<f32> {
+        let low = (node.min - Vec3::splat(self.radius) - origin) * inverse;
+        let high = (node.max + Vec3::splat(self.radius) - origin) * inverse;
+        let near = low.min(high).max_element().max(0.0);
+        let far = low.max(high).min_element();
+        (near <= far).then_some(near)
+    }
+}
+
+/// Returns the distance along the ray and the point of the ray closest to `segment`,
+/// the ray starts at `origin` and goes along `direction`.
+fn closest_on_ray(segment: &Segment, origin: Vec3, direction: Vec3) -> (f32, Vec3) {
+    let line = segment.b - segment.a;
+    let r = origin - segment.a;
+    let a = direction.length_squared();
+    let e = line.length_squared();
+    let b = direction.dot(line);
+    let c = direction.dot(r);
+    let f = line.dot(r);
+
+    // The parameters of the closest points along the ray and the segment, see `Segment::distance_to_segment`.
+    let mut t = if e == 0.0 {
+        (-c / a).max(0.0)
+    } else {
+        let denominator = a * e - b * b;
+        let t = if denominator > 0.0 {
+            ((b * f - c * e) / denominator).max(0.0)
+        } else {
+            0.0
+        };
+        let s = (b * t + f) / e;
+        if s < 0.0 {
+            (-c / a).max(0.0)
+        } else if s > 1.0 {
+            ((b - c) / a).max(0.0)
+        } else {
+            t
+        }
+    };
+    if !t.is_finite() {
+        t = 0.0;
+    }
+    (t, origin + direction * t)
+}
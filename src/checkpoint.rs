@@ -0,0 +1,162 @@
+//! Checkpoints of a run that can be restored to continue running from them, see `ExecuteContext::save_state`.
+use macaw::Vec3;
+
+use crate::{prelude::*, turtles::ParkedTurtle, ExecuteContext, Turtle, TurtleTransformStack};
+
+/// The state of a run between two symbols, saved by `ExecuteContext::save_state`.
+///
+/// Restoring it with `ExecuteContext::restore_state` rewinds the context it was saved from
+/// and `LSystem::run_with_context` continues from it, e.g. to step through a run
+/// or to run the symbols after an edit again without running the symbols before it.
+///
+/// The state holds the turtles, their transform stacks and the position in the alphabet.
+/// The drawing is referred to by its length, restoring removes what was drawn after the checkpoint.
+/// The generator of the context and the states of the actions are not saved,
+/// restoring continues their sequences.
+/// Of the `RunStats` the symbols run and the collisions are rewound,
+/// the resolved actions and the durations count the work done and keep growing.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionState {
+    /// The turtles of the run, see `ExecuteContext::spawn_turtle`.
+    turtles: Vec<TurtleState>,
+    active_turtle: usize,
+    open_polygons: Vec<Vec<Vec3>>,
+    material: Option<String>,
+    polygons: usize,
+    elements: usize,
+    queries: usize,
+    commands: usize,
+    /// The number of hashed lines and the lines shared by the turtles if collisions are tested.
+    collision: Option<(usize, usize)>,
+    position: usize,
+    skipped_until: Option<usize>,
+    index: usize,
+    branch_depth: usize,
+    /// The `RunStats::symbols` and `RunStats::collisions` of the run.
+    symbols: usize,
+    collisions: usize,
+}
+
+/// The state of one turtle of a run.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct TurtleState {
+    turtle: Turtle,
+    stack: TurtleTransformStack,
+    recorded: Option<(Vec3, usize)>,
+    events: usize,
+    branches: Vec<(usize, usize)>,
+}
+
+impl ExecutionState {
+    /// Returns the index of the symbol the run continues with.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the selected turtle.
+    pub fn turtle(&self) -> &Turtle {
+        &self.turtles[self.active_turtle].turtle
+    }
+
+    /// Returns the number of transforms saved by the selected turtle.
+    pub fn stack_depth(&self) -> usize {
+        self.turtles[self.active_turtle].stack.len()
+    }
+}
+
+impl<E> ExecuteContext<E> {
+    /// Saves the state of the run, see `ExecutionState`.
+    pub fn save_state(&self) -> ExecutionState {
+        let turtles = (0..self.turtles.len())
+            .map(|index| {
+                let (turtle, stack, recorded, branches) = if index == self.active_turtle {
+                    let branches = self.collision.as_ref().map(|state| state.branches.clone());
+                    (
+                        self.turtle,
+                        &self.transform_stack,
+                        self.recorded,
+                        branches.unwrap_or_default(),
+                    )
+                } else {
+                    let slot = &self.turtles[index];
+                    (
+                        slot.turtle,
+                        &slot.transform_stack,
+                        slot.recorded,
+                        slot.branches.clone(),
+                    )
+                };
+                TurtleState {
+                    turtle,
+                    stack: stack.clone(),
+                    recorded,
+                    events: self.turtle_events(index).map_or(0, <[_]>::len),
+                    branches,
+                }
+            })
+            .collect();
+
+        ExecutionState {
+            turtles,
+            active_turtle: self.active_turtle,
+            open_polygons: self.open_polygons.clone(),
+            material: self.material.clone(),
+            polygons: self.polygons.len(),
+            elements: self.elements.len(),
+            queries: self.queries.len(),
+            commands: self.commands.len(),
+            collision: self
+                .collision
+                .as_ref()
+                .map(|state| (state.lines.len(), state.shared)),
+            position: self.position,
+            skipped_until: self.skipped_until,
+            index: self.index,
+            branch_depth: self.branch_depth,
+            symbols: self.stats.symbols,
+            collisions: self.stats.collisions,
+        }
+    }
+
+    /// Rewinds the run to `state` saved from this context, or a context that ran the same symbols,
+    /// removing the events, polygons, elements, queries and commands recorded after it.
+    pub fn restore_state(&mut self, state: &ExecutionState) {
+        // Park the selected turtle, thus every turtle is restored into its slot.
+        self.swap_turtle(self.active_turtle);
+        self.turtles.resize_with(state.turtles.len(), || {
+            ParkedTurtle::new(Turtle::new(), None)
+        });
+        for (slot, saved) in self.turtles.iter_mut().zip(&state.turtles) {
+            slot.turtle = saved.turtle;
+            slot.transform_stack = saved.stack.clone();
+            slot.recorded = saved.recorded;
+            slot.events.truncate(saved.events);
+            slot.branches = saved.branches.clone();
+        }
+        self.swap_turtle(state.active_turtle);
+        self.active_turtle = state.active_turtle;
+
+        self.open_polygons = state.open_polygons.clone();
+        self.material = state.material.clone();
+        self.polygons.truncate(state.polygons);
+        self.elements.truncate(state.elements);
+        self.queries.truncate(state.queries);
+        self.commands.truncate(state.commands);
+        if let (Some(collision), Some((lines, shared))) = (&mut self.collision, state.collision) {
+            collision.lines.truncate(lines);
+            collision.shared = shared;
+        }
+        self.recompute_bounds();
+
+        self.position = state.position;
+        self.skipped_until = state.skipped_until;
+        self.index = state.index;
+        self.branch_depth = state.branch_depth;
+        self.stats.symbols = state.symbols;
+        self.stats.collisions = state.collisions;
+        self.collided = false;
+        self.error = None;
+    }
+}
@@ -0,0 +1,182 @@
+//! Finds lines coming closer to each other than a tolerance,
+//! see `Geometry::self_intersections` and `RunOptions::collision`.
+use macaw::Vec3;
+
+// Only used if `std` is not linked, see `Float`.
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use crate::prelude::Float;
+use crate::{prelude::*, Segment};
+
+/// What happens to a branch whose next line enters occupied space, see `Collision`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CollisionResponse {
+    /// The line is not drawn and the rest of the branch is skipped, as after the cut symbol `%`.
+    #[default]
+    Terminate,
+    /// The lines drawn by the branch are removed as well, the branch is drawn entirely or not at all.
+    /// Lines outside of any branch are terminated instead.
+    Reject,
+}
+
+/// Keeps the lines drawn by the turtle out of the space occupied by the lines drawn before,
+/// e.g. for space filling plants whose branches do not grow into each other, see `RunOptions::collision`.
+///
+/// A line enters occupied space if it comes closer than `tolerance` to a line it is not connected to,
+/// lines sharing an end point are connected. Only the lines of the turtle are tested,
+/// polygons and the elements pushed by actions are not.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collision {
+    pub tolerance: f32,
+    pub response: CollisionResponse,
+}
+
+impl Collision {
+    pub fn new(tolerance: f32, response: CollisionResponse) -> Self {
+        Self {
+            tolerance,
+            response,
+        }
+    }
+}
+
+/// The smallest edge length of the cells, smaller cells would make long segments overlap too many of them.
+const MIN_CELL_SIZE: f32 = 1e-3;
+
+/// Segments sorted into the cells of a uniform grid, thus finding the segments close to another one
+/// only tests the segments of the cells around it.
+#[derive(Clone, Debug)]
+pub(crate) struct SpatialHash {
+    /// The edge length of the cells.
+    cell_size: f32,
+    /// The distance below which segments are close.
+    tolerance: f32,
+    segments: Vec<Segment>,
+    /// The indices of the segments overlapping a cell, in ascending order.
+    cells: HashMap<(i32, i32, i32), Vec<usize>>,
+}
+
+impl SpatialHash {
+    /// Creates an empty hash, cells around the length of the segments keep the number of cells a segment overlaps low.
+    ///
+    /// The cells are at least as large as `tolerance` and `MIN_CELL_SIZE`, e.g. if the turtle does not move.
+    pub(crate) fn new(tolerance: f32, cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(tolerance).max(MIN_CELL_SIZE),
+            // Crossing segments are a rounding error apart.
+            tolerance: tolerance.max(1e-6),
+            segments: vec![],
+            cells: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub(crate) fn insert(&mut self, segment: Segment) {
+        let index = self.segments.len();
+        for cell in self.cells_of(&segment, 0.0) {
+            self.cells.entry(cell).or_default().push(index);
+        }
+        self.segments.push(segment);
+    }
+
+    /// Removes the segments inserted after the first `len` ones.
+    pub(crate) fn truncate(&mut self, len: usize) {
+        while self.segments.len() > len {
+            let segment = self
+                .segments
+                .pop()
+                .expect("The hash has more than `len` segments.");
+            for cell in self.cells_of(&segment, 0.0) {
+                // The removed segment was inserted last, thus it is the last index of its cells.
+                if let Some(indices) = self.cells.get_mut(&cell) {
+                    indices.pop();
+                    if indices.is_empty() {
+                        self.cells.remove(&cell);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the indices of the inserted segments closer than the tolerance to `segment` that are not connected to it,
+    /// in ascending order.
+    pub(crate) fn close_to(&self, segment: &Segment) -> Vec<usize> {
+        let mut indices = self
+            .cells_of(segment, self.tolerance)
+            .iter()
+            .filter_map(|cell| self.cells.get(cell))
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices.dedup();
+        indices.retain(|&index| {
+            let other = &self.segments[index];
+            !connected(segment, other) && segment.distance_to_segment(other) <= self.tolerance
+        });
+        indices
+    }
+
+    /// Returns the cells overlapped by `segment` grown by `margin`, each once.
+    ///
+    /// The segment is walked in pieces no longer than a cell, thus a long diagonal segment
+    /// only visits the cells along it instead of all cells of its bounds.
+    fn cells_of(&self, segment: &Segment, margin: f32) -> Vec<(i32, i32, i32)> {
+        let pieces = ((segment.length() / self.cell_size).ceil() as u32).max(1);
+        let mut cells = vec![];
+        for piece in 0..pieces {
+            let along = |piece: u32| segment.a.lerp(segment.b, piece as f32 / pieces as f32);
+            let (a, b) = (along(piece), along(piece + 1));
+            let min = self.cell(a.min(b) - Vec3::splat(margin));
+            let max = self.cell(a.max(b) + Vec3::splat(margin));
+            for x in min.0..=max.0 {
+                for y in min.1..=max.1 {
+                    cells.extend((min.2..=max.2).map(|z| (x, y, z)));
+                }
+            }
+        }
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+
+    fn cell(&self, point: Vec3) -> (i32, i32, i32) {
+        let cell = point / self.cell_size;
+        (
+            cell.x.floor() as i32,
+            cell.y.floor() as i32,
+            cell.z.floor() as i32,
+        )
+    }
+}
+
+/// Returns whether the segments share an end point, e.g. consecutive lines or the lines starting a branch.
+fn connected(a: &Segment, b: &Segment) -> bool {
+    a.a == b.a || a.a == b.b || a.b == b.a || a.b == b.b
+}
+
+/// The lines checked for collisions while running, see `ExecuteContext::record`.
+pub(crate) struct CollisionState {
+    pub(crate) collision: Collision,
+    pub(crate) lines: SpatialHash,
+    /// The number of events and hashed lines when the open branches started, the innermost last.
+    pub(crate) branches: Vec<(usize, usize)>,
+    /// The number of lines hashed when the selected turtle was selected, see `ExecuteContext::select_turtle`.
+    /// Other turtles may have drawn them, thus only branches opened after them are rejected.
+    pub(crate) shared: usize,
+}
+
+impl CollisionState {
+    /// Creates the state for a run whose turtle moves `step` per unit.
+    pub(crate) fn new(collision: Collision, step: f32) -> Self {
+        Self {
+            collision,
+            lines: SpatialHash::new(collision.tolerance, step.abs()),
+            branches: vec![],
+            shared: 0,
+        }
+    }
+}
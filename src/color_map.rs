@@ -0,0 +1,197 @@
+//! Coloring geometry by the depth or the path length of its elements, see `ColorMap`.
+use macaw::Vec3;
+
+use crate::{prelude::*, Color, Geometry, Geometry2D};
+
+/// The value of an element a `ColorMap` picks its color by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorKey {
+    /// The branch depth the element was drawn at, from the trunk at depth `0` to the deepest branch.
+    #[default]
+    Depth,
+    /// The length of the lines drawn from the start of the drawing to the element, from the root to the farthest tip.
+    ///
+    /// Lines continue the path of the line ending where they start, elements that are not lines
+    /// take the path length of the line ending at their first point, the farthest one if there is none.
+    PathLength,
+}
+
+/// A color gradient over the depth or the path length of the drawn elements, e.g. from a brown trunk to green leaves.
+///
+/// The positions of the stops go from `0.0` at the trunk to `1.0` at the deepest or farthest element of the geometry.
+/// Applied to the run with `RunOptions::color_map` or afterwards with `Geometry::apply_color_map`,
+/// it replaces the colors of the segments, polygons, meshes and instances.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColorMap {
+    pub key: ColorKey,
+    /// The colors at positions in `0.0..=1.0`, sorted by position.
+    pub stops: Vec<(f32, Color)>,
+}
+
+impl ColorMap {
+    /// Creates a map without stops, it keeps the colors until stops are added with `with_stop`.
+    pub fn new(key: ColorKey) -> Self {
+        Self { key, stops: vec![] }
+    }
+
+    /// Creates a map fading from `from` at the trunk to `to` at the tips.
+    pub fn gradient(key: ColorKey, from: Color, to: Color) -> Self {
+        Self::new(key).with_stop(0.0, from).with_stop(1.0, to)
+    }
+
+    /// Creates a map from a brown trunk to green leaves, a reasonable default for plants.
+    pub fn plant(key: ColorKey) -> Self {
+        Self::new(key)
+            .with_stop(0.0, Color::rgb(0.36, 0.25, 0.13))
+            .with_stop(0.5, Color::rgb(0.42, 0.47, 0.18))
+            .with_stop(1.0, Color::rgb(0.3, 0.65, 0.2))
+    }
+
+    /// Adds the stop `color` at `position`, keeping the stops sorted.
+    pub fn with_stop(mut self, position: f32, color: Color) -> Self {
+        let index = self.stops.partition_point(|(at, _)| *at <= position);
+        self.stops.insert(index, (position, color));
+        self
+    }
+
+    /// Returns the color at `position` interpolated between the surrounding stops,
+    /// the first or last stop outside of them and `None` if the map has no stops.
+    pub fn color_at(&self, position: f32) -> Option<Color> {
+        let index = self.stops.partition_point(|(at, _)| *at <= position);
+        let &(to_position, to) = match self.stops.get(index) {
+            Some(stop) if index > 0 => stop,
+            Some(&(_, first)) => return Some(first),
+            None => return self.stops.last().map(|&(_, last)| last),
+        };
+        let (from_position, from) = self.stops[index - 1];
+        let t = (position - from_position) / (to_position - from_position);
+        Some(from.lerp(to, t))
+    }
+
+    /// Returns the color of an element at `value` of the key, where `max` is the value of the deepest or farthest element.
+    fn color_of(&self, value: f32, max: f32) -> Option<Color> {
+        self.color_at(if max > 0.0 { value / max } else { 0.0 })
+    }
+}
+
+/// The path length at the end of every line, see `ColorKey::PathLength`.
+struct PathLengths {
+    ends: HashMap<[u32; 3], f32>,
+    max: f32,
+}
+
+impl PathLengths {
+    /// Follows the lines in the order they were drawn and returns the path length at the middle of every line.
+    fn measure(lines: impl Iterator<Item = (Vec3, Vec3)>) -> (Self, Vec<f32>) {
+        let mut lengths = Self {
+            ends: HashMap::new(),
+            max: 0.0,
+        };
+        let middles = lines
+            .map(|(a, b)| {
+                let start = lengths.at(a).unwrap_or(0.0);
+                let end = start + a.distance(b);
+                lengths.ends.insert(Self::key(b), end);
+                lengths.max = lengths.max.max(end);
+                (start + end) * 0.5
+            })
+            .collect();
+        (lengths, middles)
+    }
+
+    fn at(&self, point: Vec3) -> Option<f32> {
+        self.ends.get(&Self::key(point)).copied()
+    }
+
+    /// Returns the path length of an element starting at `point`, the longest path if no line ends there.
+    fn at_or_max(&self, point: Option<Vec3>) -> f32 {
+        point.and_then(|point| self.at(point)).unwrap_or(self.max)
+    }
+
+    fn key(point: Vec3) -> [u32; 3] {
+        point.to_array().map(f32::to_bits)
+    }
+}
+
+impl Geometry {
+    /// Replaces the colors of the segments, polygons, meshes and instances with the colors `map` picks for them,
+    /// the colors of meshes with colored positions are all replaced.
+    ///
+    /// Keeps the colors if the map has no stops.
+    pub fn apply_color_map(&mut self, map: &ColorMap) {
+        if map.stops.is_empty() {
+            return;
+        }
+
+        let (values, max) = match map.key {
+            ColorKey::Depth => {
+                let depths = self
+                    .segments
+                    .iter()
+                    .map(|segment| segment.depth)
+                    .chain(self.polygons.iter().map(|polygon| polygon.depth))
+                    .chain(self.meshes.iter().map(|mesh| mesh.depth))
+                    .chain(self.instances.iter().map(|instance| instance.depth))
+                    .map(|depth| depth as f32)
+                    .collect::<Vec<_>>();
+                let max = depths.iter().copied().fold(0.0, f32::max);
+                (depths, max)
+            }
+            ColorKey::PathLength => {
+                let (lengths, mut values) = PathLengths::measure(
+                    self.segments.iter().map(|segment| (segment.a, segment.b)),
+                );
+                values.extend(
+                    self.polygons
+                        .iter()
+                        .map(|polygon| lengths.at_or_max(polygon.vertices.first().copied())),
+                );
+                values.extend(
+                    self.meshes
+                        .iter()
+                        .map(|mesh| lengths.at_or_max(mesh.positions.first().copied())),
+                );
+                values.extend(
+                    self.instances
+                        .iter()
+                        .map(|instance| lengths.at_or_max(Some(instance.position()))),
+                );
+                (values, lengths.max)
+            }
+        };
+
+        let mut colors = values
+            .into_iter()
+            .map(|value| map.color_of(value, max).expect("The map has stops"));
+        for segment in &mut self.segments {
+            segment.color = colors.next().expect("Every element has a value");
+        }
+        for polygon in &mut self.polygons {
+            polygon.color = colors.next().expect("Every element has a value");
+        }
+        for mesh in &mut self.meshes {
+            mesh.color = colors.next().expect("Every element has a value");
+            mesh.colors.fill(mesh.color);
+        }
+        for instance in &mut self.instances {
+            instance.color = colors.next().expect("Every element has a value");
+        }
+    }
+}
+
+impl Geometry2D {
+    /// Replaces the colors of the segments and polygons with the colors `map` picks for them,
+    /// see `Geometry::apply_color_map`.
+    pub fn apply_color_map(&mut self, map: &ColorMap) {
+        let mut geometry = self.to_geometry();
+        geometry.apply_color_map(map);
+        for (segment, mapped) in self.segments.iter_mut().zip(&geometry.segments) {
+            segment.color = mapped.color;
+        }
+        for (polygon, mapped) in self.polygons.iter_mut().zip(&geometry.polygons) {
+            polygon.color = mapped.color;
+        }
+    }
+}
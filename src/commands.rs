@@ -0,0 +1,181 @@
+//! Plain text turtle commands, e.g. `F1 +45 [ -30 F1 ] F1`, to exchange drawings with other turtle graphics tools.
+use core::{fmt, str::FromStr};
+
+use crate::{prelude::*, ExecuteContext, RunOptions, RuntimeError};
+
+/// A single turtle command with its resolved arguments, e.g. `+45` turning left by 45 degrees.
+///
+/// The commands of the default actions are written with the conventional turtle symbols,
+/// independent of the symbol that triggered them:
+/// `F` draws and `f` moves forward, `+ -` turn left and right, `^ &` pitch up and down,
+/// `\ /` roll left and right, `|` turns around, `[ ]` push and pop the turtle, `{ . }` build polygons,
+/// `'` selects the next color or the color at its argument, `!` multiplies and `#` sets the width.
+/// Angles are written in degrees.
+///
+/// Any other action is written with the symbol it was triggered by and is skipped by `replay`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TurtleCommand {
+    pub symbol: char,
+    pub args: Vec<f32>,
+}
+
+impl TurtleCommand {
+    pub fn new(symbol: char, args: Vec<f32>) -> Self {
+        Self { symbol, args }
+    }
+
+    /// Creates the command of the action `name` triggered by `symbol` with the resolved `args`.
+    pub fn from_action(symbol: char, name: &str, args: Vec<f32>) -> Self {
+        let first = args.first().copied().unwrap_or(0.0);
+        // The rotation actions take signed radians, the sign selects the command.
+        let rotation = |positive: char, negative: char, angle: f32| {
+            let symbol = if angle < 0.0 { negative } else { positive };
+            Self::new(symbol, vec![angle.abs().to_degrees()])
+        };
+
+        match name {
+            "DrawForwardAction" => Self::new('F', args),
+            "MoveForwardAction" => Self::new('f', args),
+            "RotateZAction" => rotation('+', '-', first),
+            "RotateXAction" => rotation('^', '&', first),
+            "PitchUpAction" => rotation('^', '&', first),
+            "PitchDownAction" => rotation('^', '&', -first),
+            "RotateYAction" => rotation('\\', '/', first),
+            "RollLeftAction" => rotation('\\', '/', first),
+            "RollRightAction" => rotation('\\', '/', -first),
+            "TurnAroundAction" => Self::new('|', vec![]),
+            "PushTranformToStackAction" => Self::new('[', vec![]),
+            "PopTransformFromStackAction" => Self::new(']', vec![]),
+            "BeginPolygonAction" => Self::new('{', vec![]),
+            "AddVertexAction" => Self::new('.', vec![]),
+            "EndPolygonAction" => Self::new('}', vec![]),
+            "IncColorAction" | "SetColorAction" => Self::new('\'', args),
+            "MultiplyWidthAction" => Self::new('!', args),
+            "SetWidthAction" => Self::new('#', args),
+            _ => Self::new(symbol, args),
+        }
+    }
+
+    /// Applies the command to the turtle of `context`, commands of unknown symbols do nothing.
+    ///
+    /// A `]` without matching `[` fails the context, see `ExecuteContext::fail`.
+    pub fn apply<E>(&self, context: &mut ExecuteContext<E>) {
+        let arg = |default: f32| self.args.first().copied().unwrap_or(default);
+        let angle = || arg(0.0).to_radians();
+
+        match self.symbol {
+            'F' => context.draw_forward(arg(1.0)),
+            'f' => context.move_forward(arg(1.0)),
+            '+' => context.turtle.rotate_z(angle()),
+            '-' => context.turtle.rotate_z(-angle()),
+            '^' => context.turtle.rotate_x(angle()),
+            '&' => context.turtle.rotate_x(-angle()),
+            '\\' => context.turtle.rotate_y(angle()),
+            '/' => context.turtle.rotate_y(-angle()),
+            '|' => context.turtle.rotate_z(core::f32::consts::PI),
+            '[' => context.push(context.turtle),
+            ']' => match context.pop() {
+                Ok(turtle) => context.turtle = turtle,
+                Err(error) => context.fail(error),
+            },
+            '{' => context.begin_polygon(),
+            '.' => context.add_vertex(),
+            '}' => context.end_polygon(),
+            '\'' => match self.args.first() {
+                Some(index) => context.set_color_index(*index as usize),
+                None => context.increment_color(),
+            },
+            '!' => {
+                let width = context.turtle.width() * arg(1.0);
+                context.turtle.set_width(width);
+            }
+            '#' => context.turtle.set_width(arg(1.0)),
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for TurtleCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol)?;
+        for (index, arg) in self.args.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            // The shortest representation reading back as the same `f32`, thus replaying loses no precision.
+            write!(f, "{arg}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TurtleCommand {
+    type Err = CommandParseError;
+
+    /// Parses a single command, a symbol followed by comma separated arguments, e.g. `F` or `+45`.
+    fn from_str(command: &str) -> Result<Self, Self::Err> {
+        let invalid = || CommandParseError {
+            command: command.to_string(),
+        };
+
+        let mut chars = command.chars();
+        let symbol = chars.next().ok_or_else(invalid)?;
+        let args = chars.as_str();
+        if args.is_empty() {
+            return Ok(Self::new(symbol, vec![]));
+        }
+
+        args.split(',')
+            .map(|arg| arg.parse::<f32>().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()
+            .map(|args| Self::new(symbol, args))
+    }
+}
+
+/// A command of a turtle command text whose arguments are no numbers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandParseError {
+    pub command: String,
+}
+
+impl fmt::Display for CommandParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` is not a valid turtle command", self.command)
+    }
+}
+
+impl core::error::Error for CommandParseError {}
+
+/// Writes `commands` separated by spaces.
+pub fn format_commands(commands: &[TurtleCommand]) -> String {
+    commands
+        .iter()
+        .map(TurtleCommand::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parses the whitespace separated commands of `text`.
+pub fn parse_commands(text: &str) -> Result<Vec<TurtleCommand>, CommandParseError> {
+    text.split_whitespace().map(str::parse).collect()
+}
+
+/// Draws `commands` with a turtle starting with the pose and pen from `options`, without an lsystem or its rules.
+pub fn replay(
+    commands: &[TurtleCommand],
+    options: &RunOptions,
+) -> Result<ExecuteContext, RuntimeError> {
+    let mut context = ExecuteContext::with_options(options);
+    context.record();
+
+    for (index, command) in commands.iter().enumerate() {
+        context.index = index;
+        command.apply(&mut context);
+        if let Some(error) = context.error.take() {
+            return Err(error);
+        }
+        context.record();
+    }
+
+    Ok(context)
+}
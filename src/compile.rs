@@ -0,0 +1,235 @@
+use alloc::{collections::VecDeque, sync::Arc};
+use core::{hash::Hasher, time::Duration};
+#[cfg(feature = "std")]
+use std::{
+    cell::Cell,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::Once,
+};
+
+use crate::{
+    prelude::*, try_parse, Item, LSystem, LSystemParser, LexedTokens, Lexer, ParseError, Stopwatch,
+};
+
+/// A script that has been lexed, parsed and turned into an L-system.
+pub struct CompiledLSystem {
+    /// Trivia insensitive hash of the source, see `source_hash`.
+    pub hash: u64,
+    pub item: Item,
+    pub lsystem: LSystem,
+    /// Time spent lexing and parsing the script, see the `timing` feature.
+    pub compile_time: Duration,
+}
+
+impl CompiledLSystem {
+    /// Lexes and parses the script `source`.
+    pub fn compile(source: &str) -> Self {
+        Self::compile_tokens(source).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Same as `compile` but returns an error instead of panicking if the script is invalid.
+    ///
+    /// Apart from `ParseError`s the lexer and `LSystemParser` report errors by panicking, the panic is caught
+    /// and returned as `CompileError::Parse` without being printed, see `catch_compile_panic`.
+    /// Requires `std` to catch the panic.
+    #[cfg(feature = "std")]
+    pub fn try_compile(source: &str) -> Result<Self, CompileError> {
+        catch_compile_panic(|| Self::compile_tokens(source))
+            .map_err(|payload| {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "invalid script".to_string());
+                CompileError::Parse(message)
+            })?
+            .map_err(|error| CompileError::Parse(error.to_string()))
+    }
+
+    fn compile_tokens(source: &str) -> Result<Self, ParseError> {
+        let stopwatch = Stopwatch::start();
+        let lex = Lexer::new().lex(source.to_string());
+        let item = try_parse(LexedTokens::new(lex))?;
+        let lsystem = LSystemParser::parse(item.clone());
+
+        Ok(CompiledLSystem {
+            hash: source_hash(source),
+            item,
+            lsystem,
+            compile_time: stopwatch.elapsed(),
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// Whether the thread is inside `catch_compile_panic`.
+    static COMPILING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Catches the panics of the lexer and parser without printing them.
+///
+/// The panic hook is wrapped once to skip the panics raised while compiling,
+/// any other panic is passed to the previous hook. Replacing the hook later prints compile panics again.
+#[cfg(feature = "std")]
+fn catch_compile_panic<T>(compile: impl FnOnce() -> T) -> std::thread::Result<T> {
+    static WRAP_HOOK: Once = Once::new();
+    WRAP_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !COMPILING.with(Cell::get) {
+                previous(info);
+            }
+        }));
+    });
+
+    COMPILING.with(|compiling| compiling.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(compile));
+    COMPILING.with(|compiling| compiling.set(false));
+    result
+}
+
+/// A script that could not be compiled.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompileError {
+    /// The script file at `path` could not be read.
+    Read { path: PathBuf, message: String },
+    /// The script is invalid, the message is the error of the lexer or parser.
+    Parse(String),
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Read { path, message } => {
+                write!(f, "could not read `{}`: {message}", path.display())
+            }
+            CompileError::Parse(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::error::Error for CompileError {}
+
+/// Hashes the script `source` while ignoring trivia.
+///
+/// Whitespace only changes the meaning of a script when it separates two identifiers or numbers,
+/// thus `replace F by F+F;` and `replace  F by F + F;` have the same hash.
+pub fn source_hash(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for c in strip_trivia(source).chars() {
+        hasher.write_u32(c as u32);
+    }
+    hasher.finish()
+}
+
+/// Returns `source` without the whitespace that does not separate two identifiers or numbers,
+/// scripts with the same stripped source have the same meaning, see `source_hash`.
+fn strip_trivia(source: &str) -> String {
+    let is_word = |c: char| c.is_alphanumeric() || c == '.' || c == '_';
+
+    let mut stripped = String::with_capacity(source.len());
+    let mut previous = None;
+    let mut separated = false;
+
+    for c in source.chars() {
+        if c.is_whitespace() {
+            separated = true;
+            continue;
+        }
+
+        if separated && previous.is_some_and(is_word) && is_word(c) {
+            stripped.push(' ');
+        }
+
+        stripped.push(c);
+        previous = Some(c);
+        separated = false;
+    }
+
+    stripped
+}
+
+/// The default number of scripts kept by a `CompileCache`.
+pub const DEFAULT_COMPILE_CACHE_CAPACITY: usize = 32;
+
+/// In-process cache of compiled scripts keyed by `source_hash`.
+///
+/// Hosts that recompile on every keystroke skip lexing and parsing when the script did not effectively change.
+/// Entries keep the source without trivia, thus a script whose hash collides with a cached one is compiled
+/// and replaces the cached one. Once full, the script that was compiled first is evicted.
+pub struct CompileCache {
+    /// The compiled scripts with their source without trivia.
+    pub(crate) entries: HashMap<u64, (String, Arc<CompiledLSystem>)>,
+    /// Hashes in the order they were inserted, used for eviction.
+    order: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl CompileCache {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_COMPILE_CACHE_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Returns the compiled script for `source`, compiling it only if it is not cached yet.
+    pub fn get_or_compile(&mut self, source: &str) -> Arc<CompiledLSystem> {
+        let stripped = strip_trivia(source);
+        let hash = source_hash(source);
+
+        match self.entries.get(&hash) {
+            Some((cached, compiled)) if *cached == stripped => return compiled.clone(),
+            // A different script with the same hash, it is replaced below.
+            Some(_) => {}
+            None => {
+                if self.entries.len() >= self.capacity {
+                    if let Some(oldest) = self.order.pop_front() {
+                        self.entries.remove(&oldest);
+                    }
+                }
+                self.order.push_back(hash);
+            }
+        }
+
+        let compiled = Arc::new(CompiledLSystem::compile(source));
+        self.entries.insert(hash, (stripped, compiled.clone()));
+        compiled
+    }
+
+    pub fn contains(&self, source: &str) -> bool {
+        self.entries
+            .get(&source_hash(source))
+            .is_some_and(|(cached, _)| *cached == strip_trivia(source))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+impl Default for CompileCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
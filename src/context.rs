@@ -0,0 +1,212 @@
+//! Bracket aware symbol neighborhoods used for context sensitive matching.
+//!
+//! The context of a symbol skips over branches, thus in `A[B]C` the right context of `A` is `C`
+//! and the left context of `B` and `C` is `A`. A branch ends the right context at its closing `]`.
+//! Parameter lists like `(1,2)` belong to the symbol before them and are never part of a context.
+use crate::{prelude::*, Alphabet, Symbol};
+
+/// A symbol together with its left and right neighborhood, see `Alphabet::iter_with_context`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolContext<'a> {
+    /// Index of the symbol in the alphabet.
+    pub index: usize,
+    pub symbol: &'a Symbol,
+    /// Up to `left_n` symbols left of the symbol, in alphabet order.
+    pub left: Vec<&'a Symbol>,
+    /// Up to `right_n` symbols right of the symbol, in alphabet order.
+    pub right: Vec<&'a Symbol>,
+}
+
+/// Iterator over the symbols of an alphabet with their context, see `Alphabet::iter_with_context`.
+pub struct ContextIter<'a> {
+    symbols: &'a [Symbol],
+    index: usize,
+    left_n: usize,
+    right_n: usize,
+}
+
+impl<'a> ContextIter<'a> {
+    pub(crate) fn new(alphabet: &'a Alphabet, left_n: usize, right_n: usize) -> Self {
+        Self {
+            symbols: &alphabet.symbols,
+            index: 0,
+            left_n,
+            right_n,
+        }
+    }
+}
+
+impl<'a> Iterator for ContextIter<'a> {
+    type Item = SymbolContext<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let symbol = self.symbols.get(self.index)?;
+        let index = self.index;
+        self.index += 1;
+
+        let resolve = |indices: Vec<usize>| indices.into_iter().map(|i| &self.symbols[i]).collect();
+
+        Some(SymbolContext {
+            index,
+            symbol,
+            left: resolve(left_context_indices(
+                self.symbols,
+                index,
+                self.left_n,
+                Symbol::char,
+            )),
+            right: resolve(right_context_indices(
+                self.symbols,
+                index,
+                self.right_n,
+                Symbol::char,
+            )),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.symbols.len() - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+/// Returns up to `n` chars left of `index` in `chars`, see the module documentation.
+///
+/// Use this from a `ContextSensitiveRuleCB` to match the left context of a symbol.
+pub fn left_context(chars: &[char], index: usize, n: usize) -> Vec<char> {
+    left_context_indices(chars, index, n, |c| *c)
+        .into_iter()
+        .map(|i| chars[i])
+        .collect()
+}
+
+/// Returns up to `n` chars right of `index` in `chars`, see the module documentation.
+///
+/// Use this from a `ContextSensitiveRuleCB` to match the right context of a symbol.
+pub fn right_context(chars: &[char], index: usize, n: usize) -> Vec<char> {
+    right_context_indices(chars, index, n, |c| *c)
+        .into_iter()
+        .map(|i| chars[i])
+        .collect()
+}
+
+/// Returns the indices of up to `n` symbols left of `index`, in order.
+pub fn left_context_indices<T>(
+    symbols: &[T],
+    index: usize,
+    n: usize,
+    char_of: impl Fn(&T) -> char,
+) -> Vec<usize> {
+    let mut indices = Vec::with_capacity(n);
+    let mut cursor = index.min(symbols.len());
+
+    while indices.len() < n && cursor > 0 {
+        cursor -= 1;
+
+        match char_of(&symbols[cursor]) {
+            // Skip the branch that ends here, the symbols before it are the context.
+            ']' => match matching_open(symbols, cursor, '[', ']', &char_of) {
+                Some(open) => cursor = open,
+                None => break,
+            },
+            // The start of the branch the symbol is in, the context continues in the parent branch.
+            '[' => {}
+            // The symbol before a parameter list is the context symbol.
+            ')' => match matching_open(symbols, cursor, '(', ')', &char_of) {
+                Some(open) if open > 0 => {
+                    cursor = open - 1;
+                    indices.push(cursor);
+                }
+                _ => break,
+            },
+            _ => indices.push(cursor),
+        }
+    }
+
+    indices.reverse();
+    indices
+}
+
+/// Returns the indices of up to `n` symbols right of `index`, in order.
+pub fn right_context_indices<T>(
+    symbols: &[T],
+    index: usize,
+    n: usize,
+    char_of: impl Fn(&T) -> char,
+) -> Vec<usize> {
+    let mut indices = Vec::with_capacity(n);
+    let mut cursor = skip_params(symbols, index + 1, &char_of);
+
+    while indices.len() < n && cursor < symbols.len() {
+        match char_of(&symbols[cursor]) {
+            // Skip the branch that starts here, the symbols after it are the context.
+            '[' => match matching_close(symbols, cursor, '[', ']', &char_of) {
+                Some(close) => cursor = close + 1,
+                None => break,
+            },
+            // The end of the branch the symbol is in ends its right context.
+            ']' => break,
+            _ => {
+                indices.push(cursor);
+                cursor = skip_params(symbols, cursor + 1, &char_of);
+            }
+        }
+    }
+
+    indices
+}
+
+/// Returns the index after the parameter list starting at `index`, or `index` if there is none.
+fn skip_params<T>(symbols: &[T], index: usize, char_of: &impl Fn(&T) -> char) -> usize {
+    match symbols.get(index).map(char_of) {
+        Some('(') => matching_close(symbols, index, '(', ')', char_of)
+            .map_or(symbols.len(), |close| close + 1),
+        _ => index,
+    }
+}
+
+fn matching_close<T>(
+    symbols: &[T],
+    open_index: usize,
+    open: char,
+    close: char,
+    char_of: &impl Fn(&T) -> char,
+) -> Option<usize> {
+    let mut depth = 0;
+    for (index, symbol) in symbols.iter().enumerate().skip(open_index) {
+        match char_of(symbol) {
+            c if c == open => depth += 1,
+            c if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn matching_open<T>(
+    symbols: &[T],
+    close_index: usize,
+    open: char,
+    close: char,
+    char_of: &impl Fn(&T) -> char,
+) -> Option<usize> {
+    let mut depth = 0;
+    for index in (0..=close_index).rev() {
+        match char_of(&symbols[index]) {
+            c if c == close => depth += 1,
+            c if c == open => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
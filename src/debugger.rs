@@ -0,0 +1,167 @@
+//! Stepping through the rewriting of an lsystem one rule application at a time, see `Debugger`.
+
+use crate::{
+    left_context, prelude::*, right_context, seeded_rng, DefaultAlphabetSymbolDefiner, DefaultRng,
+    LSystem, RuleKind, SymbolDefiner,
+};
+
+/// Number of symbols left and right of a rewritten symbol in its `DebugStep` by default.
+const DEFAULT_CONTEXT_SIZE: usize = 3;
+
+/// The rewrite of one symbol of a generation, see `Debugger::step`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DebugStep {
+    /// The generation the symbol is part of, its successor is part of the next generation.
+    pub generation: u8,
+    /// Index of the symbol in its generation.
+    pub index: usize,
+    /// The rewritten symbol with its parameters, e.g. `A(1,2)`.
+    pub predecessor: String,
+    /// The symbols written for the predecessor, the predecessor itself if no rule fired.
+    pub successor: String,
+    /// The kind of the rule that fired, `None` if the symbol was copied unchanged.
+    pub rule: Option<RuleKind>,
+    /// The index of the stochastic rule that fired among the stochastic rules of the symbol, in the order they were added.
+    pub stochastic: Option<usize>,
+    /// The symbols left of the symbol a context sensitive rule sees, skipping branches, see `left_context`.
+    pub left_context: Vec<char>,
+    /// The symbols right of the symbol a context sensitive rule sees, skipping branches, see `right_context`.
+    pub right_context: Vec<char>,
+}
+
+/// Rewrites an lsystem one symbol at a time, e.g. to find out why a context sensitive
+/// or stochastic grammar misbehaves.
+///
+/// Every step applies the rule `LSystem::generate` would apply to the next symbol and reports it as a `DebugStep`,
+/// stochastic rules are picked with the seed of the lsystem, thus the debugger derives the same generations.
+/// Interpretation rules are not applied.
+pub struct Debugger<'a, A: SymbolDefiner = DefaultAlphabetSymbolDefiner> {
+    lsystem: &'a LSystem<A>,
+    generation: u8,
+    current: Vec<char>,
+    /// The symbols of the next generation written so far.
+    next: Vec<char>,
+    /// Index in `current` of the next symbol to rewrite.
+    index: usize,
+    rng: DefaultRng,
+    context_size: usize,
+}
+
+impl<A: SymbolDefiner> LSystem<A> {
+    /// Returns a `Debugger` starting at the first symbol of the axiom.
+    pub fn debugger(&self) -> Debugger<'_, A> {
+        Debugger::new(self)
+    }
+}
+
+impl<'a, A: SymbolDefiner> Debugger<'a, A> {
+    pub fn new(lsystem: &'a LSystem<A>) -> Self {
+        Self {
+            lsystem,
+            generation: 0,
+            current: lsystem.axiom.chars().collect(),
+            next: Vec::new(),
+            index: 0,
+            rng: seeded_rng(lsystem.seed()),
+            context_size: DEFAULT_CONTEXT_SIZE,
+        }
+    }
+
+    /// Reports up to `size` symbols left and right of every rewritten symbol.
+    pub fn with_context_size(mut self, size: usize) -> Self {
+        self.context_size = size;
+        self
+    }
+
+    /// Returns the generation that is being rewritten, `0` for the axiom.
+    pub fn generation(&self) -> u8 {
+        self.generation
+    }
+
+    /// Returns the index in the current generation of the symbol the next step rewrites.
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the generation that is being rewritten.
+    pub fn current(&self) -> String {
+        String::from_iter(&self.current)
+    }
+
+    /// Returns the string as it is now, the symbols written for the rewritten symbols
+    /// followed by the symbols of the current generation that are not rewritten yet.
+    pub fn string(&self) -> String {
+        self.next
+            .iter()
+            .chain(&self.current[self.index..])
+            .collect()
+    }
+
+    /// Rewrites the next symbol, the next generation starts once every symbol of the current one is rewritten.
+    ///
+    /// Returns `None` if the current generation is empty or generation `u8::MAX` is reached.
+    pub fn step(&mut self) -> Option<DebugStep> {
+        if self.generation == u8::MAX || self.index >= self.current.len() {
+            return None;
+        }
+
+        let index = self.index;
+        let start = self.next.len();
+        let rewrite = self.lsystem.rewrite_symbol(
+            self.lsystem.active_rules(self.generation),
+            &self.current,
+            index,
+            &mut self.next,
+            &mut self.rng,
+        );
+        let step = DebugStep {
+            generation: self.generation,
+            index,
+            predecessor: String::from_iter(&self.current[index..=rewrite.end]),
+            successor: String::from_iter(&self.next[start..]),
+            rule: rewrite.rule,
+            stochastic: rewrite.stochastic,
+            left_context: left_context(&self.current, index, self.context_size),
+            right_context: right_context(&self.current, index, self.context_size),
+        };
+
+        self.index = rewrite.end + 1;
+        if self.index >= self.current.len() {
+            self.current = core::mem::take(&mut self.next);
+            self.generation += 1;
+            self.index = 0;
+        }
+        Some(step)
+    }
+
+    /// Rewrites the rest of the current generation, returns its steps.
+    pub fn finish_generation(&mut self) -> Vec<DebugStep> {
+        let generation = self.generation;
+        let mut steps = Vec::new();
+        while self.generation == generation {
+            match self.step() {
+                Some(step) => steps.push(step),
+                None => break,
+            }
+        }
+        steps
+    }
+
+    /// Rewrites the rest of the current generation until a step hits `breakpoint`, returns that step,
+    /// `None` if no step of the generation did.
+    ///
+    /// E.g. `debugger.continue_until(|step| step.rule == Some(RuleKind::ContextSensitive))`.
+    pub fn continue_until(
+        &mut self,
+        mut breakpoint: impl FnMut(&DebugStep) -> bool,
+    ) -> Option<DebugStep> {
+        let generation = self.generation;
+        while self.generation == generation {
+            let step = self.step()?;
+            if breakpoint(&step) {
+                return Some(step);
+            }
+        }
+        None
+    }
+}
@@ -1,80 +1,92 @@
 ///! This module defines some very common actions that can be reused by various lsystems.
 ///! For example many lsystems use rotation or transform stacking.
-use crate::{action::LSystemAction, action::ParamsResolver, ExecuteContext, Symbol};
+use crate::{action::LSystemAction, action::ParamsResolver, ExecuteContext, ParamError, Symbol};
 
 /// Rotation action arround the z axis.
 pub struct RotateZAction(pub f32, pub char);
 
-impl LSystemAction for RotateZAction {
+impl<E> LSystemAction<E> for RotateZAction {
     fn trigger(&self) -> Symbol {
         Symbol::Constant(self.1)
     }
 
-    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext) {
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
         context.turtle.rotate_z(self.0);
     }
 
-    fn from_params(symbol: Symbol,params: &ParamsResolver) -> Option<Self> {
-        let z = params.get(0).unwrap();
-
-        println!("Interpret {} ({})", "RotateZAction", z);
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
 
-        Some(RotateZAction(z, 'a'))
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(RotateZAction(params.require(0)?, 'a'))
     }
 
     fn name() -> &'static str {
         "RotateZAction"
     }
+
+    fn arity() -> usize {
+        1
+    }
 }
 
 /// Rotation action arround the x axis.
 pub struct RotateXAction(pub f32, pub char);
 
-impl LSystemAction for RotateXAction {
+impl<E> LSystemAction<E> for RotateXAction {
     fn trigger(&self) -> Symbol {
         Symbol::Constant(self.1)
     }
 
-    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext) {
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
         context.turtle.rotate_x(self.0);
     }
 
-    fn from_params(symbol: Symbol,params: &ParamsResolver) -> Option<Self> {
-        let x = params.get(0).unwrap();
-
-        println!("Interpret {} ({})", "RotateXAction", x);
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
 
-        Some(RotateXAction(x, 'a'))
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(RotateXAction(params.require(0)?, 'a'))
     }
 
     fn name() -> &'static str {
         "RotateXAction"
     }
+
+    fn arity() -> usize {
+        1
+    }
 }
 
 /// Rotation action arround the z axis.
 pub struct RotateYAction(pub f32, pub char);
 
-impl LSystemAction for RotateYAction {
+impl<E> LSystemAction<E> for RotateYAction {
     fn trigger(&self) -> Symbol {
         Symbol::Constant(self.1)
     }
 
-    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext) {
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
         context.turtle.rotate_y(self.0);
     }
 
-    fn from_params(symbol: Symbol,params: &ParamsResolver) -> Option<Self> {
-        let y = params.get(0).unwrap();
-
-        println!("Interpret {} ({})", "RotateYAction", y);
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
 
-        Some(RotateYAction(y, 'a'))
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(RotateYAction(params.require(0)?, 'a'))
     }
 
     fn name() -> &'static str {
         "RotateYAction"
     }
+
+    fn arity() -> usize {
+        1
+    }
 }
 
 /// L systems commonly saves transforms while generating actions.
@@ -83,18 +95,21 @@ impl LSystemAction for RotateYAction {
 /// This action triggers on `]`.
 pub struct PushTranformToStackAction;
 
-impl LSystemAction for PushTranformToStackAction {
+impl<E> LSystemAction<E> for PushTranformToStackAction {
     fn trigger(&self) -> Symbol {
         Symbol::Constant('[')
     }
 
-    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext) {
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
         context.push(context.turtle.clone());
     }
 
-    fn from_params(symbol: Symbol,_params: &ParamsResolver) -> Option<Self> {
-        println!("PushTranformToStackAction");
-        Some(PushTranformToStackAction)
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, _params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(PushTranformToStackAction)
     }
 
     fn name() -> &'static str {
@@ -104,24 +119,514 @@ impl LSystemAction for PushTranformToStackAction {
 
 /// L systems commonly saves transforms while generating actions.
 /// This action pops a saved transform at the end of a recursive path.
-/// This action triggers on `]`.
+/// This action triggers on `]`, the run fails with `RuntimeError::UnbalancedBrackets` if no transform was saved.
 pub struct PopTransformFromStackAction;
 
-impl LSystemAction for PopTransformFromStackAction {
+impl<E> LSystemAction<E> for PopTransformFromStackAction {
     fn trigger(&self) -> Symbol {
         Symbol::Constant(']')
     }
 
-    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext) {
-        context.turtle = context.pop();
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        match context.pop() {
+            Ok(turtle) => context.turtle = turtle,
+            Err(error) => context.fail(error),
+        }
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
     }
 
-    fn from_params(symbol: Symbol,_params: &ParamsResolver) -> Option<Self> {
-        println!("PushTranformToStackAction");
-        Some(PopTransformFromStackAction)
+    fn try_from_params(_symbol: Symbol, _params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(PopTransformFromStackAction)
     }
 
     fn name() -> &'static str {
-        "PushTranformToStackAction"
+        "PopTransformFromStackAction"
+    }
+}
+
+/// Moves the turtle forward by the length and draws a line with the width and color of the context,
+/// see `ExecuteContext::draw_forward`.
+pub struct DrawForwardAction(pub f32);
+
+impl<E> LSystemAction<E> for DrawForwardAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('F')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.draw_forward(self.0);
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(DrawForwardAction(params.require(0)?))
+    }
+
+    fn name() -> &'static str {
+        "DrawForwardAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Moves the turtle forward by the length without drawing, the pen is up,
+/// see `ExecuteContext::move_forward`.
+pub struct MoveForwardAction(pub f32);
+
+impl<E> LSystemAction<E> for MoveForwardAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('f')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.move_forward(self.0);
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(MoveForwardAction(params.require(0)?))
+    }
+
+    fn name() -> &'static str {
+        "MoveForwardAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Selects the color at the index of the palette, see `ExecuteContext::set_color_index`.
+pub struct SetColorAction(pub usize);
+
+impl<E> LSystemAction<E> for SetColorAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('\'')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.set_color_index(self.0);
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(SetColorAction(params.require(0)?.max(0.0) as usize))
+    }
+
+    fn name() -> &'static str {
+        "SetColorAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Selects the next color of the palette, see `ExecuteContext::increment_color`.
+pub struct IncColorAction;
+
+impl<E> LSystemAction<E> for IncColorAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('\'')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.increment_color();
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, _params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(IncColorAction)
+    }
+
+    fn name() -> &'static str {
+        "IncColorAction"
+    }
+}
+
+/// Tags the lines drawn and the polygons closed afterwards with the material at the index,
+/// e.g. `SetMaterialAction(1)` for leaves, see `ExecuteContext::set_material_index`.
+pub struct SetMaterialAction(pub usize);
+
+impl<E> LSystemAction<E> for SetMaterialAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('@')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.set_material_index(self.0);
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(SetMaterialAction(params.require(0)?.max(0.0) as usize))
+    }
+
+    fn name() -> &'static str {
+        "SetMaterialAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Sets the width of the lines drawn by the turtle, see `Turtle::set_width`.
+pub struct SetWidthAction(pub f32);
+
+impl<E> LSystemAction<E> for SetWidthAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('!')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.turtle.set_width(self.0);
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(SetWidthAction(params.require(0)?))
+    }
+
+    fn name() -> &'static str {
+        "SetWidthAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Multiplies the width of the lines drawn by the turtle, e.g. by `0.7` to draw twigs thinner than the trunk.
+///
+/// The width is part of the turtle, thus closing a branch restores the width it was opened with.
+pub struct MultiplyWidthAction(pub f32);
+
+impl<E> LSystemAction<E> for MultiplyWidthAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('!')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        let width = context.turtle.width() * self.0;
+        context.turtle.set_width(width);
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(MultiplyWidthAction(params.require(0)?))
+    }
+
+    fn name() -> &'static str {
+        "MultiplyWidthAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Pitches the heading of the turtle down, away from its up direction, by the angle in radians.
+pub struct PitchDownAction(pub f32);
+
+impl<E> LSystemAction<E> for PitchDownAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('&')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.turtle.rotate_x(-self.0);
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(PitchDownAction(params.require(0)?))
+    }
+
+    fn name() -> &'static str {
+        "PitchDownAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Pitches the heading of the turtle up, towards its up direction, by the angle in radians.
+pub struct PitchUpAction(pub f32);
+
+impl<E> LSystemAction<E> for PitchUpAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('^')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.turtle.rotate_x(self.0);
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(PitchUpAction(params.require(0)?))
+    }
+
+    fn name() -> &'static str {
+        "PitchUpAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Rolls the turtle left around its heading by the angle in radians.
+pub struct RollLeftAction(pub f32);
+
+impl<E> LSystemAction<E> for RollLeftAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('\\')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.turtle.rotate_y(self.0);
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(RollLeftAction(params.require(0)?))
+    }
+
+    fn name() -> &'static str {
+        "RollLeftAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Rolls the turtle right around its heading by the angle in radians.
+pub struct RollRightAction(pub f32);
+
+impl<E> LSystemAction<E> for RollRightAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('/')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.turtle.rotate_y(-self.0);
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(RollRightAction(params.require(0)?))
+    }
+
+    fn name() -> &'static str {
+        "RollRightAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Turns the turtle around its up direction, reversing its heading.
+pub struct TurnAroundAction;
+
+impl<E> LSystemAction<E> for TurnAroundAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('|')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.turtle.rotate_z(core::f32::consts::PI);
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, _params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(TurnAroundAction)
+    }
+
+    fn name() -> &'static str {
+        "TurnAroundAction"
+    }
+}
+
+/// Opens a polygon, e.g. for a leaf, see `ExecuteContext::begin_polygon`.
+///
+/// Use `MoveForwardAction` inside the polygon to place vertices without drawing its outline.
+pub struct BeginPolygonAction;
+
+impl<E> LSystemAction<E> for BeginPolygonAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('{')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.begin_polygon();
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, _params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(BeginPolygonAction)
+    }
+
+    fn name() -> &'static str {
+        "BeginPolygonAction"
+    }
+}
+
+/// Adds the position of the turtle to the open polygon, see `ExecuteContext::add_vertex`.
+pub struct AddVertexAction;
+
+impl<E> LSystemAction<E> for AddVertexAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('.')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.add_vertex();
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, _params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(AddVertexAction)
+    }
+
+    fn name() -> &'static str {
+        "AddVertexAction"
+    }
+}
+
+/// Closes the open polygon and emits it as filled polygon, see `ExecuteContext::end_polygon`.
+pub struct EndPolygonAction;
+
+impl<E> LSystemAction<E> for EndPolygonAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('}')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.end_polygon();
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, _params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(EndPolygonAction)
+    }
+
+    fn name() -> &'static str {
+        "EndPolygonAction"
+    }
+}
+
+/// Spawns a turtle with the pose and pen of the selected turtle and selects it,
+/// the following symbols move the new turtle, see `ExecuteContext::spawn_turtle`.
+pub struct SpawnTurtleAction;
+
+impl<E> LSystemAction<E> for SpawnTurtleAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('S')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        let turtle = context.spawn_turtle();
+        if let Err(error) = context.select_turtle(turtle) {
+            context.fail(error);
+        }
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, _params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(SpawnTurtleAction)
+    }
+
+    fn name() -> &'static str {
+        "SpawnTurtleAction"
+    }
+}
+
+/// Selects the turtle at the index, the first turtle of a run has index `0`,
+/// see `ExecuteContext::select_turtle`.
+pub struct SelectTurtleAction(pub usize);
+
+impl<E> LSystemAction<E> for SelectTurtleAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('T')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        if let Err(error) = context.select_turtle(self.0) {
+            context.fail(error);
+        }
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        <Self as LSystemAction<E>>::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(SelectTurtleAction(params.require(0)?.max(0.0) as usize))
+    }
+
+    fn name() -> &'static str {
+        "SelectTurtleAction"
+    }
+
+    fn arity() -> usize {
+        1
     }
 }
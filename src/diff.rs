@@ -0,0 +1,245 @@
+//! Differences between two alphabets, e.g. to highlight the symbols a new generation added.
+use core::ops::{Index, IndexMut, Range};
+
+use crate::{prelude::*, Alphabet, Symbol};
+
+/// The result of `Alphabet::diff`, the runs of symbols that turn one alphabet into the other.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AlphabetDiff {
+    /// Runs of symbols of the new alphabet that are not in the old alphabet, in ascending order.
+    pub inserted: Vec<Range<usize>>,
+    /// Runs of symbols of the old alphabet that are not in the new alphabet, in ascending order.
+    pub removed: Vec<Range<usize>>,
+}
+
+impl AlphabetDiff {
+    /// Returns `true` if both alphabets are equal.
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.removed.is_empty()
+    }
+
+    /// Returns the number of inserted symbols.
+    pub fn inserted_len(&self) -> usize {
+        self.inserted.iter().map(|run| run.len()).sum()
+    }
+
+    /// Returns the number of removed symbols.
+    pub fn removed_len(&self) -> usize {
+        self.removed.iter().map(|run| run.len()).sum()
+    }
+
+    fn insert(&mut self, run: Range<usize>) {
+        Self::push_run(&mut self.inserted, run);
+    }
+
+    fn remove(&mut self, run: Range<usize>) {
+        Self::push_run(&mut self.removed, run);
+    }
+
+    /// Pushes `run` onto `runs`, merging it with the last run if they are adjacent.
+    fn push_run(runs: &mut Vec<Range<usize>>, run: Range<usize>) {
+        if run.is_empty() {
+            return;
+        }
+
+        match runs.last_mut() {
+            Some(last) if last.end == run.start => last.end = run.end,
+            _ => runs.push(run),
+        }
+    }
+}
+
+impl Alphabet {
+    /// Computes the shortest edit turning this alphabet into `other`,
+    /// `removed` indexes this alphabet and `inserted` indexes `other`.
+    ///
+    /// Uses the linear space variant of Myers' algorithm, taking `O((N + M) * D)` time
+    /// where `D` is the number of inserted and removed symbols.
+    pub fn diff(&self, other: &Alphabet) -> AlphabetDiff {
+        let old = &self.symbols;
+        let new = &other.symbols;
+        let max_d = max_d(old.len(), new.len());
+
+        let mut diff = AlphabetDiff::default();
+        let mut forward = Diagonals::new(max_d);
+        let mut backward = Diagonals::new(max_d);
+        conquer(
+            old,
+            0..old.len(),
+            new,
+            0..new.len(),
+            &mut forward,
+            &mut backward,
+            &mut diff,
+        );
+        diff
+    }
+}
+
+/// The furthest reaching x coordinate of every diagonal `k = x - y` of the edit graph.
+struct Diagonals {
+    offset: isize,
+    furthest: Vec<usize>,
+}
+
+impl Diagonals {
+    fn new(max_d: usize) -> Self {
+        Self {
+            offset: max_d as isize,
+            furthest: vec![0; 2 * max_d + 1],
+        }
+    }
+}
+
+impl Index<isize> for Diagonals {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &usize {
+        &self.furthest[(self.offset + k) as usize]
+    }
+}
+
+impl IndexMut<isize> for Diagonals {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.furthest[(self.offset + k) as usize]
+    }
+}
+
+/// Returns an upper bound of the number of edits the middle snake search has to consider.
+fn max_d(n: usize, m: usize) -> usize {
+    (n + m).div_ceil(2) + 1
+}
+
+fn common_prefix_len(old: &[Symbol], new: &[Symbol]) -> usize {
+    old.iter().zip(new).take_while(|(a, b)| a == b).count()
+}
+
+fn common_suffix_len(old: &[Symbol], new: &[Symbol]) -> usize {
+    old.iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+/// Splits the ranges at the middle snake and diffs both halves until only insertions or removals remain.
+fn conquer(
+    old: &[Symbol],
+    mut old_range: Range<usize>,
+    new: &[Symbol],
+    mut new_range: Range<usize>,
+    forward: &mut Diagonals,
+    backward: &mut Diagonals,
+    diff: &mut AlphabetDiff,
+) {
+    let prefix = common_prefix_len(&old[old_range.clone()], &new[new_range.clone()]);
+    old_range.start += prefix;
+    new_range.start += prefix;
+
+    let suffix = common_suffix_len(&old[old_range.clone()], &new[new_range.clone()]);
+    old_range.end -= suffix;
+    new_range.end -= suffix;
+
+    if old_range.is_empty() || new_range.is_empty() {
+        diff.remove(old_range);
+        diff.insert(new_range);
+    } else if let Some((x, y)) = find_middle_snake(
+        old,
+        old_range.clone(),
+        new,
+        new_range.clone(),
+        forward,
+        backward,
+    ) {
+        conquer(
+            old,
+            old_range.start..x,
+            new,
+            new_range.start..y,
+            forward,
+            backward,
+            diff,
+        );
+        conquer(
+            old,
+            x..old_range.end,
+            new,
+            y..new_range.end,
+            forward,
+            backward,
+            diff,
+        );
+    } else {
+        diff.remove(old_range);
+        diff.insert(new_range);
+    }
+}
+
+/// Searches the shortest edit path from both ends at once
+/// and returns the point where both searches overlap.
+fn find_middle_snake(
+    old: &[Symbol],
+    old_range: Range<usize>,
+    new: &[Symbol],
+    new_range: Range<usize>,
+    forward: &mut Diagonals,
+    backward: &mut Diagonals,
+) -> Option<(usize, usize)> {
+    let n = old_range.len();
+    let m = new_range.len();
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+    let max_d = max_d(n, m) as isize;
+
+    forward[1] = 0;
+    backward[1] = 0;
+
+    for d in 0..max_d {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && forward[k - 1] < forward[k + 1]) {
+                forward[k + 1]
+            } else {
+                forward[k - 1] + 1
+            };
+            let y = (x as isize - k) as usize;
+            let (x0, y0) = (x, y);
+
+            if x < n && y < m {
+                x += common_prefix_len(
+                    &old[old_range.start + x..old_range.end],
+                    &new[new_range.start + y..new_range.end],
+                );
+            }
+            forward[k] = x;
+
+            if odd && (k - delta).abs() < d && forward[k] + backward[delta - k] >= n {
+                return Some((old_range.start + x0, new_range.start + y0));
+            }
+        }
+
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && backward[k - 1] < backward[k + 1]) {
+                backward[k + 1]
+            } else {
+                backward[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+
+            if x < n && y < m {
+                let advance = common_suffix_len(
+                    &old[old_range.start..old_range.start + n - x],
+                    &new[new_range.start..new_range.start + m - y],
+                );
+                x += advance;
+                y += advance;
+            }
+            backward[k] = x;
+
+            if !odd && (k - delta).abs() <= d && backward[k] + forward[delta - k] >= n {
+                return Some((old_range.start + n - x, new_range.start + m - y));
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,174 @@
+//! Stores derivations and geometry on disk, so loading a game or exporting a script again skips generating.
+use std::{
+    fs,
+    hash::Hasher,
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    ActionResolver, Alphabet, FactoryError, GenerateError, GenerateOptions, Geometry, LSystem,
+};
+
+/// Part of the file names, increased whenever the serialized types or the hashed content change
+/// so old files are not read.
+const FORMAT_VERSION: u32 = 2;
+
+/// Identifies a derivation by the content of its lsystem, hashed like `LSystem::content_hash`, and its generations.
+///
+/// The content hash covers the seed and the variables, thus an lsystem changed after compiling,
+/// e.g. with `set_variable`, is not mistaken for the script it was compiled from.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct DiskCacheKey {
+    pub content_hash: u64,
+    pub generations: u8,
+}
+
+impl DiskCacheKey {
+    /// Returns `None` if the lsystem has context sensitive or parametric rules or growth functions,
+    /// functions can not be told apart by a key that is kept between builds.
+    pub fn new(lsystem: &LSystem, generations: u8) -> Option<Self> {
+        if lsystem.has_callbacks() {
+            return None;
+        }
+
+        // SipHash 2-4 with fixed keys, unlike the `DefaultHasher` its algorithm does not change between Rust versions.
+        #[allow(deprecated)]
+        let mut hasher = std::hash::SipHasher::new_with_keys(0, 0);
+        lsystem.hash_content(&mut hasher);
+        Some(Self {
+            content_hash: hasher.finish(),
+            generations,
+        })
+    }
+
+    fn file_name(&self, kind: &str) -> String {
+        format!(
+            "{:016x}-{}.v{FORMAT_VERSION}.{kind}",
+            self.content_hash, self.generations
+        )
+    }
+}
+
+/// A directory of serialized alphabets and geometry, keyed by `DiskCacheKey`.
+///
+/// Files that are missing, unreadable or of another format version are treated as not cached.
+/// The keys are the same for every build of the crate on platforms with the same pointer width and byte order,
+/// lsystems with functions have no key and are generated every time, see `DiskCacheKey::new`.
+/// Geometry depends on the actions, use one directory per action resolver.
+pub struct DiskCache {
+    directory: PathBuf,
+}
+
+impl DiskCache {
+    /// Opens the cache in `directory`, creating the directory if it does not exist.
+    pub fn new(directory: impl Into<PathBuf>) -> io::Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(Self { directory })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn load_alphabet(&self, key: &DiskCacheKey) -> Option<Alphabet> {
+        self.load(&key.file_name("alphabet"))
+    }
+
+    pub fn store_alphabet(&self, key: &DiskCacheKey, alphabet: &Alphabet) -> io::Result<()> {
+        self.store(&key.file_name("alphabet"), alphabet)
+    }
+
+    pub fn load_geometry(&self, key: &DiskCacheKey) -> Option<Geometry> {
+        self.load(&key.file_name("geometry"))
+    }
+
+    pub fn store_geometry(&self, key: &DiskCacheKey, geometry: &Geometry) -> io::Result<()> {
+        self.store(&key.file_name("geometry"), geometry)
+    }
+
+    /// Returns the cached alphabet of `generations` of `lsystem`, or generates and stores it.
+    ///
+    /// Failing to store the alphabet is not an error, it is generated again next time.
+    pub fn generate(
+        &self,
+        lsystem: &LSystem,
+        generations: u8,
+        options: &GenerateOptions,
+    ) -> Result<Alphabet, GenerateError> {
+        let Some(key) = DiskCacheKey::new(lsystem, generations) else {
+            return lsystem.generate_with(generations, options);
+        };
+        if let Some(alphabet) = self.load_alphabet(&key) {
+            return Ok(alphabet);
+        }
+
+        let alphabet = lsystem.generate_with(generations, options)?;
+        let _ = self.store_alphabet(&key, &alphabet);
+        Ok(alphabet)
+    }
+
+    /// Returns the cached geometry of `generations` of `lsystem`, or generates, runs and stores it.
+    /// The alphabet is cached as well.
+    pub fn geometry(
+        &self,
+        lsystem: &LSystem,
+        resolver: &ActionResolver,
+        generations: u8,
+        options: &GenerateOptions,
+    ) -> Result<Geometry, FactoryError> {
+        let key = DiskCacheKey::new(lsystem, generations);
+        if let Some(geometry) = key.as_ref().and_then(|key| self.load_geometry(key)) {
+            return Ok(geometry);
+        }
+
+        let alphabet = self.generate(lsystem, generations, options)?;
+        let geometry = lsystem.run(resolver, &alphabet)?.geometry();
+        if let Some(key) = key {
+            let _ = self.store_geometry(&key, &geometry);
+        }
+        Ok(geometry)
+    }
+
+    /// Removes the cached alphabet and geometry of `key`.
+    pub fn remove(&self, key: &DiskCacheKey) -> io::Result<()> {
+        for kind in ["alphabet", "geometry"] {
+            match fs::remove_file(self.directory.join(key.file_name(kind))) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes every cached file, including those of other format versions.
+    pub fn clear(&self) -> io::Result<()> {
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            let cached = path
+                .extension()
+                .is_some_and(|extension| extension == "alphabet" || extension == "geometry");
+            if cached {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn load<T: DeserializeOwned>(&self, file_name: &str) -> Option<T> {
+        let bytes = fs::read(self.directory.join(file_name)).ok()?;
+        bincode::deserialize(&bytes).ok()
+    }
+
+    /// Writes to a temporary file first, thus a concurrent `load` never reads a partially written file.
+    fn store<T: Serialize>(&self, file_name: &str, value: &T) -> io::Result<()> {
+        let bytes = bincode::serialize(value)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        let temporary = self.directory.join(format!("{file_name}.tmp"));
+        fs::write(&temporary, bytes)?;
+        fs::rename(temporary, self.directory.join(file_name))
+    }
+}
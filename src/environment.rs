@@ -0,0 +1,56 @@
+//! Query modules and environments for open L-systems.
+//!
+//! A query module `?P(x,y,z)` asks the environment about the turtle at its position.
+//! While running an lsystem every query module is recorded together with the turtle state,
+//! `LSystem::generate_with_environment` then fills the module's parameters with the response of an `Environment`
+//! before rewriting the next generation, thus rules can react to light, obstacles or neighbours.
+use macaw::Vec3;
+
+use crate::{prelude::*, Turtle};
+
+/// The symbol marking the module after it as a query module, e.g. `?P(x,y,z)`.
+pub const QUERY_SYMBOL: char = '?';
+
+/// The turtle state at a query module, recorded while running an lsystem.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Query {
+    /// The module after the `?`, e.g. `P` for `?P(x,y,z)`.
+    pub symbol: char,
+    /// Index of the `?` in the alphabet.
+    pub index: usize,
+    /// The generation of the alphabet that was run.
+    pub generation: u8,
+    pub position: Vec3,
+    /// The direction the turtle moves forward in.
+    pub heading: Vec3,
+    /// The direction pointing out of the drawing plane of the turtle.
+    pub up: Vec3,
+}
+
+impl Query {
+    pub(crate) fn new(symbol: char, index: usize, generation: u8, turtle: &Turtle) -> Self {
+        Self {
+            symbol,
+            index,
+            generation,
+            position: turtle.origin(),
+            heading: turtle.heading(),
+            up: turtle.up(),
+        }
+    }
+}
+
+/// The environment an open L-system grows in, see `LSystem::generate_with_environment`.
+pub trait Environment {
+    /// Returns the parameters of the query module of `query`.
+    /// By default query modules are filled with the turtle position, e.g. `?P(1,2,0)`.
+    fn query(&mut self, query: &Query) -> Vec<f32> {
+        vec![query.position.x, query.position.y, query.position.z]
+    }
+}
+
+/// Environment filling query modules with the turtle position only.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PositionEnvironment;
+
+impl Environment for PositionEnvironment {}
@@ -0,0 +1,112 @@
+//! The changes of the drawing recorded while running an lsystem.
+use macaw::Vec3;
+
+use crate::{prelude::*, Color, ExecuteContext, GeometryElement, Polygon};
+
+/// A change of the drawing while running an lsystem with `LSystem::run_with`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExecutionEvent<E = GeometryElement> {
+    /// The turtle moved to the point without drawing, e.g. at the start or after a branch ended.
+    MoveTo(Vec3),
+    /// The turtle drew a line from its previous position to `to`.
+    LineTo {
+        to: Vec3,
+        width: f32,
+        color: Color,
+        /// The branch depth at which the line was drawn.
+        depth: usize,
+    },
+    /// A branch was opened, the turtle was saved.
+    Push,
+    /// A branch was closed, the turtle was restored.
+    Pop,
+    /// The turtle closed a polygon, see `ExecuteContext::end_polygon`.
+    Polygon(Polygon),
+    /// An action pushed the element onto `ExecuteContext::elements`.
+    EmitElement(E),
+}
+
+/// A change of the geometry drawn by the turtle, see `ExecuteContext::record`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TurtleEvent {
+    /// The turtle drew a line from `from` to `to`.
+    Line {
+        from: Vec3,
+        to: Vec3,
+        width: f32,
+        color: Color,
+        /// The branch depth at which the line was drawn.
+        depth: usize,
+        /// Index of the symbol that drew the line in the run alphabet.
+        index: usize,
+        /// The material of the line, see `ExecuteContext::set_material`.
+        material: Option<String>,
+    },
+    /// A branch starting at `origin` was opened, `depth` is the depth inside the branch.
+    Branch { origin: Vec3, depth: usize },
+    /// The branch at `depth` was closed, the turtle jumped back to where the branch started.
+    BranchEnd { depth: usize },
+}
+
+/// Turns the events recorded by the context into execution events.
+pub(crate) struct EventEmitter {
+    position: Vec3,
+}
+
+impl EventEmitter {
+    /// Records the start position of the turtle and emits it.
+    pub(crate) fn start<E>(
+        context: &mut ExecuteContext<E>,
+        on_event: &mut impl FnMut(ExecutionEvent<E>),
+    ) -> Self {
+        let position = context.turtle.origin();
+        context.record();
+        on_event(ExecutionEvent::MoveTo(position));
+
+        Self { position }
+    }
+
+    /// Records the changes since the previous symbol and moves them,
+    /// the polygons and the elements of `context` into events.
+    pub(crate) fn emit<E>(
+        &mut self,
+        context: &mut ExecuteContext<E>,
+        on_event: &mut impl FnMut(ExecutionEvent<E>),
+    ) {
+        context.record();
+
+        for event in context.take_turtle_events() {
+            match event {
+                TurtleEvent::Line {
+                    from,
+                    to,
+                    width,
+                    color,
+                    depth,
+                    ..
+                } => {
+                    if from != self.position {
+                        on_event(ExecutionEvent::MoveTo(from));
+                    }
+                    on_event(ExecutionEvent::LineTo {
+                        to,
+                        width,
+                        color,
+                        depth,
+                    });
+                    self.position = to;
+                }
+                TurtleEvent::Branch { .. } => on_event(ExecutionEvent::Push),
+                TurtleEvent::BranchEnd { .. } => on_event(ExecutionEvent::Pop),
+            }
+        }
+
+        for polygon in context.polygons.drain(..) {
+            on_event(ExecutionEvent::Polygon(polygon));
+        }
+
+        for element in context.elements.drain(..) {
+            on_event(ExecutionEvent::EmitElement(element));
+        }
+    }
+}
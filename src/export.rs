@@ -0,0 +1,96 @@
+//! Exporters writing the result of an executed lsystem to various file formats.
+#[cfg(feature = "gif")]
+pub mod animation;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+#[cfg(feature = "sound")]
+pub mod midi;
+pub mod obj;
+pub mod raster;
+pub mod svg;
+pub mod turtle;
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use crate::ExecuteContext;
+
+/// Writes the result of an executed lsystem in a specific file format.
+///
+/// Implement this trait to plug a custom format into an `ExporterRegistry`.
+pub trait Exporter {
+    /// Name of the format, e.g. `svg`, used as key in the `ExporterRegistry`.
+    fn format(&self) -> &'static str;
+
+    /// File extension without leading dot.
+    fn extension(&self) -> &'static str {
+        self.format()
+    }
+
+    fn export(&self, context: &ExecuteContext, writer: &mut dyn Write) -> io::Result<()>;
+}
+
+/// Exporters keyed by their format name, allowing tools to enumerate the available formats.
+pub struct ExporterRegistry {
+    exporters: BTreeMap<&'static str, Box<dyn Exporter>>,
+}
+
+impl ExporterRegistry {
+    /// Creates a registry without any exporters.
+    pub fn new() -> Self {
+        Self {
+            exporters: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a registry with all exporters of this crate.
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(svg::SvgExporter::new());
+        registry.register(obj::ObjExporter);
+        registry.register(turtle::TurtleCommandExporter);
+        #[cfg(feature = "gltf")]
+        registry.register(gltf::GltfExporter::new());
+        #[cfg(feature = "png")]
+        registry.register(raster::PngExporter::default());
+        registry
+    }
+
+    /// Registers `exporter` under its format name, replacing an exporter with the same format.
+    pub fn register(&mut self, exporter: impl Exporter + 'static) {
+        self.exporters.insert(exporter.format(), Box::new(exporter));
+    }
+
+    pub fn get(&self, format: &str) -> Option<&dyn Exporter> {
+        self.exporters.get(format).map(|exporter| exporter.as_ref())
+    }
+
+    /// Returns the names of all registered formats in alphabetical order.
+    pub fn formats(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.exporters.keys().copied()
+    }
+
+    /// Exports `context` with the exporter registered for `format`.
+    pub fn export(
+        &self,
+        format: &str,
+        context: &ExecuteContext,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        match self.get(format) {
+            Some(exporter) => exporter.export(context, writer),
+            None => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("No exporter registered for format `{format}`"),
+            )),
+        }
+    }
+}
+
+impl Default for ExporterRegistry {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
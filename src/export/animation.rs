@@ -0,0 +1,102 @@
+use std::io::{self, Write};
+
+use crate::{ActionResolver, Color, LSystem, SymbolDefiner};
+
+use super::raster::{blend_channel, Raster, RasterStyle};
+
+/// Defines how a growth animation is rendered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationStyle {
+    pub raster: RasterStyle,
+    /// Time every generation is shown in hundredths of a second.
+    pub frame_delay: u16,
+    /// Time the final generation is shown in hundredths of a second, before the animation restarts.
+    pub last_frame_delay: u16,
+    /// Whether the animation loops forever or plays once.
+    pub repeat: bool,
+}
+
+impl Default for AnimationStyle {
+    fn default() -> Self {
+        Self {
+            raster: RasterStyle::default(),
+            frame_delay: 50,
+            last_frame_delay: 200,
+            repeat: true,
+        }
+    }
+}
+
+/// Writes a GIF animation showing the growth of `lsystem` from its axiom up to `generations`.
+///
+/// Every generation is executed with `action_resolver` and rasterized into its own frame.
+/// Each frame is fitted into the image individually, thus the drawing keeps its size while it grows in detail.
+pub fn write_gif<A: SymbolDefiner>(
+    lsystem: &LSystem<A>,
+    action_resolver: &ActionResolver,
+    generations: u8,
+    style: &AnimationStyle,
+    writer: impl Write,
+) -> io::Result<()> {
+    let width = u16::try_from(style.raster.width).map_err(|_| too_large())?;
+    let height = u16::try_from(style.raster.height).map_err(|_| too_large())?;
+
+    let mut encoder = gif::Encoder::new(writer, width, height, &[]).map_err(to_io_error)?;
+    let repeat = if style.repeat {
+        gif::Repeat::Infinite
+    } else {
+        gif::Repeat::Finite(0)
+    };
+    encoder.set_repeat(repeat).map_err(to_io_error)?;
+
+    for alphabet in lsystem.generate_all(generations) {
+        let context = lsystem
+            .run(action_resolver, &alphabet)
+            .map_err(io::Error::other)?;
+        let mut raster = Raster::render(&context, &style.raster);
+
+        // GIF has no partial transparency, thus every frame is drawn onto an opaque background.
+        if style.raster.background.a < 1.0 {
+            raster = flatten(raster, Color::WHITE);
+        }
+
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut raster.pixels, 10);
+        frame.delay = if alphabet.generation == generations {
+            style.last_frame_delay
+        } else {
+            style.frame_delay
+        };
+        encoder.write_frame(&frame).map_err(to_io_error)?;
+    }
+
+    Ok(())
+}
+
+fn flatten(raster: Raster, background: Color) -> Raster {
+    let mut flattened = Raster::new(raster.width, raster.height, background);
+    for (destination, source) in flattened
+        .pixels
+        .chunks_exact_mut(4)
+        .zip(raster.pixels.chunks_exact(4))
+    {
+        let alpha = source[3] as f32 / 255.0;
+        for (destination, source) in destination[..3].iter_mut().zip(source) {
+            *destination = blend_channel(*destination, *source, alpha);
+        }
+    }
+    flattened
+}
+
+fn too_large() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "GIF images can not be larger than 65535 pixels",
+    )
+}
+
+fn to_io_error(error: gif::EncodingError) -> io::Error {
+    match error {
+        gif::EncodingError::Io(error) => error,
+        error => io::Error::other(error),
+    }
+}
@@ -0,0 +1,492 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use macaw::Vec3;
+
+use crate::{
+    geometry::json_string, Branch, Color, ExecuteContext, GeometryElement, Instance, Mesh, Polygon,
+    Segment,
+};
+
+use super::Exporter;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+/// Writes the result of an lsystem as a self contained glTF 2.0 file.
+///
+/// Every branch becomes a node holding the tube mesh of its segments, with normals and texture coordinates
+/// for bark textures, see `Mesh::tube`, and one primitive per material of the segments.
+/// The branches opened inside it become its child nodes.
+/// Polygons, meshes and the tubes of segment elements are written into one extra node next to the main branch,
+/// with one primitive per material referring to a glTF material of that name, see `GeometryElement::tagged`.
+/// Instancing is preserved: every instance becomes a child node of the `instances` node placed with its transform,
+/// the instances of a model with the same color and material share one mesh, see `Geometry::models`.
+/// All vertices carry their color as `COLOR_0` attribute, the buffer is embedded as base64 data uri.
+/// Empty arrays and the buffer are left out, thus a drawing without geometry writes only its nodes.
+pub struct GltfExporter {
+    /// Number of faces around a tube.
+    pub sides: usize,
+    /// Radius of the tube around a segment with width `1.0`.
+    pub radius: f32,
+}
+
+impl GltfExporter {
+    pub fn new() -> Self {
+        Self {
+            sides: 6,
+            radius: 0.05,
+        }
+    }
+
+    pub fn with_sides(mut self, sides: usize) -> Self {
+        self.sides = sides;
+        self
+    }
+
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    pub fn export(&self, context: &ExecuteContext, writer: &mut impl Write) -> io::Result<()> {
+        let mut document = Document::default();
+
+        let root = document.add_branch(&context.branch_tree(), self);
+        let mut scene = vec![root];
+
+        let geometry = context.geometry();
+        let mut surfaces = geometry
+            .polygons
+            .iter()
+            .map(polygon_mesh)
+            .collect::<Vec<_>>();
+        surfaces.extend(geometry.meshes.iter().cloned());
+        // Segments pushed as elements are not part of the branches, they become tubes of their own.
+        let segments = context
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                GeometryElement::Segment(segment) => Some(segment.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        surfaces.extend(self.tubes(&segments));
+
+        // One primitive per material, in the order the materials first appear.
+        let mut materials = Vec::<Option<&str>>::new();
+        for surface in &surfaces {
+            if !materials.contains(&surface.material.as_deref()) {
+                materials.push(surface.material.as_deref());
+            }
+        }
+        let primitives = materials
+            .iter()
+            .map(|material| {
+                let group = surfaces
+                    .iter()
+                    .filter(|surface| surface.material.as_deref() == *material)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                Mesh {
+                    material: material.map(str::to_string),
+                    ..merge_meshes(&group)
+                }
+            })
+            .filter(|primitive| !primitive.is_empty())
+            .collect::<Vec<_>>();
+        if !primitives.is_empty() {
+            let mesh = document.add_mesh(&primitives);
+            scene.push(document.add_node("surfaces".to_string(), Some(mesh), Vec::new()));
+        }
+
+        // The mesh of every model by its color and material.
+        let mut models = HashMap::<(&str, [u32; 4], Option<&str>), usize>::new();
+        let instances = geometry
+            .instances
+            .iter()
+            .map(|instance| {
+                let mesh = geometry.models.get(&instance.name).map(|model| {
+                    let material = instance.material.as_ref().or(model.material.as_ref());
+                    let color = instance.color;
+                    let key = (
+                        instance.name.as_str(),
+                        [color.r, color.g, color.b, color.a].map(f32::to_bits),
+                        material.map(String::as_str),
+                    );
+                    *models.entry(key).or_insert_with(|| {
+                        document.add_mesh(&[Mesh {
+                            colors: Vec::new(),
+                            color,
+                            material: material.cloned(),
+                            ..model.clone()
+                        }])
+                    })
+                });
+                document.add_instance(instance, mesh)
+            })
+            .collect::<Vec<_>>();
+        if !instances.is_empty() {
+            scene.push(document.add_node("instances".to_string(), None, instances));
+        }
+
+        document.write(&scene, writer)
+    }
+
+    /// Returns a tube around the segments of every material, in the order the materials first appear.
+    fn tubes(&self, segments: &[Segment]) -> Vec<Mesh> {
+        let mut materials = Vec::<Option<&str>>::new();
+        for segment in segments {
+            if !materials.contains(&segment.material.as_deref()) {
+                materials.push(segment.material.as_deref());
+            }
+        }
+
+        materials
+            .into_iter()
+            .map(|material| {
+                let group = segments
+                    .iter()
+                    .filter(|segment| segment.material.as_deref() == material)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                Mesh {
+                    material: material.map(str::to_string),
+                    ..Mesh::tube(&group, self.sides, self.radius)
+                }
+            })
+            .filter(|tube| !tube.is_empty())
+            .collect()
+    }
+
+    pub fn to_string(&self, context: &ExecuteContext) -> String {
+        let mut buffer = Vec::new();
+        self.export(context, &mut buffer)
+            .expect("Writing to a vector can not fail.");
+        String::from_utf8(buffer).expect("glTF output is valid utf8.")
+    }
+}
+
+impl Exporter for GltfExporter {
+    fn format(&self) -> &'static str {
+        "gltf"
+    }
+
+    fn export(&self, context: &ExecuteContext, mut writer: &mut dyn Write) -> io::Result<()> {
+        GltfExporter::export(self, context, &mut writer)
+    }
+}
+
+impl Default for GltfExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The json objects and binary buffer of a glTF file under construction.
+#[derive(Default)]
+struct Document {
+    nodes: Vec<String>,
+    meshes: Vec<String>,
+    /// The names of the materials, see `material`.
+    materials: Vec<String>,
+    accessors: Vec<String>,
+    buffer_views: Vec<String>,
+    buffer: Vec<u8>,
+}
+
+impl Document {
+    /// Adds the nodes of `branch` and its children, returns the index of the branch node.
+    fn add_branch(&mut self, branch: &Branch, exporter: &GltfExporter) -> usize {
+        let children = branch
+            .children
+            .iter()
+            .map(|child| self.add_branch(child, exporter))
+            .collect();
+
+        let tubes = exporter.tubes(&branch.segments);
+        let mesh = (!tubes.is_empty()).then(|| self.add_mesh(&tubes));
+        self.add_node(format!("branch-{}", branch.depth), mesh, children)
+    }
+
+    fn add_node(&mut self, name: String, mesh: Option<usize>, children: Vec<usize>) -> usize {
+        let mut node = format!(r#"{{"name":"{name}""#);
+        if let Some(mesh) = mesh {
+            node.push_str(&format!(r#","mesh":{mesh}"#));
+        }
+        if !children.is_empty() {
+            node.push_str(&format!(r#","children":{}"#, json_array(&children)));
+        }
+        node.push('}');
+
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    /// Adds the node of `instance` placing `mesh`, the model of the instance, returns the index of the node.
+    fn add_instance(&mut self, instance: &Instance, mesh: Option<usize>) -> usize {
+        let matrix = instance
+            .transform
+            .to_cols_array()
+            .map(|value| value.to_string())
+            .join(",");
+        let mut node = format!(
+            r#"{{"name":{},"matrix":[{matrix}]"#,
+            json_string(&instance.name)
+        );
+        if let Some(mesh) = mesh {
+            node.push_str(&format!(r#","mesh":{mesh}"#));
+        }
+        node.push('}');
+
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    /// Adds a mesh with one primitive per element of `primitives`, returns the index of the mesh.
+    fn add_mesh(&mut self, primitives: &[Mesh]) -> usize {
+        let primitives = primitives
+            .iter()
+            .map(|primitive| self.add_primitive(primitive))
+            .collect::<Vec<_>>()
+            .join(",");
+        self.meshes
+            .push(format!(r#"{{"primitives":[{primitives}]}}"#));
+        self.meshes.len() - 1
+    }
+
+    /// Returns the json of a primitive drawing `mesh` with its material.
+    fn add_primitive(&mut self, mesh: &Mesh) -> String {
+        let (min, max) = mesh.positions.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), position| (min.min(*position), max.max(*position)),
+        );
+        let positions = self.add_accessor(
+            &floats(mesh.positions.iter().flat_map(|p| p.to_array())),
+            ARRAY_BUFFER,
+            FLOAT,
+            mesh.positions.len(),
+            &format!(
+                r#""VEC3","min":[{},{},{}],"max":[{},{},{}]"#,
+                min.x, min.y, min.z, max.x, max.y, max.z
+            ),
+        );
+
+        let mut attributes = format!(r#""POSITION":{positions}"#);
+
+        if mesh.normals.len() == mesh.positions.len() {
+            let normals = self.add_accessor(
+                &floats(mesh.normals.iter().flat_map(|n| n.to_array())),
+                ARRAY_BUFFER,
+                FLOAT,
+                mesh.normals.len(),
+                r#""VEC3""#,
+            );
+            attributes.push_str(&format!(r#","NORMAL":{normals}"#));
+        }
+
+        if mesh.uvs.len() == mesh.positions.len() {
+            let uvs = self.add_accessor(
+                &floats(mesh.uvs.iter().flat_map(|uv| uv.to_array())),
+                ARRAY_BUFFER,
+                FLOAT,
+                mesh.uvs.len(),
+                r#""VEC2""#,
+            );
+            attributes.push_str(&format!(r#","TEXCOORD_0":{uvs}"#));
+        }
+
+        let colors = if mesh.colors.len() == mesh.positions.len() {
+            mesh.colors.clone()
+        } else {
+            vec![mesh.color; mesh.positions.len()]
+        };
+        let colors = self.add_accessor(
+            &floats(colors.iter().flat_map(|c: &Color| [c.r, c.g, c.b, c.a])),
+            ARRAY_BUFFER,
+            FLOAT,
+            colors.len(),
+            r#""VEC4""#,
+        );
+        attributes.push_str(&format!(r#","COLOR_0":{colors}"#));
+
+        let indices = self.add_accessor(
+            &mesh
+                .indices
+                .iter()
+                .flat_map(|index| index.to_le_bytes())
+                .collect::<Vec<_>>(),
+            ELEMENT_ARRAY_BUFFER,
+            UNSIGNED_INT,
+            mesh.indices.len(),
+            r#""SCALAR""#,
+        );
+
+        let mut primitive = format!(r#"{{"attributes":{{{attributes}}},"indices":{indices}"#);
+        if let Some(material) = &mesh.material {
+            let material = self.material(material);
+            primitive.push_str(&format!(r#","material":{material}"#));
+        }
+        primitive.push('}');
+        primitive
+    }
+
+    /// Returns the index of the material named `name`, adding it if it is new.
+    fn material(&mut self, name: &str) -> usize {
+        if let Some(index) = self.materials.iter().position(|material| material == name) {
+            return index;
+        }
+        self.materials.push(name.to_string());
+        self.materials.len() - 1
+    }
+
+    /// Appends `bytes` to the buffer in its own buffer view, returns the index of the accessor.
+    fn add_accessor(
+        &mut self,
+        bytes: &[u8],
+        target: u32,
+        component_type: u32,
+        count: usize,
+        kind: &str,
+    ) -> usize {
+        self.buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{target}}}"#,
+            self.buffer.len(),
+            bytes.len()
+        ));
+        self.buffer.extend_from_slice(bytes);
+
+        self.accessors.push(format!(
+            r#"{{"bufferView":{},"componentType":{component_type},"count":{count},"type":{kind}}}"#,
+            self.buffer_views.len() - 1
+        ));
+        self.accessors.len() - 1
+    }
+
+    fn write(&self, scene: &[usize], writer: &mut impl Write) -> io::Result<()> {
+        let mut properties = vec![
+            r#""asset":{"version":"2.0","generator":"scebpl-system"}"#.to_string(),
+            r#""scene":0"#.to_string(),
+            format!(r#""scenes":[{{"nodes":{}}}]"#, json_array(scene)),
+            format!(r#""nodes":[{}]"#, self.nodes.join(",")),
+        ];
+        if !self.meshes.is_empty() {
+            properties.push(format!(r#""meshes":[{}]"#, self.meshes.join(",")));
+        }
+        if !self.materials.is_empty() {
+            let materials = self
+                .materials
+                .iter()
+                .map(|name| format!(r#"{{"name":{}}}"#, json_string(name)))
+                .collect::<Vec<_>>();
+            properties.push(format!(r#""materials":[{}]"#, materials.join(",")));
+        }
+        if !self.accessors.is_empty() {
+            properties.push(format!(r#""accessors":[{}]"#, self.accessors.join(",")));
+        }
+        if !self.buffer_views.is_empty() {
+            properties.push(format!(
+                r#""bufferViews":[{}]"#,
+                self.buffer_views.join(",")
+            ));
+        }
+        // glTF requires buffers to have a positive `byteLength`.
+        if !self.buffer.is_empty() {
+            properties.push(format!(
+                r#""buffers":[{{"byteLength":{},"uri":"data:application/octet-stream;base64,{}"}}]"#,
+                self.buffer.len(),
+                base64(&self.buffer)
+            ));
+        }
+
+        writeln!(writer, "{{\n  {}\n}}", properties.join(",\n  "))
+    }
+}
+
+/// Triangulates a convex polygon as a fan around its first vertex.
+fn polygon_mesh(polygon: &Polygon) -> Mesh {
+    let corners = polygon.vertices.len() as u32;
+    let indices = (1..corners.saturating_sub(1))
+        .flat_map(|corner| [0, corner, corner + 1])
+        .collect();
+
+    Mesh {
+        color: polygon.color,
+        depth: polygon.depth,
+        material: polygon.material.clone(),
+        ..Mesh::new(polygon.vertices.clone(), indices)
+    }
+}
+
+/// Merges meshes into one mesh with per vertex colors,
+/// normals and texture coordinates are kept only if all meshes have them.
+fn merge_meshes(meshes: &[Mesh]) -> Mesh {
+    let with_normals = meshes
+        .iter()
+        .all(|mesh| mesh.normals.len() == mesh.positions.len());
+    let with_uvs = meshes
+        .iter()
+        .all(|mesh| mesh.uvs.len() == mesh.positions.len());
+    let mut merged = Mesh::default();
+
+    for mesh in meshes {
+        let offset = merged.positions.len() as u32;
+        merged.positions.extend(&mesh.positions);
+        if with_normals {
+            merged.normals.extend(&mesh.normals);
+        }
+        if with_uvs {
+            merged.uvs.extend(&mesh.uvs);
+        }
+        if mesh.colors.len() == mesh.positions.len() {
+            merged.colors.extend(&mesh.colors);
+        } else {
+            merged
+                .colors
+                .extend(std::iter::repeat_n(mesh.color, mesh.positions.len()));
+        }
+        merged
+            .indices
+            .extend(mesh.indices.iter().map(|index| index + offset));
+    }
+
+    merged
+}
+
+fn floats(values: impl Iterator<Item = f32>) -> Vec<u8> {
+    values.flat_map(f32::to_le_bytes).collect()
+}
+
+fn json_array(values: &[usize]) -> String {
+    let values = values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("[{values}]")
+}
+
+/// Encodes `bytes` as standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let triple = chunk.iter().enumerate().fold(0u32, |triple, (i, byte)| {
+            triple | (*byte as u32) << (16 - i * 8)
+        });
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(triple >> (18 - i * 6)) as usize & 63] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
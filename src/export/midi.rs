@@ -0,0 +1,127 @@
+use std::io::{self, Write};
+
+use crate::{ExecuteContext, SoundEvent};
+
+/// Writes the notes played by an lsystem as a Standard MIDI File of format 0, a single track.
+///
+/// Run the lsystem with `ActionResolver::with_sound_actions` to collect the `SoundEvent`s.
+pub struct MidiExporter {
+    /// The resolution of the file, the number of ticks per beat.
+    pub ticks_per_beat: u16,
+    /// The tempo in beats per minute.
+    pub tempo: f32,
+    /// The MIDI channel the notes are played on, `0..16`.
+    pub channel: u8,
+}
+
+impl MidiExporter {
+    pub fn new() -> Self {
+        Self {
+            ticks_per_beat: 480,
+            tempo: 120.0,
+            channel: 0,
+        }
+    }
+
+    pub fn with_tempo(mut self, tempo: f32) -> Self {
+        self.tempo = tempo;
+        self
+    }
+
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = channel & 0x0f;
+        self
+    }
+
+    pub fn export(
+        &self,
+        context: &ExecuteContext<SoundEvent>,
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        self.export_events(&context.elements, writer)
+    }
+
+    /// Writes `events` in the order of their time, notes stopping at a time are stopped before notes starting at it.
+    ///
+    /// Notes last at least one tick, a `NoteOff` at the time of its `NoteOn` would otherwise be written first.
+    pub fn export_events(&self, events: &[SoundEvent], writer: &mut impl Write) -> io::Result<()> {
+        // The ticks of the notes that are playing, by pitch, a `NoteOff` stops the earliest one.
+        let mut playing = vec![Vec::new(); 128];
+        let mut events = events
+            .iter()
+            .map(|event| {
+                let ticks = self.ticks(event.time());
+                match *event {
+                    SoundEvent::NoteOn { pitch, .. } => {
+                        playing[usize::from(pitch & 0x7f)].push(ticks);
+                        (ticks, event)
+                    }
+                    SoundEvent::NoteOff { pitch, .. } => {
+                        let notes = &mut playing[usize::from(pitch & 0x7f)];
+                        let started = (!notes.is_empty()).then(|| notes.remove(0));
+                        let ticks = started.map_or(ticks, |started| ticks.max(started + 1));
+                        (ticks, event)
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        events.sort_by_key(|(ticks, event)| (*ticks, matches!(event, SoundEvent::NoteOn { .. })));
+
+        let mut track = vec![];
+        let microseconds_per_beat = (60_000_000.0 / self.tempo.max(1.0)).round() as u32;
+        write_delta(&mut track, 0);
+        track.extend([0xff, 0x51, 0x03]);
+        track.extend(&microseconds_per_beat.to_be_bytes()[1..]);
+
+        let mut previous = 0;
+        for (ticks, event) in events {
+            write_delta(&mut track, ticks - previous);
+            previous = ticks;
+            match *event {
+                SoundEvent::NoteOn {
+                    pitch, velocity, ..
+                } => track.extend([0x90 | self.channel, pitch & 0x7f, velocity & 0x7f]),
+                SoundEvent::NoteOff { pitch, .. } => {
+                    track.extend([0x80 | self.channel, pitch & 0x7f, 0])
+                }
+            }
+        }
+        write_delta(&mut track, 0);
+        track.extend([0xff, 0x2f, 0x00]);
+
+        writer.write_all(b"MThd")?;
+        writer.write_all(&6u32.to_be_bytes())?;
+        // Format 0 with a single track.
+        writer.write_all(&0u16.to_be_bytes())?;
+        writer.write_all(&1u16.to_be_bytes())?;
+        writer.write_all(&self.ticks_per_beat.to_be_bytes())?;
+
+        let len = u32::try_from(track.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "track is too long"))?;
+        writer.write_all(b"MTrk")?;
+        writer.write_all(&len.to_be_bytes())?;
+        writer.write_all(&track)
+    }
+
+    /// Converts a time in beats into ticks, negative times start the track.
+    fn ticks(&self, time: f32) -> u32 {
+        (time.max(0.0) * self.ticks_per_beat as f32).round() as u32
+    }
+}
+
+impl Default for MidiExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Writes `delta` as a variable length quantity, seven bits per byte with the highest byte first.
+fn write_delta(track: &mut Vec<u8>, delta: u32) {
+    let mut bytes = vec![(delta & 0x7f) as u8];
+    let mut rest = delta >> 7;
+    while rest > 0 {
+        bytes.push((rest & 0x7f) as u8 | 0x80);
+        rest >>= 7;
+    }
+    track.extend(bytes.iter().rev());
+}
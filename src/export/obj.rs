@@ -0,0 +1,215 @@
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use crate::{Color, ExecuteContext, Geometry, Mesh, Polygon, Segment};
+
+use super::Exporter;
+
+/// Writes the geometry of an lsystem as a Wavefront OBJ file.
+///
+/// Connected segments are written as `l` polyline elements, polygons and the triangles of meshes as `f` faces.
+/// The texture coordinates and normals of meshes are written as `vt` and `vn` elements referred to by their faces,
+/// e.g. to texture the bark of `Mesh::tube`.
+/// Segments, polygons and meshes with a material are written after the others, grouped by material
+/// into a `g` group using the `usemtl` material of the same name, see `GeometryElement::tagged`.
+/// OBJ names end at whitespace, thus whitespace in material names is written as `_`.
+/// OBJ has no instancing, instances are written as copies of their model, see `Geometry::expand_instances`.
+/// Instances of models that are not part of the geometry are not written.
+pub struct ObjExporter;
+
+impl ObjExporter {
+    pub fn export_geometry(&self, geometry: &Geometry, writer: &mut impl Write) -> io::Result<()> {
+        let geometry = &*expand_instances(geometry);
+        // OBJ indices start at 1.
+        let mut index = 1;
+        let mut uv_index = 1;
+        let mut normal_index = 1;
+
+        let polylines = geometry.polylines();
+
+        for material in materials(geometry) {
+            if let Some((name, _)) = &material {
+                writeln!(writer, "g {name}")?;
+                writeln!(writer, "usemtl {name}")?;
+            }
+            let name = material.map(|(name, _)| name);
+            let of_material = |material: &Option<String>| material.as_deref().map(obj_name) == name;
+
+            for polyline in polylines
+                .iter()
+                .filter(|polyline| of_material(&polyline.material))
+            {
+                for point in &polyline.points {
+                    writeln!(writer, "v {} {} {}", point.x, point.y, point.z)?;
+                }
+
+                let indices = (index..index + polyline.points.len())
+                    .map(|index| index.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                writeln!(writer, "l {indices}")?;
+                index += polyline.points.len();
+            }
+
+            for polygon in geometry
+                .polygons
+                .iter()
+                .filter(|polygon| of_material(&polygon.material))
+            {
+                for vertex in &polygon.vertices {
+                    writeln!(writer, "v {} {} {}", vertex.x, vertex.y, vertex.z)?;
+                }
+
+                let indices = (index..index + polygon.vertices.len())
+                    .map(|index| index.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                writeln!(writer, "f {indices}")?;
+                index += polygon.vertices.len();
+            }
+
+            for mesh in geometry
+                .meshes
+                .iter()
+                .filter(|mesh| of_material(&mesh.material))
+            {
+                for position in &mesh.positions {
+                    writeln!(writer, "v {} {} {}", position.x, position.y, position.z)?;
+                }
+                let with_uvs = mesh.uvs.len() == mesh.positions.len();
+                if with_uvs {
+                    for uv in &mesh.uvs {
+                        writeln!(writer, "vt {} {}", uv.x, uv.y)?;
+                    }
+                }
+                let with_normals = mesh.normals.len() == mesh.positions.len();
+                if with_normals {
+                    for normal in &mesh.normals {
+                        writeln!(writer, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+                    }
+                }
+
+                for triangle in mesh.indices.chunks_exact(3) {
+                    let [a, b, c] = [0, 1, 2].map(|corner| {
+                        let vertex = triangle[corner] as usize;
+                        match (with_uvs, with_normals) {
+                            (false, false) => (index + vertex).to_string(),
+                            (true, false) => format!("{0}/{1}", index + vertex, uv_index + vertex),
+                            (false, true) => {
+                                format!("{0}//{1}", index + vertex, normal_index + vertex)
+                            }
+                            (true, true) => format!(
+                                "{}/{}/{}",
+                                index + vertex,
+                                uv_index + vertex,
+                                normal_index + vertex
+                            ),
+                        }
+                    });
+                    writeln!(writer, "f {a} {b} {c}")?;
+                }
+                index += mesh.positions.len();
+                if with_uvs {
+                    uv_index += mesh.uvs.len();
+                }
+                if with_normals {
+                    normal_index += mesh.normals.len();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the OBJ file referring to the material library `library`, e.g. `tree.mtl`,
+    /// and the library itself into `materials`, see `export_materials`.
+    pub fn export_with_materials(
+        &self,
+        geometry: &Geometry,
+        library: &str,
+        writer: &mut impl Write,
+        materials: &mut impl Write,
+    ) -> io::Result<()> {
+        writeln!(writer, "mtllib {library}")?;
+        self.export_geometry(geometry, writer)?;
+        self.export_materials(geometry, materials)
+    }
+
+    /// Writes an MTL material library with a material for every material of the segments, polygons and meshes,
+    /// with the diffuse color of the first element of that material.
+    pub fn export_materials(&self, geometry: &Geometry, writer: &mut impl Write) -> io::Result<()> {
+        for (name, color) in materials(&expand_instances(geometry)).into_iter().flatten() {
+            writeln!(writer, "newmtl {name}")?;
+            writeln!(writer, "Kd {} {} {}", color.r, color.g, color.b)?;
+            writeln!(writer, "d {}", color.a)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the geometry with the instances replaced by their models, borrowed if no instance has a model.
+fn expand_instances(geometry: &Geometry) -> Cow<'_, Geometry> {
+    if geometry
+        .instances
+        .iter()
+        .any(|instance| geometry.models.contains_key(&instance.name))
+    {
+        let mut expanded = geometry.clone();
+        expanded.expand_instances();
+        Cow::Owned(expanded)
+    } else {
+        Cow::Borrowed(geometry)
+    }
+}
+
+/// Returns the names of the materials of the segments, polygons and meshes in the order they first appear
+/// with the color of their first element, `None` first for the elements without material.
+fn materials(geometry: &Geometry) -> Vec<Option<(String, Color)>> {
+    let segments = geometry.segments.iter().map(
+        |Segment {
+             material, color, ..
+         }| (material, *color),
+    );
+    let polygons = geometry.polygons.iter().map(
+        |Polygon {
+             material, color, ..
+         }| (material, *color),
+    );
+    let meshes = geometry.meshes.iter().map(
+        |Mesh {
+             material, color, ..
+         }| (material, *color),
+    );
+
+    let mut materials = vec![None];
+    for (material, color) in segments.chain(polygons).chain(meshes) {
+        if let Some(name) = material.as_deref().map(obj_name) {
+            if !materials.iter().flatten().any(|(known, _)| *known == name) {
+                materials.push(Some((name, color)));
+            }
+        }
+    }
+    materials
+}
+
+/// Returns `name` with its whitespace replaced by `_`, an empty name as `_`, thus it is a single OBJ name.
+fn obj_name(name: &str) -> String {
+    if name.is_empty() {
+        return "_".to_string();
+    }
+    name.chars()
+        .map(|char| if char.is_whitespace() { '_' } else { char })
+        .collect()
+}
+
+impl Exporter for ObjExporter {
+    fn format(&self) -> &'static str {
+        "obj"
+    }
+
+    fn export(&self, context: &ExecuteContext, mut writer: &mut dyn Write) -> io::Result<()> {
+        self.export_geometry(&context.geometry(), &mut writer)
+    }
+}
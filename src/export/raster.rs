@@ -0,0 +1,298 @@
+#[cfg(feature = "png")]
+use std::io::{self, Write};
+
+use macaw::Vec2;
+
+use crate::{Color, ExecuteContext, Geometry, Geometry2D, Polygon2D, Projection};
+
+/// Defines how the geometry of an lsystem is rasterized.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RasterStyle {
+    /// Width of the image in pixels.
+    pub width: u32,
+    /// Height of the image in pixels.
+    pub height: u32,
+    pub background: Color,
+    /// Line width in pixels of a segment with width `1.0`.
+    pub line_width: f32,
+    /// Margin around the drawing in pixels.
+    pub margin: f32,
+    /// Projects the geometry onto the image, onto the xy plane by default.
+    pub projection: Projection,
+}
+
+impl Default for RasterStyle {
+    fn default() -> Self {
+        Self {
+            width: 512,
+            height: 512,
+            background: Color::WHITE,
+            line_width: 1.0,
+            margin: 8.0,
+            projection: Projection::default(),
+        }
+    }
+}
+
+/// An RGBA image with the rasterized geometry of an lsystem.
+///
+/// The geometry is projected with `RasterStyle::projection` and fitted into the image, keeping its aspect ratio.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Raster {
+    pub width: u32,
+    pub height: u32,
+    /// Row major RGBA pixels, 4 bytes per pixel.
+    pub pixels: Vec<u8>,
+}
+
+impl Raster {
+    /// Creates an image filled with `background`.
+    pub fn new(width: u32, height: u32, background: Color) -> Self {
+        let pixel = Self::to_rgba(background);
+        Self {
+            width,
+            height,
+            pixels: pixel.repeat((width * height) as usize),
+        }
+    }
+
+    pub fn render(context: &ExecuteContext, style: &RasterStyle) -> Self {
+        Self::render_geometry(&context.geometry(), style)
+    }
+
+    pub fn render_geometry(geometry: &Geometry, style: &RasterStyle) -> Self {
+        Self::render_planar(&style.projection.project(geometry), style)
+    }
+
+    /// Rasterizes geometry that is already projected, e.g. drawn by `LSystem::run_planar`.
+    pub fn render_planar(geometry: &Geometry2D, style: &RasterStyle) -> Self {
+        let mut raster = Raster::new(style.width, style.height, style.background);
+
+        let Some((min, max)) = geometry.bounds() else {
+            return raster;
+        };
+
+        // Scale the extent of the drawing into the image, keeping the aspect ratio.
+        let size = max - min;
+        let available_x = (style.width as f32 - style.margin * 2.0).max(1.0);
+        let available_y = (style.height as f32 - style.margin * 2.0).max(1.0);
+        let scale =
+            (available_x / size.x.max(f32::EPSILON)).min(available_y / size.y.max(f32::EPSILON));
+        let center = (min + max) * 0.5;
+        let image_center = Vec2::new(style.width as f32, style.height as f32) * 0.5;
+
+        // Images have their y axis pointing down, thus all y coordinates are flipped.
+        let project =
+            |point: Vec2| image_center + Vec2::new(point.x - center.x, center.y - point.y) * scale;
+
+        for polygon in &geometry.polygons {
+            raster.fill_polygon(polygon, &project);
+        }
+
+        for segment in &geometry.segments {
+            let width = (segment.width * style.line_width).max(1.0);
+            raster.draw_line(project(segment.a), project(segment.b), width, segment.color);
+        }
+
+        raster
+    }
+
+    /// Returns the RGBA color of the pixel at `x`, `y`.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * self.width + x) * 4) as usize;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.pixels[index..index + 4]);
+        pixel
+    }
+
+    /// Draws an anti-aliased line of `width` pixels from `a` to `b`.
+    pub fn draw_line(&mut self, a: Vec2, b: Vec2, width: f32, color: Color) {
+        let half_width = width * 0.5;
+        let min = a.min(b) - Vec2::splat(half_width + 1.0);
+        let max = a.max(b) + Vec2::splat(half_width + 1.0);
+
+        self.for_each_pixel(
+            min,
+            max,
+            |center| {
+                let distance = distance_to_segment(center, a, b);
+                // Pixels partially covered by the line edge are blended proportionally.
+                (half_width + 0.5 - distance).clamp(0.0, 1.0)
+            },
+            color,
+        );
+    }
+
+    fn fill_polygon(&mut self, polygon: &Polygon2D, project: &impl Fn(Vec2) -> Vec2) {
+        let vertices = polygon
+            .vertices
+            .iter()
+            .map(|vertex| project(*vertex))
+            .collect::<Vec<_>>();
+        let Some(first) = vertices.first() else {
+            return;
+        };
+
+        let (min, max) = vertices
+            .iter()
+            .fold((*first, *first), |(min, max), vertex| {
+                (min.min(*vertex), max.max(*vertex))
+            });
+
+        self.for_each_pixel(
+            min,
+            max,
+            |center| {
+                if contains_point(&vertices, center) {
+                    1.0
+                } else {
+                    0.0
+                }
+            },
+            polygon.color,
+        );
+    }
+
+    /// Blends `color` into all pixels between `min` and `max` with the coverage returned by `coverage`.
+    fn for_each_pixel(
+        &mut self,
+        min: Vec2,
+        max: Vec2,
+        coverage: impl Fn(Vec2) -> f32,
+        color: Color,
+    ) {
+        let x_range = min.x.floor().max(0.0) as u32..(max.x.ceil().max(0.0) as u32).min(self.width);
+        let y_range =
+            min.y.floor().max(0.0) as u32..(max.y.ceil().max(0.0) as u32).min(self.height);
+
+        for y in y_range {
+            for x in x_range.clone() {
+                let coverage = coverage(Vec2::new(x as f32 + 0.5, y as f32 + 0.5));
+                if coverage > 0.0 {
+                    self.blend(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: Color, coverage: f32) {
+        let index = ((y * self.width + x) * 4) as usize;
+        let alpha = color.a * coverage;
+        let source = Self::to_rgba(color);
+
+        for (destination, source) in self.pixels[index..index + 3].iter_mut().zip(source) {
+            *destination = blend_channel(*destination, source, alpha);
+        }
+
+        let destination_alpha = self.pixels[index + 3] as f32 / 255.0;
+        self.pixels[index + 3] =
+            ((alpha + destination_alpha * (1.0 - alpha)) * 255.0).round() as u8;
+    }
+
+    fn to_rgba(color: Color) -> [u8; 4] {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        [
+            channel(color.r),
+            channel(color.g),
+            channel(color.b),
+            channel(color.a),
+        ]
+    }
+}
+
+#[cfg(feature = "png")]
+impl Raster {
+    /// Encodes the image as an 8 bit RGBA PNG.
+    pub fn write_png(&self, writer: impl Write) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write_png(&mut buffer)
+            .expect("Writing to a vector can not fail.");
+        buffer
+    }
+}
+
+/// Rasterizes `geometry` with the default style into a PNG image of `width` by `height` pixels.
+///
+/// Rendering happens entirely on the CPU, no graphics context is required.
+#[cfg(feature = "png")]
+pub fn render_png(geometry: &Geometry, width: u32, height: u32) -> Vec<u8> {
+    let style = RasterStyle {
+        width,
+        height,
+        ..Default::default()
+    };
+    Raster::render_geometry(geometry, &style).to_png()
+}
+
+/// Writes the result of an lsystem as PNG image.
+#[cfg(feature = "png")]
+#[derive(Default)]
+pub struct PngExporter {
+    pub style: RasterStyle,
+}
+
+#[cfg(feature = "png")]
+impl PngExporter {
+    pub fn new(style: RasterStyle) -> Self {
+        Self { style }
+    }
+}
+
+#[cfg(feature = "png")]
+impl super::Exporter for PngExporter {
+    fn format(&self) -> &'static str {
+        "png"
+    }
+
+    fn export(&self, context: &ExecuteContext, writer: &mut dyn Write) -> io::Result<()> {
+        Raster::render(context, &self.style).write_png(writer)
+    }
+}
+
+/// Blends the `source` channel over the `destination` channel with opacity `alpha`.
+pub(crate) fn blend_channel(destination: u8, source: u8, alpha: f32) -> u8 {
+    let destination = destination as f32;
+    (destination + (source as f32 - destination) * alpha).round() as u8
+}
+
+fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared == 0.0 {
+        return point.distance(a);
+    }
+
+    let t = ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0);
+    point.distance(a + ab * t)
+}
+
+/// Even-odd test whether `point` lies inside the polygon formed by `vertices`.
+fn contains_point(vertices: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    let mut previous = vertices[vertices.len() - 1];
+
+    for vertex in vertices {
+        if (vertex.y > point.y) != (previous.y > point.y)
+            && point.x
+                < (previous.x - vertex.x) * (point.y - vertex.y) / (previous.y - vertex.y)
+                    + vertex.x
+        {
+            inside = !inside;
+        }
+        previous = *vertex;
+    }
+
+    inside
+}
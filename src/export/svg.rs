@@ -0,0 +1,205 @@
+use std::io::{self, Write};
+
+use crate::{ExecuteContext, Geometry, Geometry2D, Polygon2D, Projection, Segment2D};
+
+use super::Exporter;
+
+/// Defines how the geometry of the lsystem is grouped into `<g>` layers.
+pub enum SvgLayers {
+    /// All geometry is written into a single layer.
+    Single,
+    /// Every branch depth gets its own layer, named `depth-N`.
+    Depth,
+    /// Maps the branch depth to a layer name, depths mapping to the same name share a layer.
+    Named(Box<dyn Fn(usize) -> String>),
+}
+
+impl SvgLayers {
+    fn layer_name(&self, depth: usize) -> String {
+        match self {
+            SvgLayers::Single => "lsystem".to_string(),
+            SvgLayers::Depth => format!("depth-{depth}"),
+            SvgLayers::Named(name) => name(depth),
+        }
+    }
+}
+
+#[derive(Default)]
+struct SvgLayer<'a> {
+    segments: Vec<&'a Segment2D>,
+    polygons: Vec<&'a Polygon2D>,
+}
+
+/// Writes the geometry of an lsystem as an SVG document.
+///
+/// Every layer is written as a `<g>` element with an id and class equal to the layer name.
+/// The layers are also marked as inkscape layers so that plotter software can pen-swap per layer.
+pub struct SvgExporter {
+    pub layers: SvgLayers,
+    /// Stroke width of a segment with width `1.0`.
+    pub stroke_width: f32,
+    /// Margin around the drawing in drawing units.
+    pub margin: f32,
+    /// Projects the geometry onto the drawing, onto the xy plane by default.
+    pub projection: Projection,
+}
+
+impl SvgExporter {
+    pub fn new() -> Self {
+        Self {
+            layers: SvgLayers::Single,
+            stroke_width: 0.01,
+            margin: 0.05,
+            projection: Projection::default(),
+        }
+    }
+
+    pub fn with_layers(mut self, layers: SvgLayers) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    pub fn export(&self, context: &ExecuteContext, writer: &mut impl Write) -> io::Result<()> {
+        self.export_geometry(&context.geometry(), writer)
+    }
+
+    pub fn export_geometry(&self, geometry: &Geometry, writer: &mut impl Write) -> io::Result<()> {
+        self.export_planar(&self.projection.project(geometry), writer)
+    }
+
+    /// Writes geometry that is already projected, e.g. drawn by `LSystem::run_planar`.
+    pub fn export_planar(&self, geometry: &Geometry2D, writer: &mut impl Write) -> io::Result<()> {
+        let (min, max) = geometry.bounds().unwrap_or_default();
+
+        let margin = self.margin;
+        let width = max.x - min.x + margin * 2.0;
+        let height = max.y - min.y + margin * 2.0;
+
+        // SVG has its y axis pointing down, thus all y coordinates are flipped.
+        writeln!(
+            writer,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" viewBox="{} {} {} {}">"#,
+            min.x - margin,
+            -max.y - margin,
+            width,
+            height
+        )?;
+
+        for (name, layer) in self.group_layers(geometry) {
+            let name = escape_xml(&name);
+            writeln!(
+                writer,
+                r#"  <g id="{name}" class="{name}" inkscape:groupmode="layer" inkscape:label="{name}" stroke-linecap="round">"#,
+            )?;
+
+            for polygon in layer.polygons {
+                let points = polygon
+                    .vertices
+                    .iter()
+                    .map(|vertex| format!("{},{}", vertex.x, -vertex.y))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                writeln!(
+                    writer,
+                    r#"    <polygon points="{points}" fill="{}" stroke="none"/>"#,
+                    polygon.color.to_hex()
+                )?;
+            }
+
+            for segment in layer.segments {
+                writeln!(
+                    writer,
+                    r#"    <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
+                    segment.a.x,
+                    -segment.a.y,
+                    segment.b.x,
+                    -segment.b.y,
+                    segment.color.to_hex(),
+                    self.stroke_width * segment.width
+                )?;
+            }
+
+            writeln!(writer, "  </g>")?;
+        }
+
+        writeln!(writer, "</svg>")
+    }
+
+    pub fn to_string(&self, context: &ExecuteContext) -> String {
+        let mut buffer = Vec::new();
+        self.export(context, &mut buffer)
+            .expect("Writing to a vector can not fail.");
+        String::from_utf8(buffer).expect("SVG output is valid utf8.")
+    }
+
+    /// Groups the geometry into layers ordered by their lowest depth.
+    fn group_layers<'a>(&self, geometry: &'a Geometry2D) -> Vec<(String, SvgLayer<'a>)> {
+        let mut depths = geometry
+            .segments
+            .iter()
+            .map(|segment| segment.depth)
+            .chain(geometry.polygons.iter().map(|polygon| polygon.depth))
+            .collect::<Vec<_>>();
+        depths.sort_unstable();
+        depths.dedup();
+
+        let mut layers: Vec<(String, SvgLayer)> = Vec::new();
+        for depth in depths {
+            let name = self.layers.layer_name(depth);
+            let index = match layers.iter().position(|(layer, _)| *layer == name) {
+                Some(index) => index,
+                None => {
+                    layers.push((name, SvgLayer::default()));
+                    layers.len() - 1
+                }
+            };
+
+            let layer = &mut layers[index].1;
+            layer
+                .segments
+                .extend(geometry.segments.iter().filter(|s| s.depth == depth));
+            layer
+                .polygons
+                .extend(geometry.polygons.iter().filter(|p| p.depth == depth));
+        }
+        layers
+    }
+}
+
+/// Escapes `name` for use in an attribute value, layer names are user defined.
+fn escape_xml(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for char in name.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+impl Exporter for SvgExporter {
+    fn format(&self) -> &'static str {
+        "svg"
+    }
+
+    fn export(&self, context: &ExecuteContext, mut writer: &mut dyn Write) -> io::Result<()> {
+        self.export_geometry(&context.geometry(), &mut writer)
+    }
+}
+
+impl Default for SvgExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,24 @@
+use std::io::{self, Write};
+
+use crate::{format_commands, ExecuteContext};
+
+use super::Exporter;
+
+/// Writes the turtle commands recorded while running an lsystem, see `RunOptions::record_commands`.
+///
+/// The text can be read back with `parse_commands` and drawn with `replay` without the lsystem.
+pub struct TurtleCommandExporter;
+
+impl Exporter for TurtleCommandExporter {
+    fn format(&self) -> &'static str {
+        "turtle"
+    }
+
+    fn extension(&self) -> &'static str {
+        "txt"
+    }
+
+    fn export(&self, context: &ExecuteContext, writer: &mut dyn Write) -> io::Result<()> {
+        writeln!(writer, "{}", format_commands(&context.commands))
+    }
+}
@@ -0,0 +1,198 @@
+//! Reads the classic bracketed L-system files of Fractint (`.l`) and the collections in the style of ABOP.
+//!
+//! ```text
+//! Koch {          ; comments start with a semicolon
+//!   Angle 6
+//!   Axiom F--F--F
+//!   F=F+F--F+F
+//! }
+//! ```
+//!
+//! Files without `Name { }` entries are read as a single lsystem, their rules may be written as `rule F=F+F`.
+use core::{f32::consts::TAU, fmt};
+
+use crate::{prelude::*, Action, ActionParam, Item, ItemKind, StatementKind};
+
+/// Commands of Fractint that have no equivalent in the default actions.
+const UNSUPPORTED_COMMANDS: [char; 8] = ['!', '\\', '/', '@', '<', '>', 'D', 'M'];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FractintError {
+    /// The line is neither an `angle`, `axiom` nor a rule statement.
+    InvalidLine { line: usize, text: String },
+    /// The lsystem `name` has no `axiom` statement.
+    MissingAxiom { name: String },
+    /// The lsystem `name` turns but has no `angle` statement.
+    MissingAngle { name: String },
+    /// The lsystem `name` uses a command that can not be expressed with the default actions,
+    /// e.g. `!` swapping the turn directions or `C12` selecting a color.
+    UnsupportedCommand { name: String, command: char },
+}
+
+impl fmt::Display for FractintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FractintError::InvalidLine { line, text } => {
+                write!(f, "line {line} `{text}` is no angle, axiom or rule")
+            }
+            FractintError::MissingAxiom { name } => write!(f, "lsystem `{name}` has no axiom"),
+            FractintError::MissingAngle { name } => write!(f, "lsystem `{name}` has no angle"),
+            FractintError::UnsupportedCommand { name, command } => {
+                write!(f, "lsystem `{name}` uses unsupported command `{command}`")
+            }
+        }
+    }
+}
+
+impl core::error::Error for FractintError {}
+
+/// The statements of one lsystem of a Fractint file.
+#[derive(Default)]
+struct Entry {
+    name: String,
+    /// Number of divisions of a full turn.
+    angle: Option<f32>,
+    axiom: Option<String>,
+    rules: Vec<(String, String)>,
+}
+
+/// Parses every lsystem of the Fractint file `source` into an `Item`, load them with `LSystemParser::parse`.
+///
+/// Fractint ignores case, thus all symbols are read uppercase.
+/// `F` draws, `G` moves without drawing, `+ -` turn left and right by `360 / angle` degrees,
+/// `|` turns around and `[ ]` push and pop the turtle.
+/// The items interpret these symbols with the default actions, `G` is aliased to `f` to match their conventions.
+pub fn parse_fractint(source: &str) -> Result<Vec<Item>, FractintError> {
+    let mut entries = vec![];
+    let mut entry: Option<Entry> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line = line.split(';').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_suffix('{') {
+            entries.extend(entry.take());
+            entry = Some(Entry {
+                name: name.trim().to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+        if line == "}" {
+            entries.extend(entry.take());
+            continue;
+        }
+
+        let current = entry.get_or_insert_with(Entry::default);
+        let invalid = || FractintError::InvalidLine {
+            line: index + 1,
+            text: line.to_string(),
+        };
+
+        let (keyword, value) = line
+            .split_once(char::is_whitespace)
+            .map(|(keyword, value)| (keyword.to_ascii_lowercase(), value.trim()))
+            .unwrap_or_default();
+        match keyword.as_str() {
+            "angle" => current.angle = Some(value.parse().map_err(|_| invalid())?),
+            "axiom" => current.axiom = Some(symbols(value)),
+            _ => {
+                let rule = if keyword == "rule" { value } else { line };
+                let (predecessor, successor) = rule
+                    .split_once("->")
+                    .or_else(|| rule.split_once('='))
+                    .ok_or_else(invalid)?;
+                let predecessor = symbols(predecessor);
+                if predecessor.chars().count() != 1 {
+                    return Err(invalid());
+                }
+                current.rules.push((predecessor, symbols(successor)));
+            }
+        }
+    }
+    entries.extend(entry);
+
+    entries.into_iter().map(Entry::into_item).collect()
+}
+
+impl Entry {
+    fn into_item(self) -> Result<Item, FractintError> {
+        let axiom = self.axiom.ok_or_else(|| FractintError::MissingAxiom {
+            name: self.name.clone(),
+        })?;
+
+        let used = self
+            .rules
+            .iter()
+            .flat_map(|(_, successor)| successor.chars())
+            .chain(axiom.chars())
+            .collect::<Vec<_>>();
+
+        let mut previous = None;
+        for &symbol in &used {
+            let command = match symbol {
+                '0'..='9' | '.' => previous.unwrap_or(symbol),
+                _ if UNSUPPORTED_COMMANDS.contains(&symbol) => symbol,
+                _ => {
+                    previous = Some(symbol);
+                    continue;
+                }
+            };
+            return Err(FractintError::UnsupportedCommand {
+                name: self.name,
+                command,
+            });
+        }
+
+        let mut statements = vec![StatementKind::Axiom(axiom)];
+        if used.contains(&'G') {
+            statements.push(StatementKind::Alias('G', 'f'));
+        }
+
+        let turns = used.contains(&'+') || used.contains(&'-');
+        let angle = match self.angle {
+            Some(divisions) => TAU / divisions,
+            None if turns => return Err(FractintError::MissingAngle { name: self.name }),
+            None => 0.0,
+        };
+
+        let commands = [
+            ('F', "DrawForwardAction", vec![1.0]),
+            ('G', "MoveForwardAction", vec![1.0]),
+            ('+', "RotateZAction", vec![angle]),
+            ('-', "RotateZAction", vec![-angle]),
+            ('|', "TurnAroundAction", vec![]),
+            ('[', "PushTranformToStackAction", vec![]),
+            (']', "PopTransformFromStackAction", vec![]),
+        ];
+        for (symbol, name, params) in commands {
+            if used.contains(&symbol) {
+                let params = params.into_iter().map(ActionParam::Number).collect();
+                statements.push(StatementKind::Interpret(
+                    symbol.to_string(),
+                    Action::new(name.to_string(), params),
+                ));
+            }
+        }
+
+        statements.extend(
+            self.rules
+                .into_iter()
+                .map(|(predecessor, successor)| StatementKind::Replace(predecessor, successor)),
+        );
+
+        Ok(Item {
+            item_kind: ItemKind::LSystem(self.name, statements),
+        })
+    }
+}
+
+/// Returns the symbols of `text` uppercase and without whitespace.
+fn symbols(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
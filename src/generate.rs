@@ -0,0 +1,198 @@
+use core::{fmt, time::Duration};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use crate::{CancellationToken, Progress};
+
+/// Limits and options applied while generating an L-system with `LSystem::generate_with`.
+///
+/// Exponential systems like `replace F by FFFFFFFF;` grow beyond any available memory within a few generations.
+/// All limits are disabled by default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GenerateOptions {
+    /// Maximum number of symbols a generation may contain.
+    pub max_symbols: Option<usize>,
+    /// Maximum number of bytes used by the symbol buffers.
+    pub max_memory: Option<usize>,
+    /// Maximum time generating all generations may take, not supported on `wasm32-unknown-unknown` which has no clock.
+    /// Ignored without the `std` feature.
+    pub timeout: Option<Duration>,
+    /// Records a `DerivationTrace` in the `GenerationReport`, disabled by default.
+    pub trace: bool,
+    /// Expands every symbol once per number of remaining generations and copies the expansion wherever the symbol occurs,
+    /// if the lsystem is memoizable and no trace is recorded, see `LSystem::is_memoizable`. Disabled by default.
+    /// All generations are expanded at once, thus exceeded budgets are reported for the last generation.
+    pub memoize: bool,
+    /// Aborts generation with `GenerateError::Cancelled` once the token is cancelled.
+    pub cancellation: Option<CancellationToken>,
+    /// Called with the number of rewritten generations after every generation.
+    pub progress: Option<Progress>,
+}
+
+impl GenerateOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_symbols(mut self, max_symbols: usize) -> Self {
+        self.max_symbols = Some(max_symbols);
+        self
+    }
+
+    pub fn with_max_memory(mut self, max_memory: usize) -> Self {
+        self.max_memory = Some(max_memory);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_trace(mut self) -> Self {
+        self.trace = true;
+        self
+    }
+
+    pub fn with_memoization(mut self) -> Self {
+        self.memoize = true;
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    pub fn with_progress(
+        mut self,
+        progress: impl Fn(usize, usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Progress::new(progress));
+        self
+    }
+}
+
+/// The budget of `GenerateOptions` that was exceeded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Budget {
+    Symbols,
+    Memory,
+    Timeout,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GenerateError {
+    /// Generation was aborted while rewriting `generation` because `budget` was exceeded.
+    BudgetExceeded { budget: Budget, generation: u8 },
+    /// Generation was aborted while rewriting `generation` because the `CancellationToken` was cancelled.
+    Cancelled { generation: u8 },
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerateError::BudgetExceeded { budget, generation } => {
+                let budget = match budget {
+                    Budget::Symbols => "symbol",
+                    Budget::Memory => "memory",
+                    Budget::Timeout => "time",
+                };
+                write!(
+                    f,
+                    "generation {} exceeded the {} budget",
+                    generation, budget
+                )
+            }
+            GenerateError::Cancelled { generation } => {
+                write!(f, "generation {generation} was cancelled")
+            }
+        }
+    }
+}
+
+impl core::error::Error for GenerateError {}
+
+/// How many symbols are rewritten between two checks of the timeout and the cancellation.
+const TIMEOUT_CHECK_INTERVAL: usize = 1024;
+
+/// Why a `GenerateGuard` aborted generation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Abort {
+    Budget(Budget),
+    Cancelled,
+}
+
+impl Abort {
+    /// Returns the error of aborting while rewriting `generation`.
+    pub(crate) fn error(self, generation: u8) -> GenerateError {
+        match self {
+            Abort::Budget(budget) => GenerateError::BudgetExceeded { budget, generation },
+            Abort::Cancelled => GenerateError::Cancelled { generation },
+        }
+    }
+}
+
+/// Tracks the `GenerateOptions` of a single `LSystem::generate_with` call.
+pub(crate) struct GenerateGuard<'a> {
+    options: &'a GenerateOptions,
+    #[cfg(feature = "std")]
+    deadline: Option<Instant>,
+}
+
+impl<'a> GenerateGuard<'a> {
+    pub(crate) fn new(options: &'a GenerateOptions) -> Self {
+        Self {
+            options,
+            // Only query the clock when a timeout is set, `Instant` is not available on every target.
+            #[cfg(feature = "std")]
+            deadline: options.timeout.map(|timeout| Instant::now() + timeout),
+        }
+    }
+
+    /// Caps the capacity reserved for the next generation by the symbol budget.
+    pub(crate) fn cap_reservation(&self, reservation: usize) -> usize {
+        match self.options.max_symbols {
+            Some(max_symbols) => reservation.min(max_symbols + 1),
+            None => reservation,
+        }
+    }
+
+    /// Checks the budgets and the cancellation after `rewritten` symbols of the current generation have been rewritten.
+    pub(crate) fn check(&self, current: usize, next: usize, rewritten: usize) -> Result<(), Abort> {
+        if self.options.max_symbols.is_some_and(|max| next > max) {
+            return Err(Abort::Budget(Budget::Symbols));
+        }
+
+        let memory = (current + next) * core::mem::size_of::<char>();
+        if self.options.max_memory.is_some_and(|max| memory > max) {
+            return Err(Abort::Budget(Budget::Memory));
+        }
+
+        if rewritten.is_multiple_of(TIMEOUT_CHECK_INTERVAL) {
+            #[cfg(feature = "std")]
+            if let Some(deadline) = self.deadline {
+                if Instant::now() > deadline {
+                    return Err(Abort::Budget(Budget::Timeout));
+                }
+            }
+            self.check_cancellation()?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn check_cancellation(&self) -> Result<(), Abort> {
+        match &self.options.cancellation {
+            Some(cancellation) if cancellation.is_cancelled() => Err(Abort::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reports that `done` of `total` generations have been rewritten.
+    pub(crate) fn report_progress(&self, done: u8, total: u8) {
+        if let Some(progress) = &self.options.progress {
+            progress.report(done as usize, total as usize);
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Breeding lsystems: mutating and crossing them to evolve shapes, e.g. plants picked interactively.
+//!
+//! Both operators return a new lsystem and keep it valid: brackets stay balanced,
+//! only symbols of the lsystem or of `MutationConfig::symbols` are inserted and stochastic rules keep their total probability.
+//! Rule tables, context sensitive, parametric and timed rules are not bred, they are kept as they are.
+use crate::{prelude::*, ActionParam, LSystem, Rng, SymbolDefiner, P};
+
+/// How much `mutate` changes an lsystem.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MutationConfig {
+    /// The probability that a symbol of a successor is replaced, removed, followed by another symbol or put in a branch.
+    pub symbol_rate: f32,
+    /// The symbols inserted into successors, the symbols the successors and the axiom already use if empty.
+    /// Brackets and parameter lists are never inserted or replaced, brackets and parentheses of `symbols` are ignored.
+    pub symbols: Vec<char>,
+    /// Successors are not grown beyond this many symbols.
+    pub max_successor_len: usize,
+    /// The probability that the probabilities of the stochastic rules of a predecessor are perturbed.
+    pub weight_rate: f32,
+    /// The largest relative change of a probability, `0.2` scales it by `0.8..1.2`.
+    pub weight_scale: f32,
+    /// The probability that a number parameter of an action or a variable is perturbed.
+    pub param_rate: f32,
+    /// The largest relative change of a parameter, `0.1` scales it by `0.9..1.1`.
+    pub param_scale: f32,
+}
+
+impl Default for MutationConfig {
+    fn default() -> Self {
+        Self {
+            symbol_rate: 0.05,
+            symbols: vec![],
+            max_successor_len: 64,
+            weight_rate: 0.5,
+            weight_scale: 0.2,
+            param_rate: 0.2,
+            param_scale: 0.1,
+        }
+    }
+}
+
+/// Returns a copy of `lsystem` with perturbed successors, stochastic probabilities, action parameters and variables.
+///
+/// The same `rng` state gives the same mutation.
+pub fn mutate<A: SymbolDefiner + Clone>(
+    lsystem: &LSystem<A>,
+    config: &MutationConfig,
+    rng: &mut dyn Rng,
+) -> LSystem<A> {
+    let mut mutant = lsystem.clone();
+    let symbols = if config.symbols.is_empty() {
+        used_symbols(lsystem)
+    } else {
+        // Inserted brackets and parentheses would unbalance the successors.
+        config
+            .symbols
+            .iter()
+            .copied()
+            .filter(|symbol| !matches!(symbol, '[' | ']' | '(' | ')'))
+            .collect()
+    };
+
+    for (predecessor, successor) in lsystem.generic_rules() {
+        let successor = mutate_successor(successor, &symbols, config, rng);
+        mutant.override_rule(predecessor, successor);
+    }
+
+    for (predecessor, rules) in lsystem.stochastic_rule_sets() {
+        let mut probabilities = rules
+            .iter()
+            .map(|(probability, _)| *probability)
+            .collect::<Vec<_>>();
+        if rng.next_f32() < config.weight_rate {
+            perturb_weights(&mut probabilities, config.weight_scale, rng);
+        }
+
+        mutant.remove_stochastic_rules(predecessor);
+        for (probability, (_, successor)) in probabilities.into_iter().zip(rules) {
+            let successor = mutate_successor(successor, &symbols, config, rng);
+            mutant.add_stochastic_rule(predecessor, probability, successor);
+        }
+    }
+
+    for (symbol, actions) in lsystem.action_bindings() {
+        mutant.remove_action_rules(&symbol);
+        for action in actions {
+            let mut action = action.clone();
+            for param in &mut action.params.params {
+                perturb_param(param, config, rng);
+            }
+            mutant.add_action_rule(symbol.clone(), action);
+        }
+    }
+
+    let mut variables = lsystem.variables().iter().collect::<Vec<_>>();
+    variables.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, value) in variables {
+        if rng.next_f32() < config.param_rate {
+            mutant.set_variable(name, value * scale(config.param_scale, rng));
+        }
+    }
+
+    mutant
+}
+
+/// Returns a child of `a` and `b` inheriting the axiom, the rules, the actions and the variables of either parent.
+///
+/// Each predecessor, interpreted symbol and variable is taken from `b` with probability `0.5`, all its rules at once.
+/// Everything `b` does not define, e.g. the symbol definer and the seed, is inherited from `a`.
+pub fn crossover<A: SymbolDefiner + Clone>(
+    a: &LSystem<A>,
+    b: &LSystem<A>,
+    rng: &mut dyn Rng,
+) -> LSystem<A> {
+    let mut child = a.clone();
+    let mut from_b = || rng.next_f32() < 0.5;
+
+    if from_b() {
+        child.axiom = b.axiom.clone();
+    }
+
+    for (predecessor, successor) in b.generic_rules() {
+        if from_b() {
+            child.override_rule(predecessor, successor);
+        }
+    }
+
+    for (predecessor, rules) in b.stochastic_rule_sets() {
+        if from_b() {
+            child.remove_stochastic_rules(predecessor);
+            for (probability, successor) in rules {
+                child.add_stochastic_rule(predecessor, probability, successor);
+            }
+        }
+    }
+
+    for (symbol, actions) in b.action_bindings() {
+        if from_b() {
+            child.remove_action_rules(&symbol);
+            for action in actions {
+                child.add_action_rule(symbol.clone(), action.clone());
+            }
+        }
+    }
+
+    let mut variables = b.variables().iter().collect::<Vec<_>>();
+    variables.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, value) in variables {
+        if from_b() {
+            child.set_variable(name, value);
+        }
+    }
+
+    child
+}
+
+/// Returns the symbols of the axiom and the successors, without brackets and parameter lists.
+fn used_symbols<A: SymbolDefiner>(lsystem: &LSystem<A>) -> Vec<char> {
+    let successors = lsystem
+        .generic_rules()
+        .into_iter()
+        .map(|(_, successor)| successor)
+        .chain(
+            lsystem
+                .stochastic_rule_sets()
+                .into_iter()
+                .flat_map(|(_, rules)| rules.into_iter().map(|(_, successor)| successor)),
+        );
+
+    let mut symbols = vec![];
+    for symbols_of in core::iter::once(lsystem.axiom.as_str()).chain(successors) {
+        let mut depth = 0usize;
+        for symbol in symbols_of.chars() {
+            match symbol {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                '[' | ']' => {}
+                _ if depth == 0 => symbols.push(symbol),
+                _ => {}
+            }
+        }
+    }
+    symbols.sort_unstable();
+    symbols.dedup();
+    symbols
+}
+
+/// Mutates the symbols of `successor`, brackets and symbols with parameter lists like `F(1)` are kept.
+fn mutate_successor(
+    successor: &str,
+    symbols: &[char],
+    config: &MutationConfig,
+    rng: &mut dyn Rng,
+) -> String {
+    let chars = successor.chars().collect::<Vec<_>>();
+    let mut mutated = String::with_capacity(successor.len());
+    let mut len = chars.len();
+
+    let mut index = 0;
+    while index < chars.len() {
+        let symbol = chars[index];
+        index += 1;
+
+        if chars.get(index) == Some(&'(') {
+            // Copy the module with its parameter list.
+            mutated.push(symbol);
+            let mut depth = 0usize;
+            while let Some(&symbol) = chars.get(index) {
+                mutated.push(symbol);
+                index += 1;
+                match symbol {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                if depth == 0 {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if matches!(symbol, '[' | ']') || symbols.is_empty() || rng.next_f32() >= config.symbol_rate
+        {
+            mutated.push(symbol);
+            continue;
+        }
+
+        let grows = len + 2 <= config.max_successor_len;
+        match (rng.next_u32() % 4, grows) {
+            // Replace the symbol.
+            (0, _) => mutated.push(pick(symbols, rng)),
+            // Remove the symbol.
+            (1, _) => len -= 1,
+            // Insert another symbol after it.
+            (2, true) => {
+                mutated.push(symbol);
+                mutated.push(pick(symbols, rng));
+                len += 1;
+            }
+            // Put the symbol in a branch.
+            (3, true) => {
+                mutated.push('[');
+                mutated.push(symbol);
+                mutated.push(']');
+                len += 2;
+            }
+            _ => mutated.push(symbol),
+        }
+    }
+
+    mutated
+}
+
+fn pick(symbols: &[char], rng: &mut dyn Rng) -> char {
+    symbols[rng.next_u32() as usize % symbols.len()]
+}
+
+/// Returns a factor in `1.0 - scale..1.0 + scale`.
+fn scale(scale: f32, rng: &mut dyn Rng) -> f32 {
+    rng.range_f32(1.0 - scale..1.0 + scale)
+}
+
+/// Scales every probability randomly and normalizes them to their previous total.
+fn perturb_weights(probabilities: &mut [f32], weight_scale: f32, rng: &mut dyn Rng) {
+    let total = probabilities.iter().sum::<f32>();
+    let perturbed = probabilities
+        .iter()
+        .map(|probability| (probability * scale(weight_scale, rng)).max(0.0))
+        .collect::<Vec<_>>();
+
+    let perturbed_total = perturbed.iter().sum::<f32>();
+    if perturbed_total > 0.0 {
+        for (probability, perturbed) in probabilities.iter_mut().zip(perturbed) {
+            *probability = perturbed * total / perturbed_total;
+        }
+    }
+}
+
+fn perturb_param(param: &mut ActionParam, config: &MutationConfig, rng: &mut dyn Rng) {
+    match param {
+        ActionParam::Number(number) if rng.next_f32() < config.param_rate => {
+            *number *= scale(config.param_scale, rng);
+        }
+        ActionParam::Named(_, value) => {
+            let mut named = ActionParam::clone(value);
+            perturb_param(&mut named, config, rng);
+            *value = P::new(named);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,890 @@
+use macaw::{BoundingBox, Mat4, Vec2, Vec3};
+
+use crate::{prelude::*, GpuVec, SpatialHash, Turtle};
+
+/// RGBA color with components in the range `0.0..=1.0`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Color = Color::rgb(1.0, 1.0, 1.0);
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::new(r, g, b, 1.0)
+    }
+
+    /// Returns the color as `#rrggbb` hex string, ignoring alpha.
+    pub fn to_hex(&self) -> String {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            channel(self.r),
+            channel(self.g),
+            channel(self.b)
+        )
+    }
+
+    /// Parses a `#rrggbb` or `#rrggbbaa` hex string, `None` if it is malformed.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#')?;
+        if !matches!(hex.len(), 6 | 8) {
+            return None;
+        }
+
+        let channel = |index: usize| {
+            let value = u8::from_str_radix(hex.get(index..index + 2)?, 16).ok()?;
+            Some(value as f32 / 255.0)
+        };
+        let alpha = if hex.len() == 8 { channel(6)? } else { 1.0 };
+
+        Some(Self::new(channel(0)?, channel(2)?, channel(4)?, alpha))
+    }
+
+    /// Returns the color at `t` between `self` at `0.0` and `other` at `1.0`, alpha included.
+    pub fn lerp(self, other: Color, t: f32) -> Self {
+        let mix = |from: f32, to: f32| from + (to - from) * t;
+        Self::new(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+            mix(self.a, other.a),
+        )
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Self::BLACK
+    }
+}
+
+/// Floats per instance written by `instance_attributes`, its transform and its color.
+pub const INSTANCE_COMPONENTS: usize = 20;
+
+/// Distance below which a point counts as on a line, see `Polyline::simplify`.
+const COLLINEAR_TOLERANCE: f32 = 1e-5;
+
+/// A straight line drawn by the turtle from `a` to `b`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub width: f32,
+    pub color: Color,
+    /// The branch depth at which the segment was drawn.
+    pub depth: usize,
+    /// The material the segment is exported with, e.g. `"bark"`, see `GeometryElement::tagged`.
+    pub material: Option<String>,
+}
+
+impl Segment {
+    pub fn new(a: Vec3, b: Vec3) -> Self {
+        Self {
+            a,
+            b,
+            width: 1.0,
+            color: Color::default(),
+            depth: 0,
+            material: None,
+        }
+    }
+
+    pub fn length(&self) -> f32 {
+        self.a.distance(self.b)
+    }
+
+    /// Returns the distance of `point` to the closest point of this segment.
+    pub fn distance_to(&self, point: Vec3) -> f32 {
+        let line = self.b - self.a;
+        let length_squared = line.length_squared();
+        if length_squared == 0.0 {
+            return point.distance(self.a);
+        }
+
+        let t = ((point - self.a).dot(line) / length_squared).clamp(0.0, 1.0);
+        point.distance(self.a + line * t)
+    }
+
+    /// Returns the distance between the closest points of this segment and `other`, `0.0` if they cross.
+    pub fn distance_to_segment(&self, other: &Segment) -> f32 {
+        let d1 = self.b - self.a;
+        let d2 = other.b - other.a;
+        let r = self.a - other.a;
+        let a = d1.length_squared();
+        let e = d2.length_squared();
+        let f = d2.dot(r);
+
+        if a == 0.0 {
+            return other.distance_to(self.a);
+        }
+        if e == 0.0 {
+            return self.distance_to(other.a);
+        }
+
+        // The parameters of the closest points along both segments, clamped to the segments.
+        let b = d1.dot(d2);
+        let c = d1.dot(r);
+        let denominator = a * e - b * b;
+        let mut s = if denominator > 0.0 {
+            ((b * f - c * e) / denominator).clamp(0.0, 1.0)
+        } else {
+            // Parallel segments, any point of this segment will do.
+            0.0
+        };
+        let mut t = (b * s + f) / e;
+        if t < 0.0 {
+            t = 0.0;
+            s = (-c / a).clamp(0.0, 1.0);
+        } else if t > 1.0 {
+            t = 1.0;
+            s = ((b - c) / a).clamp(0.0, 1.0);
+        }
+
+        (self.a + d1 * s).distance(other.a + d2 * t)
+    }
+
+    /// Returns the normalized direction from `a` to `b`, or `None` for a zero length segment.
+    pub fn direction(&self) -> Option<Vec3> {
+        (self.b - self.a).try_normalize()
+    }
+
+    pub fn transform(&mut self, transform: &Mat4) {
+        self.a = transform.transform_point3(self.a);
+        self.b = transform.transform_point3(self.b);
+    }
+}
+
+/// A connected sequence of points sharing width, color, depth and material.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Polyline {
+    pub points: Vec<Vec3>,
+    pub width: f32,
+    pub color: Color,
+    pub depth: usize,
+    pub material: Option<String>,
+}
+
+impl Polyline {
+    fn from_segment(segment: &Segment) -> Self {
+        Self {
+            points: vec![segment.a, segment.b],
+            width: segment.width,
+            color: segment.color,
+            depth: segment.depth,
+            material: segment.material.clone(),
+        }
+    }
+
+    /// Merges consecutive segments that connect and share their attributes into polylines.
+    pub fn from_segments(segments: &[Segment]) -> Vec<Polyline> {
+        let mut polylines: Vec<Polyline> = Vec::new();
+
+        for segment in segments {
+            match polylines.last_mut() {
+                Some(polyline) if polyline.continues_with(segment) => polyline.points.push(segment.b),
+                _ => polylines.push(Polyline::from_segment(segment)),
+            }
+        }
+
+        polylines
+    }
+
+    fn continues_with(&self, segment: &Segment) -> bool {
+        self.points.last() == Some(&segment.a)
+            && self.width == segment.width
+            && self.color == segment.color
+            && self.depth == segment.depth
+            && self.material == segment.material
+    }
+
+    /// Returns the segments between the points of this polyline.
+    pub fn segments(&self) -> impl Iterator<Item = Segment> + '_ {
+        self.points.windows(2).map(|points| Segment {
+            a: points[0],
+            b: points[1],
+            width: self.width,
+            color: self.color,
+            depth: self.depth,
+            material: self.material.clone(),
+        })
+    }
+
+    pub fn length(&self) -> f32 {
+        self.segments().map(|segment| segment.length()).sum()
+    }
+
+    /// Removes the points that are less than `tolerance` away from the line through their neighbours,
+    /// with the Ramer-Douglas-Peucker algorithm. The first and the last point are always kept.
+    ///
+    /// A `tolerance` of `0.0` only removes the points between collinear segments.
+    pub fn simplify(&mut self, tolerance: f32) {
+        if self.points.len() < 3 {
+            return;
+        }
+
+        // Rounding places points between collinear segments slightly off the line.
+        let tolerance = tolerance.max(COLLINEAR_TOLERANCE);
+        let mut keep = vec![false; self.points.len()];
+        keep[0] = true;
+        keep[self.points.len() - 1] = true;
+
+        let mut ranges = vec![(0, self.points.len() - 1)];
+        while let Some((first, last)) = ranges.pop() {
+            let chord = Segment::new(self.points[first], self.points[last]);
+            let farthest = (first + 1..last)
+                .map(|index| (index, chord.distance_to(self.points[index])))
+                .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+            if let Some((index, distance)) = farthest {
+                if distance > tolerance {
+                    keep[index] = true;
+                    ranges.push((first, index));
+                    ranges.push((index, last));
+                }
+            }
+        }
+
+        let mut keep = keep.into_iter();
+        self.points.retain(|_| keep.next().unwrap_or(true));
+    }
+
+    pub fn transform(&mut self, transform: &Mat4) {
+        for point in &mut self.points {
+            *point = transform.transform_point3(*point);
+        }
+    }
+}
+
+/// A closed, filled, polygon.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Polygon {
+    pub vertices: Vec<Vec3>,
+    pub color: Color,
+    pub depth: usize,
+    /// The material the polygon is exported with, e.g. `"leaf"`, see `GeometryElement::tagged`.
+    pub material: Option<String>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<Vec3>) -> Self {
+        Self {
+            vertices,
+            color: Color::default(),
+            depth: 0,
+            material: None,
+        }
+    }
+
+    /// Returns the average of the vertices.
+    pub fn centroid(&self) -> Vec3 {
+        if self.vertices.is_empty() {
+            return Vec3::ZERO;
+        }
+        self.vertices.iter().fold(Vec3::ZERO, |sum, &vertex| sum + vertex) / self.vertices.len() as f32
+    }
+
+    pub fn transform(&mut self, transform: &Mat4) {
+        for vertex in &mut self.vertices {
+            *vertex = transform.transform_point3(*vertex);
+        }
+    }
+}
+
+/// A triangle mesh, e.g. a leaf or the tube around a branch.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    /// The normal of every position, empty if the mesh has no normals.
+    pub normals: Vec<Vec3>,
+    /// The texture coordinates of every position, empty if the mesh has no texture coordinates.
+    pub uvs: Vec<Vec2>,
+    /// Indices into `positions`, three per triangle.
+    pub indices: Vec<u32>,
+    /// The color of every position, empty if the whole mesh has `color`.
+    pub colors: Vec<Color>,
+    pub color: Color,
+    pub depth: usize,
+    /// The material the mesh is exported with, e.g. `"bark"`, see `GeometryElement::tagged`.
+    pub material: Option<String>,
+}
+
+impl Mesh {
+    pub fn new(positions: Vec<Vec3>, indices: Vec<u32>) -> Self {
+        Self {
+            positions,
+            indices,
+            ..Default::default()
+        }
+    }
+
+    /// Builds a tube around every segment, a cylinder of `sides` faces without caps.
+    ///
+    /// The radius of a segment with width `1.0` is `radius`, the vertices take the color of their segment.
+    /// A segment starting where another one ends continues its tube: the ring at the joint is tilted halfway
+    /// between both directions and turned along without twisting, thus the normals are smooth along a branch.
+    ///
+    /// The `uvs` are cylindrical, `u` goes around the circumference from `0.0` to `1.0`, with a seam vertex
+    /// at `1.0` in every ring, and `v` along the branch in circumferences of a tube of width `1.0`,
+    /// thus a square bark texture tiles without stretching.
+    pub fn tube(segments: &[Segment], sides: usize, radius: f32) -> Self {
+        let sides = sides.max(3);
+        let circumference = (core::f32::consts::TAU * radius).max(f32::EPSILON);
+        let key = |point: Vec3| point.to_array().map(f32::to_bits);
+        let mut mesh = Mesh::default();
+
+        // The direction of the first segment starting at every point.
+        let mut starts = HashMap::new();
+        for segment in segments.iter().rev() {
+            let direction = (segment.b - segment.a).normalize_or_zero();
+            if direction != Vec3::ZERO {
+                starts.insert(key(segment.a), direction);
+            }
+        }
+        // The direction, the first axis of the ring and the `v` coordinate at the end of every segment.
+        let mut joints = HashMap::<_, (Vec3, Vec3, f32)>::new();
+
+        for segment in segments {
+            let direction = (segment.b - segment.a).normalize_or_zero();
+            if direction == Vec3::ZERO {
+                continue;
+            }
+
+            let previous = joints.get(&key(segment.a)).copied();
+            let tilted = |other: Vec3| (direction + other).try_normalize().unwrap_or(direction);
+            let start_tangent = previous.map_or(direction, |(previous, _, _)| tilted(previous));
+            let end_tangent = starts
+                .get(&key(segment.b))
+                .copied()
+                .map_or(direction, tilted);
+            // Turns the first axis of the ring into the plane of the next ring.
+            let along = |axis: Vec3, tangent: Vec3| {
+                (axis - tangent * axis.dot(tangent))
+                    .try_normalize()
+                    .unwrap_or_else(|| tangent.any_orthonormal_vector())
+            };
+            let start_axis = along(
+                previous.map_or_else(|| direction.any_orthonormal_vector(), |(_, axis, _)| axis),
+                start_tangent,
+            );
+            let end_axis = along(start_axis, end_tangent);
+            let start_v = previous.map_or(0.0, |(_, _, v)| v);
+            let end_v = start_v + segment.length() / circumference;
+            joints.insert(key(segment.b), (direction, end_axis, end_v));
+
+            let radius = radius * segment.width;
+            let start = mesh.positions.len() as u32;
+
+            for (center, tangent, u, v) in [
+                (segment.a, start_tangent, start_axis, start_v),
+                (segment.b, end_tangent, end_axis, end_v),
+            ] {
+                let w = tangent.cross(u);
+                // The last vertex closes the ring at the seam with `u` of `1.0`.
+                for side in 0..=sides {
+                    let around = side as f32 / sides as f32;
+                    let angle = around * core::f32::consts::TAU;
+                    let normal = u * angle.cos() + w * angle.sin();
+                    mesh.positions.push(center + normal * radius);
+                    mesh.normals.push(normal);
+                    mesh.uvs.push(Vec2::new(around, v));
+                    mesh.colors.push(segment.color);
+                }
+            }
+
+            // Counter clockwise seen from the outside.
+            let ring = sides as u32 + 1;
+            for side in 0..sides as u32 {
+                let [a0, a1] = [start + side, start + side + 1];
+                let [b0, b1] = [a0 + ring, a1 + ring];
+                mesh.indices.extend([a0, a1, b0, a1, b1, b0]);
+            }
+        }
+
+        mesh
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Returns the corners of the triangles.
+    pub fn triangles(&self) -> impl Iterator<Item = [Vec3; 3]> + '_ {
+        self.indices.chunks_exact(3).map(|triangle| {
+            [
+                self.positions[triangle[0] as usize],
+                self.positions[triangle[1] as usize],
+                self.positions[triangle[2] as usize],
+            ]
+        })
+    }
+
+    pub fn transform(&mut self, transform: &Mat4) {
+        for position in &mut self.positions {
+            *position = transform.transform_point3(*position);
+        }
+
+        // Normals stay perpendicular to the surface with the inverse transpose.
+        let normal_transform = transform.inverse().transpose();
+        for normal in &mut self.normals {
+            *normal = normal_transform
+                .transform_vector3(*normal)
+                .normalize_or_zero();
+        }
+    }
+}
+
+/// A placement of a model shared by many places of the drawing, e.g. the same leaf at every leaf symbol.
+///
+/// The model is stored once in `Geometry::models` and resolved by `name`, thus a canopy of thousands of leaves
+/// holds one leaf mesh and a transform per leaf. Models not in the geometry are left to the renderer or exporter.
+/// The instance draws its model in its own color, see `ExecuteContext::place_model`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Instance {
+    pub name: String,
+    /// Transforms the model into the drawing.
+    pub transform: Mat4,
+    pub color: Color,
+    pub depth: usize,
+    /// The material the model is placed with, e.g. `"flower"`, see `GeometryElement::tagged`.
+    pub material: Option<String>,
+}
+
+impl Instance {
+    pub fn new(name: impl Into<String>, transform: Mat4) -> Self {
+        Self {
+            name: name.into(),
+            transform,
+            color: Color::default(),
+            depth: 0,
+            material: None,
+        }
+    }
+
+    /// Creates an instance placed at the position and in the orientation of `turtle`,
+    /// the `+Y` axis of the model points along the heading and `+Z` along the up direction.
+    pub fn at_turtle(name: impl Into<String>, turtle: &Turtle) -> Self {
+        Self {
+            color: turtle.color(),
+            ..Self::new(name, turtle.to_mat4())
+        }
+    }
+
+    /// Returns the position the origin of the model is placed at.
+    pub fn position(&self) -> Vec3 {
+        self.transform.transform_point3(Vec3::ZERO)
+    }
+
+    pub fn transform(&mut self, transform: &Mat4) {
+        self.transform = *transform * self.transform;
+    }
+}
+
+/// A primitive pushed by an action onto `ExecuteContext::elements`, the default element type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GeometryElement {
+    Segment(Segment),
+    Polygon(Polygon),
+    Mesh(Mesh),
+    Instance(Instance),
+}
+
+impl GeometryElement {
+    /// Returns the element with the material `material`, a material id or label like `"leaf"`,
+    /// letting engines tell bark from leaves and flowers. Exporters group the elements by material,
+    /// e.g. into OBJ groups or glTF materials, see `ExecuteContext::emit_tagged`.
+    ///
+    pub fn tagged(mut self, material: impl Into<String>) -> Self {
+        let material = Some(material.into());
+        match &mut self {
+            GeometryElement::Segment(segment) => segment.material = material,
+            GeometryElement::Polygon(polygon) => polygon.material = material,
+            GeometryElement::Mesh(mesh) => mesh.material = material,
+            GeometryElement::Instance(instance) => instance.material = material,
+        }
+        self
+    }
+}
+
+/// The geometry drawn by an executed lsystem.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Geometry {
+    pub segments: Vec<Segment>,
+    pub polygons: Vec<Polygon>,
+    pub meshes: Vec<Mesh>,
+    pub instances: Vec<Instance>,
+    /// The meshes placed by the `instances`, by the name of the model.
+    pub models: BTreeMap<String, Mesh>,
+}
+
+impl Geometry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+            && self.polygons.is_empty()
+            && self.meshes.is_empty()
+            && self.instances.is_empty()
+    }
+
+    /// Returns the segments merged into polylines, see `Polyline::from_segments`.
+    pub fn polylines(&self) -> Vec<Polyline> {
+        Polyline::from_segments(&self.segments)
+    }
+
+    /// Returns the bounding box of all segments, polygons, meshes and the positions of the instances,
+    /// `None` if the geometry is empty.
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        let mut points = self
+            .segments
+            .iter()
+            .flat_map(|segment| [segment.a, segment.b])
+            .chain(self.polygons.iter().flat_map(|polygon| polygon.vertices.iter().copied()))
+            .chain(self.meshes.iter().flat_map(|mesh| mesh.positions.iter().copied()))
+            .chain(self.instances.iter().map(Instance::position));
+
+        let first = points.next()?;
+        let (min, max) = points.fold((first, first), |(min, max), point| {
+            (min.min(point), max.max(point))
+        });
+
+        Some(BoundingBox::from_min_max(min, max))
+    }
+
+    /// Returns the pairs of segments closer than `tolerance` to each other, by their indices in `segments`
+    /// with the lower index first, in ascending order.
+    ///
+    /// Segments sharing an end point are connected and never intersect, a `tolerance` of `0.0` finds the crossing segments.
+    /// Close segments are found with a spatial hash, e.g. to validate a drawing before it is printed.
+    pub fn self_intersections(&self, tolerance: f32) -> Vec<(usize, usize)> {
+        let total_length = self.segments.iter().map(Segment::length).sum::<f32>();
+        let mean_length = total_length / self.segments.len().max(1) as f32;
+        let mut hash = SpatialHash::new(tolerance, mean_length);
+
+        let mut intersections = vec![];
+        for (index, segment) in self.segments.iter().enumerate() {
+            intersections.extend(
+                hash.close_to(segment)
+                    .into_iter()
+                    .map(|other| (other, index)),
+            );
+            hash.insert(segment.clone());
+        }
+        intersections.sort_unstable();
+        intersections
+    }
+
+    /// Writes the geometry as compact JSON, e.g. to draw it in a browser.
+    ///
+    /// Points are `[x, y, z]` arrays and colors `[r, g, b, a]` arrays,
+    /// `bounds` is `null` if the geometry is empty and non finite numbers are written as `null`.
+    /// The `material` of segments, polygons, meshes and instances is `null` if they have none.
+    pub fn to_json(&self) -> String {
+        let color = |color: &Color| {
+            format!(
+                "[{},{},{},{}]",
+                json_number(color.r),
+                json_number(color.g),
+                json_number(color.b),
+                json_number(color.a)
+            )
+        };
+        let points = |points: &[Vec3]| {
+            let points = points
+                .iter()
+                .map(|point| json_point(*point))
+                .collect::<Vec<_>>();
+            format!("[{}]", points.join(","))
+        };
+        let material =
+            |material: &Option<String>| material.as_deref().map_or("null".to_string(), json_string);
+
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| {
+                format!(
+                    r#"{{"a":{},"b":{},"width":{},"color":{},"depth":{},"material":{}}}"#,
+                    json_point(segment.a),
+                    json_point(segment.b),
+                    json_number(segment.width),
+                    color(&segment.color),
+                    segment.depth,
+                    material(&segment.material)
+                )
+            })
+            .collect::<Vec<_>>();
+        let polygons = self
+            .polygons
+            .iter()
+            .map(|polygon| {
+                format!(
+                    r#"{{"vertices":{},"color":{},"depth":{},"material":{}}}"#,
+                    points(&polygon.vertices),
+                    color(&polygon.color),
+                    polygon.depth,
+                    material(&polygon.material)
+                )
+            })
+            .collect::<Vec<_>>();
+        let meshes = self
+            .meshes
+            .iter()
+            .map(|mesh| {
+                let indices = mesh.indices.iter().map(u32::to_string).collect::<Vec<_>>();
+                format!(
+                    r#"{{"positions":{},"indices":[{}],"color":{},"depth":{},"material":{}}}"#,
+                    points(&mesh.positions),
+                    indices.join(","),
+                    color(&mesh.color),
+                    mesh.depth,
+                    material(&mesh.material)
+                )
+            })
+            .collect::<Vec<_>>();
+        let instances = self
+            .instances
+            .iter()
+            .map(|instance| {
+                format!(
+                    r#"{{"name":{},"position":{},"color":{},"depth":{},"material":{}}}"#,
+                    json_string(&instance.name),
+                    json_point(instance.position()),
+                    color(&instance.color),
+                    instance.depth,
+                    material(&instance.material)
+                )
+            })
+            .collect::<Vec<_>>();
+        let bounds = match self.bounds() {
+            Some(bounds) => format!(
+                r#"{{"min":{},"max":{}}}"#,
+                json_point(bounds.min),
+                json_point(bounds.max)
+            ),
+            None => "null".to_string(),
+        };
+
+        format!(
+            r#"{{"segments":[{}],"polygons":[{}],"meshes":[{}],"instances":[{}],"bounds":{}}}"#,
+            segments.join(","),
+            polygons.join(","),
+            meshes.join(","),
+            instances.join(","),
+            bounds
+        )
+    }
+
+    pub fn transform(&mut self, transform: &Mat4) {
+        for segment in &mut self.segments {
+            segment.transform(transform);
+        }
+        for polygon in &mut self.polygons {
+            polygon.transform(transform);
+        }
+        for mesh in &mut self.meshes {
+            mesh.transform(transform);
+        }
+        for instance in &mut self.instances {
+            instance.transform(transform);
+        }
+    }
+
+    pub fn translate(&mut self, translation: Vec3) {
+        self.transform(&Mat4::from_translation(translation));
+    }
+
+    pub fn scale(&mut self, scale: f32) {
+        self.transform(&Mat4::from_scale(Vec3::splat(scale)));
+    }
+
+    /// Merges connected segments into fewer, longer, segments, see `Polyline::simplify`.
+    ///
+    /// Collinear segments with equal width, color, and depth are merged, e.g. the many `F` of `replace F by FF`.
+    /// A positive `tolerance` also removes corners that are less than `tolerance` off the simplified line.
+    /// Polygons are kept as they are.
+    pub fn simplify(&mut self, tolerance: f32) {
+        self.segments = self
+            .polylines()
+            .into_iter()
+            .flat_map(|mut polyline| {
+                polyline.simplify(tolerance);
+                polyline.segments().collect::<Vec<_>>()
+            })
+            .collect();
+    }
+
+    /// Scales and moves the geometry to fit into `target`, keeping its proportions, centered in `target`.
+    ///
+    /// The geometry is scaled uniformly so its largest extent relative to `target` just fits,
+    /// axes along which the geometry is flat do not limit the scale. Empty geometry is left unchanged.
+    pub fn fit_into(&mut self, target: BoundingBox) {
+        let Some(bounds) = self.bounds() else {
+            return;
+        };
+
+        let size = bounds.size();
+        let target_size = target.size();
+        let scale = (0..3)
+            .filter(|&axis| size[axis] > f32::EPSILON)
+            .map(|axis| target_size[axis] / size[axis])
+            .reduce(f32::min)
+            .unwrap_or(1.0);
+
+        self.transform(
+            &(Mat4::from_translation(target.center())
+                * Mat4::from_scale(Vec3::splat(scale))
+                * Mat4::from_translation(-bounds.center())),
+        );
+    }
+
+    /// Scales and moves the geometry into the box of size `1.0` centered at the origin, see `fit_into`.
+    ///
+    /// Makes the drawings of different lsystems or generations comparable in size.
+    pub fn normalize_to_unit_box(&mut self) {
+        self.fit_into(BoundingBox::from_center_size(Vec3::ZERO, Vec3::ONE));
+    }
+
+    /// Appends the geometry of `other` to this geometry, its models replace the models of the same name.
+    pub fn merge(&mut self, other: Geometry) {
+        self.segments.extend(other.segments);
+        self.polygons.extend(other.polygons);
+        self.meshes.extend(other.meshes);
+        self.instances.extend(other.instances);
+        self.models.extend(other.models);
+    }
+
+    /// Returns the mesh of every instance whose model is in `models`, transformed into the drawing,
+    /// e.g. for formats without instancing. The mesh takes the color, the depth and, if it has one,
+    /// the material of its instance.
+    pub fn instance_meshes(&self) -> Vec<Mesh> {
+        self.instances
+            .iter()
+            .filter_map(|instance| {
+                let mut mesh = self.models.get(&instance.name)?.clone();
+                mesh.transform(&instance.transform);
+                mesh.colors.clear();
+                mesh.color = instance.color;
+                mesh.depth = instance.depth;
+                if instance.material.is_some() {
+                    mesh.material = instance.material.clone();
+                }
+                Some(mesh)
+            })
+            .collect()
+    }
+
+    /// Replaces the instances whose model is in `models` by their meshes, see `instance_meshes`.
+    pub fn expand_instances(&mut self) {
+        self.meshes.extend(self.instance_meshes());
+        let models = &self.models;
+        self.instances
+            .retain(|instance| !models.contains_key(&instance.name));
+    }
+}
+
+fn json_number(value: f32) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn json_point(point: Vec3) -> String {
+    format!(
+        "[{},{},{}]",
+        json_number(point.x),
+        json_number(point.y),
+        json_number(point.z)
+    )
+}
+
+/// Expands every segment of `polylines` into a quad of two triangles facing the z axis, six vertices per segment.
+///
+/// A vertex is its position with `w = 1.0` followed by its color, the layout the `lines.comp` compute shader writes,
+/// thus renderers fall back to this when compute shaders are not available, e.g. on macOS and WebGL.
+/// A quad is `thickness` times the width of its polyline wide, zero length segments collapse into degenerate triangles.
+pub fn expand_lines_to_quads(polylines: &[Polyline], thickness: f32) -> GpuVec<f32> {
+    let mut vertices = GpuVec::new(8);
+
+    for polyline in polylines {
+        let color = polyline.color;
+        for points in polyline.points.windows(2) {
+            let (start, end) = (points[0], points[1]);
+            let right =
+                Vec3::Z.cross(end - start).normalize_or_zero() * thickness * polyline.width * 0.5;
+
+            let corners = [
+                start + right,
+                start - right,
+                end + right,
+                start - right,
+                end - right,
+                end + right,
+            ];
+            for corner in corners {
+                vertices.push(&[
+                    corner.x, corner.y, corner.z, 1.0, color.r, color.g, color.b, color.a,
+                ]);
+            }
+        }
+    }
+
+    vertices
+}
+
+/// Returns the placement of every instance of the model `name` for instanced drawing, one vertex per instance.
+///
+/// A vertex is the transform of the instance as four columns followed by its color, 20 floats,
+/// e.g. to bind the columns as four `vec4` attributes advancing once per instance.
+pub fn instance_attributes(instances: &[Instance], name: &str) -> GpuVec<f32> {
+    let mut attributes = GpuVec::new(INSTANCE_COMPONENTS);
+
+    for instance in instances.iter().filter(|instance| instance.name == name) {
+        let mut vertex = [0.0; INSTANCE_COMPONENTS];
+        vertex[..16].copy_from_slice(&instance.transform.to_cols_array());
+        let color = instance.color;
+        vertex[16..].copy_from_slice(&[color.r, color.g, color.b, color.a]);
+        attributes.push(&vertex);
+    }
+
+    attributes
+}
@@ -0,0 +1,112 @@
+//! Typed, size checked buffers for uploading geometry to and reading it back from the GPU.
+//!
+//! Raw byte casts of vertex data make it easy to read back more bytes than a buffer holds
+//! or to dispatch too few workgroups, `GpuVec` and `workgroups` keep the sizes consistent.
+use bytemuck::Pod;
+
+use crate::prelude::*;
+
+/// A buffer of vertices with `components` elements each, e.g. 4 `f32` per `vec4`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GpuVec<T: Pod> {
+    data: Vec<T>,
+    components: usize,
+}
+
+impl<T: Pod> GpuVec<T> {
+    /// Creates an empty buffer of vertices with `components` elements.
+    pub fn new(components: usize) -> Self {
+        Self::from_vec(Vec::new(), components)
+    }
+
+    /// Creates a buffer of `vertices` zeroed vertices, e.g. to read back the output of a compute shader.
+    pub fn zeroed(vertices: usize, components: usize) -> Self {
+        Self::from_vec(vec![T::zeroed(); vertices * components], components)
+    }
+
+    /// Creates a buffer from the flattened vertices in `data`.
+    ///
+    /// Panics if `data` does not hold a whole number of vertices.
+    pub fn from_vec(data: Vec<T>, components: usize) -> Self {
+        assert!(components > 0, "A vertex needs at least one component");
+        assert_eq!(
+            data.len() % components,
+            0,
+            "{} elements are no whole number of vertices with {components} components",
+            data.len()
+        );
+
+        Self { data, components }
+    }
+
+    pub fn push(&mut self, vertex: &[T]) {
+        debug_assert_eq!(vertex.len(), self.components);
+        self.data.extend_from_slice(vertex);
+    }
+
+    /// Returns the number of vertices.
+    pub fn len(&self) -> usize {
+        self.data.len() / self.components
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the number of elements of a vertex.
+    pub fn components(&self) -> usize {
+        self.components
+    }
+
+    /// Returns the number of bytes of a vertex, the stride of a vertex attribute.
+    pub fn stride(&self) -> usize {
+        self.components * core::mem::size_of::<T>()
+    }
+
+    /// Returns the number of bytes of the buffer.
+    pub fn byte_len(&self) -> usize {
+        self.data.len() * core::mem::size_of::<T>()
+    }
+
+    pub fn vertex(&self, index: usize) -> Option<&[T]> {
+        self.data
+            .get(index * self.components..(index + 1) * self.components)
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.data)
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        bytemuck::cast_slice_mut(&mut self.data)
+    }
+
+    /// Returns the byte offset and bytes of `count` vertices starting at vertex `offset`,
+    /// sized for reading them back with `get_buffer_sub_data`.
+    /// Returns `None` if the range exceeds the buffer.
+    pub fn sub_data_mut(&mut self, offset: usize, count: usize) -> Option<(usize, &mut [u8])> {
+        let stride = self.stride();
+        let end = offset.checked_add(count)?;
+        if end > self.len() {
+            return None;
+        }
+
+        let bytes = &mut self.as_bytes_mut()[offset * stride..end * stride];
+        Some((offset * stride, bytes))
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+}
+
+/// Returns the number of workgroups of `local_size` invocations needed to run `invocations` invocations.
+pub fn workgroups(invocations: usize, local_size: u32) -> u32 {
+    debug_assert!(local_size > 0, "A workgroup needs at least one invocation");
+    let groups = invocations.div_ceil(local_size as usize);
+    u32::try_from(groups).expect("The number of workgroups exceeds u32::MAX")
+}
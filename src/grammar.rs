@@ -1,4 +1,13 @@
-use std::slice::Iter;
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    slice::Iter,
+};
+
+use smallvec::SmallVec;
+
+use crate::{prelude::*, ContextIter, RuleKind, SymbolId};
 
 /*
     - Ignore constant symbols when context matching.
@@ -9,13 +18,28 @@ use std::slice::Iter;
 
 /// A symbol in the alphabet defining an l-system.
 #[derive(Clone, Hash, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Symbol {
     /// Variable symbols can be replaced and execute actions.
     Variable(char),
     /// Constant symbols can only perform actions.
     Constant(char),
     // A module is a symbol with a list of parameters.
-    Module(char, Vec<char>),
+    Module(char, ModuleParams),
+}
+
+impl Symbol {
+    /// Returns the char of this symbol.
+    pub fn char(&self) -> char {
+        match self {
+            Symbol::Variable(char) | Symbol::Constant(char) | Symbol::Module(char, _) => *char,
+        }
+    }
+
+    /// Returns the id of this symbol.
+    pub fn id(&self) -> SymbolId {
+        SymbolId::from(self.char())
+    }
 }
 
 impl From<char> for Symbol {
@@ -24,6 +48,65 @@ impl From<char> for Symbol {
     }
 }
 
+/// The evaluated parameters of a module, e.g. `1.0, 2.0` of `F(1.0, 2.0)`.
+///
+/// Up to four parameters are stored inline, thus most modules are cloned without allocating.
+/// Parameters are compared and hashed by their bits.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleParams(SmallVec<[f32; 4]>);
+
+impl ModuleParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, param: f32) {
+        self.0.push(param);
+    }
+}
+
+impl Deref for ModuleParams {
+    type Target = [f32];
+
+    fn deref(&self) -> &[f32] {
+        &self.0
+    }
+}
+
+impl PartialEq for ModuleParams {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|(a, b)| a.to_bits() == b.to_bits())
+    }
+}
+
+impl Eq for ModuleParams {}
+
+impl Hash for ModuleParams {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for param in self.iter() {
+            param.to_bits().hash(state);
+        }
+    }
+}
+
+impl FromIterator<f32> for ModuleParams {
+    fn from_iter<I: IntoIterator<Item = f32>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl From<Vec<f32>> for ModuleParams {
+    fn from(params: Vec<f32>) -> Self {
+        Self(SmallVec::from_vec(params))
+    }
+}
+
 pub enum Params {
     Param(char),
     Seperator(char),
@@ -31,6 +114,7 @@ pub enum Params {
 
 /// A set of symbols containing both elements that can be replaced (variables) and those which cannot be replaced (constants or terminals).
 #[derive(Clone, Hash, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Alphabet {
     /// The symbols making up the alphabet.
     pub symbols: Vec<Symbol>,
@@ -66,8 +150,24 @@ impl Alphabet {
         alphabet
     }
 
+    /// Creates an alphabet from a sequence of chars.
+    /// This will panic if the given alphabet definition does not define a symbol for some char.
+    pub fn from_chars<A: SymbolDefiner>(
+        chars: &[char],
+        generations: u8,
+        alphabet_definition: &A,
+    ) -> Alphabet {
+        let mut alphabet = Alphabet::with_capacity(chars.len(), generations);
+
+        for char in chars {
+            alphabet.add_symbol(alphabet_definition.into_symbol(*char));
+        }
+
+        alphabet
+    }
+
     pub fn to_string(&self) -> String {
-        use std::fmt::Write;
+        use core::fmt::Write;
         let mut out_string = String::with_capacity(self.symbols.len());
 
         for l in &self.symbols {
@@ -87,34 +187,187 @@ impl Alphabet {
     pub fn iter(&self) -> Iter<'_, Symbol> {
         self.symbols.iter()
     }
+
+    /// Returns an iterator over the symbols together with up to `left_n` symbols of their left
+    /// and `right_n` symbols of their right context. Branches are skipped, see the `context` module.
+    pub fn iter_with_context(&self, left_n: usize, right_n: usize) -> ContextIter<'_> {
+        ContextIter::new(self, left_n, right_n)
+    }
+
+    /// Returns an iterator over the ids of the symbols.
+    pub fn ids(&self) -> impl Iterator<Item = SymbolId> + '_ {
+        self.symbols.iter().map(Symbol::id)
+    }
 }
 
 /// Definer of alphabet symbols from chars.
 pub trait SymbolDefiner {
     /// Returns the `Symbol` for the given char.
     fn into_symbol(&self, char: char) -> Symbol;
+
+    /// Returns the `Symbol` for the given char, `None` if the definer does not define a symbol for it.
+    fn try_into_symbol(&self, char: char) -> Option<Symbol> {
+        Some(self.into_symbol(char))
+    }
 }
 
+/// The cut symbol, while running an lsystem it skips all symbols until the end of its branch.
+pub const CUT_SYMBOL: char = '%';
+
 /// Default alphabet symbol definer, maps:
-/// A-Z and 0-1 to `Symbol::Variable`
-/// ∧, \\, /, &, '+', '-', '[', and ']' to `Symbol::Constant`
+/// A-Z, f and 0-1 to `Symbol::Variable`
+/// ∧, ^, \\, /, |, &, '+', '-', '[', ']', '\\'', '!', '{', '}', '.' and the cut symbol '%' to `Symbol::Constant`
 ///
 /// Using any other character with this definition will panic.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DefaultAlphabetSymbolDefiner;
 
 impl SymbolDefiner for DefaultAlphabetSymbolDefiner {
     fn into_symbol(&self, char: char) -> Symbol {
+        self.try_into_symbol(char)
+            .unwrap_or_else(|| panic!("Non supported char '{char}'"))
+    }
+
+    fn try_into_symbol(&self, char: char) -> Option<Symbol> {
         match char {
             'A' | 'B' | 'C' | 'D' | 'E' | 'F' | 'G' | 'H' | 'I' | 'J' | 'K' | 'L' | 'M' | 'N'
             | 'O' | 'P' | 'Q' | 'R' | 'S' | 'T' | 'U' | 'V' | 'W' | 'X' | 'Y' | 'Z' | 'f' => {
-                Symbol::Variable(char)
+                Some(Symbol::Variable(char))
             }
-            '0' | '1' => Symbol::Variable(char),
-            '∧' | '\\' | '/' | '|' | '&' | '+' | '-' | '[' | ']' => Symbol::Constant(char),
-            _ => panic!("Non supported char '{char}'"),
+            '0' | '1' => Some(Symbol::Variable(char)),
+            '∧' | '^' | '\\' | '/' | '|' | '&' | '+' | '-' | '[' | ']' | '\'' | '!' | '{' | '}'
+            | '.' | CUT_SYMBOL => Some(Symbol::Constant(char)),
+            _ => None,
+        }
+    }
+}
+
+/// Alphabet symbol definer inferred from a parsed lscript.
+/// Symbols appearing on the left side of a `replace` statement are mapped to `Symbol::Variable`,
+/// every other character is mapped to `Symbol::Constant`.
+///
+/// Unlike `DefaultAlphabetSymbolDefiner` this definer never panics.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InferredSymbolDefiner {
+    variables: HashSet<char>,
+}
+
+impl InferredSymbolDefiner {
+    /// Creates a definer that treats the given chars as variables.
+    pub fn new(variables: impl IntoIterator<Item = char>) -> Self {
+        Self {
+            variables: variables.into_iter().collect(),
+        }
+    }
+
+    /// Returns whether the given char is a variable in this alphabet.
+    pub fn is_variable(&self, char: char) -> bool {
+        self.variables.contains(&char)
+    }
+}
+
+impl SymbolDefiner for InferredSymbolDefiner {
+    fn into_symbol(&self, char: char) -> Symbol {
+        if self.is_variable(char) {
+            Symbol::Variable(char)
+        } else {
+            Symbol::Constant(char)
         }
     }
 }
 
 /// A string of symbols from Alphabet defining the initial state of the system.
 pub type Axiom = &'static str;
+
+/// Problems in the rules of an L-system reported by `LSystem::validate`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GrammarWarning {
+    /// Rules of a symbol compete with equal priority and are only resolved by the default rule order.
+    AmbiguousRules {
+        predecessor: String,
+        rules: Vec<RuleKind>,
+    },
+    /// A rule is never applied to symbols without parameters because a rule with a higher priority always applies.
+    ShadowedRule {
+        predecessor: String,
+        rule: RuleKind,
+        by: RuleKind,
+    },
+    /// A rule replaced an earlier rule of the same kind for the same predecessor.
+    DuplicateRule { predecessor: String, rule: RuleKind },
+    /// A rule with a predecessor of multiple symbols, which never matches because symbols are rewritten one at a time.
+    UnreachableRule { predecessor: String },
+    /// A rule whose predecessor appears neither in the axiom nor in any successor derived from it.
+    UnusedRule { predecessor: String },
+    /// A successor with a `[` that is never closed or a `]` that closes no branch.
+    UnbalancedBrackets {
+        predecessor: String,
+        successor: String,
+    },
+    /// An interpret statement referencing an action that is not known to the action resolver.
+    UnknownAction { symbol: String, action: String },
+    /// A symbol in a successor that has no rule and no action, thus it never changes and does nothing.
+    InertSymbol { symbol: char },
+}
+
+impl GrammarWarning {
+    /// Returns whether the warning is an error refused by `LSystemBuilder::build`,
+    /// a rule that replaces another rule, never matches or has unbalanced brackets.
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            GrammarWarning::DuplicateRule { .. }
+                | GrammarWarning::UnreachableRule { .. }
+                | GrammarWarning::UnbalancedBrackets { .. }
+        )
+    }
+}
+
+impl fmt::Display for GrammarWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrammarWarning::AmbiguousRules { predecessor, rules } => write!(
+                f,
+                "rules {rules:?} of `{predecessor}` have equal priority, set explicit priorities to resolve them"
+            ),
+            GrammarWarning::ShadowedRule {
+                predecessor,
+                rule,
+                by,
+            } => write!(
+                f,
+                "{rule:?} rule of `{predecessor}` is shadowed by the {by:?} rule with a higher priority"
+            ),
+            GrammarWarning::DuplicateRule { predecessor, rule } => write!(
+                f,
+                "{rule:?} rule of `{predecessor}` replaces an earlier rule, use `override` if this is intended"
+            ),
+            GrammarWarning::UnreachableRule { predecessor } => write!(
+                f,
+                "rule for `{predecessor}` never applies, predecessors must be a single symbol"
+            ),
+            GrammarWarning::UnusedRule { predecessor } => write!(
+                f,
+                "rule for `{predecessor}` never applies, `{predecessor}` is not derived from the axiom"
+            ),
+            GrammarWarning::UnbalancedBrackets {
+                predecessor,
+                successor,
+            } => write!(
+                f,
+                "successor `{successor}` of `{predecessor}` has unbalanced brackets"
+            ),
+            GrammarWarning::UnknownAction { symbol, action } => write!(
+                f,
+                "`{symbol}` is interpreted as unknown action `{action}`"
+            ),
+            GrammarWarning::InertSymbol { symbol } => write!(
+                f,
+                "`{symbol}` is neither replaced nor interpreted"
+            ),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+//! Callbacks around the actions executed while running an lsystem, e.g. for tracing or metrics.
+use crate::{ExecuteContext, GeometryElement, Symbol};
+
+/// Called for every executed symbol, see `ActionResolver::add_hook`.
+///
+/// Hooks that keep data across symbols can store it in the context, see `ExecuteContext::state`.
+pub trait ExecutionHook<E = GeometryElement> {
+    /// Called before the actions of `symbol` are executed.
+    fn before(&self, _symbol: &Symbol, _context: &mut ExecuteContext<E>) {}
+
+    /// Called after the actions of `symbol` were executed.
+    fn after(&self, _symbol: &Symbol, _context: &mut ExecuteContext<E>) {}
+}
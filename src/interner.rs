@@ -0,0 +1,86 @@
+use crate::prelude::*;
+
+/// The hasher of the symbol keyed maps that are queried for every symbol while generating and running an lsystem.
+///
+/// With the `fxhash` feature this is the faster and deterministic `FxBuildHasher`,
+/// otherwise the default, DoS resistant, SipHash is used. Without `std` it is the default hasher of `hashbrown`.
+#[cfg(feature = "fxhash")]
+pub type SymbolHasher = fxhash::FxBuildHasher;
+#[cfg(all(feature = "std", not(feature = "fxhash")))]
+pub type SymbolHasher = std::collections::hash_map::RandomState;
+#[cfg(not(feature = "std"))]
+pub type SymbolHasher = hashbrown::DefaultHashBuilder;
+
+/// A map keyed by symbols, see `SymbolHasher`.
+pub type SymbolMap<K, V> = HashMap<K, V, SymbolHasher>;
+
+/// Integer identifier of a symbol.
+///
+/// Single char symbols map directly onto their unicode code point, which makes
+/// converting a char into an id free and doesn't require access to an interner.
+/// Symbol names longer than one char are assigned ids beyond `char::MAX` by a `SymbolInterner`.
+#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SymbolId(pub u32);
+
+impl SymbolId {
+    /// The first id assigned to interned, multi char, symbol names.
+    const FIRST_NAME_ID: u32 = char::MAX as u32 + 1;
+
+    /// Returns the char of this symbol, or `None` if this id belongs to an interned name.
+    pub fn as_char(self) -> Option<char> {
+        char::from_u32(self.0)
+    }
+}
+
+impl From<char> for SymbolId {
+    fn from(value: char) -> Self {
+        SymbolId(value as u32)
+    }
+}
+
+/// Interns symbol names into `SymbolId`s.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SymbolInterner {
+    names: Vec<String>,
+    ids: HashMap<String, SymbolId>,
+}
+
+impl SymbolInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id for the given symbol name, interning the name if it wasn't seen before.
+    pub fn intern(&mut self, name: &str) -> SymbolId {
+        if let Some(id) = self.get(name) {
+            return id;
+        }
+
+        let id = SymbolId(SymbolId::FIRST_NAME_ID + self.names.len() as u32);
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+
+    /// Returns the id for the given symbol name if it is a single char or has been interned.
+    pub fn get(&self, name: &str) -> Option<SymbolId> {
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(char), None) => Some(SymbolId::from(char)),
+            _ => self.ids.get(name).copied(),
+        }
+    }
+
+    /// Returns the symbol name belonging to the given id.
+    pub fn resolve(&self, id: SymbolId) -> Option<String> {
+        match id.as_char() {
+            Some(char) => Some(char.to_string()),
+            None => self
+                .names
+                .get((id.0 - SymbolId::FIRST_NAME_ID) as usize)
+                .cloned(),
+        }
+    }
+}
@@ -1,42 +1,39 @@
-use std::ops::Range;
-
-use regex::Regex;
-
-struct LanguageRegex {
-    operator_regex: Regex,
-    char_regex: Regex,
-    symbol_regex: Regex,
-    branching_regex: Regex,
-    param_regex: Regex,
-    whitespace_regex: Regex,
-    break_regex: Regex,
-    parentesis_regex: Regex,
-    number_regex: Regex,
-}
+use core::ops::Range;
 
-impl LanguageRegex {
-    pub fn new() -> Self {
-        let operator_regex = Regex::new(r"\+|-|/|\*|%").unwrap();
-        let char_regex = Regex::new(r"[a-zA-Z]").unwrap();
-        let symbol_regex = Regex::new(r"\+|-|\*|/|>|<|&|\||\\|\^|=|,").unwrap();
-        let branching_regex = Regex::new(r"\[|\]").unwrap();
-        let param_regex = Regex::new(r"\(|\)").unwrap();
-        let whitespace_regex = Regex::new(r"\s").unwrap();
-        let break_regex = Regex::new(r";").unwrap();
-        let parentesis_regex = Regex::new(r"\{|\}").unwrap();
-        let number_regex = Regex::new(r"\d+.\d+|\d+").unwrap();
-
-        LanguageRegex {
-            operator_regex,
-            char_regex,
-            symbol_regex,
-            branching_regex,
-            param_regex,
-            whitespace_regex,
-            break_regex,
-            parentesis_regex,
-            number_regex,
-        }
+use crate::prelude::*;
+
+/// The classes of the chars of the language, every token starts with a single char.
+mod class {
+    pub fn is_symbol(c: char) -> bool {
+        "+-*/><&|\\^=!,%'.".contains(c)
+    }
+
+    pub fn is_break(c: char) -> bool {
+        c == ';'
+    }
+
+    pub fn is_parentesis(c: char) -> bool {
+        matches!(c, '{' | '}')
+    }
+
+    pub fn is_branching(c: char) -> bool {
+        matches!(c, '[' | ']')
+    }
+
+    pub fn is_param(c: char) -> bool {
+        matches!(c, '(' | ')')
+    }
+
+    pub fn is_char(c: char) -> bool {
+        c.is_ascii_alphabetic()
+    }
+
+    pub fn is_number(c: char) -> bool {
+        c.is_ascii_digit()
+    }
+
+    pub fn is_whitespace(c: char) -> bool {
+        c.is_whitespace()
     }
 }
 
@@ -53,7 +50,7 @@ impl UnlexedTokens {
         }
     }
     pub fn finished(&self) -> bool {
-        self.index > self.tokens.len() - 1
+        self.index >= self.tokens.len()
     }
 
     pub fn current_token(&self) -> &str {
@@ -73,15 +70,11 @@ impl UnlexedTokens {
     }
 }
 
-pub struct Lexer {
-    regex: LanguageRegex,
-}
+pub struct Lexer;
 
 impl Lexer {
     pub fn new() -> Self {
-        Self {
-            regex: LanguageRegex::new(),
-        }
+        Self
     }
 
     pub fn lex(&self, input: String) -> Vec<Token> {
@@ -101,27 +94,27 @@ impl Lexer {
         let current_symbol = unlexed_tokens.current_token();
         let current_char = UnlexedTokens::first_char(current_symbol);
 
-        if self.regex.symbol_regex.is_match(current_symbol) {
+        if class::is_symbol(current_char) {
             tokens.push(Token::Symbol(current_char));
             unlexed_tokens.advance();
-        } else if self.regex.break_regex.is_match(current_symbol) {
+        } else if class::is_break(current_char) {
             tokens.push(Token::Break);
             unlexed_tokens.advance();
-        } else if self.regex.parentesis_regex.is_match(current_symbol) {
+        } else if class::is_parentesis(current_char) {
             tokens.push(Token::Parentesis(current_char));
             unlexed_tokens.advance();
-        } else if self.regex.branching_regex.is_match(current_symbol) {
+        } else if class::is_branching(current_char) {
             tokens.push(Token::Bracket(current_char));
             unlexed_tokens.advance();
-        } else if self.regex.param_regex.is_match(current_symbol) {
+        } else if class::is_param(current_char) {
             tokens.push(Token::Param(current_char));
             unlexed_tokens.advance();
-        } else if self.regex.char_regex.is_match(current_symbol) {
+        } else if class::is_char(current_char) {
             let mut string = Vec::new();
             self.lex_string(&mut unlexed_tokens, &mut string);
             let ident = string.join("");
             tokens.push(Token::Ident(ident));
-        } else if self.regex.number_regex.is_match(current_symbol) {
+        } else if class::is_number(current_char) {
             let mut number = String::new();
             self.lex_number(&mut unlexed_tokens, &mut number);
 
@@ -144,7 +137,7 @@ impl Lexer {
             }
 
             unlexed_tokens.advance();
-        } else if self.regex.whitespace_regex.is_match(current_symbol) {
+        } else if class::is_whitespace(current_char) {
             tokens.push(Token::Space);
             unlexed_tokens.advance();
         } else {
@@ -161,7 +154,7 @@ impl Lexer {
 
         let current_token = unlexed_tokens.current_token();
 
-        if self.regex.char_regex.is_match(current_token) {
+        if class::is_char(UnlexedTokens::first_char(current_token)) {
             chars.push(current_token.to_string())
         } else {
             return;
@@ -179,7 +172,7 @@ impl Lexer {
 
         let current_token = unlexed_tokens.current_token();
 
-        if self.regex.number_regex.is_match(current_token) || current_token == "." {
+        if class::is_number(UnlexedTokens::first_char(current_token)) || current_token == "." {
             number.push_str(current_token);
         } else {
             unlexed_tokens.index -= 1;
@@ -1,21 +1,136 @@
+//! Lindenmayer systems: lexing and parsing scripts, rewriting them and drawing them with a turtle.
+//!
+//! The core engine also runs without `std`, disable the default features and enable `alloc`.
+//! Exporting, rendering and everything else touching files, threads or clocks requires `std`.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+compile_error!("enable either the `std` or the `alloc` feature");
+
+extern crate alloc;
+
 mod abs;
 mod action;
+mod branch;
+mod builder;
+#[cfg(feature = "std")]
+mod bundle;
+mod bvh;
+mod cancel;
+mod checkpoint;
+mod collision;
+mod color_map;
+mod commands;
+mod compile;
+mod context;
+mod debugger;
 pub mod default_actions;
+mod diff;
+#[cfg(feature = "disk-cache")]
+mod disk_cache;
+mod environment;
+mod events;
+#[cfg(feature = "std")]
+pub mod export;
+mod fractint;
+mod generate;
+pub mod genetics;
+mod geometry;
+mod gpu;
 mod grammar;
+mod hooks;
+mod interner;
 mod lexer;
+mod lod;
 mod lsystem;
+mod lsystem_cacher;
 mod parser;
+mod planar;
+mod prelude;
+pub mod presets;
+mod projection;
+#[cfg(feature = "std")]
+pub mod render;
+mod repl;
+mod rng;
+mod runtime;
+#[cfg(feature = "watch")]
+mod script_host;
+#[cfg(feature = "sound")]
+mod sound;
+mod stats;
+mod stepper;
+mod timed;
+mod timing;
+mod trace;
 mod turtle_graphics;
+mod turtles;
+#[cfg(feature = "egui")]
+pub mod ui;
+mod variables;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 pub use abs::*;
 pub use action::*;
+pub use branch::*;
+pub use builder::*;
+#[cfg(feature = "std")]
+pub use bundle::*;
+pub use bvh::*;
+pub use cancel::*;
+pub use checkpoint::*;
+pub use collision::*;
+pub use color_map::*;
+pub use commands::*;
+pub use compile::*;
+pub use context::*;
+pub use debugger::*;
 pub use default_actions::*;
+pub use diff::*;
+#[cfg(feature = "disk-cache")]
+pub use disk_cache::*;
+pub use environment::*;
+pub use events::*;
+pub use fractint::*;
+pub use generate::*;
+pub use geometry::*;
+pub use gpu::*;
 pub use grammar::*;
 pub use grammar::*;
+pub use hooks::*;
+pub use interner::*;
 pub use lexer::*;
+pub use lod::*;
 pub use lsystem::*;
+pub use lsystem_cacher::*;
 pub use parser::*;
+pub use planar::*;
+pub use projection::*;
+pub use repl::*;
+pub use rng::*;
+pub use runtime::*;
+#[cfg(feature = "watch")]
+pub use script_host::*;
+#[cfg(feature = "sound")]
+pub use sound::*;
+pub use stats::*;
+pub use stepper::*;
+pub use timed::*;
+pub use timing::*;
+pub use trace::*;
 pub use turtle_graphics::*;
+pub use variables::*;
+
+#[cfg(feature = "derive")]
+pub use scebpl_system_derive::LSystemAction;
+
+#[cfg(feature = "macros")]
+pub use scebpl_system_derive::lsystem;
+
+// Lets the code generated by the macros name this crate from inside it.
+#[cfg(any(feature = "derive", feature = "macros"))]
+extern crate self as scebpl_system;
 
 #[cfg(test)]
 pub mod tests;
@@ -0,0 +1,155 @@
+//! Levels of detail of one lsystem drawn for several generation counts, see `LodSet`.
+use macaw::{Mat4, Vec3};
+
+use crate::{
+    prelude::*, ActionResolver, Geometry, LSystem, RunOptions, RuntimeError, SymbolDefiner,
+};
+
+/// How the levels of a `LodSet` are drawn and when they are switched.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LodOptions {
+    /// The pose and pen every level is run with, all levels grow from `start_position`.
+    pub run: RunOptions,
+    /// Scales every level about its root to the size of the finest level,
+    /// the generations of most plants grow with every generation.
+    pub match_size: bool,
+    /// The angle in radians the details of a level may span in view when it is switched to,
+    /// see `LodLevel::switch_distance`.
+    pub tolerance: f32,
+}
+
+impl Default for LodOptions {
+    fn default() -> Self {
+        Self {
+            run: RunOptions::default(),
+            match_size: true,
+            tolerance: 0.01,
+        }
+    }
+}
+
+/// The drawing of a `LodSet` for one generation count.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LodLevel {
+    pub generations: u8,
+    pub geometry: Geometry,
+    /// The size of the details of the level, the average length of its segments.
+    pub detail: f32,
+    /// The view distance from which on this level replaces the finer levels, `0.0` for the finest level.
+    ///
+    /// At that distance the details of the level span at most `LodOptions::tolerance` radians.
+    pub switch_distance: f32,
+}
+
+/// Cheap distant versions of the same plant: the geometry of an lsystem for several generation counts.
+///
+/// Every level is generated and run with the seed of the lsystem, thus stochastic rules and random actions
+/// pick alike, and grows from the same root. The levels are sorted from the finest, with the most generations,
+/// to the coarsest, with increasing switch distances, see `level_at`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LodSet {
+    pub levels: Vec<LodLevel>,
+}
+
+impl LodSet {
+    /// Draws `lsystem` for every count of `generations` with the `default_actions` and `LodOptions::default`,
+    /// e.g. `LodSet::build(&lsystem, &[3, 5, 7])`.
+    pub fn build<A: SymbolDefiner>(
+        lsystem: &LSystem<A>,
+        generations: &[u8],
+    ) -> Result<Self, RuntimeError> {
+        Self::build_with(
+            lsystem,
+            generations,
+            &ActionResolver::with_default_actions(),
+            &LodOptions::default(),
+        )
+    }
+
+    /// Same as `build` but runs the actions of `action_resolver` with `options`.
+    ///
+    /// Repeated generation counts are drawn once.
+    pub fn build_with<A: SymbolDefiner>(
+        lsystem: &LSystem<A>,
+        generations: &[u8],
+        action_resolver: &ActionResolver,
+        options: &LodOptions,
+    ) -> Result<Self, RuntimeError> {
+        let mut generations = generations.to_vec();
+        generations.sort_unstable_by(|a, b| b.cmp(a));
+        generations.dedup();
+
+        let mut levels = generations
+            .into_iter()
+            .map(|generations| {
+                let alphabet = lsystem.generate(generations);
+                let context = lsystem.run_with_options(action_resolver, &alphabet, &options.run)?;
+                Ok(LodLevel {
+                    generations,
+                    geometry: context.geometry(),
+                    detail: 0.0,
+                    switch_distance: 0.0,
+                })
+            })
+            .collect::<Result<Vec<_>, RuntimeError>>()?;
+
+        if options.match_size {
+            let root = options.run.start_position;
+            let finest = levels.first().map_or(0.0, |level| size(&level.geometry));
+            for level in levels.iter_mut().skip(1) {
+                let size = size(&level.geometry);
+                if size > f32::EPSILON && finest > f32::EPSILON {
+                    level.geometry.transform(
+                        &(Mat4::from_translation(root)
+                            * Mat4::from_scale(Vec3::splat(finest / size))
+                            * Mat4::from_translation(-root)),
+                    );
+                }
+            }
+        }
+
+        let tolerance = options.tolerance.max(f32::EPSILON);
+        let mut switch_distance = 0.0f32;
+        for (index, level) in levels.iter_mut().enumerate() {
+            level.detail = detail(&level.geometry);
+            if index > 0 {
+                switch_distance = switch_distance.max(level.detail / tolerance);
+            }
+            level.switch_distance = switch_distance;
+        }
+
+        Ok(Self { levels })
+    }
+
+    /// Returns the level to show at the view distance `distance`, the coarsest one switched to,
+    /// `None` if the set is empty.
+    pub fn level_at(&self, distance: f32) -> Option<&LodLevel> {
+        self.levels
+            .iter()
+            .take_while(|level| level.switch_distance <= distance)
+            .last()
+            .or(self.levels.first())
+    }
+}
+
+/// Returns the length of the diagonal of the bounds of `geometry`, `0.0` if it is empty.
+fn size(geometry: &Geometry) -> f32 {
+    geometry
+        .bounds()
+        .map_or(0.0, |bounds| bounds.size().length())
+}
+
+/// Returns the average length of the segments of `geometry`, the size of the geometry if it has no segments.
+fn detail(geometry: &Geometry) -> f32 {
+    if geometry.segments.is_empty() {
+        return size(geometry);
+    }
+    let length = geometry
+        .segments
+        .iter()
+        .map(|segment| segment.length())
+        .sum::<f32>();
+    length / geometry.segments.len() as f32
+}
@@ -1,15 +1,29 @@
-use std::{collections::HashMap, vec};
+use alloc::collections::BTreeSet;
+use core::{
+    any::{Any, TypeId},
+    hash::{Hash, Hasher},
+    ops::{Bound, RangeBounds},
+};
 
-use macaw::Vec3;
-use perchance::PerchanceContext;
-use regex::Regex;
+use macaw::{BoundingBox, Vec3};
 
 use crate::{
-    abs::*, action::ActionResolver, action::*, Alphabet, DefaultAlphabetSymbolDefiner, Symbol,
-    SymbolDefiner, Turtle, TurtleTransformStack,
+    abs::*, action::ActionResolver, action::*, prelude::*, seeded_rng, timed::check_initial_ages,
+    turtles::ParkedTurtle, Abort, Alphabet, CancellationToken, Collision, CollisionResponse,
+    CollisionState, Color, ColorMap, DefaultAlphabetSymbolDefiner, DefaultRng, DerivationTrace,
+    Environment, EventEmitter, ExecutionEvent, GenerateError, GenerateGuard, GenerateOptions,
+    GenerationReport, Geometry, GeometryElement, GrammarWarning, GrowthFunction, Instance, Mesh,
+    ModuleParams, Polygon, Progress, Query, Rng, RunStats, RuntimeError, Segment, Stopwatch,
+    Symbol, SymbolDefiner, SymbolId, SymbolInterner, SymbolMap, TimedAlphabet, TimedModule,
+    TimedRule, TimedRuleError, TracedSymbol, Turtle, TurtleCommand, TurtleEvent,
+    TurtleTransformStack, Variables, CUT_SYMBOL, DEFAULT_SEED, QUERY_SYMBOL,
 };
 
-#[derive(Clone, PartialEq, Eq)]
+/// How many symbols are run between two checks of the cancellation and progress reports.
+pub(crate) const RUN_CHECK_INTERVAL: usize = 4096;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReplacementRule {
     predecessor: String,
     successor: String,
@@ -22,14 +36,47 @@ impl ReplacementRule {
             successor,
         }
     }
+}
 
-    fn apply(&self, symbols: String) -> Option<String> {
-        if symbols == self.predecessor {
-            Some(self.successor.clone())
-        } else {
-            None
-        }
-    }
+/// A replacement rule that is applied with a probability, see `LSystem::add_stochastic_rule`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct StochasticRule {
+    probability: f32,
+    rule: ReplacementRule,
+}
+
+/// A table of replacement rules that is only active for a range of generations, see `LSystem::add_rule_table`.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct RuleTable {
+    generations: (Bound<u8>, Bound<u8>),
+    rules: SymbolMap<SymbolId, ReplacementRule>,
+}
+
+/// The kinds of production rules an L-system can have for a symbol.
+///
+/// When several rules exist for a symbol, the rule with the highest priority is tried first, see `LSystem::set_rule_priority`.
+/// Rules with equal priority are tried in the order context sensitive, parametric, generic.
+/// The next rule is tried if a rule does not produce a successor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RuleKind {
+    /// Rules added with `add_context_sensitive_rule`, they apply to symbols and modules.
+    ContextSensitive,
+    /// Rules added with `add_parametic_production_rule`, they only apply to modules, e.g. `A(1,2)`.
+    Parametric,
+    /// Rules added with `add_rule` or `add_rule_table`, they only apply to symbols without parameters.
+    Generic,
+}
+
+impl RuleKind {
+    /// All rule kinds in their default order.
+    pub const ALL: [RuleKind; 3] = [
+        RuleKind::ContextSensitive,
+        RuleKind::Parametric,
+        RuleKind::Generic,
+    ];
 }
 
 /// Callback that defines a context sensitive rule.
@@ -38,9 +85,10 @@ impl ReplacementRule {
 /// * The symbols buffer with all characters and their indexes.
 ///
 /// With this callback one can write context sensitive grammar rules for the L-system.
-pub type ContextSensitiveRuleCB = fn(char, usize, &[char]) -> std::option::Option<&'static str>;
+pub type ContextSensitiveRuleCB = fn(char, usize, &[char]) -> core::option::Option<&'static str>;
 
 /// A context sensitive production rule takes into account the context of other alphabet symbols.
+#[derive(Clone)]
 pub struct ContextSensitiveProductionRule {
     rule_cb: ContextSensitiveRuleCB,
 }
@@ -55,8 +103,9 @@ impl ContextSensitiveProductionRule {
     }
 }
 
-pub type ParametricRuleCB = fn(String, ParamsResolver) -> std::option::Option<String>;
+pub type ParametricRuleCB = fn(String, ParamsResolver) -> core::option::Option<String>;
 
+#[derive(Clone)]
 pub struct ParametricProductionRule {
     rule_cb: ParametricRuleCB,
 }
@@ -76,362 +125,2583 @@ impl ParametricProductionRule {
 /// a collection of production rules that expand each symbol into some larger string of symbols,
 /// an initial "axiom" string from which to begin construction,
 /// and a mechanism for translating the generated strings into geometric structures.
+///
+/// With the `serde` feature an lsystem is serialized with its rules, actions and variables.
+/// Context sensitive and parametric rules and growth functions are functions, serializing an lsystem
+/// with any of them fails rather than dropping them, remove them and add them again after deserializing.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LSystem<A: SymbolDefiner = DefaultAlphabetSymbolDefiner> {
     pub axiom: String,
-    generic_rule: HashMap<String, ReplacementRule>,
-    context_sensitive_rules: HashMap<String, ContextSensitiveProductionRule>,
-    parametric_production_rules: HashMap<String, ParametricProductionRule>,
+    generic_rule: SymbolMap<SymbolId, ReplacementRule>,
+    rule_tables: Vec<RuleTable>,
+    stochastic_rules: SymbolMap<SymbolId, Vec<StochasticRule>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            skip_deserializing,
+            skip_serializing_if = "SymbolMap::is_empty",
+            serialize_with = "functions::context_sensitive_rules"
+        )
+    )]
+    context_sensitive_rules: SymbolMap<SymbolId, ContextSensitiveProductionRule>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            skip_deserializing,
+            skip_serializing_if = "SymbolMap::is_empty",
+            serialize_with = "functions::parametric_rules"
+        )
+    )]
+    parametric_production_rules: SymbolMap<SymbolId, ParametricProductionRule>,
+    #[cfg_attr(feature = "serde", serde(with = "entries"))]
+    rule_priorities: SymbolMap<(SymbolId, RuleKind), i32>,
+    interpretation_rules: SymbolMap<SymbolId, ReplacementRule>,
+    /// Warnings about rules that replaced an earlier rule for the same predecessor.
+    duplicate_rules: Vec<GrammarWarning>,
+    /// Seed of the generator used by stochastic rules and random action parameters.
+    seed: u64,
 
     alphabet_definer: A,
+    interner: SymbolInterner,
     pub name: String,
-    pub action_rules: Vec<(String, Action)>,
+    /// The actions each symbol is interpreted as, in the order they were added.
+    action_rules: SymbolMap<SymbolId, Vec<Action>>,
+    /// The variables used by the parameters of the actions.
+    variables: Variables,
+    /// The rules of the timed lsystem, see `LSystem::simulate`.
+    timed_rules: SymbolMap<SymbolId, TimedRule>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            skip_deserializing,
+            skip_serializing_if = "SymbolMap::is_empty",
+            serialize_with = "functions::growth_functions"
+        )
+    )]
+    growth_functions: SymbolMap<SymbolId, GrowthFunction>,
+    /// Scales the branches by their depth unless the run options replace it, see `Taper`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    taper: Option<Taper>,
+}
+
+/// Serializes a map as a sequence of its entries, formats like JSON only support maps keyed by strings.
+#[cfg(feature = "serde")]
+mod entries {
+    use core::hash::{BuildHasher, Hash};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::prelude::*;
+
+    pub fn serialize<K, V, H, S>(map: &HashMap<K, V, H>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, K, V, H, D>(deserializer: D) -> Result<HashMap<K, V, H>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        H: BuildHasher + Default,
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// Fails to serialize the rules that are functions, they are only written if there are any.
+#[cfg(feature = "serde")]
+mod functions {
+    use serde::{ser::Error, Serializer};
+
+    use crate::prelude::*;
+
+    fn fail<E: Error>(count: usize, rules: &str) -> E {
+        E::custom(format!(
+            "An lsystem with {count} {rules} can not be serialized, functions are no data. Remove them before serializing and add them again after deserializing."
+        ))
+    }
+
+    pub fn context_sensitive_rules<K, V, H, S: Serializer>(
+        map: &HashMap<K, V, H>,
+        _serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Err(fail(map.len(), "context sensitive rules"))
+    }
+
+    pub fn parametric_rules<K, V, H, S: Serializer>(
+        map: &HashMap<K, V, H>,
+        _serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Err(fail(map.len(), "parametric rules"))
+    }
+
+    pub fn growth_functions<K, V, H, S: Serializer>(
+        map: &HashMap<K, V, H>,
+        _serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Err(fail(map.len(), "growth functions"))
+    }
 }
 
 impl<A: SymbolDefiner> LSystem<A> {
     pub fn new(axiom: impl ToString, alphabet_definer: A) -> Self {
         Self {
             axiom: axiom.to_string(),
-            generic_rule: HashMap::new(),
+            generic_rule: SymbolMap::default(),
+            rule_tables: vec![],
+            stochastic_rules: SymbolMap::default(),
             alphabet_definer,
-            context_sensitive_rules: HashMap::new(),
-            parametric_production_rules: HashMap::new(),
+            interner: SymbolInterner::new(),
+            context_sensitive_rules: SymbolMap::default(),
+            parametric_production_rules: SymbolMap::default(),
+            rule_priorities: SymbolMap::default(),
+            interpretation_rules: SymbolMap::default(),
+            duplicate_rules: vec![],
+            seed: DEFAULT_SEED,
             name: String::new(),
-            action_rules: vec![],
+            action_rules: SymbolMap::default(),
+            variables: Variables::default(),
+            timed_rules: SymbolMap::default(),
+            growth_functions: SymbolMap::default(),
+            taper: None,
         }
     }
 
-    pub fn run(&mut self, action_resolver: &ActionResolver, alphabet: &Alphabet) -> ExecuteContext {
-        let mut context = ExecuteContext::new();
+    /// Executes the actions the symbols of `alphabet` are interpreted as.
+    ///
+    /// Fails if a symbol is interpreted as an action unknown to `action_resolver`,
+    /// an action misses parameters or a `]` closes a branch that was never opened.
+    /// Use `run_with_options` to cancel the run or observe its progress.
+    pub fn run<E>(
+        &self,
+        action_resolver: &ActionResolver<E>,
+        alphabet: &Alphabet,
+    ) -> Result<ExecuteContext<E>, RuntimeError> {
+        self.run_with_options(action_resolver, alphabet, &RunOptions::default())
+    }
 
-        context.snapshot();
-        println!("{:?}", self.action_rules.iter());
+    /// Same as `run` but starts the turtle with the pose and pen from `options`.
+    pub fn run_with_options<E>(
+        &self,
+        action_resolver: &ActionResolver<E>,
+        alphabet: &Alphabet,
+        options: &RunOptions,
+    ) -> Result<ExecuteContext<E>, RuntimeError> {
+        let context = ExecuteContext::with_options(options).with_rng(seeded_rng(self.seed));
+        self.run_with_context(action_resolver, alphabet, context)
+    }
 
-        for token in alphabet.iter() {
-            match token {
-                Symbol::Variable(var) => {
-                    if let Some((_interpret, by)) =
-                        self.action_rules.iter().find(|x| x.0 == var.to_string())
-                    {
-                        println!("found var!");
-                        if let Some(action) = action_resolver.resolve(token,by) {
-                            println!("execute");
-                            action.execute(token, &mut context)
-                        }
-                    }
-                }
-                Symbol::Constant(constant) => {
-                    if let Some((_interpret, by)) = self
-                        .action_rules
-                        .iter()
-                        .find(|x| x.0 == constant.to_string())
-                    {
-                        println!("found!");
-                        if let Some(action) = action_resolver.resolve(token,by) {
-                            println!("execute");
-                            action.execute(token, &mut context)
-                        }
-                    }
-                }
-                Symbol::Module(_x, _params) => todo!(),
+    /// Same as `run` but runs in the prepared `context`, e.g. one with another generator set with `ExecuteContext::with_rng`.
+    ///
+    /// The run starts at `ExecuteContext::position`, not at the first symbol: a context stopped by `run_until`
+    /// or restored with `ExecuteContext::restore_state` continues with the symbol it stopped before,
+    /// and a context that already ran `alphabet` to its end runs nothing. Run a new context to draw it again.
+    pub fn run_with_context<E>(
+        &self,
+        action_resolver: &ActionResolver<E>,
+        alphabet: &Alphabet,
+        mut context: ExecuteContext<E>,
+    ) -> Result<ExecuteContext<E>, RuntimeError> {
+        self.run_until(
+            action_resolver,
+            alphabet,
+            &mut context,
+            alphabet.symbols.len(),
+        )?;
+        Ok(context)
+    }
+
+    /// Runs the symbols of `alphabet` in `context` up to the symbol at index `end`, which is not run yet,
+    /// starting with the symbol the context stopped before.
+    ///
+    /// Lets a run be interrupted at a symbol, e.g. to checkpoint it with `ExecuteContext::save_state`,
+    /// and continued with `run_until` or `run_with_context`.
+    pub fn run_until<E>(
+        &self,
+        action_resolver: &ActionResolver<E>,
+        alphabet: &Alphabet,
+        context: &mut ExecuteContext<E>,
+        end: usize,
+    ) -> Result<(), RuntimeError> {
+        context.record();
+        self.execute(
+            action_resolver,
+            alphabet,
+            context,
+            end,
+            ExecuteContext::record,
+        )
+    }
+
+    /// Same as `run` but streams the drawing as `ExecutionEvent`s to `on_event`
+    /// instead of collecting it in the context.
+    ///
+    /// The recorded events and the elements pushed by actions are moved into the `ExecutionEvent`s,
+    /// thus the returned context has neither events nor elements.
+    pub fn run_with<E>(
+        &self,
+        action_resolver: &ActionResolver<E>,
+        alphabet: &Alphabet,
+        mut on_event: impl FnMut(ExecutionEvent<E>),
+    ) -> Result<ExecuteContext<E>, RuntimeError> {
+        let mut context = ExecuteContext::default().with_rng(seeded_rng(self.seed));
+        let mut emitter = EventEmitter::start(&mut context, &mut on_event);
+
+        let end = alphabet.symbols.len();
+        self.execute(action_resolver, alphabet, &mut context, end, |context| {
+            emitter.emit(context, &mut on_event)
+        })?;
+        Ok(context)
+    }
+
+    /// Executes the actions of the symbols of `alphabet` before `end` in `context`
+    /// and calls `step` after every executed symbol.
+    ///
+    /// Starts at the symbol the context stopped before, the first one of a new context.
+    fn execute<E>(
+        &self,
+        action_resolver: &ActionResolver<E>,
+        alphabet: &Alphabet,
+        context: &mut ExecuteContext<E>,
+        end: usize,
+        mut step: impl FnMut(&mut ExecuteContext<E>),
+    ) -> Result<(), RuntimeError> {
+        let stopwatch = Stopwatch::start();
+        let mut cache = ActionCache::new();
+
+        let chars = alphabet.iter().map(Symbol::char).collect::<Vec<_>>();
+        self.prepare_context(alphabet, context);
+
+        while context.position < end.min(chars.len()) {
+            self.execute_symbol(
+                action_resolver,
+                alphabet,
+                &chars,
+                &mut cache,
+                context,
+                &mut step,
+            )?;
+        }
+
+        if let Some(progress) = context
+            .progress
+            .as_ref()
+            .filter(|_| context.position >= chars.len())
+        {
+            progress.report(chars.len(), chars.len());
+        }
+        context.stats.run += stopwatch.elapsed();
+        Ok(())
+    }
+
+    /// Sets the generation and the taper of the run of `alphabet` in `context`.
+    pub(crate) fn prepare_context<E>(&self, alphabet: &Alphabet, context: &mut ExecuteContext<E>) {
+        context.generation = alphabet.generation;
+        context.taper = context.taper.or(self.taper);
+    }
+
+    /// Executes the symbol of `alphabet` at `ExecuteContext::position` and advances the context to the next symbol.
+    pub(crate) fn execute_symbol<E>(
+        &self,
+        action_resolver: &ActionResolver<E>,
+        alphabet: &Alphabet,
+        chars: &[char],
+        cache: &mut ActionCache<E>,
+        context: &mut ExecuteContext<E>,
+        step: &mut impl FnMut(&mut ExecuteContext<E>),
+    ) -> Result<(), RuntimeError> {
+        let index = context.position;
+        let token = &alphabet.symbols[index];
+        context.position += 1;
+
+        if index.is_multiple_of(RUN_CHECK_INTERVAL) {
+            if context
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            {
+                return Err(RuntimeError::Cancelled { index });
+            }
+            if let Some(progress) = &context.progress {
+                progress.report(index, alphabet.symbols.len());
+            }
+        }
+
+        // Parameter lists belong to the module before them and never execute actions.
+        if context.skipped_until.is_some_and(|end| index <= end) {
+            return Ok(());
+        }
+        let params_end = Self::module_params_end(chars, index);
+        context.skipped_until = params_end;
+        context.stats.symbols += 1;
+
+        if token.char() == QUERY_SYMBOL && index + 1 < chars.len() {
+            context.queries.push(Query::new(
+                chars[index + 1],
+                index,
+                alphabet.generation,
+                &context.turtle,
+            ));
+        }
+
+        let module_params = Self::module_params(token, chars, index, params_end);
+        // The number of branches that are open.
+        let depth = match token.char() {
+            '[' => context.branch_depth + 1,
+            ']' => context
+                .branch_depth
+                .checked_sub(1)
+                .ok_or(RuntimeError::UnbalancedBrackets { index })?,
+            _ => context.branch_depth,
+        };
+
+        context.index = index;
+        context.branch_depth = depth;
+        action_resolver.before(token, context);
+        self.execute_action(
+            token,
+            module_params.as_ref(),
+            action_resolver,
+            cache,
+            context,
+        )?;
+        if let Some(error) = context.error.take() {
+            return Err(error);
+        }
+        action_resolver.after(token, context);
+        step(context);
+
+        // The cut symbol and lines entering occupied space skip the rest of their branch,
+        // the closing `]` still restores the turtle.
+        if token.char() == CUT_SYMBOL || core::mem::take(&mut context.collided) {
+            context.skipped_until = Some(Self::branch_end(chars, index) - 1);
+        }
+        Ok(())
+    }
+
+    /// Executes the actions `token` is interpreted as, in the order their interpret bindings were added.
+    /// The parameters of a module instance replace the parameters of the bindings at the same index.
+    fn execute_action<E>(
+        &self,
+        token: &Symbol,
+        module_params: Option<&ParamsResolver>,
+        action_resolver: &ActionResolver<E>,
+        cache: &mut ActionCache<E>,
+        context: &mut ExecuteContext<E>,
+    ) -> Result<(), RuntimeError> {
+        for by in self.bindings(token) {
+            let Some(resolve) = action_resolver.resolver(token, &by.name) else {
+                return Err(RuntimeError::UnknownAction {
+                    symbol: token.char(),
+                    action: by.name.clone(),
+                });
             };
-            context.snapshot();
+
+            let action = self.bind_action(by, module_params, || context.implicit_variables())?;
+            if action.params.is_random() {
+                let action = Action {
+                    name: action.name,
+                    params: action.params.sample(context.rng.as_mut()),
+                };
+                let resolved = resolve(&action)?;
+                context.stats.resolved_actions += 1;
+                context.record_command(token, &action);
+                resolved.execute(token, context);
+            } else {
+                context.record_command(token, &action);
+                let resolved_actions = &mut context.stats.resolved_actions;
+                let resolved = cache.get_or_resolve(token, &action, || {
+                    *resolved_actions += 1;
+                    resolve(&action)
+                })?;
+                resolved.execute(token, context);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the parameters of the module instance at `index`, e.g. `10` of `F(10)`,
+    /// `params_end` is the index of the `)` closing its parameter list.
+    pub(crate) fn module_params(
+        token: &Symbol,
+        chars: &[char],
+        index: usize,
+        params_end: Option<usize>,
+    ) -> Option<ParamsResolver> {
+        match token {
+            Symbol::Module(_, params) if !params.is_empty() => Some(ParamsResolver {
+                params: params.iter().copied().map(ActionParam::Number).collect(),
+            }),
+            Symbol::Module(..) => None,
+            Symbol::Variable(_) | Symbol::Constant(_) => params_end
+                .map(|end| String::from_iter(&chars[index + 2..end]))
+                .filter(|params| !params.is_empty())
+                .map(ParamsResolver::from_string),
         }
-        context
+    }
+
+    /// Returns the action of the interpret binding `by` with the parameters of the module instance
+    /// and the variables bound, fails if a parameter uses an unknown variable.
+    ///
+    /// Variables of the lsystem shadow the `implicit` variables of the running symbol.
+    pub(crate) fn bind_action(
+        &self,
+        by: &Action,
+        module_params: Option<&ParamsResolver>,
+        implicit: impl FnOnce() -> Variables,
+    ) -> Result<Action, RuntimeError> {
+        let params = match module_params {
+            Some(params) => params.clone().or(&by.params),
+            None => by.params.clone(),
+        };
+        let mut params = params.bind(&self.variables);
+        if params.unbound().is_some() {
+            params = params.bind(&implicit());
+        }
+        if let Some(name) = params.unbound() {
+            return Err(RuntimeError::UnknownVariable {
+                action: by.name.clone(),
+                name: name.to_string(),
+            });
+        }
+        Ok(Action {
+            name: by.name.clone(),
+            params,
+        })
+    }
+
+    /// Returns the interpret bindings of `token`, in the order they were added.
+    pub(crate) fn bindings(&self, token: &Symbol) -> &[Action] {
+        self.action_rules
+            .get(&token.id())
+            .map_or(&[], Vec::as_slice)
     }
 
     /// The rules of the L-system grammar are applied iteratively starting from the initial state.
     /// As many rules as possible are applied simultaneously, per iteration
+    ///
+    /// Generation is unbounded, use `generate_with` to limit symbols, memory or time.
     pub fn generate(&self, generations: u8) -> Alphabet {
-        let mut result = String::new();
-
-        // Apply grammar rules recursive.
-        // Can be parralelized.
-        Self::apply_rules_recursive(
-            self.axiom.clone(),
-            &mut result,
-            &self.context_sensitive_rules,
-            &self.parametric_production_rules,
-            &self.generic_rule,
-            generations,
-        );
+        self.generate_with(generations, &GenerateOptions::default())
+            .expect("Unbounded generation can not exceed a budget.")
+    }
+
+    /// Same as `generate` but aborts with `GenerateError::BudgetExceeded` as soon as one of the `options` budgets is exceeded,
+    /// or with `GenerateError::Cancelled` once the `options` cancellation token is cancelled.
+    pub fn generate_with(
+        &self,
+        generations: u8,
+        options: &GenerateOptions,
+    ) -> Result<Alphabet, GenerateError> {
+        self.generate_with_report(generations, options)
+            .map(|report| report.alphabet)
+    }
+
+    /// Returns `true` if every symbol expands independently of its neighbours, the generation and chance,
+    /// thus the expansions of a symbol can be reused, see `GenerateOptions::memoize`.
+    ///
+    /// This holds if the lsystem only has generic rules, no rule tables and no modules in its axiom or successors.
+    pub fn is_memoizable(&self) -> bool {
+        let modules = self.axiom.contains('(')
+            || self
+                .generic_rule
+                .values()
+                .any(|rule| rule.successor.contains('('));
 
-        // Kindof syntax tree containing the letters with the generated symbols.
-        // Not the most efficient, could perhaps be constructed during recursive rule applying,
-        // or removed entirely.
-        Alphabet::from_string(result, generations, &self.alphabet_definer)
+        self.context_sensitive_rules.is_empty()
+            && self.parametric_production_rules.is_empty()
+            && self.stochastic_rules.is_empty()
+            && self.rule_tables.is_empty()
+            && !modules
     }
 
-    fn recursively_iterate_params(symbols: &[char], symbol_index: &mut usize) -> String {
-        let mut params = String::new();
-        loop {
-            *symbol_index += 1;
-            let current_symbol = symbols[*symbol_index];
+    /// Same as `generate_with` but also reports the time spent in each phase, see the `timing` feature,
+    /// and the derivation trace if `GenerateOptions::trace` is enabled.
+    pub fn generate_with_report(
+        &self,
+        generations: u8,
+        options: &GenerateOptions,
+    ) -> Result<GenerationReport, GenerateError> {
+        let guard = GenerateGuard::new(options);
+        let mut state = GenerationState::new(&self.axiom, self.seed);
+        state.trace = options.trace.then(DerivationTrace::default);
 
-            if current_symbol == ')' {
-                *symbol_index += 1;
-                return params;
+        let rewrite = Stopwatch::start();
+        if options.memoize && !options.trace && self.is_memoizable() {
+            state.current = Expansions::new(self, &guard)
+                .expand(&self.axiom, generations)
+                .map_err(|abort| abort.error(generations))?;
+            state.generation = generations;
+            guard.report_progress(generations, generations);
+        } else {
+            for generation in 0..generations {
+                guard
+                    .check_cancellation()
+                    .map_err(|abort| abort.error(generation + 1))?;
+                state.advance(self, &guard)?;
+                guard.report_progress(generation + 1, generations);
             }
+        }
+        let rewrite = rewrite.elapsed();
+
+        // The symbols are only defined once for the final generation,
+        // thus the alphabet definer does not have to define intermediate symbols.
+        let interpretation_rules = Stopwatch::start();
+        let alphabet = state
+            .alphabet(self, &guard)
+            .map_err(|abort| abort.error(generations))?;
+
+        Ok(GenerationReport {
+            alphabet,
+            rewrite,
+            interpretation_rules: interpretation_rules.elapsed(),
+            trace: state.trace.take(),
+        })
+    }
 
-            params.push(current_symbol);
+    /// Same as `generate` but grows the L-system in `environment`.
+    ///
+    /// Before every generation is rewritten, it is run with `action_resolver` and `options`
+    /// and the parameters of its query modules `?P(x,y,z)` are replaced by the response of `environment`.
+    /// Thus parametric rules for `P` see the environment of the previous generation.
+    /// The returned alphabet has its query modules filled as well.
+    /// Fails with the first `RuntimeError` of running a generation.
+    ///
+    /// The alphabet definer must define `?`, `(`, `)`, `,` and the chars of the numbers, e.g. `InferredSymbolDefiner`.
+    pub fn generate_with_environment<E>(
+        &self,
+        generations: u8,
+        action_resolver: &ActionResolver<E>,
+        options: &RunOptions,
+        environment: &mut impl Environment,
+    ) -> Result<Alphabet, RuntimeError> {
+        let unbounded = GenerateOptions::default();
+        let guard = GenerateGuard::new(&unbounded);
+        let mut state = GenerationState::new(&self.axiom, self.seed);
+
+        for _ in 0..generations {
+            self.answer_queries(&mut state, action_resolver, options, environment)?;
+            state
+                .advance(self, &guard)
+                .expect("Unbounded generation can not exceed a budget.");
         }
+
+        self.answer_queries(&mut state, action_resolver, options, environment)?;
+        Ok(state
+            .alphabet(self, &guard)
+            .expect("Unbounded generation can not exceed a budget."))
     }
 
-    fn apply_rules_recursive(
-        symbols: String,
-        string_result: &mut String,
-        context_sensitive_rules: &HashMap<String, ContextSensitiveProductionRule>,
-        parametic_rules: &HashMap<String, ParametricProductionRule>,
-        generic_rules: &HashMap<String, ReplacementRule>,
-        generations_left: u8,
-    ) {
-        println!("{symbols}");
+    /// Predicts the number of symbols of every generation up to `generations` from the expansion factors of the rules,
+    /// `estimate[0]` is the length of the axiom.
+    ///
+    /// Stochastic rules contribute their expected successor.
+    /// Context sensitive and parametric rules depend on the derived symbols, thus symbols rewritten by them are counted as if they remain unchanged.
+    /// Modules like `F(1)` are copied unchanged as well, every char of them counts like in `Alphabet::stats`.
+    /// Interpretation rules are not applied.
+    pub fn growth_estimate(&self, generations: u8) -> Vec<f64> {
+        let (counts, module_chars) = Self::count_rewritable(&self.axiom);
+        let mut counts = counts
+            .into_iter()
+            .map(|(symbol, count)| (symbol, count as f64))
+            .collect::<HashMap<_, _>>();
+        let mut module_chars = module_chars as f64;
+
+        let mut estimate = Vec::with_capacity(generations as usize + 1);
+        estimate.push(counts.values().sum::<f64>() + module_chars);
+
+        for generation in 0..generations {
+            let generic_rules = self.active_rules(generation);
+            let mut next = HashMap::<char, f64>::new();
+
+            for (&symbol, &count) in &counts {
+                let id = SymbolId::from(symbol);
+                let mut remaining = 1.0;
+
+                for rule in self.stochastic_rules.get(&id).into_iter().flatten() {
+                    let probability = (rule.probability as f64).min(remaining);
+                    remaining -= probability;
+                    let (successors, successor_module_chars) =
+                        Self::count_rewritable(&rule.rule.successor);
+                    for (successor, successor_count) in successors {
+                        *next.entry(successor).or_default() +=
+                            count * probability * successor_count as f64;
+                    }
+                    module_chars += count * probability * successor_module_chars as f64;
+                }
+
+                match generic_rules.get(&id) {
+                    Some(rule) => {
+                        let (successors, successor_module_chars) =
+                            Self::count_rewritable(&rule.successor);
+                        for (successor, successor_count) in successors {
+                            *next.entry(successor).or_default() +=
+                                count * remaining * successor_count as f64;
+                        }
+                        module_chars += count * remaining * successor_module_chars as f64;
+                    }
+                    None => *next.entry(symbol).or_default() += count * remaining,
+                }
+            }
 
-        // If no more generations to generate, stop, and append leave symbols.
-        if generations_left == 0 {
-            string_result.push_str(&symbols);
+            counts = next;
+            estimate.push(counts.values().sum::<f64>() + module_chars);
         }
 
-        let symbols = symbols.chars().collect::<Vec<char>>();
+        estimate
+    }
 
-        let mut symbol_index = 0;
+    /// Runs the current generation and fills its query modules with the response of `environment`.
+    fn answer_queries<E>(
+        &self,
+        state: &mut GenerationState,
+        action_resolver: &ActionResolver<E>,
+        options: &RunOptions,
+        environment: &mut impl Environment,
+    ) -> Result<(), RuntimeError> {
+        // Interpretation rules are not applied, the query indices refer to the derived symbols.
+        let alphabet =
+            Alphabet::from_chars(&state.current, state.generation, &self.alphabet_definer);
+        let context = self.run_with_options(action_resolver, &alphabet, options)?;
+        if context.queries.is_empty() {
+            return Ok(());
+        }
 
-        if generations_left == 0 || symbols.is_empty() {
-            return;
+        let current = &state.current;
+        let mut answered = Vec::with_capacity(current.len());
+        let mut copied = 0;
+
+        for query in &context.queries {
+            let module_index = query.index + 1;
+            answered.extend_from_slice(&current[copied..=module_index]);
+
+            let params = environment
+                .query(query)
+                .iter()
+                .map(|param| param.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            answered.push('(');
+            answered.extend(params.chars());
+            answered.push(')');
+
+            // The previous parameters of the module are replaced.
+            copied = Self::module_params_end(current, module_index).unwrap_or(module_index) + 1;
+        }
+
+        answered.extend_from_slice(&current[copied..]);
+        state.current = answered;
+        Ok(())
+    }
+
+    /// Returns every generation from the axiom (generation 0) up to and including `generations`.
+    pub fn generate_all(&self, generations: u8) -> Vec<Alphabet> {
+        self.generations().take(generations as usize + 1).collect()
+    }
+
+    /// Returns an iterator over the generations of this L-system, starting with the axiom.
+    /// Every generation is rewritten from the previous one, the iterator ends after generation `u8::MAX`.
+    pub fn generations(&self) -> Generations<'_, A> {
+        Generations {
+            lsystem: self,
+            state: GenerationState::new(&self.axiom, self.seed),
+            started: false,
         }
+    }
+
+    /// Applies the rules to every symbol of the `current` generation and writes the next generation into `next`.
+    /// The replacement rules are taken from the rule table active for `generation`.
+    /// If `trace` is given, the parent of every symbol written into `next` is pushed onto it.
+    fn rewrite_generation(
+        &self,
+        generation: u8,
+        current: &[char],
+        next: &mut Vec<char>,
+        guard: &GenerateGuard,
+        rng: &mut dyn Rng,
+        mut trace: Option<&mut Vec<TracedSymbol>>,
+    ) -> Result<(), Abort> {
+        let generic_rules = self.active_rules(generation);
+        let mut symbol_index = 0;
 
-        loop {
-            let symbol = symbols[symbol_index];
-            let next_symbol = symbols.get(symbol_index + 1);
+        while symbol_index < current.len() {
+            guard.check(current.len(), next.len(), symbol_index)?;
 
-            let read_till_closing_param =
-                |symbols: &Vec<char>, symbol_index: &mut usize| -> ParamsResolver {
-                    *symbol_index += 2;
-                    let args = Self::recursively_iterate_params(&symbols, symbol_index);
+            let start = next.len();
+            let rewrite = self.rewrite_symbol(generic_rules, current, symbol_index, next, rng);
 
-                    ParamsResolver::from_string(args)
+            if let Some(trace) = trace.as_deref_mut() {
+                let parent = TracedSymbol {
+                    parent: symbol_index,
+                    rule: rewrite.rule,
                 };
+                trace.resize(trace.len() + next.len() - start, parent);
+            }
 
-            println!("{next_symbol:?}");
-            // Check if current symbol is start of parametric module.
-            if let Some('(') = next_symbol {
-                let args = read_till_closing_param(&symbols, &mut symbol_index);
-                println!("params: {args:?}");
-                if let Some(rule) = parametic_rules.get(&symbol.to_string()) {
-                    if let Some(result) = rule.apply(symbol.to_string(), args) {
-                        string_result.push_str(&result);
-                    }
-                }
-                symbol_index += 1;
-            }
-
-            if let Some(rule) = context_sensitive_rules.get(&symbol.to_string()) {
-                // Check if current rule is a context sensitive production rule.
-                if let Some(result) = rule.apply(symbol, symbol_index, symbols.as_slice()) {
-                    Self::apply_rules_recursive(
-                        result.to_string(),
-                        string_result,
-                        context_sensitive_rules,
-                        parametic_rules,
-                        generic_rules,
-                        generations_left - 1,
-                    );
-                }
-            } else if let Some(rule) = generic_rules.get(&symbol.to_string()) {
-                println!("Apply generic rule");
+            symbol_index = rewrite.end + 1;
+        }
 
-                let stochastic_match = Regex::new(r"\([+-]?([0-9]*[.])?[0-9]+\)").unwrap();
+        guard.check(current.len(), next.len(), symbol_index)
+    }
 
-                if let Some(Some(capture)) = stochastic_match
-                    .captures(&rule.predecessor)
-                    .and_then(|x| x.iter().next())
-                {
-                    println!("{}", capture.as_str());
-                }
+    /// Applies the first rule that rewrites the symbol at `symbol_index` of `current` and appends the result to `next`,
+    /// a symbol without such rule is appended unchanged, together with its parameters.
+    pub(crate) fn rewrite_symbol(
+        &self,
+        generic_rules: &SymbolMap<SymbolId, ReplacementRule>,
+        current: &[char],
+        symbol_index: usize,
+        next: &mut Vec<char>,
+        rng: &mut dyn Rng,
+    ) -> SymbolRewrite {
+        let symbol = current[symbol_index];
+        let id = SymbolId::from(symbol);
+        // Check if current symbol is start of parametric module, e.g. `a(1,2)`.
+        let params_end = Self::module_params_end(current, symbol_index);
+        let end = params_end.unwrap_or(symbol_index);
 
-                // Check if current rule is a context sensitive production rule.
-                if let Some(result) = rule.apply(symbol.to_string()) {
-                    Self::apply_rules_recursive(
-                        result.to_string(),
-                        string_result,
-                        context_sensitive_rules,
-                        parametic_rules,
-                        generic_rules,
-                        generations_left - 1,
-                    );
+        let mut stochastic = None;
+        let mut applied = None;
+        for kind in self.rule_order(id) {
+            let rewritten = match kind {
+                RuleKind::ContextSensitive => self
+                    .context_sensitive_rules
+                    .get(&id)
+                    .and_then(|rule| rule.apply(symbol, symbol_index, current))
+                    .map(|result| next.extend(result.chars()))
+                    .is_some(),
+                RuleKind::Parametric => params_end
+                    .and_then(|params_end| {
+                        let rule = self.parametric_production_rules.get(&id)?;
+                        let params = String::from_iter(&current[symbol_index + 2..params_end]);
+                        rule.apply(symbol.to_string(), ParamsResolver::from_string(params))
+                    })
+                    .map(|result| next.extend(result.chars()))
+                    .is_some(),
+                RuleKind::Generic if params_end.is_none() => {
+                    stochastic = self.stochastic_rule(id, rng);
+                    stochastic
+                        .and_then(|index| self.stochastic_rules.get(&id)?.get(index))
+                        .map(|rule| &rule.rule.successor)
+                        .or_else(|| generic_rules.get(&id).map(|rule| &rule.successor))
+                        .map(|successor| next.extend(successor.chars()))
+                        .is_some()
                 }
-            } else {
-                // If there is no rule for the symbol, then its the end of recurion, append symbol.
-                string_result.push_str(&symbol.to_string());
+                RuleKind::Generic => false,
+            };
+
+            if rewritten {
+                applied = Some(kind);
+                break;
             }
+        }
+
+        if applied.is_none() {
+            // If there is no rule for the symbol, it remains unchanged.
+            next.extend_from_slice(&current[symbol_index..=end]);
+        }
 
-            symbol_index += 1;
+        SymbolRewrite {
+            rule: applied,
+            stochastic,
+            end,
+        }
+    }
 
-            if symbol_index > symbols.len() - 1 {
-                break;
+    /// Picks one of the stochastic rules of `id` by their probability and returns its index,
+    /// or `None` if the drawn number exceeds their total probability.
+    fn stochastic_rule(&self, id: SymbolId, rng: &mut dyn Rng) -> Option<usize> {
+        let rules = self.stochastic_rules.get(&id)?;
+        let mut draw = rng.next_f32();
+
+        for (index, rule) in rules.iter().enumerate() {
+            if draw < rule.probability {
+                return Some(index);
             }
+            draw -= rule.probability;
         }
+
+        None
     }
 
-    pub fn execute<'a>(
+    /// Applies the interpretation rules once to every symbol of `symbols` and writes the result into `interpreted`.
+    /// If `origins` is given, the index in `symbols` of every symbol written into `interpreted` is pushed onto it.
+    fn interpret(
         &self,
-        origin: Vec3,
-        scale: f32,
-        rotation: Vec3,
-        alphabet: &Alphabet,
-    ) -> Vec<()> {
-        let mut context = ExecuteContext {
-            elements: Vec::new(),
-            transform_stack: TurtleTransformStack::new(),
-            turtle: Turtle::new(),
-            snapshot: vec![],
-            rng: PerchanceContext::new(56165165),
-            is_leave: false,
-        };
+        symbols: &[char],
+        interpreted: &mut Vec<char>,
+        guard: &GenerateGuard,
+        mut origins: Option<&mut Vec<usize>>,
+    ) -> Result<(), Abort> {
+        let mut symbol_index = 0;
 
-        context.turtle.scale(scale);
-        context.turtle.set_origin(origin);
-        context.turtle.rotate_z(rotation.z);
-        context.turtle.rotate_x(rotation.x);
-        context.turtle.rotate_y(rotation.y);
+        while symbol_index < symbols.len() {
+            guard.check(symbols.len(), interpreted.len(), symbol_index)?;
+            let start = interpreted.len();
+
+            // Modules keep their parameters and are never interpreted.
+            let end = match Self::module_params_end(symbols, symbol_index) {
+                Some(params_end) => {
+                    interpreted.extend_from_slice(&symbols[symbol_index..=params_end]);
+                    params_end
+                }
+                None => {
+                    let symbol = symbols[symbol_index];
+                    match self.interpretation_rules.get(&SymbolId::from(symbol)) {
+                        Some(rule) => interpreted.extend(rule.successor.chars()),
+                        None => interpreted.push(symbol),
+                    }
+                    symbol_index
+                }
+            };
 
-        for _letter in alphabet.iter() {
-            // if let Some(action) = self.actions.get(letter) {
-            //     action.execute(letter, &mut context);
-            // }
+            if let Some(origins) = origins.as_deref_mut() {
+                origins.resize(origins.len() + interpreted.len() - start, symbol_index);
+            }
+            symbol_index = end + 1;
         }
 
-        context.elements
+        guard.check(symbols.len(), interpreted.len(), symbol_index)
     }
 
-    pub fn add_rule(
-        &mut self,
-        predecessor: impl Into<String> + Clone,
-        successor: impl Into<String>,
-    ) {
-        self.generic_rule.insert(
-            predecessor.clone().into(),
-            ReplacementRule::new(predecessor.into(), successor.into()),
-        );
+    /// Returns the rule kinds in the order they are tried for the symbol `id`.
+    fn rule_order(&self, id: SymbolId) -> [RuleKind; 3] {
+        let mut order = RuleKind::ALL;
+        if !self.rule_priorities.is_empty() {
+            // The sort is stable, thus rules with equal priority keep their default order.
+            order.sort_by_key(|kind| core::cmp::Reverse(self.rule_priority(id, *kind)));
+        }
+        order
     }
 
-    pub fn add_context_sensitive_rule(
-        &mut self,
-        predecessor: impl Into<String>,
-        rule_cb: ContextSensitiveRuleCB,
-    ) {
-        self.context_sensitive_rules.insert(
-            predecessor.into(),
-            ContextSensitiveProductionRule::new(rule_cb),
-        );
+    fn rule_priority(&self, id: SymbolId, kind: RuleKind) -> i32 {
+        self.rule_priorities.get(&(id, kind)).copied().unwrap_or(0)
     }
 
-    pub fn add_parametic_production_rule(
+    /// Sets the priority of the `kind` rule of `predecessor`, the default priority is `0`.
+    /// Rules with a higher priority are tried before rules with a lower priority.
+    pub fn set_rule_priority(
         &mut self,
         predecessor: impl Into<String>,
-        rule_cb: ParametricRuleCB,
+        kind: RuleKind,
+        priority: i32,
     ) {
-        self.parametric_production_rules
-            .insert(predecessor.into(), ParametricProductionRule::new(rule_cb));
+        let id = self.interner.intern(&predecessor.into());
+        self.rule_priorities.insert((id, kind), priority);
     }
-}
-
-pub struct LSystemBuilder<A: SymbolDefiner = DefaultAlphabetSymbolDefiner> {
-    lsystem: LSystem<A>,
-}
 
-impl<A: SymbolDefiner> LSystemBuilder<A> {
-    pub fn new(axiom: &'static str, alphabet_definer: A) -> Self {
-        Self {
-            lsystem: LSystem::new(axiom, alphabet_definer),
+    fn has_rule(&self, id: SymbolId, kind: RuleKind) -> bool {
+        match kind {
+            RuleKind::ContextSensitive => self.context_sensitive_rules.contains_key(&id),
+            RuleKind::Parametric => self.parametric_production_rules.contains_key(&id),
+            RuleKind::Generic => {
+                self.generic_rule.contains_key(&id)
+                    || self.stochastic_rules.contains_key(&id)
+                    || self
+                        .rule_tables
+                        .iter()
+                        .any(|table| table.rules.contains_key(&id))
+            }
         }
     }
 
-    pub fn with_rules(mut self, rules: &[(String, String)]) -> Self {
-        for (predecessor, successor) in rules {
-            self.lsystem
-                .add_rule(predecessor.clone(), successor.clone());
+    /// Reports rules that are duplicated, ambiguous, conflicting or can never be applied,
+    /// and successors with unbalanced brackets.
+    ///
+    /// Use `validate_with_actions` to validate the interpret statements as well.
+    pub fn validate(&self) -> Vec<GrammarWarning> {
+        let mut ids = self
+            .generic_rule
+            .keys()
+            .chain(self.rule_tables.iter().flat_map(|table| table.rules.keys()))
+            .chain(self.stochastic_rules.keys())
+            .chain(self.context_sensitive_rules.keys())
+            .chain(self.parametric_production_rules.keys())
+            .chain(self.interpretation_rules.keys())
+            .copied()
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.dedup();
+
+        let reachable = self.reachable_symbols();
+        let mut warnings = self.duplicate_rules.clone();
+        for id in ids {
+            let predecessor = self.interner.resolve(id).unwrap_or_default();
+
+            // Symbols are rewritten one at a time, a rule for multiple symbols never matches.
+            let Some(symbol) = id.as_char() else {
+                warnings.push(GrammarWarning::UnreachableRule { predecessor });
+                continue;
+            };
+
+            if reachable
+                .as_ref()
+                .is_some_and(|reachable| !reachable.contains(&symbol))
+            {
+                warnings.push(GrammarWarning::UnusedRule {
+                    predecessor: predecessor.clone(),
+                });
+            }
+
+            for successor in self.successors(id) {
+                if !Self::is_balanced(successor) {
+                    warnings.push(GrammarWarning::UnbalancedBrackets {
+                        predecessor: predecessor.clone(),
+                        successor: successor.clone(),
+                    });
+                }
+            }
+
+            let has_context_sensitive = self.has_rule(id, RuleKind::ContextSensitive);
+            let context_sensitive = self.rule_priority(id, RuleKind::ContextSensitive);
+
+            if has_context_sensitive
+                && self.has_rule(id, RuleKind::Parametric)
+                && context_sensitive == self.rule_priority(id, RuleKind::Parametric)
+            {
+                warnings.push(GrammarWarning::AmbiguousRules {
+                    predecessor: predecessor.clone(),
+                    rules: vec![RuleKind::ContextSensitive, RuleKind::Parametric],
+                });
+            }
+
+            if has_context_sensitive
+                && self.has_rule(id, RuleKind::Generic)
+                && context_sensitive < self.rule_priority(id, RuleKind::Generic)
+            {
+                warnings.push(GrammarWarning::ShadowedRule {
+                    predecessor,
+                    rule: RuleKind::ContextSensitive,
+                    by: RuleKind::Generic,
+                });
+            }
         }
-        self
+
+        warnings
     }
 
-    pub fn with_context_sensitive_rules(
-        mut self,
-        predecessor: char,
-        rules: &[ContextSensitiveRuleCB],
-    ) -> Self {
-        for rule in rules {
-            self.lsystem.add_context_sensitive_rule(predecessor, *rule);
+    /// Same as `validate` but also reports interpret statements whose action is not known to `action_resolver`
+    /// and symbols in successors that are neither rewritten nor interpreted.
+    pub fn validate_with_actions<E>(
+        &self,
+        action_resolver: &ActionResolver<E>,
+    ) -> Vec<GrammarWarning> {
+        let mut warnings = self.validate();
+
+        let mut interpreted = self.action_rules.iter().collect::<Vec<_>>();
+        interpreted.sort_by_key(|(id, _)| **id);
+        for (id, actions) in interpreted {
+            for action in actions {
+                if !action_resolver
+                    .actions
+                    .contains_key(&(action.name.clone(), *id))
+                {
+                    warnings.push(GrammarWarning::UnknownAction {
+                        symbol: self.interner.resolve(*id).unwrap_or_default(),
+                        action: action.name.clone(),
+                    });
+                }
+            }
         }
-        self
-    }
 
-    pub fn build(self) -> LSystem<A> {
-        self.lsystem
-    }
-}
+        let mut inert = BTreeSet::new();
+        for id in self.rule_ids() {
+            for successor in self.successors(id) {
+                for symbol in Self::module_symbols(successor) {
+                    let id = SymbolId::from(symbol);
+                    let rewritten = RuleKind::ALL.iter().any(|kind| self.has_rule(id, *kind))
+                        || self.interpretation_rules.contains_key(&id);
+                    let interpreted = self.action_rules.contains_key(&id);
 
-pub trait LSystemDefinition {
-    fn new() -> Self;
-    fn name(&self) -> &'static str;
-    fn lsystem(&self) -> &LSystem;
-}
+                    if !rewritten && !interpreted && !matches!(symbol, CUT_SYMBOL | QUERY_SYMBOL) {
+                        inert.insert(symbol);
+                    }
+                }
+            }
+        }
+        warnings.extend(
+            inert
+                .into_iter()
+                .map(|symbol| GrammarWarning::InertSymbol { symbol }),
+        );
 
-pub struct ExecuteContext {
-    /// Elements generated by the lsystem.
-    pub elements: Vec<()>,
-    /// Used for saving transforms during lsystem generation.
-    transform_stack: TurtleTransformStack,
-    /// Used for turtle graphics.
-    pub turtle: Turtle,
-    pub snapshot: Vec<ExecuteContextSnapshot>,
-    pub rng: PerchanceContext,
-    pub is_leave: bool,
-}
+        warnings
+    }
 
-impl ExecuteContext {
-    pub fn push(&mut self, transform: Turtle) {
-        self.transform_stack.push(transform);
+    /// Returns the ids of all predecessors with a replacement or interpretation rule.
+    fn rule_ids(&self) -> Vec<SymbolId> {
+        let mut ids = self
+            .generic_rule
+            .keys()
+            .chain(self.rule_tables.iter().flat_map(|table| table.rules.keys()))
+            .chain(self.stochastic_rules.keys())
+            .chain(self.interpretation_rules.keys())
+            .copied()
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
     }
 
-    pub fn pop(&mut self) -> Turtle {
-        self.snapshot.last_mut().map(|x|{x.is_leave=true});
-        self.transform_stack.pop()
+    /// Returns the successors of the generic and stochastic rules of `id` in every rule table.
+    fn rewrite_successors(&self, id: SymbolId) -> impl Iterator<Item = &String> {
+        self.generic_rule
+            .get(&id)
+            .into_iter()
+            .chain(
+                self.rule_tables
+                    .iter()
+                    .filter_map(move |table| table.rules.get(&id)),
+            )
+            .chain(
+                self.stochastic_rules
+                    .get(&id)
+                    .into_iter()
+                    .flatten()
+                    .map(|rule| &rule.rule),
+            )
+            .map(|rule| &rule.successor)
     }
-}
 
-pub struct ExecuteContextSnapshot {
-    pub turtle: Turtle,
-    pub is_leave: bool,
-}
+    /// Same as `rewrite_successors` but includes the successor of the interpretation rule.
+    fn successors(&self, id: SymbolId) -> impl Iterator<Item = &String> {
+        self.rewrite_successors(id).chain(
+            self.interpretation_rules
+                .get(&id)
+                .map(|rule| &rule.successor),
+        )
+    }
 
-impl ExecuteContext {
-    pub fn new() -> Self {
-        //  perchance::seed_global(perchance::gen_time_seed());
+    /// Returns the symbols the derivation can reach from the axiom,
+    /// or `None` if a reachable symbol has a context sensitive or parametric rule whose successors are unknown.
+    fn reachable_symbols(&self) -> Option<HashSet<char>> {
+        let mut reachable = HashSet::new();
+        let mut pending = Self::module_symbols(&self.axiom);
 
-        Self {
-            elements: vec![],
-            transform_stack: TurtleTransformStack::new(),
-            turtle: Turtle::new(),
-            snapshot: vec![],
-            rng: PerchanceContext::new(32132132151651),
-            is_leave: false,
+        while let Some(symbol) = pending.pop() {
+            if !reachable.insert(symbol) {
+                continue;
+            }
+
+            let id = SymbolId::from(symbol);
+            if self.has_rule(id, RuleKind::ContextSensitive)
+                || self.has_rule(id, RuleKind::Parametric)
+            {
+                return None;
+            }
+
+            for successor in self.rewrite_successors(id) {
+                pending.extend(Self::module_symbols(successor));
+            }
         }
+
+        Some(reachable)
     }
 
-    pub fn snapshot(&mut self) {
-        self.snapshot.push(ExecuteContextSnapshot {
-            turtle: self.turtle,
-            is_leave: false,
+    /// Returns the symbols of `symbols` without the parameter lists of modules.
+    fn module_symbols(symbols: &str) -> Vec<char> {
+        let chars = symbols.chars().collect::<Vec<_>>();
+        let mut symbols = Vec::with_capacity(chars.len());
+        let mut index = 0;
+
+        while index < chars.len() {
+            symbols.push(chars[index]);
+            index = Self::module_params_end(&chars, index).unwrap_or(index) + 1;
+        }
+
+        symbols
+    }
+
+    /// Returns `true` if every `[` in `successor` is closed by a `]`.
+    fn is_balanced(successor: &str) -> bool {
+        let mut depth = 0usize;
+
+        for symbol in successor.chars() {
+            match symbol {
+                '[' => depth += 1,
+                ']' if depth == 0 => return false,
+                ']' => depth -= 1,
+                _ => {}
+            }
+        }
+
+        depth == 0
+    }
+
+    /// Returns the index of the closing `)` if the symbol at `symbol_index` is followed by a parameter list.
+    pub(crate) fn module_params_end(symbols: &[char], symbol_index: usize) -> Option<usize> {
+        if symbols.get(symbol_index + 1) != Some(&'(') {
+            return None;
+        }
+
+        symbols[symbol_index + 2..]
+            .iter()
+            .position(|symbol| *symbol == ')')
+            .map(|position| symbol_index + 2 + position)
+    }
+
+    /// Returns the index of the `]` closing the branch of the symbol at `symbol_index`,
+    /// or the length of `symbols` if the symbol is in the main branch.
+    pub(crate) fn branch_end(symbols: &[char], symbol_index: usize) -> usize {
+        let mut depth = 0usize;
+
+        for (index, symbol) in symbols.iter().enumerate().skip(symbol_index + 1) {
+            match symbol {
+                '[' => depth += 1,
+                ']' if depth == 0 => return index,
+                ']' => depth -= 1,
+                _ => {}
+            }
+        }
+
+        symbols.len()
+    }
+
+    /// Returns the largest generation count for which the number of segments stays within `target_segments`.
+    ///
+    /// The number of segments is predicted from the expansion factors of the replacement rules,
+    /// where every variable symbol counts as one segment, chars the alphabet does not define count as none.
+    /// Context sensitive and parametric rules are assumed to leave their symbol unchanged.
+    /// If the system stops growing, the generation at which it stops is returned.
+    pub fn suggest_generations(&self, target_segments: usize) -> u8 {
+        let mut counts = Self::count_chars(&self.axiom);
+        let mut generation = 0;
+
+        while generation < u8::MAX {
+            let expansions = self.rule_expansions(generation);
+            let next = Self::expand_counts(&counts, &expansions);
+
+            // With rule tables a system that stopped growing can start growing again in a later generation.
+            let stopped_growing = next == counts && self.rule_tables.is_empty();
+            if stopped_growing || self.segment_count(&next) > target_segments as u64 {
+                break;
+            }
+
+            counts = next;
+            generation += 1;
+        }
+
+        generation
+    }
+
+    /// Returns for every rule predecessor active in `generation` how often each symbol occurs in its successor.
+    fn rule_expansions(&self, generation: u8) -> HashMap<char, HashMap<char, u64>> {
+        self.active_rules(generation)
+            .iter()
+            .filter_map(|(predecessor, rule)| {
+                predecessor
+                    .as_char()
+                    .map(|symbol| (symbol, Self::count_chars(&rule.successor)))
+            })
+            .collect()
+    }
+
+    /// Counts the symbols of `symbols`, the chars of parameter lists are not symbols.
+    /// A `(` that is never closed makes the rest a parameter list, a `)` that closes nothing is ignored.
+    fn count_chars(symbols: &str) -> HashMap<char, u64> {
+        let mut counts = HashMap::new();
+        let mut params_depth = 0usize;
+        for symbol in symbols.chars() {
+            match symbol {
+                '(' => params_depth += 1,
+                ')' => params_depth = params_depth.saturating_sub(1),
+                _ if params_depth == 0 => *counts.entry(symbol).or_insert(0) += 1,
+                _ => {}
+            }
+        }
+        counts
+    }
+
+    /// Counts the symbols of `symbols` that generic rules rewrite and the chars of its modules, which they do not,
+    /// e.g. `F(1)+G` has one `+` and one `G` and 4 chars of modules. Modules are found like `rewrite_symbol` finds them.
+    fn count_rewritable(symbols: &str) -> (HashMap<char, u64>, u64) {
+        let symbols = symbols.chars().collect::<Vec<_>>();
+        let mut counts = HashMap::new();
+        let mut module_chars = 0;
+
+        let mut index = 0;
+        while index < symbols.len() {
+            match Self::module_params_end(&symbols, index) {
+                Some(params_end) => {
+                    module_chars += (params_end + 1 - index) as u64;
+                    index = params_end + 1;
+                }
+                None => {
+                    *counts.entry(symbols[index]).or_insert(0) += 1;
+                    index += 1;
+                }
+            }
+        }
+
+        (counts, module_chars)
+    }
+
+    fn expand_counts(
+        counts: &HashMap<char, u64>,
+        expansions: &HashMap<char, HashMap<char, u64>>,
+    ) -> HashMap<char, u64> {
+        let mut next = HashMap::new();
+
+        for (symbol, count) in counts {
+            match expansions.get(symbol) {
+                Some(successor) => {
+                    for (successor_symbol, successor_count) in successor {
+                        let total = next.entry(*successor_symbol).or_insert(0u64);
+                        *total = total.saturating_add(count.saturating_mul(*successor_count));
+                    }
+                }
+                None => {
+                    let total = next.entry(*symbol).or_insert(0u64);
+                    *total = total.saturating_add(*count);
+                }
+            }
+        }
+
+        next
+    }
+
+    fn segment_count(&self, counts: &HashMap<char, u64>) -> u64 {
+        counts
+            .iter()
+            .filter(|(symbol, _)| {
+                matches!(
+                    self.alphabet_definer.try_into_symbol(**symbol),
+                    Some(Symbol::Variable(_))
+                )
+            })
+            .fold(0u64, |total, (_, count)| total.saturating_add(*count))
+    }
+
+    /// Interprets `symbol` as `action` when running the lsystem,
+    /// after the actions it was interpreted as before.
+    pub fn add_action_rule(&mut self, symbol: impl Into<String>, action: Action) {
+        let id = self.interner.intern(&symbol.into());
+        self.action_rules.entry(id).or_default().push(action);
+    }
+
+    /// Returns the actions `symbol` is interpreted as, in the order they were added.
+    pub fn action_rules(&self, symbol: &str) -> &[Action] {
+        self.interner
+            .get(symbol)
+            .and_then(|id| self.action_rules.get(&id))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Removes and returns the actions `symbol` is interpreted as.
+    pub fn remove_action_rules(&mut self, symbol: &str) -> Vec<Action> {
+        self.interner
+            .get(symbol)
+            .and_then(|id| self.action_rules.remove(&id))
+            .unwrap_or_default()
+    }
+
+    /// Removes all interpret bindings.
+    pub fn clear_action_rules(&mut self) {
+        self.action_rules.clear();
+    }
+
+    /// Sets the variable `name` used by action parameters, e.g. `angle` of `interpret + as Rotate(angle);`.
+    pub fn set_variable(&mut self, name: impl Into<String>, value: f32) {
+        self.variables.set(name, value);
+    }
+
+    pub fn variables(&self) -> &Variables {
+        &self.variables
+    }
+
+    /// Adds a timed rule replacing `predecessor` once it reached the terminal age of `rule`, replacing an earlier timed rule.
+    ///
+    /// Timed rules only apply to `simulate`, `generate` uses the other rules.
+    /// Fails if a successor module starts at or after the terminal age of its own timed rule.
+    pub fn add_timed_rule(
+        &mut self,
+        predecessor: char,
+        rule: TimedRule,
+    ) -> Result<(), TimedRuleError> {
+        for (id, other) in &self.timed_rules {
+            let Some(symbol) = id.as_char().filter(|symbol| *symbol != predecessor) else {
+                continue;
+            };
+            check_initial_ages(predecessor, &rule, symbol, other)?;
+            check_initial_ages(symbol, other, predecessor, &rule)?;
+        }
+        check_initial_ages(predecessor, &rule, predecessor, &rule)?;
+
+        self.timed_rules.insert(SymbolId::from(predecessor), rule);
+        Ok(())
+    }
+
+    pub fn timed_rule(&self, symbol: char) -> Option<&TimedRule> {
+        self.timed_rules.get(&SymbolId::from(symbol))
+    }
+
+    /// Passes the age of every `symbol` module through `growth` into the first parameter of its actions when running a
+    /// timed alphabet, e.g. `|age, terminal_age| age / terminal_age` grows a segment until it is replaced.
+    pub fn set_growth_function(&mut self, symbol: char, growth: GrowthFunction) {
+        self.growth_functions.insert(SymbolId::from(symbol), growth);
+    }
+
+    /// Returns the axiom as timed alphabet, its modules start at age zero.
+    pub fn timed_axiom(&self) -> TimedAlphabet {
+        TimedAlphabet {
+            modules: self
+                .axiom
+                .chars()
+                .map(|symbol| TimedModule::new(symbol, 0.0))
+                .collect(),
+            time: 0.0,
+        }
+    }
+
+    /// Advances `alphabet` by `dt`, the modules age and are replaced by their timed rules.
+    ///
+    /// Modules reaching their terminal age in between are replaced at that time,
+    /// thus one large step results in the same modules as many small ones.
+    ///
+    /// # Panics
+    /// If `dt` is negative.
+    pub fn simulate(&self, alphabet: &mut TimedAlphabet, dt: f32) {
+        assert!(dt >= 0.0, "time can not run backwards, got {dt}");
+        alphabet.advance(dt, |symbol| self.timed_rule(symbol));
+    }
+
+    /// Returns the alphabet of the modules of `timed`, modules with a growth function carry their grown parameter.
+    pub fn timed_to_alphabet(&self, timed: &TimedAlphabet) -> Alphabet {
+        let symbols = timed
+            .modules
+            .iter()
+            .map(|module| {
+                let id = SymbolId::from(module.symbol);
+                match self.growth_functions.get(&id) {
+                    Some(growth) => {
+                        let terminal_age = self
+                            .timed_rules
+                            .get(&id)
+                            .map_or(f32::INFINITY, TimedRule::terminal_age);
+                        let param = growth(module.age, terminal_age);
+                        Symbol::Module(module.symbol, ModuleParams::from(vec![param]))
+                    }
+                    None => self.alphabet_definer.into_symbol(module.symbol),
+                }
+            })
+            .collect();
+
+        Alphabet {
+            symbols,
+            generation: 0,
+        }
+    }
+
+    /// Executes the actions of the modules of `timed`, e.g. every frame after `simulate`.
+    pub fn run_timed<E>(
+        &self,
+        action_resolver: &ActionResolver<E>,
+        timed: &TimedAlphabet,
+    ) -> Result<ExecuteContext<E>, RuntimeError> {
+        self.run(action_resolver, &self.timed_to_alphabet(timed))
+    }
+
+    /// Adds a rule replacing `predecessor` by `successor`.
+    ///
+    /// An earlier rule for the same predecessor is replaced with a warning, use `override_rule` when this is intended.
+    pub fn add_rule(&mut self, predecessor: impl Into<String>, successor: impl Into<String>) {
+        let predecessor = predecessor.into();
+        let id = self.interner.intern(&predecessor);
+        let previous = self.generic_rule.insert(
+            id,
+            ReplacementRule::new(predecessor.clone(), successor.into()),
+        );
+
+        if previous.is_some() {
+            self.warn_duplicate(predecessor, RuleKind::Generic);
+        }
+    }
+
+    /// Same as `add_rule` but replaces an earlier rule for the same predecessor without warning.
+    pub fn override_rule(&mut self, predecessor: impl Into<String>, successor: impl Into<String>) {
+        let predecessor = predecessor.into();
+        let id = self.interner.intern(&predecessor);
+        self.generic_rule
+            .insert(id, ReplacementRule::new(predecessor, successor.into()));
+    }
+
+    /// Adds a rule replacing `predecessor` by `successor` with the given `probability`, between `0.0` and `1.0`.
+    ///
+    /// All stochastic rules of a predecessor are alternatives, e.g. two rules with probability `0.5` always replace it.
+    /// If none of them is picked, the rule added with `add_rule` applies or the symbol remains unchanged.
+    /// The rules are picked with the generator seeded by `set_seed`.
+    pub fn add_stochastic_rule(
+        &mut self,
+        predecessor: impl Into<String>,
+        probability: f32,
+        successor: impl Into<String>,
+    ) {
+        let predecessor = predecessor.into();
+        let id = self.interner.intern(&predecessor);
+        self.stochastic_rules
+            .entry(id)
+            .or_default()
+            .push(StochasticRule {
+                probability,
+                rule: ReplacementRule::new(predecessor, successor.into()),
+            });
+    }
+
+    /// Sets the seed of the generator used by stochastic rules and random action parameters.
+    /// The same seed gives the same alphabet and drawing, the default seed is `DEFAULT_SEED`.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Sets how the branches are scaled by their depth when running, `RunOptions::taper` replaces it.
+    /// Scripts set it with `taper 0.8;`, see `Taper`.
+    pub fn set_taper(&mut self, taper: Option<Taper>) {
+        self.taper = taper;
+    }
+
+    pub fn taper(&self) -> Option<Taper> {
+        self.taper
+    }
+
+    /// Hashes the axiom, the rules, the interpret bindings, the variables and the seed,
+    /// thus lsystems with equal hashes generate and draw the same, see `LSystemFactory`.
+    ///
+    /// The name and the symbol definer are not hashed.
+    /// Callbacks of context sensitive and parametric rules and growth functions are hashed by their address.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash_content(&mut hasher);
+
+        for (name, rule) in self.sorted_by_name(&self.context_sensitive_rules) {
+            (name, rule.rule_cb as usize).hash(&mut hasher);
+        }
+        for (name, rule) in self.sorted_by_name(&self.parametric_production_rules) {
+            (name, rule.rule_cb as usize).hash(&mut hasher);
+        }
+        for (name, growth) in self.sorted_by_name(&self.growth_functions) {
+            (name, *growth as usize).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Whether the lsystem has context sensitive or parametric rules or growth functions,
+    /// whose callbacks can only be told apart by their address.
+    pub fn has_callbacks(&self) -> bool {
+        !self.context_sensitive_rules.is_empty()
+            || !self.parametric_production_rules.is_empty()
+            || !self.growth_functions.is_empty()
+    }
+
+    /// Hashes everything `content_hash` does but the callbacks, see `has_callbacks`.
+    pub(crate) fn hash_content(&self, hasher: &mut impl Hasher) {
+        self.axiom.hash(hasher);
+        self.seed.hash(hasher);
+
+        for (name, rule) in self.sorted_by_name(&self.generic_rule) {
+            (name, rule).hash(hasher);
+        }
+        for table in &self.rule_tables {
+            table.generations.hash(hasher);
+            for (name, rule) in self.sorted_by_name(&table.rules) {
+                (name, rule).hash(hasher);
+            }
+        }
+        for (name, rules) in self.sorted_by_name(&self.stochastic_rules) {
+            name.hash(hasher);
+            for rule in rules {
+                (rule.probability.to_bits(), &rule.rule).hash(hasher);
+            }
+        }
+        let mut priorities = self
+            .rule_priorities
+            .iter()
+            .map(|((id, kind), priority)| (self.interner.resolve(*id), kind, priority))
+            .collect::<Vec<_>>();
+        priorities.sort();
+        priorities.hash(hasher);
+        for (name, rule) in self.sorted_by_name(&self.interpretation_rules) {
+            (name, rule).hash(hasher);
+        }
+
+        for (name, actions) in self.sorted_by_name(&self.action_rules) {
+            name.hash(hasher);
+            for action in actions {
+                action.name.hash(hasher);
+                for param in &action.params.params {
+                    hash_param(param, hasher);
+                }
+            }
+        }
+        let mut variables = self.variables.iter().collect::<Vec<_>>();
+        variables.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in variables {
+            (name, value.to_bits()).hash(hasher);
+        }
+
+        for (name, rule) in self.sorted_by_name(&self.timed_rules) {
+            (name, rule.terminal_age().to_bits()).hash(hasher);
+            for module in rule.successor() {
+                (module.symbol, module.age.to_bits()).hash(hasher);
+            }
+        }
+        if let Some(taper) = self.taper {
+            (taper.length.to_bits(), taper.width.to_bits()).hash(hasher);
+        }
+    }
+
+    /// Returns the entries of `map` sorted by symbol name, the iteration order of a map differs between instances.
+    fn sorted_by_name<'a, V>(
+        &self,
+        map: &'a SymbolMap<SymbolId, V>,
+    ) -> Vec<(Option<String>, &'a V)> {
+        let mut entries = map
+            .iter()
+            .map(|(id, value)| (self.interner.resolve(*id), value))
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+
+    /// Returns the generic rules as `(predecessor, successor)` sorted by predecessor.
+    pub(crate) fn generic_rules(&self) -> Vec<(&str, &str)> {
+        let mut rules = self
+            .generic_rule
+            .values()
+            .map(|rule| (rule.predecessor.as_str(), rule.successor.as_str()))
+            .collect::<Vec<_>>();
+        rules.sort();
+        rules
+    }
+
+    /// Returns the stochastic rules of every predecessor as `(probability, successor)`, sorted by predecessor.
+    pub(crate) fn stochastic_rule_sets(&self) -> Vec<(&str, Vec<(f32, &str)>)> {
+        let mut sets = self
+            .stochastic_rules
+            .values()
+            .filter_map(|rules| {
+                let predecessor = rules.first()?.rule.predecessor.as_str();
+                let rules = rules
+                    .iter()
+                    .map(|rule| (rule.probability, rule.rule.successor.as_str()))
+                    .collect();
+                Some((predecessor, rules))
+            })
+            .collect::<Vec<_>>();
+        sets.sort_by(|a, b| a.0.cmp(b.0));
+        sets
+    }
+
+    /// Removes the stochastic rules of `predecessor`.
+    pub(crate) fn remove_stochastic_rules(&mut self, predecessor: &str) {
+        if let Some(id) = self.interner.get(predecessor) {
+            self.stochastic_rules.remove(&id);
+        }
+    }
+
+    /// Returns the actions of every interpreted symbol, sorted by symbol.
+    pub(crate) fn action_bindings(&self) -> Vec<(String, &[Action])> {
+        self.sorted_by_name(&self.action_rules)
+            .into_iter()
+            .filter_map(|(symbol, actions)| Some((symbol?, actions.as_slice())))
+            .collect()
+    }
+
+    fn warn_duplicate(&mut self, predecessor: String, rule: RuleKind) {
+        self.duplicate_rules
+            .push(GrammarWarning::DuplicateRule { predecessor, rule });
+    }
+
+    /// Returns the warnings raised while adding rules, e.g. a rule replacing an earlier rule
+    /// of the same kind for the same predecessor, for hosts to show them. `validate` reports them as well.
+    pub fn warnings(&self) -> &[GrammarWarning] {
+        &self.duplicate_rules
+    }
+
+    /// Adds a table of replacement rules that is used instead of the rules added with `add_rule`
+    /// while rewriting any of the given `generations`, where rewriting the axiom is generation `0`.
+    ///
+    /// For example a vegetative table for generations `0..=4` and a flowering table for `5..`.
+    /// If multiple tables cover a generation, the table added first is used.
+    /// Context sensitive and parametric rules apply to all generations.
+    pub fn add_rule_table<P: Into<String>, S: Into<String>>(
+        &mut self,
+        generations: impl RangeBounds<u8>,
+        rules: impl IntoIterator<Item = (P, S)>,
+    ) {
+        let rules = rules
+            .into_iter()
+            .map(|(predecessor, successor)| {
+                let predecessor = predecessor.into();
+                let id = self.interner.intern(&predecessor);
+                (id, ReplacementRule::new(predecessor, successor.into()))
+            })
+            .collect();
+
+        self.rule_tables.push(RuleTable {
+            generations: (
+                generations.start_bound().cloned(),
+                generations.end_bound().cloned(),
+            ),
+            rules,
         });
     }
 
+    /// Adds a rule that is applied once to the final generation, after all generations are derived.
+    ///
+    /// This homomorphism expands abstract symbols into drawable ones, e.g. `A` into `F[+L][-L]`,
+    /// without the expansion taking part in the derivation itself.
+    /// Interpretation rules only apply to symbols without parameters.
+    pub fn add_interpretation_rule(
+        &mut self,
+        predecessor: impl Into<String>,
+        successor: impl Into<String>,
+    ) {
+        let predecessor = predecessor.into();
+        let id = self.interner.intern(&predecessor);
+        self.interpretation_rules
+            .insert(id, ReplacementRule::new(predecessor, successor.into()));
+    }
+
+    /// Returns the replacement rules used while rewriting `generation`.
+    pub(crate) fn active_rules(&self, generation: u8) -> &SymbolMap<SymbolId, ReplacementRule> {
+        self.rule_tables
+            .iter()
+            .find(|table| table.generations.contains(&generation))
+            .map_or(&self.generic_rule, |table| &table.rules)
+    }
+
+    pub fn add_context_sensitive_rule(
+        &mut self,
+        predecessor: impl Into<String>,
+        rule_cb: ContextSensitiveRuleCB,
+    ) {
+        let predecessor = predecessor.into();
+        let id = self.interner.intern(&predecessor);
+        let previous = self
+            .context_sensitive_rules
+            .insert(id, ContextSensitiveProductionRule::new(rule_cb));
+
+        if previous.is_some() {
+            self.warn_duplicate(predecessor, RuleKind::ContextSensitive);
+        }
+    }
+
+    pub fn add_parametic_production_rule(
+        &mut self,
+        predecessor: impl Into<String>,
+        rule_cb: ParametricRuleCB,
+    ) {
+        let predecessor = predecessor.into();
+        let id = self.interner.intern(&predecessor);
+        let previous = self
+            .parametric_production_rules
+            .insert(id, ParametricProductionRule::new(rule_cb));
+
+        if previous.is_some() {
+            self.warn_duplicate(predecessor, RuleKind::Parametric);
+        }
+    }
+
+    /// Returns the interner holding the ids of the rule predecessors.
+    pub fn interner(&self) -> &SymbolInterner {
+        &self.interner
+    }
+}
+
+/// How `LSystem::rewrite_symbol` rewrote a symbol.
+pub(crate) struct SymbolRewrite {
+    /// The kind of the rule that rewrote the symbol, `None` if it was copied.
+    pub(crate) rule: Option<RuleKind>,
+    /// The index of the stochastic rule that rewrote the symbol.
+    pub(crate) stochastic: Option<usize>,
+    /// The index of the last char of the symbol, the `)` closing its parameters if it has any.
+    pub(crate) end: usize,
+}
+
+/// The symbols of the current generation while rewriting an L-system.
+struct GenerationState {
+    generation: u8,
+    current: Vec<char>,
+    // Both buffers are reused for every generation, swapping roles after each rewrite.
+    next: Vec<char>,
+    growth: f32,
+    /// Picks the stochastic rules, drawn from in symbol order to be reproducible.
+    rng: DefaultRng,
+    /// Records the derivation if tracing is enabled.
+    trace: Option<DerivationTrace>,
+}
+
+impl GenerationState {
+    fn new(axiom: &str, seed: u64) -> Self {
+        Self {
+            generation: 0,
+            current: axiom.chars().collect(),
+            next: Vec::new(),
+            growth: 1.0,
+            rng: seeded_rng(seed),
+            trace: None,
+        }
+    }
+
+    /// Rewrites the current generation into the next one.
+    fn advance<A: SymbolDefiner>(
+        &mut self,
+        lsystem: &LSystem<A>,
+        guard: &GenerateGuard,
+    ) -> Result<(), GenerateError> {
+        let generation = self.generation + 1;
+
+        self.next.clear();
+        self.next
+            .reserve(guard.cap_reservation((self.current.len() as f32 * self.growth) as usize));
+
+        let mut step = self
+            .trace
+            .as_ref()
+            .map(|_| Vec::with_capacity(self.next.capacity()));
+        lsystem
+            .rewrite_generation(
+                self.generation,
+                &self.current,
+                &mut self.next,
+                guard,
+                &mut self.rng,
+                step.as_mut(),
+            )
+            .map_err(|abort| abort.error(generation))?;
+
+        if let (Some(trace), Some(step)) = (&mut self.trace, step) {
+            trace.steps.push(step);
+        }
+
+        self.growth = self.next.len() as f32 / self.current.len().max(1) as f32;
+        core::mem::swap(&mut self.current, &mut self.next);
+        self.generation = generation;
+        Ok(())
+    }
+
+    /// Returns the alphabet of the current generation with the interpretation rules of `lsystem` applied.
+    fn alphabet<A: SymbolDefiner>(
+        &mut self,
+        lsystem: &LSystem<A>,
+        guard: &GenerateGuard,
+    ) -> Result<Alphabet, Abort> {
+        if lsystem.interpretation_rules.is_empty() {
+            return Ok(Alphabet::from_chars(
+                &self.current,
+                self.generation,
+                &lsystem.alphabet_definer,
+            ));
+        }
+
+        let mut interpreted = Vec::with_capacity(self.current.len());
+        let mut origins = self
+            .trace
+            .as_ref()
+            .map(|_| Vec::with_capacity(self.current.len()));
+        lsystem.interpret(&self.current, &mut interpreted, guard, origins.as_mut())?;
+        if let Some(trace) = &mut self.trace {
+            trace.interpretation = origins;
+        }
+
+        Ok(Alphabet::from_chars(
+            &interpreted,
+            self.generation,
+            &lsystem.alphabet_definer,
+        ))
+    }
+}
+
+/// The expansions of the symbols of a memoizable lsystem, keyed by symbol and number of generations.
+///
+/// Every expansion is built once from the expansions of its successor, thus generating takes about as long as
+/// copying the result, instead of rewriting every generation.
+struct Expansions<'a, A: SymbolDefiner> {
+    lsystem: &'a LSystem<A>,
+    guard: &'a GenerateGuard<'a>,
+    expansions: HashMap<(char, u8), Vec<char>>,
+    /// The number of symbols held by `expansions`, counted towards the memory budget.
+    expanded: usize,
+    /// The number of copied expansions, the timeout and cancellation are checked every `TIMEOUT_CHECK_INTERVAL` copies.
+    copies: usize,
+}
+
+impl<'a, A: SymbolDefiner> Expansions<'a, A> {
+    fn new(lsystem: &'a LSystem<A>, guard: &'a GenerateGuard<'a>) -> Self {
+        Self {
+            lsystem,
+            guard,
+            expansions: HashMap::new(),
+            expanded: 0,
+            copies: 0,
+        }
+    }
+
+    /// Returns `symbols` expanded by `generations` generations.
+    fn expand(&mut self, symbols: &str, generations: u8) -> Result<Vec<char>, Abort> {
+        self.guard.check_cancellation()?;
+        let mut expanded = Vec::new();
+        for symbol in symbols.chars() {
+            self.expand_into(symbol, generations, &mut expanded)?;
+        }
+        Ok(expanded)
+    }
+
+    fn expand_into(
+        &mut self,
+        symbol: char,
+        generations: u8,
+        expanded: &mut Vec<char>,
+    ) -> Result<(), Abort> {
+        let rule = match self.lsystem.generic_rule.get(&SymbolId::from(symbol)) {
+            Some(rule) if generations > 0 => rule,
+            _ => {
+                expanded.push(symbol);
+                return Ok(());
+            }
+        };
+
+        if !self.expansions.contains_key(&(symbol, generations)) {
+            let mut expansion = Vec::new();
+            for successor in rule.successor.chars() {
+                self.expand_into(successor, generations - 1, &mut expansion)?;
+            }
+            self.expanded += expansion.len();
+            self.expansions.insert((symbol, generations), expansion);
+        }
+
+        expanded.extend_from_slice(&self.expansions[&(symbol, generations)]);
+        self.copies += 1;
+        self.guard.check(self.expanded, expanded.len(), self.copies)
+    }
+}
+
+/// Iterator over the generations of an L-system, see `LSystem::generations`.
+pub struct Generations<'a, A: SymbolDefiner = DefaultAlphabetSymbolDefiner> {
+    lsystem: &'a LSystem<A>,
+    state: GenerationState,
+    started: bool,
+}
+
+impl<'a, A: SymbolDefiner> Iterator for Generations<'a, A> {
+    type Item = Alphabet;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.started {
+            if self.state.generation == u8::MAX {
+                return None;
+            }
+
+            let unbounded = GenerateOptions::default();
+            self.state
+                .advance(self.lsystem, &GenerateGuard::new(&unbounded))
+                .expect("Unbounded generation can not exceed a budget.");
+        }
+
+        self.started = true;
+
+        let unbounded = GenerateOptions::default();
+        let alphabet = self
+            .state
+            .alphabet(self.lsystem, &GenerateGuard::new(&unbounded))
+            .expect("Unbounded generation can not exceed a budget.");
+        Some(alphabet)
+    }
+}
+
+/// Describes an lsystem by building it, e.g. to register the plants of a game as plugins.
+///
+/// Definitions are object safe, a registry can hold `Box<dyn LSystemDefinition<Definer = A>>`.
+pub trait LSystemDefinition {
+    type Definer: SymbolDefiner;
+
+    fn name(&self) -> &str;
+
+    /// Builds a new lsystem, every call returns an equal one.
+    fn build(&self) -> LSystem<Self::Definer>;
+
+    /// The number of generations the lsystem is meant to be drawn at.
+    fn default_generations(&self) -> u8 {
+        4
+    }
+
+    /// The resolver of the actions interpreted by the lsystem, the `default_actions` by default.
+    fn actions(&self) -> ActionResolver {
+        ActionResolver::with_default_actions()
+    }
+}
+
+/// The start state of the turtle when running an lsystem and how to observe the run, see `LSystem::run_with_options`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunOptions {
+    pub start_position: Vec3,
+    /// The direction the turtle moves forward in.
+    pub start_heading: Vec3,
+    /// The direction pointing out of the drawing plane of the turtle, rotations around z turn around this axis.
+    pub start_up: Vec3,
+    /// Scale applied to every forward move of the turtle.
+    pub step: f32,
+    /// Width of the drawn segments, the start width of the turtle.
+    pub width: f32,
+    /// Color of the drawn segments, the start color of the turtle.
+    pub color: Color,
+    /// The colors selected by index with `SetColorAction` and `IncColorAction`, see `ExecuteContext::palette`.
+    pub palette: Vec<Color>,
+    /// The names of the materials selected by index with `SetMaterialAction`, see `ExecuteContext::materials`.
+    pub materials: Vec<String>,
+    /// Aborts the run with `RuntimeError::Cancelled` once the token is cancelled.
+    pub cancellation: Option<CancellationToken>,
+    /// Called with the number of run symbols of the alphabet every few thousand symbols.
+    pub progress: Option<Progress>,
+    /// Records the executed actions as `ExecuteContext::commands`, e.g. to export them with `format_commands`.
+    pub record_commands: bool,
+    /// Keeps branches out of the space occupied by the lines drawn before, see `Collision`.
+    /// Lines are only tested if the run records them, `LSystem::run_with` ignores this option.
+    pub collision: Option<Collision>,
+    /// Scales the moves and lines of the branches by their depth, replacing the taper of the lsystem.
+    pub taper: Option<Taper>,
+    /// Colors the drawn geometry by the depth or the path length of its elements,
+    /// replacing the colors set by actions, see `ColorMap`.
+    pub color_map: Option<ColorMap>,
+}
+
+impl RunOptions {
+    /// Returns the turtle placed at the start pose.
+    pub fn turtle(&self) -> Turtle {
+        let mut turtle = Turtle::with_pose(self.start_position, self.start_heading, self.start_up);
+        turtle.scale(self.step);
+        turtle.set_width(self.width);
+        turtle.set_color(self.color);
+        turtle
+    }
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            start_position: Vec3::new(0.0, -0.5, 0.0),
+            start_heading: Vec3::Y,
+            start_up: Vec3::Z,
+            step: 1.0,
+            width: 1.0,
+            color: Color::BLACK,
+            palette: vec![],
+            materials: vec![],
+            cancellation: None,
+            progress: None,
+            record_commands: false,
+            collision: None,
+            taper: None,
+            color_map: None,
+        }
+    }
+}
+
+/// Scales the branches by their depth, the moves of the turtle by `length` and the width of its lines by `width`
+/// per saved transform, e.g. `DrawForwardAction(1)` draws `0.8 * 0.8` long at depth 2 with a `length` of `0.8`.
+///
+/// This is how trees get their silhouette without growth rules for every branch,
+/// see `RunOptions::taper` and `LSystem::set_taper`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Taper {
+    pub length: f32,
+    pub width: f32,
+}
+
+impl Taper {
+    /// Scales the length and the width by `decay` per depth, like `taper 0.8;` in scripts.
+    pub fn new(decay: f32) -> Self {
+        Self {
+            length: decay,
+            width: decay,
+        }
+    }
+
+    pub fn with_length(mut self, length: f32) -> Self {
+        self.length = length;
+        self
+    }
+
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Returns the factor of the moves at `depth`, `length` to the power of `depth`.
+    pub fn length_at(&self, depth: usize) -> f32 {
+        Self::scale(self.length, depth)
+    }
+
+    /// Returns the factor of the line widths at `depth`, `width` to the power of `depth`.
+    pub fn width_at(&self, depth: usize) -> f32 {
+        Self::scale(self.width, depth)
+    }
+
+    fn scale(decay: f32, depth: usize) -> f32 {
+        (0..depth).fold(1.0, |scale, _| scale * decay)
+    }
+}
+
+impl Default for Taper {
+    /// Keeps every branch at its size.
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+/// The state of running an lsystem, actions push their user defined primitives of type `E` onto `elements`.
+pub struct ExecuteContext<E = GeometryElement> {
+    /// Elements generated by the lsystem.
+    pub elements: Vec<E>,
+    /// Used for saving transforms during lsystem generation.
+    pub(crate) transform_stack: TurtleTransformStack,
+    /// Used for turtle graphics.
+    pub turtle: Turtle,
+    /// The changes of the geometry drawn by the selected turtle, see `record` and `turtle_events`.
+    pub events: Vec<TurtleEvent>,
+    /// The polygons closed by the turtle, see `end_polygon`.
+    pub polygons: Vec<Polygon>,
+    /// The vertices of the polygons that are still open, the innermost last.
+    pub(crate) open_polygons: Vec<Vec<Vec3>>,
+    /// The bounds of the recorded lines and closed polygons, see `bounds`.
+    bounds: Option<BoundingBox>,
+    /// The position and branch depth of the turtle when it was last recorded.
+    pub(crate) recorded: Option<(Vec3, usize)>,
+    /// The turtles of the run, the slot of the selected turtle is empty, see `spawn_turtle`.
+    pub(crate) turtles: Vec<ParkedTurtle>,
+    /// The index of the selected turtle in `turtles`.
+    pub(crate) active_turtle: usize,
+    /// The recorded lines tested for collisions if `RunOptions::collision` is set.
+    pub(crate) collision: Option<CollisionState>,
+    /// Whether the line recorded last entered occupied space, the rest of its branch is skipped.
+    pub(crate) collided: bool,
+    pub rng: Box<dyn Rng>,
+    /// The colors selected by index, see `set_color_index`.
+    pub palette: Vec<Color>,
+    /// The names of the materials selected by index, see `set_material_index`.
+    pub materials: Vec<String>,
+    /// The meshes placed by `Instance`s, by the name of the model, see `place_model`.
+    pub models: BTreeMap<String, Mesh>,
+    /// The query modules encountered while running, in alphabet order.
+    pub queries: Vec<Query>,
+    pub stats: RunStats,
+    cancellation: Option<CancellationToken>,
+    progress: Option<Progress>,
+    /// The state of the actions, one value per type, see `state`.
+    states: HashMap<TypeId, Box<dyn Any>>,
+    /// The turtle commands of the executed actions, recorded if `RunOptions::record_commands` is set.
+    pub commands: Vec<TurtleCommand>,
+    record_commands: bool,
+    /// The actions executed for the running symbols with their parameters, collected while stepping,
+    /// see `ExecutionStepper`.
+    pub(crate) executed_actions: Option<Vec<Action>>,
+    /// The index of the running symbol in the alphabet.
+    pub(crate) index: usize,
+    /// The index of the symbol the run continues with, see `save_state`.
+    pub(crate) position: usize,
+    /// The index of the last symbol skipped by the run, e.g. the end of a parameter list or a cut branch.
+    pub(crate) skipped_until: Option<usize>,
+    /// The number of branches enclosing the running symbol, see `depth`.
+    pub(crate) branch_depth: usize,
+    /// The generation of the run alphabet, see `generation`.
+    pub(crate) generation: u8,
+    /// The generation every symbol of the alphabet was produced in, see `with_origins`.
+    origins: Option<Vec<u8>>,
+    /// Scales the moves and lines by the depth, see `Taper`.
+    taper: Option<Taper>,
+    /// Colors the geometry returned by `turtle_geometry` and `geometry`, see `RunOptions::color_map`.
+    color_map: Option<ColorMap>,
+    /// The material of the polygons closed by the turtle, see `set_material`.
+    pub(crate) material: Option<String>,
+    /// The error an action failed with, see `fail`.
+    pub(crate) error: Option<RuntimeError>,
+}
+
+impl<E> ExecuteContext<E> {
+    pub fn push(&mut self, transform: Turtle) {
+        self.transform_stack.push(transform);
+    }
+
+    /// Returns the last pushed transform,
+    /// `RuntimeError::UnbalancedBrackets` at the index of the running symbol if none was pushed.
+    pub fn pop(&mut self) -> Result<Turtle, RuntimeError> {
+        self.transform_stack
+            .pop()
+            .ok_or(RuntimeError::UnbalancedBrackets { index: self.index })
+    }
+
+    /// Aborts the run with `error` once the running action returns, e.g. with the error of `pop`.
+    ///
+    /// The first error is kept if actions fail several times.
+    pub fn fail(&mut self, error: RuntimeError) {
+        self.error.get_or_insert(error);
+    }
+
+    fn record_command(&mut self, symbol: &Symbol, action: &Action) {
+        if let Some(actions) = &mut self.executed_actions {
+            actions.push(action.clone());
+        }
+        if self.record_commands {
+            let args = (0..action.params.len())
+                .filter_map(|index| action.params.get(index))
+                .collect();
+            self.commands.push(TurtleCommand::from_action(
+                symbol.char(),
+                &action.name,
+                args,
+            ));
+        }
+    }
+}
+
+impl ExecuteContext {
+    pub fn new() -> Self {
+        Self::with_options(&RunOptions::default())
+    }
+}
+
+impl<E> ExecuteContext<E> {
+    pub fn with_options(options: &RunOptions) -> Self {
+        //  perchance::seed_global(perchance::gen_time_seed());
+
+        Self {
+            elements: vec![],
+            transform_stack: TurtleTransformStack::new(),
+            turtle: options.turtle(),
+            events: vec![],
+            polygons: vec![],
+            open_polygons: vec![],
+            bounds: None,
+            recorded: None,
+            turtles: vec![ParkedTurtle::new(Turtle::default(), None)],
+            active_turtle: 0,
+            collision: options
+                .collision
+                .map(|collision| CollisionState::new(collision, options.step)),
+            collided: false,
+            rng: Box::new(seeded_rng(DEFAULT_SEED)),
+            palette: options.palette.clone(),
+            materials: options.materials.clone(),
+            models: BTreeMap::new(),
+            queries: vec![],
+            stats: RunStats::default(),
+            cancellation: options.cancellation.clone(),
+            progress: options.progress.clone(),
+            states: HashMap::new(),
+            commands: vec![],
+            record_commands: options.record_commands,
+            executed_actions: None,
+            index: 0,
+            position: 0,
+            skipped_until: None,
+            branch_depth: 0,
+            generation: 0,
+            origins: None,
+            taper: options.taper,
+            color_map: options.color_map.clone(),
+            material: None,
+            error: None,
+        }
+    }
+
+    /// Returns the state of type `T` kept during this run, it is created with `T::default()` on first use.
+    ///
+    /// Lets actions coordinate across invocations, e.g. count branches or accumulate the path length.
+    pub fn state<T: Default + 'static>(&mut self) -> &mut T {
+        self.states
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::<T>::default())
+            .downcast_mut()
+            .expect("States are stored by their type id")
+    }
+
+    /// Returns the state of type `T` if an action used it, see `state`.
+    pub fn get_state<T: 'static>(&self) -> Option<&T> {
+        self.states.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    /// Returns the branch depth of the running symbol, the number of `[` enclosing it,
+    /// thus a `[` is in the branch it opens and a `]` in the branch it returns to.
+    /// Available to action parameters as the implicit variable `depth`, e.g. to taper or color branches.
+    pub fn depth(&self) -> usize {
+        self.branch_depth
+    }
+
+    /// Returns the generation the running symbol was produced in, the generation of the run alphabet
+    /// unless the generation of every symbol was set with `with_origins`.
+    /// Available to action parameters as the implicit variable `gen`, e.g. to place leaves on young branches only.
+    pub fn generation(&self) -> u8 {
+        self.origins
+            .as_ref()
+            .and_then(|origins| origins.get(self.index).copied())
+            .unwrap_or(self.generation)
+    }
+
+    /// Sets the generation every symbol of the run alphabet was produced in, see `DerivationTrace::origins`.
+    pub fn with_origins(mut self, origins: Vec<u8>) -> Self {
+        self.origins = Some(origins);
+        self
+    }
+
+    /// Returns the variables `depth` and `gen` of the running symbol, see `depth` and `generation`.
+    pub(crate) fn implicit_variables(&self) -> Variables {
+        let mut variables = Variables::new();
+        variables.set("depth", self.depth() as f32);
+        variables.set("gen", self.generation() as f32);
+        variables
+    }
+
+    /// Returns the number of saved transforms, the depth of the geometry drawn by the turtle.
+    fn stack_depth(&self) -> usize {
+        self.transform_stack.len()
+    }
+
+    /// Records how the geometry changed since the previous call onto `events`,
+    /// the first call only records the start position of the turtle.
+    ///
+    /// Opened and closed branches are recorded as `TurtleEvent::Branch` and `TurtleEvent::BranchEnd`.
+    /// A line is recorded if the turtle moved, unless a branch was closed, then the turtle jumped back.
+    pub fn record(&mut self) {
+        let origin = self.turtle.origin();
+        let depth = self.stack_depth();
+        let Some((previous, previous_depth)) = self.recorded.replace((origin, depth)) else {
+            return;
+        };
+
+        for depth in previous_depth + 1..=depth {
+            self.events.push(TurtleEvent::Branch {
+                origin: previous,
+                depth,
+            });
+            if let Some(state) = &mut self.collision {
+                state.branches.push((self.events.len(), state.lines.len()));
+            }
+        }
+        for depth in (depth + 1..=previous_depth).rev() {
+            self.events.push(TurtleEvent::BranchEnd { depth });
+            if let Some(state) = &mut self.collision {
+                state.branches.pop();
+            }
+        }
+
+        if origin != previous && depth >= previous_depth {
+            if self.collides(Segment::new(previous, origin)) {
+                return;
+            }
+            self.include(previous);
+            self.include(origin);
+            self.events.push(TurtleEvent::Line {
+                from: previous,
+                to: origin,
+                width: self.turtle.width() * self.taper.map_or(1.0, |taper| taper.width_at(depth)),
+                color: self.turtle.color(),
+                depth,
+                index: self.index,
+                material: self.material.clone(),
+            });
+        }
+    }
+
+    /// Selects the color at `index` of the `palette` for the lines drawn by the turtle from now on.
+    ///
+    /// The color is part of the turtle, thus closing a branch restores the color it was opened with.
+    pub fn set_color_index(&mut self, index: usize) {
+        self.turtle.set_color_index(index, &self.palette);
+    }
+
+    /// Selects the next color of the `palette`, wrapping around to the first color.
+    pub fn increment_color(&mut self) {
+        self.set_color_index(self.turtle.color_index() + 1);
+    }
+
+    /// Opens a polygon, the following `add_vertex` calls add its vertices until `end_polygon` closes it.
+    ///
+    /// Polygons can be nested, e.g. a leaf opened while drawing another leaf, vertices go to the innermost one.
+    pub fn begin_polygon(&mut self) {
+        self.open_polygons.push(vec![]);
+    }
+
+    /// Adds the position of the turtle to the innermost open polygon, does nothing if no polygon is open.
+    pub fn add_vertex(&mut self) {
+        let origin = self.turtle.origin();
+        if let Some(vertices) = self.open_polygons.last_mut() {
+            vertices.push(origin);
+        }
+    }
+
+    /// Sets the material of the lines drawn and the polygons closed afterwards, e.g. `"leaf"`,
+    /// `None` draws them without material.
+    ///
+    /// The material is kept until it is set again, returning from a branch does not restore it.
+    pub fn set_material(&mut self, material: Option<String>) {
+        self.material = material;
+    }
+
+    /// Sets the material named at `index` of `materials`, the index itself, e.g. `"2"`, if it has no name.
+    pub fn set_material_index(&mut self, index: usize) {
+        let material = self
+            .materials
+            .get(index)
+            .cloned()
+            .unwrap_or_else(|| index.to_string());
+        self.set_material(Some(material));
+    }
+
+    /// Returns the material of the lines and polygons drawn by the turtle, see `set_material`.
+    pub fn material(&self) -> Option<&str> {
+        self.material.as_deref()
+    }
+
+    /// Closes the innermost open polygon and pushes it, filled with the color of the turtle, onto `polygons`.
+    ///
+    /// Polygons with less than three vertices enclose no area and are dropped.
+    pub fn end_polygon(&mut self) {
+        let Some(vertices) = self.open_polygons.pop() else {
+            return;
+        };
+        if vertices.len() >= 3 {
+            for vertex in &vertices {
+                self.include(*vertex);
+            }
+            self.polygons.push(Polygon {
+                vertices,
+                color: self.turtle.color(),
+                depth: self.stack_depth(),
+                material: self.material.clone(),
+            });
+        }
+    }
+
+    /// Moves the turtle forward and records the line it drew right away,
+    /// thus several moves of one symbol are recorded as separate lines.
+    /// The length is scaled by the depth if the run tapers, see `Taper`.
+    pub fn draw_forward(&mut self, len: f32) {
+        self.record();
+        self.turtle.forward(len * self.length_scale());
+        self.record();
+    }
+
+    /// Moves the turtle forward without drawing a line, the pen is up.
+    /// The length is scaled by the depth if the run tapers, see `Taper`.
+    pub fn move_forward(&mut self, len: f32) {
+        self.record();
+        self.turtle.forward(len * self.length_scale());
+        if let Some((position, _)) = &mut self.recorded {
+            *position = self.turtle.origin();
+        }
+    }
+
+    fn length_scale(&self) -> f32 {
+        self.taper
+            .map_or(1.0, |taper| taper.length_at(self.stack_depth()))
+    }
+
+    /// Returns the bounds of the geometry drawn by the turtle, `None` if nothing was drawn yet.
+    ///
+    /// The bounds are kept up to date while running and include the lines already streamed by `LSystem::run_with`,
+    /// the `elements` pushed by actions are not included.
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        self.bounds
+    }
+
+    /// Returns the center of `bounds`, e.g. to center the drawing in a viewport.
+    pub fn center(&self) -> Option<Vec3> {
+        self.bounds.map(|bounds| bounds.center())
+    }
+
+    /// Returns whether `line` enters occupied space, then it is not drawn and with `CollisionResponse::Reject`
+    /// the lines of its branch are removed. Otherwise it occupies its space from now on.
+    fn collides(&mut self, line: Segment) -> bool {
+        let Some(state) = &mut self.collision else {
+            return false;
+        };
+        if state.lines.close_to(&line).is_empty() {
+            state.lines.insert(line);
+            return false;
+        }
+
+        self.stats.collisions += 1;
+        self.collided = true;
+        if let (CollisionResponse::Reject, Some(&(events, lines))) = (
+            state.collision.response,
+            state
+                .branches
+                .last()
+                .filter(|(_, lines)| *lines >= state.shared),
+        ) {
+            self.events.truncate(events);
+            state.lines.truncate(lines);
+
+            // The removed lines may have extended the bounds.
+            self.recompute_bounds();
+        }
+        true
+    }
+
+    /// Computes the bounds from the recorded lines and closed polygons, e.g. after lines were removed.
+    pub(crate) fn recompute_bounds(&mut self) {
+        self.bounds = None;
+        let points = self
+            .all_turtle_events()
+            .filter_map(|event| match *event {
+                TurtleEvent::Line { from, to, .. } => Some([from, to]),
+                TurtleEvent::Branch { .. } | TurtleEvent::BranchEnd { .. } => None,
+            })
+            .flatten()
+            .chain(
+                self.polygons
+                    .iter()
+                    .flat_map(|polygon| polygon.vertices.clone()),
+            )
+            .collect::<Vec<_>>();
+        for point in points {
+            self.include(point);
+        }
+    }
+
+    fn include(&mut self, point: Vec3) {
+        self.bounds
+            .get_or_insert(BoundingBox::from_min_max(point, point))
+            .extend(point);
+    }
+
+    /// Replaces the generator used by actions and random action parameters.
+    pub fn with_rng(mut self, rng: impl Rng + 'static) -> Self {
+        self.rng = Box::new(rng);
+        self
+    }
+
     pub fn random_float(&mut self) -> f32 {
-        self.rng.uniform_f32()
+        self.rng.next_f32()
+    }
+
+    fn apply_color_map(&self, geometry: &mut Geometry) {
+        if let Some(map) = &self.color_map {
+            geometry.apply_color_map(map);
+        }
+    }
+
+    /// Returns the geometry drawn by the turtles, the recorded lines and the closed `polygons`,
+    /// without the `elements` pushed by actions.
+    pub fn turtle_geometry(&self) -> Geometry {
+        let mut geometry = self.drawn_geometry();
+        self.apply_color_map(&mut geometry);
+        geometry
+    }
+
+    /// Same as `turtle_geometry` but keeps the colors the turtles drew with.
+    fn drawn_geometry(&self) -> Geometry {
+        let segments = self
+            .all_turtle_events()
+            .filter_map(|event| match event {
+                TurtleEvent::Line {
+                    from,
+                    to,
+                    width,
+                    color,
+                    depth,
+                    material,
+                    ..
+                } => Some(Segment {
+                    width: *width,
+                    color: *color,
+                    depth: *depth,
+                    material: material.clone(),
+                    ..Segment::new(*from, *to)
+                }),
+                TurtleEvent::Branch { .. } | TurtleEvent::BranchEnd { .. } => None,
+            })
+            .collect();
+
+        Geometry {
+            segments,
+            polygons: self.polygons.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the index of the symbol that drew every segment of `turtle_geometry` in the run alphabet,
+    /// in the order of the segments, which are also the first segments of `geometry`.
+    ///
+    /// Editors find the rule that produced a picked segment, see `Bvh::raycast`,
+    /// by following the symbol to its ancestors with `DerivationTrace::ancestors`.
+    pub fn segment_symbols(&self) -> Vec<usize> {
+        self.all_turtle_events()
+            .filter_map(|event| match *event {
+                TurtleEvent::Line { index, .. } => Some(index),
+                TurtleEvent::Branch { .. } | TurtleEvent::BranchEnd { .. } => None,
+            })
+            .collect()
+    }
+}
+
+impl ExecuteContext {
+    /// Returns the geometry drawn by the turtle together with the segments, polygons, meshes and instances
+    /// pushed onto `elements` and the `models` of the instances.
+    pub fn geometry(&self) -> Geometry {
+        let mut geometry = self.drawn_geometry();
+        for element in &self.elements {
+            match element {
+                GeometryElement::Segment(segment) => geometry.segments.push(segment.clone()),
+                GeometryElement::Polygon(polygon) => geometry.polygons.push(polygon.clone()),
+                GeometryElement::Mesh(mesh) => geometry.meshes.push(mesh.clone()),
+                GeometryElement::Instance(instance) => geometry.instances.push(instance.clone()),
+            }
+        }
+        geometry.models = self.models.clone();
+        self.apply_color_map(&mut geometry);
+        geometry
+    }
+
+    /// Adds the mesh of the model `name`, e.g. a leaf, placed by `place_model`.
+    ///
+    /// The mesh is in the space of the model, `+Y` points along the heading of the turtle placing it, see `Instance::at_turtle`.
+    pub fn add_model(&mut self, name: impl Into<String>, mesh: Mesh) {
+        self.models.insert(name.into(), mesh);
+    }
+
+    /// Pushes an `Instance` of the model `name` onto `elements`, placed at the turtle with its color,
+    /// the depth of the turtle and the material of the polygons, see `set_material`.
+    ///
+    /// The model is stored once, however often it is placed.
+    pub fn place_model(&mut self, name: impl Into<String>) {
+        let instance = Instance {
+            depth: self.stack_depth(),
+            material: self.material.clone(),
+            ..Instance::at_turtle(name, &self.turtle)
+        };
+        self.elements.push(GeometryElement::Instance(instance));
+    }
+
+    /// Pushes `element` with the material `material` onto `elements`, see `GeometryElement::tagged`.
+    pub fn emit_tagged(&mut self, material: impl Into<String>, element: GeometryElement) {
+        self.elements.push(element.tagged(material));
+    }
+
+    /// Same as `geometry` but records the time it took in `stats`.
+    pub fn build_geometry(&mut self) -> Geometry {
+        let stopwatch = Stopwatch::start();
+        let geometry = self.geometry();
+        self.stats.geometry = stopwatch.elapsed();
+        geometry
     }
 }
 
-impl Default for ExecuteContext {
+impl<E> Default for ExecuteContext<E> {
     fn default() -> Self {
-        Self::new()
+        Self::with_options(&RunOptions::default())
     }
 }
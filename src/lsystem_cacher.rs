@@ -1,50 +1,216 @@
+//! Caches the generated alphabets and drawings of lsystems, for engines instantiating many identical plants.
+use core::fmt;
 
-#[derive(Hash, PartialEq, Eq, Clone)]
-pub struct LSystemKey(&'static str, u8);
+use crate::{
+    prelude::*, ActionResolver, Alphabet, ExecuteContext, GenerateError, GenerateOptions,
+    GeometryElement, LSystem, LSystemDefinition, RuntimeError, SymbolDefiner,
+};
 
-pub struct LSystemFactory<T> {
-    lsystems_alphabets: HashMap<LSystemKey, Alphabet>,
-    lsystems_meshes: HashMap<LSystemKey, Vec<T>>,
+/// Identifies a generation of an lsystem by its `LSystem::content_hash`,
+/// thus equal lsystems share their entry while an edited lsystem gets a new one.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct LSystemKey {
+    pub hash: u64,
+    pub generation: u8,
 }
 
-impl<T> LSystemFactory<T> {
+impl LSystemKey {
+    pub fn new<A: SymbolDefiner>(lsystem: &LSystem<A>, generation: u8) -> Self {
+        Self {
+            hash: lsystem.content_hash(),
+            generation,
+        }
+    }
+}
+
+/// An error of generating or running an lsystem for the `LSystemFactory`.
+#[derive(Debug)]
+pub enum FactoryError {
+    Generate(GenerateError),
+    Run(RuntimeError),
+}
+
+impl fmt::Display for FactoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FactoryError::Generate(error) => write!(f, "{error}"),
+            FactoryError::Run(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl core::error::Error for FactoryError {}
+
+impl From<GenerateError> for FactoryError {
+    fn from(error: GenerateError) -> Self {
+        FactoryError::Generate(error)
+    }
+}
+
+impl From<RuntimeError> for FactoryError {
+    fn from(error: RuntimeError) -> Self {
+        FactoryError::Run(error)
+    }
+}
+
+/// Generates and runs every generation of an lsystem once, e.g. for a forest of identical trees.
+///
+/// The entries are keyed by `LSystemKey`, the drawings are in the space of the lsystem,
+/// place the instances with their own transforms.
+/// The action resolver is not part of the key, use one factory per resolver.
+/// Once `max_entries` generations are cached, the least recently used one is evicted.
+pub struct LSystemFactory<E = GeometryElement> {
+    entries: HashMap<LSystemKey, Entry<E>>,
+    generate_options: GenerateOptions,
+    max_entries: usize,
+    /// Counts the accesses, the entry with the oldest access is evicted first.
+    clock: u64,
+}
+
+struct Entry<E> {
+    alphabet: Alphabet,
+    /// The run of `alphabet`, `None` until it is requested.
+    context: Option<ExecuteContext<E>>,
+    last_used: u64,
+}
+
+impl<E> LSystemFactory<E> {
+    /// Creates an empty factory caching up to 64 generations.
     pub fn new() -> Self {
         Self {
-            lsystems_alphabets: HashMap::new(),
-            lsystems_meshes: HashMap::new(),
+            entries: HashMap::new(),
+            generate_options: GenerateOptions::default(),
+            max_entries: 64,
+            clock: 0,
         }
     }
 
-    pub fn generate<L: LSystemDefinition<T>>(
+    /// Limits generation, e.g. to bound the memory of lsystems loaded from user scripts.
+    pub fn with_generate_options(mut self, generate_options: GenerateOptions) -> Self {
+        self.generate_options = generate_options;
+        self
+    }
+
+    /// Sets the number of generations cached before the least recently used one is evicted, at least one.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self.evict(self.max_entries);
+        self
+    }
+
+    /// Returns the alphabet of `generation` of `lsystem`, generating it if it is not cached.
+    pub fn generate<A: SymbolDefiner>(
+        &mut self,
+        lsystem: &LSystem<A>,
+        generation: u8,
+    ) -> Result<&Alphabet, GenerateError> {
+        Ok(&self.entry(lsystem, generation)?.alphabet)
+    }
+
+    /// Returns the run of `generation` of `lsystem` with the actions of `resolver`, generating and running it if it is not cached.
+    pub fn run<A: SymbolDefiner>(
         &mut self,
-        definition: &L,
+        lsystem: &LSystem<A>,
+        resolver: &ActionResolver<E>,
         generation: u8,
-    ) -> &Alphabet {
-        let key = LSystemKey(definition.name(), generation);
+    ) -> Result<&ExecuteContext<E>, FactoryError> {
+        let entry = self.entry(lsystem, generation)?;
+        let context = match entry.context.take() {
+            Some(context) => context,
+            None => lsystem.run(resolver, &entry.alphabet)?,
+        };
+        Ok(entry.context.insert(context))
+    }
+
+    pub fn contains(&self, key: &LSystemKey) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Removes the cached generations of `lsystem`.
+    ///
+    /// An edited lsystem has another key, thus this only frees the memory of generations that are not needed anymore.
+    pub fn invalidate<A: SymbolDefiner>(&mut self, lsystem: &LSystem<A>) {
+        let hash = lsystem.content_hash();
+        self.entries.retain(|key, _| key.hash != hash);
+    }
+
+    /// Removes a cached generation, returns `true` if it was cached.
+    pub fn remove(&mut self, key: &LSystemKey) -> bool {
+        self.entries.remove(key).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns the number of cached generations.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
 
-        self.lsystems_alphabets.entry(key).or_insert_with(|| {
-            let lsystem = definition.lsystem();
-            lsystem.generate(generation)
-        })
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
     }
 
-    pub fn render<L: LSystemDefinition<T>>(
+    fn entry<A: SymbolDefiner>(
         &mut self,
-        definition: &L,
+        lsystem: &LSystem<A>,
         generation: u8,
-        position: Vec3,
-        rotation: Vec3,
-        scale: f32,
-    ) -> Option<&Vec<T>> {
-        let key = LSystemKey(definition.name(), generation);
-        if let Some(alphabet) = self.lsystems_alphabets.get(&key) {
-            let entities = self.lsystems_meshes.entry(key).or_insert_with(|| {
-                let lsystem = definition.lsystem();
-                lsystem.execute(position, scale, rotation, &alphabet)
-            });
-
-            return Some(entities);
+    ) -> Result<&mut Entry<E>, GenerateError> {
+        let key = LSystemKey::new(lsystem, generation);
+        self.clock += 1;
+
+        if !self.entries.contains_key(&key) {
+            let alphabet = lsystem.generate_with(generation, &self.generate_options)?;
+            self.evict(self.max_entries - 1);
+            self.entries.insert(
+                key,
+                Entry {
+                    alphabet,
+                    context: None,
+                    last_used: 0,
+                },
+            );
         }
-        None
+
+        let entry = self.entries.get_mut(&key).expect("Entry is inserted.");
+        entry.last_used = self.clock;
+        Ok(entry)
     }
-}
\ No newline at end of file
+
+    /// Evicts the least recently used entries until at most `len` are left.
+    fn evict(&mut self, len: usize) {
+        while self.entries.len() > len {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key)
+                .expect("Entries are not empty.");
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+impl LSystemFactory {
+    /// Returns the run of the default generations of `definition` with its actions, see `run`.
+    ///
+    /// The lsystem and its actions are built on every call, keep them to run a definition repeatedly.
+    pub fn run_definition<D: LSystemDefinition + ?Sized>(
+        &mut self,
+        definition: &D,
+    ) -> Result<&ExecuteContext, FactoryError> {
+        let lsystem = definition.build();
+        self.run(
+            &lsystem,
+            &definition.actions(),
+            definition.default_generations(),
+        )
+    }
+}
+
+impl<E> Default for LSystemFactory<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,9 +1,10 @@
+use alloc::collections::VecDeque;
 use core::panic;
-use std::{collections::VecDeque, vec};
 
-use regex::Regex;
-
-use crate::{abs::*, lexer::Token, DefaultAlphabetSymbolDefiner, LSystem};
+use crate::{
+    abs::*, lexer::Token, prelude::*, DefaultAlphabetSymbolDefiner, InferredSymbolDefiner, LSystem,
+    ParamsResolver, SymbolDefiner, Taper, Variables,
+};
 
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum ParsedToken {
@@ -13,10 +14,65 @@ pub enum ParsedToken {
     Mul,
 }
 
+/// A script that can not be parsed, see `try_parse`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ParseError {
+    /// A parameter expression is nested deeper than the limit of `LexedTokens::with_max_depth`.
+    TooDeep { max_depth: usize },
+    /// A token that is not allowed at its position, `found` is `None` if the script ended before.
+    /// `expected` describes what is allowed instead.
+    UnexpectedToken {
+        found: Option<String>,
+        expected: &'static str,
+    },
+    /// A statement has `found` values instead of the `expected` ones, e.g. `let x = 1, 2;`.
+    UnexpectedValues {
+        found: usize,
+        expected: &'static str,
+    },
+    /// A `(` of the parameters is never closed.
+    UnclosedParenthesis,
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseError::TooDeep { max_depth } => write!(
+                f,
+                "Parameter expression exceeds the maximum recursion depth of {max_depth}. Reduce the nesting of parentheses or split up the expression."
+            ),
+            ParseError::UnexpectedToken {
+                found: Some(found),
+                expected,
+            } => write!(f, "Unexpected '{found}', expected {expected}."),
+            ParseError::UnexpectedToken {
+                found: None,
+                expected,
+            } => write!(f, "Unexpected end of the script, expected {expected}."),
+            ParseError::UnexpectedValues { found, expected } => {
+                write!(f, "Found {found} values, expected {expected}.")
+            }
+            ParseError::UnclosedParenthesis => {
+                write!(f, "A '(' of the parameters is never closed.")
+            }
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+/// The default limit of the recursion depth of parameter expressions, see `LexedTokens::with_max_depth`.
+pub const DEFAULT_MAX_EXPRESSION_DEPTH: usize = 128;
+
 #[derive(PartialEq, Clone, Debug)]
 pub struct LexedTokens {
     pub tokens: Vec<Token>,
     index: usize,
+    /// Current recursion depth of the expression parser.
+    depth: usize,
+    /// Deepest recursion depth reached by the expression parser.
+    max_depth_reached: usize,
+    max_depth: usize,
 }
 
 impl LexedTokens {
@@ -27,10 +83,42 @@ impl LexedTokens {
                 .filter(|x| !matches!(x, Token::Space))
                 .collect(),
             index: 0,
+            depth: 0,
+            max_depth_reached: 0,
+            max_depth: DEFAULT_MAX_EXPRESSION_DEPTH,
+        }
+    }
+
+    /// Limits the recursion depth of parameter expressions.
+    /// Nested parentheses and negations `-x` increase the depth.
+    /// Parsing fails with `ParseError::TooDeep` once the limit is exceeded, instead of overflowing the stack.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Returns the deepest recursion depth the expression parser reached so far.
+    pub fn max_depth_reached(&self) -> usize {
+        self.max_depth_reached
+    }
+
+    fn enter_expression(&mut self) -> Result<(), ParseError> {
+        self.depth += 1;
+        self.max_depth_reached = self.max_depth_reached.max(self.depth);
+
+        if self.depth > self.max_depth {
+            return Err(ParseError::TooDeep {
+                max_depth: self.max_depth,
+            });
         }
+        Ok(())
+    }
+
+    fn exit_expression(&mut self) {
+        self.depth -= 1;
     }
     pub fn finished(&self) -> bool {
-        self.index > self.tokens.len() - 1
+        self.index >= self.tokens.len()
     }
 
     pub fn current_token_ref(&mut self) -> Option<&Token> {
@@ -48,29 +136,34 @@ impl LexedTokens {
     pub fn advance_by(&mut self, count: usize) {
         self.index += count;
     }
+
+    /// Returns the error for the current token, `expected` describes what is allowed instead.
+    fn unexpected(&self, expected: &'static str) -> ParseError {
+        ParseError::UnexpectedToken {
+            found: self.tokens.get(self.index).map(Token::to_string),
+            expected,
+        }
+    }
 }
 
-pub fn parse(mut tokens: LexedTokens) -> Item {
-    let current_token = tokens.current_token_ref();
+/// Parses the tokens of a script, panics if the script is invalid.
+pub fn parse(tokens: LexedTokens) -> Item {
+    try_parse(tokens).unwrap_or_else(|error| panic!("{error}"))
+}
 
-    match current_token {
-        Some(Token::Ident(ident)) => match ident.as_str() {
-            "lsystem" => {
-                tokens.advance();
-                let item_kind = parse_lsystem(tokens);
-                Item { item_kind }
-            }
-            _ => {
-                panic!("Expected lsystem keyword found {:?}", current_token);
-            }
-        },
-        _ => {
-            panic!("Expected lsystem keyword found {:?}", current_token);
+/// Parses the tokens of a script, returns an error if the script is invalid.
+pub fn try_parse(mut tokens: LexedTokens) -> Result<Item, ParseError> {
+    match tokens.current_token_ref() {
+        Some(Token::Ident(ident)) if ident == "lsystem" => {
+            tokens.advance();
+            let item_kind = parse_lsystem(tokens)?;
+            Ok(Item { item_kind })
         }
+        _ => Err(tokens.unexpected("the 'lsystem' keyword")),
     }
 }
 
-fn parse_lsystem(mut tokens: LexedTokens) -> ItemKind {
+fn parse_lsystem(mut tokens: LexedTokens) -> Result<ItemKind, ParseError> {
     if let Some(Token::Ident(l_system_name)) = tokens.current_token() {
         tokens.advance();
         tokens.advance();
@@ -81,45 +174,52 @@ fn parse_lsystem(mut tokens: LexedTokens) -> ItemKind {
                 break;
             }
 
-            let statement = parse_statement(&mut tokens);
+            let statement = parse_statement(&mut tokens)?;
 
             statements.push(statement);
         }
 
-        ItemKind::LSystem(l_system_name, statements)
+        Ok(ItemKind::LSystem(l_system_name, statements))
     } else {
-        panic!("Expected lsystem name after 'lsystem' keyworld. Expected: 'lsystem MyLSystem {{ .. }}'");
+        Err(tokens.unexpected(
+            "an lsystem name after the 'lsystem' keyword. Expected: 'lsystem MyLSystem { .. }'",
+        ))
     }
 }
 
-fn parse_statement(tokens: &mut LexedTokens) -> StatementKind {
+fn parse_statement(tokens: &mut LexedTokens) -> Result<StatementKind, ParseError> {
+    const STATEMENT: &str =
+        "a 'let', 'interpret', 'replace', 'override', 'axiom', 'alias' or 'taper' statement";
+
     let statement = match tokens.current_token_ref() {
         Some(Token::Ident(ident)) => match ident.as_str() {
-            "replace" => parse_replace(tokens),
-            "interpret" => parse_interpret(tokens),
-            "let" => StatementKind::DefineVariable,
-            "axiom" => parse_axiom(tokens),
-            _ => panic!(
-                "Expected 'let' or 'interpret' or 'replace' keyword found {:?}",
-                ident
-            ),
+            "replace" => parse_replace(tokens)?,
+            "override" => match parse_replace(tokens)? {
+                StatementKind::Replace(replace, by) => StatementKind::Override(replace, by),
+                statement => statement,
+            },
+            "interpret" => parse_interpret(tokens)?,
+            "let" => parse_let(tokens)?,
+            "axiom" => parse_axiom(tokens)?,
+            "alias" => parse_alias(tokens)?,
+            "taper" => parse_taper(tokens)?,
+            _ => return Err(tokens.unexpected(STATEMENT)),
         },
-        Some(t) => panic!("Token '{:?}' not expected.", t),
-        None => panic!("Not found"),
+        _ => return Err(tokens.unexpected(STATEMENT)),
     };
 
     tokens.advance();
 
-    statement
+    Ok(statement)
 }
 
-fn parse_axiom(tokens: &mut LexedTokens) -> StatementKind {
+fn parse_axiom(tokens: &mut LexedTokens) -> Result<StatementKind, ParseError> {
     tokens.advance();
 
     let mut symbols = Vec::new();
     while let Some(token) = tokens.current_token() {
         match token {
-            Token::Symbol(symbol) => {
+            Token::Symbol(symbol) | Token::Bracket(symbol) | Token::Parentesis(symbol) => {
                 symbols.push(symbol.to_string());
             }
             Token::Ident(symbol) => {
@@ -128,20 +228,120 @@ fn parse_axiom(tokens: &mut LexedTokens) -> StatementKind {
             Token::Number(symbol) => {
                 symbols.push(symbol.to_string());
             }
-            Token::Break => return StatementKind::Axiom(String::from_iter(symbols.into_iter())),
-            _ => {
-                panic!("Non supported symbol after keyworld 'axiom'. {:?}", token);
+            Token::Break => {
+                return Ok(StatementKind::Axiom(String::from_iter(symbols.into_iter())))
             }
+            _ => return Err(tokens.unexpected("a symbol after the 'axiom' keyword")),
         }
         tokens.advance();
     }
 
-    panic!("No break found after 'axiom' keyword. Expected: 'axiom AB;'");
+    Err(tokens.unexpected("';' after the 'axiom' keyword. Expected: 'axiom AB;'"))
 }
 
-fn parse_let_statement() {}
+fn parse_let(tokens: &mut LexedTokens) -> Result<StatementKind, ParseError> {
+    tokens.advance();
+
+    let Some(Token::Ident(name)) = tokens.current_token() else {
+        return Err(tokens.unexpected("a variable name after 'let'. Expected: 'let x = 1.0;'"));
+    };
+    tokens.advance();
+
+    if tokens.current_token() != Some(Token::Symbol('=')) {
+        return Err(tokens.unexpected("'=' after the variable. Expected: 'let x = 1.0;'"));
+    }
+    tokens.advance();
+
+    // The value is parsed as the single parameter of `(value)`.
+    let mut value_tokens = vec![Token::Param('(')];
+    while let Some(token) = tokens.current_token() {
+        if token == Token::Break {
+            break;
+        }
+        value_tokens.push(token);
+        tokens.advance();
+    }
+    if tokens.current_token_ref() != Some(&Token::Break) {
+        return Err(tokens.unexpected("';' after the let statement. Expected: 'let x = 1.0;'"));
+    }
+    value_tokens.push(Token::Param(')'));
+
+    let mut value_tokens = LexedTokens::new(value_tokens).with_max_depth(tokens.max_depth);
+    let mut values = parse_module_parameters(&mut value_tokens)?;
+    tokens.max_depth_reached = tokens.max_depth_reached.max(value_tokens.max_depth_reached);
+
+    if values.len() != 1 {
+        return Err(ParseError::UnexpectedValues {
+            found: values.len(),
+            expected: "a single value in the let statement. Expected: 'let x = 1.0;'",
+        });
+    }
+    Ok(StatementKind::DefineVariable(name, values.remove(0)))
+}
 
-fn parse_interpret(tokens: &mut LexedTokens) -> StatementKind {
+fn parse_taper(tokens: &mut LexedTokens) -> Result<StatementKind, ParseError> {
+    tokens.advance();
+
+    // The factors are parsed as the parameters of `(length, width)`.
+    let mut value_tokens = vec![Token::Param('(')];
+    while let Some(token) = tokens.current_token() {
+        if token == Token::Break {
+            break;
+        }
+        value_tokens.push(token);
+        tokens.advance();
+    }
+    if tokens.current_token_ref() != Some(&Token::Break) {
+        return Err(tokens.unexpected("';' after the taper statement. Expected: 'taper 0.8;'"));
+    }
+    value_tokens.push(Token::Param(')'));
+
+    let mut value_tokens = LexedTokens::new(value_tokens).with_max_depth(tokens.max_depth);
+    let values = parse_module_parameters(&mut value_tokens)?;
+    tokens.max_depth_reached = tokens.max_depth_reached.max(value_tokens.max_depth_reached);
+
+    if !(1..=2).contains(&values.len()) {
+        return Err(ParseError::UnexpectedValues {
+            found: values.len(),
+            expected: "one or two factors in the taper statement. Expected: 'taper 0.8;' or 'taper 0.9, 0.7;'",
+        });
+    }
+    Ok(StatementKind::Taper(values))
+}
+
+fn parse_alias(tokens: &mut LexedTokens) -> Result<StatementKind, ParseError> {
+    tokens.advance();
+
+    fn symbol(tokens: &mut LexedTokens) -> Result<char, ParseError> {
+        let symbol = match tokens.current_token() {
+            Some(Token::Ident(ident)) if ident.chars().count() == 1 => ident.chars().next(),
+            Some(Token::Symbol(symbol)) | Some(Token::Bracket(symbol)) => Some(symbol),
+            _ => None,
+        };
+        let symbol = symbol.ok_or_else(|| {
+            tokens.unexpected("a single symbol in the alias statement. Expected: 'alias X = F;'")
+        })?;
+        tokens.advance();
+        Ok(symbol)
+    }
+
+    let alias = symbol(tokens)?;
+
+    if tokens.current_token() != Some(Token::Symbol('=')) {
+        return Err(tokens.unexpected("'=' after the alias. Expected: 'alias X = F;'"));
+    }
+    tokens.advance();
+
+    let aliased = symbol(tokens)?;
+
+    if tokens.current_token_ref() != Some(&Token::Break) {
+        return Err(tokens.unexpected("';' after the alias statement. Expected: 'alias X = F;'"));
+    }
+
+    Ok(StatementKind::Alias(alias, aliased))
+}
+
+fn parse_interpret(tokens: &mut LexedTokens) -> Result<StatementKind, ParseError> {
     tokens.advance();
 
     let mut action_tokens = Vec::new();
@@ -168,59 +368,54 @@ fn parse_interpret(tokens: &mut LexedTokens) -> StatementKind {
                 tokens.advance();
                 action_tokens.push(symbol.to_string());
             }
-            token => panic!(
-                "Unexpected token: {:?}. Expected: 'interpret X as Y(Z);'",
-                token
-            ),
+            _ => {
+                return Err(
+                    tokens.unexpected("the interpreted symbol. Expected: 'interpret X as Y(Z);'")
+                )
+            }
         }
     }
 
-    if tokens.current_token_ref().is_none() {
-        panic!("Unfinished interpret statement. Could not find 'as' keyword. Expected: 'interpret X as Y(Z);'");
-    }
+    let Some(Token::Ident(action_name)) = tokens.current_token() else {
+        return Err(
+            tokens.unexpected("'as' followed by the action name. Expected: 'interpret X as Y(Z);'")
+        );
+    };
+    tokens.advance();
 
-    if let Some(Token::Ident(action_name)) = tokens.current_token() {
-        tokens.advance();
+    if tokens.current_token_ref() != Some(&Token::Param('(')) {
+        return Err(
+            tokens.unexpected("'(' after the action name. Expected: 'interpret X as Y(Z);'")
+        );
+    }
 
-        if let Some(Token::Param(lh_param)) = tokens.current_token_ref() {
-            if *lh_param != '(' {
-                panic!("Unexpected parameter character: {:?}.", lh_param);
-            }
+    let params = parse_module_parameters(tokens)?;
 
-            let params = parse_module_parameters(tokens);
-
-            assert!(
-                action_tokens.len() == 1,
-                "At the moment only one interpret symbol allowed."
-            );
-            return StatementKind::Interpret(
-                action_tokens
-                    .first()
-                    .expect("Expect at least on interpret symbol.")
-                    .clone(),
-                Action::new(action_name, params),
-            );
-        } else {
-            panic!("Expected left parameter '(' after action found no parameter. Expected: 'interpret X as Y(Z); {:?}'",tokens.current_token_ref());
-        }
-    } else {
-        panic!("Expected action identity.")
+    if action_tokens.len() != 1 {
+        return Err(ParseError::UnexpectedValues {
+            found: action_tokens.len(),
+            expected: "a single interpreted symbol. Expected: 'interpret X as Y(Z);'",
+        });
     }
+    Ok(StatementKind::Interpret(
+        action_tokens.remove(0),
+        Action::new(action_name, params),
+    ))
 }
 
-pub fn parse_module_parameters(tokens: &mut LexedTokens) -> Vec<ActionParam> {
+pub fn parse_module_parameters(tokens: &mut LexedTokens) -> Result<Vec<ActionParam>, ParseError> {
     let mut params = Vec::new();
     let mut param_stack = VecDeque::new();
 
     while let Some(token) = tokens.current_token() {
         if let Token::Param(ident) = token {
+            if ident == ')' && param_stack.pop_back().is_none() {
+                return Err(tokens.unexpected("'(' before the parameters"));
+            }
             params.push(token.clone());
             tokens.advance();
 
             if ident == ')' {
-                let param = param_stack.pop_back().expect("msg");
-                assert_eq!(param, '(');
-
                 if param_stack.is_empty() {
                     break;
                 }
@@ -233,116 +428,184 @@ pub fn parse_module_parameters(tokens: &mut LexedTokens) -> Vec<ActionParam> {
         }
     }
 
-    let mut tokens = LexedTokens::new(params);
+    if !param_stack.is_empty() {
+        return Err(ParseError::UnclosedParenthesis);
+    }
+
+    let mut param_tokens = LexedTokens::new(params).with_max_depth(tokens.max_depth);
+    // Skips the parenthesis opening the parameter list.
+    param_tokens.advance();
     let mut params = Vec::new();
 
-    while !tokens.finished() {
-        let parsed_token = parse_parameters(&mut tokens, &ActionParam::None);
+    while !param_tokens.finished() {
+        let parsed_token = parse_parameters(&mut param_tokens);
+        tokens.max_depth_reached = tokens.max_depth_reached.max(param_tokens.max_depth_reached);
+        let parsed_token = parsed_token?;
         if parsed_token != ActionParam::None {
             params.push(parsed_token);
         }
+
+        // `,` separates the parameters and `)` closes the list, ranges may follow each other without `,`.
+        if matches!(
+            param_tokens.current_token_ref(),
+            Some(Token::Symbol(',') | Token::Param(')'))
+        ) {
+            param_tokens.advance();
+        }
     }
 
-    params
+    Ok(params)
 }
 
-pub fn parse_parameters(tokens: &mut LexedTokens, prev_parsed: &ActionParam) -> ActionParam {
-    if tokens.finished() {
-        panic!("No more tokens in param list.");
+/// Parses a single parameter, an expression or a named parameter `length = 1.0`.
+pub fn parse_parameters(tokens: &mut LexedTokens) -> Result<ActionParam, ParseError> {
+    tokens.enter_expression()?;
+    let param = parse_parameter(tokens);
+    tokens.exit_expression();
+
+    param
+}
+
+fn parse_parameter(tokens: &mut LexedTokens) -> Result<ActionParam, ParseError> {
+    let param = parse_expression(tokens, 0)?;
+
+    if tokens.current_token_ref() != Some(&Token::Symbol('=')) {
+        return Ok(param);
     }
 
-    match tokens.current_token().unwrap() {
-        Token::Number(number) => {
-            let param = ActionParam::Number(number);
+    // The name of a named parameter, `length = 1.0`.
+    let ActionParam::Constant(name) = param else {
+        return Err(tokens.unexpected("a parameter name before '='. Expected: 'length = 1.0'"));
+    };
+    tokens.advance();
 
-            // Perhaps operator, comma, decimal.
-            if !tokens.finished() {
-                tokens.advance();
-                let parsed_parameter = parse_parameters(tokens, &param);
-                parsed_parameter
-            } else {
-                // Just a single number.
-                param
-            }
+    let value = parse_expression(tokens, 0)?;
+    Ok(ActionParam::Named(name, P::new(value)))
+}
+
+/// Parses the binary operations binding at least as tight as `min_precedence` by precedence climbing,
+/// see `BinOpKind::precedence`.
+///
+/// Operators of equal precedence group left to right, `8 - 2 - 1` is `(8 - 2) - 1`.
+fn parse_expression(
+    tokens: &mut LexedTokens,
+    min_precedence: u8,
+) -> Result<ActionParam, ParseError> {
+    let mut lh = parse_operand(tokens)?;
+
+    while let Some((op, length)) = peek_binary(tokens) {
+        let precedence = op.precedence();
+        if precedence < min_precedence {
+            break;
         }
-        Token::Ident(ident) => {
-            let param = ActionParam::Constant(ident);
+        tokens.advance_by(length);
 
-            if !tokens.finished() {
-                tokens.advance();
-                let rh = parse_parameters(tokens, &param);
-                rh
-            } else {
-                param
-            }
+        let rh = parse_expression(tokens, precedence + 1)?;
+        if rh == ActionParam::None {
+            return Err(tokens.unexpected("an operand after the operator"));
         }
-        Token::Symbol(symbol) => {
-            tokens.advance();
+        lh = ActionParam::Expression(ExprKind::Binary(op, P::new(lh), P::new(rh)));
+    }
 
-            match symbol {
-                '*' => {
-                    // fetch the right hand side.
-                    let rh = parse_parameters(tokens, prev_parsed);
-                    ActionParam::Expression(ExprKind::Binary(
-                        BinOpKind::Mul,
-                        P::new(prev_parsed.clone()),
-                        P::new(rh),
-                    ))
-                }
-                '+' => {
-                    // fetch the right hand side.
-                    let rh = parse_parameters(tokens, prev_parsed);
-                    ActionParam::Expression(ExprKind::Binary(
-                        BinOpKind::Add,
-                        P::new(prev_parsed.clone()),
-                        P::new(rh),
-                    ))
-                }
-                '-' => {
-                    let rh = parse_parameters(tokens, prev_parsed);
-                    ActionParam::Expression(ExprKind::Binary(
-                        BinOpKind::Sub,
-                        P::new(prev_parsed.clone()),
-                        P::new(rh),
-                    ))
-                }
-                '/' => {
-                    // fetch the right hand side.
-                    let rh = parse_parameters(tokens, prev_parsed);
-                    ActionParam::Expression(ExprKind::Binary(
-                        BinOpKind::Div,
-                        P::new(prev_parsed.clone()),
-                        P::new(rh),
-                    ))
-                }
-                ',' => {
-                    // return as we reached the end of the parameter expression.
-                    prev_parsed.clone()
-                }
-                _ => panic!("Unexpected symbol: {:?}", symbol),
-            }
+    Ok(lh)
+}
+
+/// Parses a number, a constant, a range, a negation or an expression in parentheses.
+///
+/// Returns `ActionParam::None` for an empty parameter, e.g. `()`.
+fn parse_operand(tokens: &mut LexedTokens) -> Result<ActionParam, ParseError> {
+    const OPERAND: &str = "a number, a variable, a range, '-' or '('";
+
+    let Some(token) = tokens.current_token() else {
+        return Err(tokens.unexpected(OPERAND));
+    };
+
+    match token {
+        Token::Number(number) => {
+            tokens.advance();
+            Ok(ActionParam::Number(number))
         }
-        Token::Param(param) => {
-            if param == '(' {
-                tokens.advance();
-                let rh = parse_parameters(tokens, prev_parsed);
-                rh
-            } else if param == ')' {
-                tokens.advance();
-                return prev_parsed.clone();
-            } else {
-                panic!();
-            }
+        // The random function `r(start..end)` is read as its range.
+        Token::Ident(ident)
+            if ident == "r" && tokens.tokens.get(tokens.index + 1) == Some(&Token::Param('(')) =>
+        {
+            tokens.advance();
+            parse_operand(tokens)
+        }
+        Token::Ident(ident) => {
+            tokens.advance();
+            Ok(ActionParam::Constant(ident))
         }
         Token::Range(range) => {
             tokens.advance();
-            ActionParam::Expression(ExprKind::Random(range))
+            Ok(ActionParam::Expression(ExprKind::Random(range)))
+        }
+        Token::Param('(') => {
+            tokens.advance();
+            tokens.enter_expression()?;
+            let param = parse_expression(tokens, 0);
+            tokens.exit_expression();
+            let param = param?;
+
+            if tokens.current_token_ref() != Some(&Token::Param(')')) {
+                return Err(tokens.unexpected("')' after the expression in parentheses"));
+            }
+            tokens.advance();
+            Ok(param)
         }
-        _ => panic!("Not expected"),
+        Token::Symbol('-') => {
+            tokens.advance();
+            tokens.enter_expression()?;
+            let param = parse_operand(tokens);
+            tokens.exit_expression();
+            let param = param?;
+            if param == ActionParam::None {
+                return Err(tokens.unexpected("an operand after '-'"));
+            }
+
+            // The negation `-x` is read as `0 - x`.
+            Ok(ActionParam::Expression(ExprKind::Binary(
+                BinOpKind::Sub,
+                P::new(ActionParam::Number(0.0)),
+                P::new(param),
+            )))
+        }
+        Token::Symbol(',') | Token::Param(')') => Ok(ActionParam::None),
+        _ => Err(tokens.unexpected(OPERAND)),
     }
 }
 
-fn parse_replace(tokens: &mut LexedTokens) -> StatementKind {
+/// Returns the binary operator at the current token and its number of tokens, e.g. 2 for `<=`.
+fn peek_binary(tokens: &LexedTokens) -> Option<(BinOpKind, usize)> {
+    let Some(Token::Symbol(symbol)) = tokens.tokens.get(tokens.index) else {
+        return None;
+    };
+    let followed_by =
+        |next: char| tokens.tokens.get(tokens.index + 1) == Some(&Token::Symbol(next));
+
+    Some(match symbol {
+        '*' => (BinOpKind::Mul, 1),
+        '+' => (BinOpKind::Add, 1),
+        '-' => (BinOpKind::Sub, 1),
+        '/' => (BinOpKind::Div, 1),
+        '%' => (BinOpKind::Rem, 1),
+        '^' => (BinOpKind::BitXor, 1),
+        '&' if followed_by('&') => (BinOpKind::And, 2),
+        '&' => (BinOpKind::BitAnd, 1),
+        '|' if followed_by('|') => (BinOpKind::Or, 2),
+        '|' => (BinOpKind::BitOr, 1),
+        '<' if followed_by('=') => (BinOpKind::Le, 2),
+        '<' => (BinOpKind::Lt, 1),
+        '>' if followed_by('=') => (BinOpKind::Ge, 2),
+        '>' => (BinOpKind::Gt, 1),
+        '!' if followed_by('=') => (BinOpKind::Ne, 2),
+        '=' if followed_by('=') => (BinOpKind::Eq, 2),
+        // A single `=` names a parameter, `,` ends it.
+        _ => return None,
+    })
+}
+
+fn parse_replace(tokens: &mut LexedTokens) -> Result<StatementKind, ParseError> {
     tokens.advance();
 
     let mut lh_tokens = Vec::new();
@@ -368,19 +631,24 @@ fn parse_replace(tokens: &mut LexedTokens) -> StatementKind {
     }
 
     if tokens.current_token_ref().is_none() {
-        panic!("Unfinished replace statement. Could not find 'by' keyworld. Expected: 'replace X by Y;'");
+        return Err(tokens.unexpected("'by' after the predecessor. Expected: 'replace X by Y;'"));
     }
 
-    while tokens.current_token_ref() != Some(&Token::Break) {
-        rh_tokens.push(tokens.current_token_ref().unwrap().clone());
+    while let Some(token) = tokens.current_token() {
+        if token == Token::Break {
+            break;
+        }
+        rh_tokens.push(token);
         tokens.advance();
     }
 
     if tokens.finished() {
-        panic!("Unfinished replace statement. Could not find ';' after replace statement. Expected: 'replace X by Y;'");
+        return Err(
+            tokens.unexpected("';' after the replace statement. Expected: 'replace X by Y;'")
+        );
     }
 
-    parse_replace_statement(lh_tokens, rh_tokens)
+    Ok(parse_replace_statement(lh_tokens, rh_tokens))
 }
 
 fn parse_replace_statement(replace: Vec<Token>, by: Vec<Token>) -> StatementKind {
@@ -399,11 +667,95 @@ fn parse_replace_statement(replace: Vec<Token>, by: Vec<Token>) -> StatementKind
     StatementKind::Replace(replace, by)
 }
 
+/// Whether symbols that only differ in case are the same symbol, see `LSystemParser::parse_with_case_policy`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CasePolicy {
+    /// `F` and `f` are distinct symbols, by convention `F` draws and `f` moves without drawing.
+    #[default]
+    Sensitive,
+    /// Lowercase symbols are aliases of their uppercase symbol, thus `f` is read as `F`.
+    Insensitive,
+}
+
+/// Maps the symbols written in a script to the symbols of the lsystem.
+/// Defined by the `alias X = F;` statements and the `CasePolicy` of a script.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymbolAliases {
+    aliases: HashMap<char, char>,
+    case_policy: CasePolicy,
+}
+
+impl SymbolAliases {
+    pub fn new(case_policy: CasePolicy) -> Self {
+        Self {
+            aliases: HashMap::new(),
+            case_policy,
+        }
+    }
+
+    /// Reads `alias` as `symbol`, a later alias of the same symbol replaces the earlier one.
+    pub fn alias(&mut self, alias: char, symbol: char) {
+        self.aliases.insert(alias, symbol);
+    }
+
+    /// Returns the symbol `symbol` is read as.
+    /// Aliases are resolved before the case policy is applied.
+    pub fn resolve(&self, symbol: char) -> char {
+        let symbol = self.aliases.get(&symbol).copied().unwrap_or(symbol);
+
+        match self.case_policy {
+            CasePolicy::Sensitive => symbol,
+            CasePolicy::Insensitive => symbol.to_ascii_uppercase(),
+        }
+    }
+
+    /// Resolves every symbol of `symbols`, parameter lists like `(0.5)` are kept as they are.
+    pub fn resolve_str(&self, symbols: &str) -> String {
+        let mut depth = 0usize;
+
+        symbols
+            .chars()
+            .map(|symbol| match symbol {
+                '(' => {
+                    depth += 1;
+                    symbol
+                }
+                ')' => {
+                    depth = depth.saturating_sub(1);
+                    symbol
+                }
+                _ if depth > 0 => symbol,
+                _ => self.resolve(symbol),
+            })
+            .collect()
+    }
+}
+
 pub struct LSystemParser {
     item: Item,
+    aliases: SymbolAliases,
 }
 
 impl LSystemParser {
+    /// Creates a parser for `item` reading its symbols with `case_policy` and the `alias` statements of the script.
+    pub fn new(item: Item, case_policy: CasePolicy) -> Self {
+        let mut aliases = SymbolAliases::new(case_policy);
+
+        let crate::parser::ItemKind::LSystem(_, statements) = &item.item_kind;
+        for statement in statements {
+            if let crate::parser::StatementKind::Alias(alias, symbol) = statement {
+                aliases.alias(*alias, *symbol);
+            }
+        }
+
+        Self { item, aliases }
+    }
+
+    /// Returns the symbol aliases used to read the script.
+    pub fn aliases(&self) -> &SymbolAliases {
+        &self.aliases
+    }
+
     pub fn lsystem_name(&self) -> String {
         let crate::parser::ItemKind::LSystem(name, _) = &self.item.item_kind;
         name.to_string()
@@ -414,26 +766,89 @@ impl LSystemParser {
 
         for statement in statements {
             if let crate::parser::StatementKind::Axiom(axiom) = statement {
-                return axiom.to_string();
+                return self.aliases.resolve_str(axiom);
             }
         }
 
         panic!("No axiom found!");
     }
 
-    pub fn replacement_rules(&mut self, lsystem: &mut LSystem<DefaultAlphabetSymbolDefiner>) {
+    pub fn replacement_rules<A: SymbolDefiner>(&mut self, lsystem: &mut LSystem<A>) {
         let crate::parser::ItemKind::LSystem(_, statements) = &self.item.item_kind;
 
         for statement in statements {
-            if let crate::parser::StatementKind::Replace(replace, by) = statement {
-                let replace = replace.to_string();
-                let by = by.to_string();
+            match statement {
+                crate::parser::StatementKind::Replace(replace, by) => {
+                    let replace = self.aliases.resolve_str(replace);
+                    let by = self.aliases.resolve_str(by);
+
+                    match Self::stochastic_predecessor(&replace) {
+                        Some((predecessor, probability)) => {
+                            lsystem.add_stochastic_rule(predecessor, probability, by)
+                        }
+                        None => lsystem.add_rule(replace, by),
+                    }
+                }
+                crate::parser::StatementKind::Override(replace, by) => lsystem.override_rule(
+                    self.aliases.resolve_str(replace),
+                    self.aliases.resolve_str(by),
+                ),
+                _ => {}
+            }
+        }
+    }
 
-                println!("{replace} by {by}");
+    /// Splits the predecessor of a stochastic rule `replace F(0.5) by ..;` into the symbol and its probability.
+    fn stochastic_predecessor(replace: &str) -> Option<(&str, f32)> {
+        let (predecessor, probability) = replace.strip_suffix(')')?.split_once('(')?;
+        Some((predecessor, probability.parse().ok()?))
+    }
+
+    /// Evaluates the `let` statements in order, a value can use the variables defined before it.
+    pub fn variables(&self) -> Variables {
+        let crate::parser::ItemKind::LSystem(_, statements) = &self.item.item_kind;
 
-                lsystem.add_rule(replace, by)
+        let mut variables = Variables::new();
+        for statement in statements {
+            if let crate::parser::StatementKind::DefineVariable(name, value) = statement {
+                let value = ParamsResolver {
+                    params: vec![value.clone()],
+                }
+                .get_with(0, &variables)
+                .unwrap_or_else(|| panic!("Could not evaluate the value of variable '{name}'."));
+                variables.set(name.clone(), value);
             }
         }
+
+        variables
+    }
+
+    /// Evaluates the last `taper` statement with the variables of the script, `None` if it has none.
+    pub fn taper(&self) -> Option<Taper> {
+        let crate::parser::ItemKind::LSystem(_, statements) = &self.item.item_kind;
+
+        let values = statements
+            .iter()
+            .rev()
+            .find_map(|statement| match statement {
+                crate::parser::StatementKind::Taper(values) => Some(values),
+                _ => None,
+            })?;
+        let factors = ParamsResolver {
+            params: values.clone(),
+        };
+        let variables = self.variables();
+        let factor = |index| {
+            factors
+                .get_with(index, &variables)
+                .unwrap_or_else(|| panic!("Could not evaluate the taper factor {index}."))
+        };
+
+        let taper = Taper::new(factor(0));
+        Some(match values.len() {
+            2 => taper.with_width(factor(1)),
+            _ => taper,
+        })
     }
 
     pub fn interpret_rules(&mut self) -> Vec<(String, Action)> {
@@ -442,22 +857,66 @@ impl LSystemParser {
         let mut interprets = vec![];
         for statement in statements {
             if let crate::parser::StatementKind::Interpret(interpret, by) = statement {
-                interprets.push((interpret.clone(), by.clone()));
+                interprets.push((self.aliases.resolve_str(interpret), by.clone()));
             }
         }
 
         interprets
     }
 
+    /// Infers the alphabet from the script.
+    /// Every symbol on the left side of a `replace` or `override` statement is a variable, all others are constants.
+    pub fn infer_symbol_definer(&self) -> InferredSymbolDefiner {
+        let crate::parser::ItemKind::LSystem(_, statements) = &self.item.item_kind;
+
+        let mut variables = vec![];
+        for statement in statements {
+            if let crate::parser::StatementKind::Replace(replace, _)
+            | crate::parser::StatementKind::Override(replace, _) = statement
+            {
+                // Ignore the stochastic parameter of a rule `replace F(0.5)`.
+                let predecessor = replace.split('(').next().unwrap_or_default();
+                variables.extend(self.aliases.resolve_str(predecessor).chars());
+            }
+        }
+
+        InferredSymbolDefiner::new(variables)
+    }
+
     pub fn parse(item: Item) -> LSystem<DefaultAlphabetSymbolDefiner> {
-        let mut builder = LSystemParser { item };
+        Self::parse_with_definer(item, DefaultAlphabetSymbolDefiner)
+    }
 
-        let mut lsystem = LSystem::<DefaultAlphabetSymbolDefiner>::new(
-            builder.axiom(),
-            DefaultAlphabetSymbolDefiner,
-        );
+    /// Parses the lsystem with an alphabet inferred from the script, see `infer_symbol_definer`.
+    /// Use this for scripts using symbols that the `DefaultAlphabetSymbolDefiner` does not support.
+    pub fn parse_inferred(item: Item) -> LSystem<InferredSymbolDefiner> {
+        let definer =
+            LSystemParser::new(item.clone(), CasePolicy::default()).infer_symbol_definer();
+        Self::parse_with_definer(item, definer)
+    }
+
+    pub fn parse_with_definer<A: SymbolDefiner>(item: Item, alphabet_definer: A) -> LSystem<A> {
+        Self::parse_with_case_policy(item, alphabet_definer, CasePolicy::default())
+    }
+
+    /// Same as `parse_with_definer` but reads the symbols of the script with `case_policy`.
+    /// Use `CasePolicy::Insensitive` to port systems that use `f` and `F` interchangeably.
+    pub fn parse_with_case_policy<A: SymbolDefiner>(
+        item: Item,
+        alphabet_definer: A,
+        case_policy: CasePolicy,
+    ) -> LSystem<A> {
+        let mut builder = LSystemParser::new(item, case_policy);
+
+        let mut lsystem = LSystem::<A>::new(builder.axiom(), alphabet_definer);
         lsystem.name = builder.lsystem_name();
-        lsystem.action_rules = builder.interpret_rules();
+        for (symbol, action) in builder.interpret_rules() {
+            lsystem.add_action_rule(symbol, action);
+        }
+        for (name, value) in builder.variables().iter() {
+            lsystem.set_variable(name, value);
+        }
+        lsystem.set_taper(builder.taper());
         builder.replacement_rules(&mut lsystem);
         lsystem
     }
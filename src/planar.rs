@@ -0,0 +1,388 @@
+//! Drawing planar lsystems with a 2D turtle, see `LSystem::run_planar`.
+//!
+//! Most classic curves never leave the plane, a position and an angle describe their turtle completely.
+use core::f32::consts::PI;
+
+use macaw::Vec2;
+
+// Only used if `std` is not linked, see `Float`.
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use crate::prelude::Float;
+use crate::{
+    prelude::*, seeded_rng, ActionResolver, Alphabet, CancellationToken, Color, Geometry, LSystem,
+    Polygon, RunOptions, RuntimeError, Segment, Symbol, SymbolDefiner, Variables, CUT_SYMBOL,
+    RUN_CHECK_INTERVAL,
+};
+
+/// A turtle moving in the xy plane, seen from `+z`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Turtle2D {
+    pub position: Vec2,
+    /// The heading in radians, counterclockwise from the x axis.
+    pub angle: f32,
+    /// The width of the lines drawn by the turtle.
+    pub width: f32,
+    /// The color of the lines drawn by the turtle.
+    pub color: Color,
+    /// The index of `color` in the palette, see `set_color_index`.
+    pub color_index: usize,
+}
+
+impl Turtle2D {
+    /// Creates a turtle at `position` heading along the y axis.
+    pub fn new(position: Vec2) -> Self {
+        Self {
+            position,
+            angle: PI / 2.0,
+            width: 1.0,
+            color: Color::BLACK,
+            color_index: 0,
+        }
+    }
+
+    /// Returns the turtle placed at the start pose of `options` projected onto the xy plane,
+    /// a heading along the z axis heads along the y axis.
+    pub fn from_options(options: &RunOptions) -> Self {
+        let heading = options.start_heading;
+        let mut turtle = Self::new(options.start_position.truncate());
+        if heading.x != 0.0 || heading.y != 0.0 {
+            turtle.angle = heading.y.atan2(heading.x);
+        }
+        turtle.width = options.width;
+        turtle.color = options.color;
+        turtle
+    }
+
+    /// Returns the unit vector the turtle moves along.
+    pub fn heading(&self) -> Vec2 {
+        let (sin, cos) = self.angle.sin_cos();
+        Vec2::new(cos, sin)
+    }
+
+    pub fn forward(&mut self, len: f32) {
+        self.position += self.heading() * len;
+    }
+
+    /// Turns the turtle left, counterclockwise, for positive angles in radians.
+    pub fn turn(&mut self, angle: f32) {
+        self.angle += angle;
+    }
+
+    /// Selects the color at `index` of `palette`, the color is kept if the palette is empty.
+    pub fn set_color_index(&mut self, index: usize, palette: &[Color]) {
+        self.color_index = index;
+        if !palette.is_empty() {
+            self.color = palette[index % palette.len()];
+        }
+    }
+}
+
+impl Default for Turtle2D {
+    fn default() -> Self {
+        Self::from_options(&RunOptions::default())
+    }
+}
+
+/// A straight line drawn by the 2D turtle from `a` to `b`, see `Segment`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment2D {
+    pub a: Vec2,
+    pub b: Vec2,
+    pub width: f32,
+    pub color: Color,
+    /// The branch depth at which the segment was drawn.
+    pub depth: usize,
+}
+
+impl Segment2D {
+    pub fn new(a: Vec2, b: Vec2) -> Self {
+        Self {
+            a,
+            b,
+            width: 1.0,
+            color: Color::default(),
+            depth: 0,
+        }
+    }
+
+    pub fn length(&self) -> f32 {
+        self.a.distance(self.b)
+    }
+}
+
+/// A closed, filled, polygon in the plane, see `Polygon`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Polygon2D {
+    pub vertices: Vec<Vec2>,
+    pub color: Color,
+    pub depth: usize,
+}
+
+/// The geometry drawn by a planar lsystem, see `LSystem::run_planar`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Geometry2D {
+    pub segments: Vec<Segment2D>,
+    pub polygons: Vec<Polygon2D>,
+}
+
+impl Geometry2D {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty() && self.polygons.is_empty()
+    }
+
+    /// Returns the minimum and maximum corner of all segments and polygons, `None` if the geometry is empty.
+    pub fn bounds(&self) -> Option<(Vec2, Vec2)> {
+        self.segments
+            .iter()
+            .flat_map(|segment| [segment.a, segment.b])
+            .chain(
+                self.polygons
+                    .iter()
+                    .flat_map(|polygon| polygon.vertices.iter().copied()),
+            )
+            .fold(None, |bounds, point| match bounds {
+                Some((min, max)) => Some((point.min(min), point.max(max))),
+                None => Some((point, point)),
+            })
+    }
+
+    /// Scales and moves the geometry into the box from `min` to `max`, keeping its aspect ratio and centering it.
+    pub fn fit_into(&mut self, min: Vec2, max: Vec2) {
+        let Some((from, to)) = self.bounds() else {
+            return;
+        };
+
+        let size = to - from;
+        let target = max - min;
+        let scale = (0..2)
+            .filter(|&axis| size[axis] > f32::EPSILON)
+            .map(|axis| target[axis] / size[axis])
+            .reduce(f32::min)
+            .unwrap_or(1.0);
+        let center = (from + to) * 0.5;
+        let target_center = (min + max) * 0.5;
+        let transform = |point: &mut Vec2| *point = target_center + (*point - center) * scale;
+
+        for segment in &mut self.segments {
+            transform(&mut segment.a);
+            transform(&mut segment.b);
+        }
+        for vertex in self
+            .polygons
+            .iter_mut()
+            .flat_map(|polygon| &mut polygon.vertices)
+        {
+            transform(vertex);
+        }
+    }
+
+    /// Returns the geometry in the xy plane of the 3D geometry, e.g. to export it.
+    pub fn to_geometry(&self) -> Geometry {
+        let point = |point: Vec2| point.extend(0.0);
+        Geometry {
+            segments: self
+                .segments
+                .iter()
+                .map(|segment| Segment {
+                    width: segment.width,
+                    color: segment.color,
+                    depth: segment.depth,
+                    ..Segment::new(point(segment.a), point(segment.b))
+                })
+                .collect(),
+            polygons: self
+                .polygons
+                .iter()
+                .map(|polygon| Polygon {
+                    color: polygon.color,
+                    depth: polygon.depth,
+                    ..Polygon::new(polygon.vertices.iter().copied().map(point).collect())
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Geometry {
+    /// Returns the segments and polygons projected onto the xy plane, dropping their z coordinates.
+    pub fn to_planar(&self) -> Geometry2D {
+        Geometry2D {
+            segments: self
+                .segments
+                .iter()
+                .map(|segment| Segment2D {
+                    width: segment.width,
+                    color: segment.color,
+                    depth: segment.depth,
+                    ..Segment2D::new(segment.a.truncate(), segment.b.truncate())
+                })
+                .collect(),
+            polygons: self
+                .polygons
+                .iter()
+                .map(|polygon| Polygon2D {
+                    vertices: polygon
+                        .vertices
+                        .iter()
+                        .map(|vertex| vertex.truncate())
+                        .collect(),
+                    color: polygon.color,
+                    depth: polygon.depth,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<A: SymbolDefiner> LSystem<A> {
+    /// Draws `alphabet` with a `Turtle2D` starting with the pose and pen from `options`,
+    /// faster than `run` for lsystems that never leave the plane.
+    ///
+    /// As with `run` every action must be registered for its symbol in `resolver`, otherwise the run fails
+    /// with `RuntimeError::UnknownAction`. The registered turtle actions are executed on the 2D turtle:
+    /// `DrawForwardAction`, `MoveForwardAction`, `RotateZAction`, `TurnAroundAction`, the transform stack,
+    /// polygon, color and width actions. Any other registered action, e.g. pitching or rolling out of the plane
+    /// or one pushing elements, is skipped, use `run` for those lsystems.
+    /// The cut symbol, the taper, the color map and the cancellation of `options` apply, collisions are not tested.
+    pub fn run_planar<E>(
+        &self,
+        resolver: &ActionResolver<E>,
+        alphabet: &Alphabet,
+        options: &RunOptions,
+    ) -> Result<Geometry2D, RuntimeError> {
+        let mut rng = seeded_rng(self.seed());
+        let taper = options.taper.or(self.taper());
+        let chars = alphabet.iter().map(Symbol::char).collect::<Vec<_>>();
+
+        let mut turtle = Turtle2D::from_options(options);
+        let mut stack = Vec::<Turtle2D>::new();
+        let mut open_polygons = Vec::<Vec<Vec2>>::new();
+        let mut geometry = Geometry2D::new();
+        // Index of the last symbol that is skipped.
+        let mut skipped_until = None;
+        // Number of branches that are open.
+        let mut depth = 0usize;
+
+        for (index, token) in alphabet.iter().enumerate() {
+            if index.is_multiple_of(RUN_CHECK_INTERVAL)
+                && options
+                    .cancellation
+                    .as_ref()
+                    .is_some_and(CancellationToken::is_cancelled)
+            {
+                return Err(RuntimeError::Cancelled { index });
+            }
+            if skipped_until.is_some_and(|end| index <= end) {
+                continue;
+            }
+            let params_end = Self::module_params_end(&chars, index);
+            skipped_until = params_end;
+            let module_params = Self::module_params(token, &chars, index, params_end);
+            match token.char() {
+                '[' => depth += 1,
+                ']' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+
+            for by in self.bindings(token) {
+                if resolver.resolver(token, &by.name).is_none() {
+                    return Err(RuntimeError::UnknownAction {
+                        symbol: token.char(),
+                        action: by.name.clone(),
+                    });
+                }
+
+                let action = self.bind_action(by, module_params.as_ref(), || {
+                    let mut variables = Variables::new();
+                    variables.set("depth", depth as f32);
+                    variables.set("gen", alphabet.generation as f32);
+                    variables
+                })?;
+                let params = if action.params.is_random() {
+                    action.params.sample(&mut rng)
+                } else {
+                    action.params
+                };
+                let arg = |index: usize| {
+                    params
+                        .get(index)
+                        .ok_or_else(|| RuntimeError::MissingParameter {
+                            action: action.name.clone(),
+                            index,
+                        })
+                };
+
+                let stack_depth = stack.len();
+                match action.name.as_str() {
+                    "DrawForwardAction" | "MoveForwardAction" => {
+                        let scale = taper.map_or(1.0, |taper| taper.length_at(stack_depth));
+                        let from = turtle.position;
+                        turtle.forward(arg(0)? * options.step * scale);
+                        if action.name == "DrawForwardAction" && turtle.position != from {
+                            let width = taper.map_or(1.0, |taper| taper.width_at(stack_depth));
+                            geometry.segments.push(Segment2D {
+                                width: turtle.width * width,
+                                color: turtle.color,
+                                depth: stack_depth,
+                                ..Segment2D::new(from, turtle.position)
+                            });
+                        }
+                    }
+                    "RotateZAction" => turtle.turn(arg(0)?),
+                    "TurnAroundAction" => turtle.turn(PI),
+                    "PushTranformToStackAction" => stack.push(turtle),
+                    "PopTransformFromStackAction" => {
+                        turtle = stack
+                            .pop()
+                            .ok_or(RuntimeError::UnbalancedBrackets { index })?;
+                    }
+                    "BeginPolygonAction" => open_polygons.push(vec![]),
+                    "AddVertexAction" => {
+                        if let Some(vertices) = open_polygons.last_mut() {
+                            vertices.push(turtle.position);
+                        }
+                    }
+                    "EndPolygonAction" => {
+                        if let Some(vertices) =
+                            open_polygons.pop().filter(|vertices| vertices.len() >= 3)
+                        {
+                            geometry.polygons.push(Polygon2D {
+                                vertices,
+                                color: turtle.color,
+                                depth: stack_depth,
+                            });
+                        }
+                    }
+                    "IncColorAction" => {
+                        turtle.set_color_index(turtle.color_index + 1, &options.palette)
+                    }
+                    "SetColorAction" => {
+                        turtle.set_color_index(arg(0)?.max(0.0) as usize, &options.palette)
+                    }
+                    "SetWidthAction" => turtle.width = arg(0)?,
+                    "MultiplyWidthAction" => turtle.width *= arg(0)?,
+                    _ => {}
+                }
+            }
+
+            if token.char() == CUT_SYMBOL {
+                skipped_until = Some(Self::branch_end(&chars, index) - 1);
+            }
+        }
+
+        if let Some(map) = &options.color_map {
+            geometry.apply_color_map(map);
+        }
+        Ok(geometry)
+    }
+}
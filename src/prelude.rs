@@ -0,0 +1,75 @@
+//! The items of `std` used throughout the crate, taken from `core` and `alloc` when `std` is disabled.
+//!
+//! Modules import this instead of the `std` prelude, thus the core engine builds with and without `std`.
+pub(crate) use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{hash_map::DefaultHasher, HashMap, HashSet};
+
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::{HashMap, HashSet};
+
+/// SipHash with fixed keys, the same algorithm as the `DefaultHasher` of `std`.
+#[cfg(not(feature = "std"))]
+#[derive(Clone, Debug, Default)]
+#[allow(deprecated)]
+pub(crate) struct DefaultHasher(core::hash::SipHasher);
+
+#[cfg(not(feature = "std"))]
+impl DefaultHasher {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl core::hash::Hasher for DefaultHasher {
+    fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.write(bytes)
+    }
+}
+
+/// The float math of `std`, implemented by `libm` when `std` is disabled.
+///
+/// Unused if the tests link `std` anyway, its inherent float methods take precedence.
+#[cfg(not(feature = "std"))]
+#[allow(dead_code)]
+pub(crate) trait Float {
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn sin_cos(self) -> (Self, Self)
+    where
+        Self: Sized;
+    fn round(self) -> Self;
+}
+
+#[cfg(not(feature = "std"))]
+impl Float for f32 {
+    fn sin(self) -> Self {
+        libm::sinf(self)
+    }
+
+    fn cos(self) -> Self {
+        libm::cosf(self)
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        libm::sincosf(self)
+    }
+
+    fn round(self) -> Self {
+        libm::roundf(self)
+    }
+}
@@ -0,0 +1,118 @@
+//! Ready-made classic lsystems with their actions, to start from or to compare against.
+//!
+//! Every preset returns the lsystem, a resolver of its actions and the suggested number of generations:
+//!
+//! ```
+//! use scebpl_system::presets;
+//!
+//! let (lsystem, resolver, generations) = presets::koch_curve();
+//! let context = lsystem.run(&resolver, &lsystem.generate(generations)).unwrap();
+//! ```
+use core::f32::consts::FRAC_PI_2;
+
+use crate::{prelude::*, Action, ActionParam, ActionResolver, LSystem, LSystemBuilder};
+
+/// An lsystem, the resolver of its actions and the suggested number of generations.
+pub type Preset = (LSystem, ActionResolver, u8);
+
+/// The quadratic Koch curve, `F` draws and `+ -` turn by 90 degrees.
+pub fn koch_curve() -> Preset {
+    let lsystem = turtle("KochCurve", FRAC_PI_2, "F")
+        .axiom("F")
+        .rule("F", "F+F-F-F+F");
+    preset(lsystem, 4)
+}
+
+/// The Heighway dragon, `F` and `G` draw and `+ -` turn by 90 degrees.
+pub fn dragon_curve() -> Preset {
+    let lsystem = turtle("DragonCurve", FRAC_PI_2, "FG")
+        .axiom("F")
+        .rule("F", "F+G")
+        .rule("G", "F-G");
+    preset(lsystem, 10)
+}
+
+/// The Sierpinski triangle, `F` and `G` draw and `+ -` turn by 120 degrees.
+pub fn sierpinski_triangle() -> Preset {
+    let lsystem = turtle("SierpinskiTriangle", 120f32.to_radians(), "FG")
+        .axiom("F-G-G")
+        .rule("F", "F-G+F+G-F")
+        .rule("G", "GG");
+    preset(lsystem, 6)
+}
+
+/// The Hilbert curve filling a square, `F` draws and `+ -` turn by 90 degrees.
+pub fn hilbert_curve() -> Preset {
+    let lsystem = turtle("HilbertCurve", FRAC_PI_2, "F")
+        .axiom("A")
+        .rule("A", "+BF-AFA-FB+")
+        .rule("B", "-AF+BFB+FA-");
+    preset(lsystem, 5)
+}
+
+/// The Hilbert curve filling a cube, from The Algorithmic Beauty of Plants.
+///
+/// `F` draws, `+ -` turn, `& ^` pitch and `\ /` roll by 90 degrees, `|` turns around.
+pub fn hilbert_curve_3d() -> Preset {
+    let lsystem = turtle("HilbertCurve3D", FRAC_PI_2, "F")
+        .interpret("&", action("PitchDownAction", &[FRAC_PI_2]))
+        .interpret("^", action("PitchUpAction", &[FRAC_PI_2]))
+        .interpret("\\", action("RollLeftAction", &[FRAC_PI_2]))
+        .interpret("/", action("RollRightAction", &[FRAC_PI_2]))
+        .interpret("|", action("TurnAroundAction", &[]))
+        .axiom("A")
+        .rule("A", "B-F+CFC+F-D&F^D-F+&&CFC+F+B//")
+        .rule("B", "A&F^CFB^F^D^^-F-D^|F^B|FC^F^A//")
+        .rule("C", "|D^|F^B-F+C^F^A&&FA&F^C+F+B^F^D//")
+        .rule("D", "|CFB-F+B|FA&F^A&&FB-F+B|FC//");
+    preset(lsystem, 3)
+}
+
+/// The fractal plant, `F` draws, `+ -` turn by 25 degrees and `[ ]` branch, `X` only grows the plant.
+pub fn fractal_plant() -> Preset {
+    let lsystem = turtle("FractalPlant", 25f32.to_radians(), "F")
+        .axiom("X")
+        .rule("X", "F+[[X]-X]-F[-FX]+X")
+        .rule("F", "FF");
+    preset(lsystem, 5)
+}
+
+/// A bush whose branches are chosen by stochastic rules, from The Algorithmic Beauty of Plants.
+///
+/// `F` draws, `+ -` turn by 25.7 degrees and `[ ]` branch, every `seed` grows another bush.
+pub fn stochastic_bush(seed: u64) -> Preset {
+    let lsystem = turtle("StochasticBush", 25.7f32.to_radians(), "F")
+        .axiom("F")
+        .stochastic_rule("F", 0.33, "F[+F]F[-F]F")
+        .stochastic_rule("F", 0.33, "F[+F]F")
+        .stochastic_rule("F", 0.34, "F[-F]F")
+        .seed(seed);
+    preset(lsystem, 5)
+}
+
+/// Starts a preset drawing with every symbol of `draw`, turning by `angle` and branching with `[ ]`.
+fn turtle(name: &str, angle: f32, draw: &str) -> LSystemBuilder {
+    let mut builder = LSystemBuilder::new()
+        .name(name)
+        .interpret("+", action("RotateZAction", &[angle]))
+        .interpret("-", action("RotateZAction", &[-angle]))
+        .interpret("[", action("PushTranformToStackAction", &[]))
+        .interpret("]", action("PopTransformFromStackAction", &[]));
+    for symbol in draw.chars() {
+        builder = builder.interpret(symbol.to_string(), action("DrawForwardAction", &[1.0]));
+    }
+    builder
+}
+
+/// Creates the action `name` with number parameters, also used by the tests.
+pub(crate) fn action(name: &str, params: &[f32]) -> Action {
+    Action::new(
+        name.to_owned(),
+        params.iter().copied().map(ActionParam::Number).collect(),
+    )
+}
+
+fn preset(lsystem: LSystemBuilder, generations: u8) -> Preset {
+    let lsystem = lsystem.build().expect("Presets are valid.");
+    (lsystem, ActionResolver::with_default_actions(), generations)
+}
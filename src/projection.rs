@@ -0,0 +1,169 @@
+//! Projecting 3D geometry onto a plane for the 2D exporters, see `Projection`.
+use macaw::{Vec2, Vec3};
+
+// Only used if `std` is not linked, see `Float`.
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use crate::prelude::Float;
+use crate::{Geometry, Geometry2D, Polygon2D, Segment2D};
+
+/// How points are mapped onto the view plane.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProjectionKind {
+    /// Parallel lines stay parallel, distant branches are drawn as large as close ones.
+    #[default]
+    Orthographic,
+    /// Distant branches are drawn smaller, `fov` is the vertical field of view in radians.
+    ///
+    /// The viewer is placed on the view direction at the distance at which the whole geometry is in view.
+    Perspective { fov: f32 },
+}
+
+/// Projects geometry onto the plane seen when looking along `direction`, e.g. to export a 3D lsystem
+/// as SVG, PNG or text from any side, see `SvgExporter::projection`, `RasterStyle::projection` and `ascii::render_planar`.
+///
+/// The default looks along `-z` onto the xy plane, thus the projection of planar lsystems is the drawing itself.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Projection {
+    /// The direction the viewer looks along.
+    pub direction: Vec3,
+    /// The direction pointing up in the projected drawing, it is made orthogonal to `direction`.
+    pub up: Vec3,
+    pub kind: ProjectionKind,
+    /// Scales and moves the projected drawing into a box of this size with its minimum corner at the origin,
+    /// keeping the aspect ratio, see `Geometry2D::fit_into`. `None` keeps the scale of the geometry.
+    pub fit: Option<Vec2>,
+}
+
+impl Projection {
+    /// Creates an orthographic projection looking onto the xy plane, see `Default`.
+    pub fn orthographic() -> Self {
+        Self::default()
+    }
+
+    /// Creates a perspective projection with the vertical field of view `fov` in radians.
+    pub fn perspective(fov: f32) -> Self {
+        Self {
+            kind: ProjectionKind::Perspective { fov },
+            ..Self::default()
+        }
+    }
+
+    /// Looks along `direction` with `up` pointing up in the drawing, e.g. `Vec3::Y` and `Vec3::Z` for a front view.
+    pub fn with_direction(mut self, direction: Vec3, up: Vec3) -> Self {
+        self.direction = direction;
+        self.up = up;
+        self
+    }
+
+    pub fn with_fit(mut self, size: Vec2) -> Self {
+        self.fit = Some(size);
+        self
+    }
+
+    /// Returns the segments and polygons of `geometry` projected onto the view plane,
+    /// meshes and instances are not projected.
+    ///
+    /// The widths of the segments are kept, they are the widths of the strokes the exporters draw.
+    pub fn project(&self, geometry: &Geometry) -> Geometry2D {
+        let view = self.view(geometry);
+        let mut projected = Geometry2D {
+            segments: geometry
+                .segments
+                .iter()
+                .map(|segment| Segment2D {
+                    width: segment.width,
+                    color: segment.color,
+                    depth: segment.depth,
+                    ..Segment2D::new(view.project(segment.a), view.project(segment.b))
+                })
+                .collect(),
+            polygons: geometry
+                .polygons
+                .iter()
+                .map(|polygon| Polygon2D {
+                    vertices: polygon
+                        .vertices
+                        .iter()
+                        .map(|vertex| view.project(*vertex))
+                        .collect(),
+                    color: polygon.color,
+                    depth: polygon.depth,
+                })
+                .collect(),
+        };
+
+        if let Some(size) = self.fit {
+            projected.fit_into(Vec2::ZERO, size);
+        }
+        projected
+    }
+
+    /// Returns the view of this projection onto `geometry`, the viewer of a perspective projection
+    /// is placed depending on the bounds of the geometry.
+    fn view(&self, geometry: &Geometry) -> View {
+        let forward = self.direction.try_normalize().unwrap_or(-Vec3::Z);
+        let right = forward
+            .cross(self.up)
+            .try_normalize()
+            .unwrap_or_else(|| forward.any_orthonormal_vector());
+        let up = right.cross(forward);
+
+        let eye = match self.kind {
+            ProjectionKind::Orthographic => None,
+            ProjectionKind::Perspective { fov } => {
+                let (center, radius) = geometry.bounds().map_or((Vec3::ZERO, 1.0), |bounds| {
+                    (
+                        bounds.center(),
+                        (bounds.size().length() * 0.5).max(f32::EPSILON),
+                    )
+                });
+                // The distance at which the bounding sphere touches the field of view.
+                let distance = radius / (fov.clamp(0.01, 3.0) * 0.5).sin();
+                Some((center - forward * distance, distance))
+            }
+        };
+
+        View {
+            forward,
+            right,
+            up,
+            eye,
+        }
+    }
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Self {
+            direction: -Vec3::Z,
+            up: Vec3::Y,
+            kind: ProjectionKind::Orthographic,
+            fit: None,
+        }
+    }
+}
+
+/// The orthonormal frame of the viewer and, for perspective projections, its position and distance to the geometry.
+struct View {
+    forward: Vec3,
+    right: Vec3,
+    up: Vec3,
+    eye: Option<(Vec3, f32)>,
+}
+
+impl View {
+    fn project(&self, point: Vec3) -> Vec2 {
+        match self.eye {
+            None => Vec2::new(point.dot(self.right), point.dot(self.up)),
+            Some((eye, distance)) => {
+                let point = point - eye;
+                // Points at the distance of the center of the geometry keep their size.
+                let scale = distance / point.dot(self.forward).max(f32::EPSILON);
+                Vec2::new(point.dot(self.right), point.dot(self.up)) * scale
+            }
+        }
+    }
+}
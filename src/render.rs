@@ -0,0 +1,48 @@
+//! Renderers drawing the geometry of an executed lsystem.
+pub mod ascii;
+#[cfg(feature = "glow")]
+pub mod glow;
+/// The CPU rasterizer, also an exporter, e.g. `render::raster::render_png` without a graphics context.
+pub use crate::export::raster;
+
+use macaw::{BoundingBox, Mat4, Vec3};
+
+/// Maps the coordinates of the drawing into clip space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub view_projection: Mat4,
+}
+
+impl Camera {
+    pub fn new(view_projection: Mat4) -> Self {
+        Self { view_projection }
+    }
+
+    /// Creates an orthographic camera looking down the z axis that fits `bounds` into a viewport
+    /// whose width divided by its height is `aspect`, keeping the aspect ratio of the drawing.
+    pub fn fit(bounds: BoundingBox, aspect: f32) -> Self {
+        let center = bounds.center();
+        let size = bounds.size().max(Vec3::splat(f32::EPSILON));
+
+        // Half the extent of the view, widened along the axis the viewport has room to spare.
+        let half_height = (size.y * 0.5).max(size.x * 0.5 / aspect);
+        let half_width = half_height * aspect;
+        let half_depth = size.length().max(1.0);
+
+        let projection = Mat4::orthographic_rh_gl(
+            -half_width,
+            half_width,
+            -half_height,
+            half_height,
+            -half_depth,
+            half_depth,
+        );
+        Self::new(projection * Mat4::from_translation(-center))
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(Mat4::IDENTITY)
+    }
+}
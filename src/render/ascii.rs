@@ -0,0 +1,152 @@
+//! Draws geometry as text for previews in a terminal, no graphics stack needed.
+use macaw::Vec2;
+
+use crate::{Geometry, Geometry2D, Polygon2D, Projection};
+
+/// The characters the drawing is made of.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Charset {
+    /// Unicode braille patterns, every character draws 2 by 4 dots.
+    #[default]
+    Braille,
+    /// Half blocks like `▀`, every character draws 1 by 2 dots, for fonts without braille.
+    Blocks,
+}
+
+impl Charset {
+    /// The number of dots a character draws horizontally and vertically.
+    fn dots(&self) -> (usize, usize) {
+        match self {
+            Charset::Braille => (2, 4),
+            Charset::Blocks => (1, 2),
+        }
+    }
+
+    /// Returns the character drawing the dots of `cell`, indexed by row then column.
+    fn char(&self, cell: impl Fn(usize, usize) -> bool) -> char {
+        match self {
+            Charset::Braille => {
+                // The bits of the dots of a braille pattern, the bottom row was added last.
+                const BITS: [[u32; 2]; 4] =
+                    [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+                let mut bits = 0;
+                for (y, row) in BITS.iter().enumerate() {
+                    for (x, bit) in row.iter().enumerate() {
+                        if cell(x, y) {
+                            bits |= bit;
+                        }
+                    }
+                }
+                char::from_u32(0x2800 + bits).expect("Braille patterns are chars.")
+            }
+            Charset::Blocks => match (cell(0, 0), cell(0, 1)) {
+                (false, false) => ' ',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (true, true) => '█',
+            },
+        }
+    }
+}
+
+/// Draws the segments and polygon outlines of `geometry` with braille patterns into `cols` by `rows` characters.
+///
+/// See `render_with`.
+pub fn render(geometry: &Geometry, cols: usize, rows: usize) -> String {
+    render_with(geometry, cols, rows, Charset::Braille)
+}
+
+/// Draws the segments and polygon outlines of `geometry` projected onto the xy plane,
+/// fitted into `cols` by `rows` characters keeping its aspect ratio, e.g. to print it in a terminal.
+///
+/// The rows are separated by newlines. Terminal characters are about twice as high as wide,
+/// both charsets draw dots that are about square. Meshes and instances are not drawn.
+/// Use `render_planar` to draw it from another side, see `Projection`.
+pub fn render_with(geometry: &Geometry, cols: usize, rows: usize, charset: Charset) -> String {
+    render_planar(
+        &Projection::default().project(geometry),
+        cols,
+        rows,
+        charset,
+    )
+}
+
+/// Same as `render_with` but draws geometry that is already projected.
+pub fn render_planar(geometry: &Geometry2D, cols: usize, rows: usize, charset: Charset) -> String {
+    let (dots_x, dots_y) = charset.dots();
+    let mut grid = DotGrid::new(cols * dots_x, rows * dots_y);
+
+    if let Some((min, max)) = geometry.bounds().filter(|_| !grid.is_empty()) {
+        // Scale the extent of the drawing into the grid, keeping the aspect ratio.
+        let size = max - min;
+        let available_x = (grid.width - 1) as f32;
+        let available_y = (grid.height - 1) as f32;
+        let scale =
+            (available_x / size.x.max(f32::EPSILON)).min(available_y / size.y.max(f32::EPSILON));
+        let center = (min + max) * 0.5;
+        let grid_center = Vec2::new(available_x, available_y) * 0.5;
+
+        // Rows count down, thus all y coordinates are flipped.
+        let project =
+            |point: Vec2| grid_center + Vec2::new(point.x - center.x, center.y - point.y) * scale;
+
+        for segment in &geometry.segments {
+            grid.draw_line(project(segment.a), project(segment.b));
+        }
+        for Polygon2D { vertices, .. } in &geometry.polygons {
+            for (index, vertex) in vertices.iter().enumerate() {
+                let next = vertices[(index + 1) % vertices.len()];
+                grid.draw_line(project(*vertex), project(next));
+            }
+        }
+    }
+
+    let mut text = String::with_capacity(rows * (cols * 3 + 1));
+    for row in 0..rows {
+        if row > 0 {
+            text.push('\n');
+        }
+        for col in 0..cols {
+            text.push(charset.char(|x, y| grid.get(col * dots_x + x, row * dots_y + y)));
+        }
+    }
+    text
+}
+
+struct DotGrid {
+    width: usize,
+    height: usize,
+    dots: Vec<bool>,
+}
+
+impl DotGrid {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            dots: vec![false; width * height],
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.dots.is_empty()
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.dots[y * self.width + x]
+    }
+
+    /// Sets the dots closest to the line from `a` to `b`, one per step along its longer axis.
+    fn draw_line(&mut self, a: Vec2, b: Vec2) {
+        let delta = b - a;
+        let steps = delta.x.abs().max(delta.y.abs()).ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let point = a + delta * (step as f32 / steps as f32);
+            let (x, y) = (point.x.round(), point.y.round());
+            if (0.0..self.width as f32).contains(&x) && (0.0..self.height as f32).contains(&y) {
+                let index = y as usize * self.width + x as usize;
+                self.dots[index] = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,531 @@
+//! Draws the segments of a drawing with OpenGL, every segment is expanded into a quad,
+//! and the models of its instances with instanced draw calls.
+use std::fmt;
+
+use glow::HasContext;
+
+use crate::{
+    expand_lines_to_quads, instance_attributes, workgroups, Geometry, GpuVec, Mesh, Polyline,
+};
+
+use super::Camera;
+
+/// Invocations per workgroup of the line expansion shader, see `local_size_x` of `lines.comp`.
+const LOCAL_SIZE: u32 = 64;
+/// Floats per segment uploaded to the compute shader, its start and width, end and color.
+const SEGMENT_COMPONENTS: usize = 12;
+/// Floats per vertex written by the compute shader, its position and color.
+const VERTEX_COMPONENTS: usize = 8;
+/// Two triangles per segment.
+const VERTICES_PER_SEGMENT: usize = 6;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RenderError {
+    /// The driver could not create a GL object.
+    Create(String),
+    /// A shader failed to compile, `log` is the info log of the driver.
+    Compile { stage: &'static str, log: String },
+    /// The shaders failed to link into a program.
+    Link(String),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Create(error) => write!(f, "could not create GL object: {error}"),
+            RenderError::Compile { stage, log } => {
+                write!(f, "could not compile {stage} shader: {log}")
+            }
+            RenderError::Link(log) => write!(f, "could not link program: {log}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// Draws the segments of a `Geometry` with their width and color, and the instances of its models.
+///
+/// The segments are expanded into quads when painted for the first time, the expanded vertices stay on the GPU.
+/// Every model is uploaded once and drawn with one instanced draw call for all its instances,
+/// in the colors of the instances, see `Geometry::models`.
+/// With OpenGL 4.3 a compute shader expands them, older and embedded contexts like macOS and WebGL
+/// fall back to `expand_lines_to_quads` on the CPU.
+/// GL objects are not freed on drop because that needs the context, call `destroy` once the renderer is no longer used.
+pub struct Renderer {
+    expansion: Expansion,
+    render_program: glow::Program,
+    /// The expanded vertices drawn by the render program.
+    vertices: glow::Buffer,
+    vertex_array: glow::VertexArray,
+    segment_count: usize,
+    line_width: f32,
+    expanded: bool,
+    /// Draws the models, compiled if the geometry places any.
+    mesh_program: Option<glow::Program>,
+    models: Vec<InstancedModel>,
+}
+
+/// A model uploaded once and drawn for every instance placing it.
+struct InstancedModel {
+    vertex_array: glow::VertexArray,
+    /// The positions, the indices and the attributes of the instances, see `instance_attributes`.
+    buffers: [glow::Buffer; 3],
+    index_count: usize,
+    instance_count: usize,
+}
+
+/// How the segments are expanded into quads.
+enum Expansion {
+    /// The compute `program` reads the uploaded `segments`.
+    Gpu {
+        program: glow::Program,
+        segments: glow::Buffer,
+    },
+    /// The polylines are expanded on the CPU and uploaded.
+    Cpu { polylines: Vec<Polyline> },
+}
+
+impl Renderer {
+    /// Uploads the segments and the instanced models of `geometry` and compiles the shaders.
+    pub fn new(gl: &glow::Context, geometry: &Geometry) -> Result<Self, RenderError> {
+        let segment_count = geometry.segments.len();
+        let vertex_bytes =
+            segment_count * VERTICES_PER_SEGMENT * VERTEX_COMPONENTS * std::mem::size_of::<f32>();
+
+        unsafe {
+            let header = render_header(gl);
+            let render_program = compile_program(
+                gl,
+                &[
+                    (
+                        glow::VERTEX_SHADER,
+                        &format!("{header}{}", include_str!("shaders/lines.vert")),
+                    ),
+                    (
+                        glow::FRAGMENT_SHADER,
+                        &format!("{header}{}", include_str!("shaders/lines.frag")),
+                    ),
+                ],
+            )?;
+            let (vertices, vertex_array) =
+                create_vertex_objects(gl).inspect_err(|_| gl.delete_program(render_program))?;
+
+            let expansion = if supports_compute(gl) {
+                create_gpu_expansion(gl, geometry).inspect_err(|_| {
+                    gl.delete_program(render_program);
+                    gl.delete_buffer(vertices);
+                    gl.delete_vertex_array(vertex_array);
+                })?
+            } else {
+                Expansion::Cpu {
+                    polylines: geometry.polylines(),
+                }
+            };
+
+            gl.bind_vertex_array(Some(vertex_array));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertices));
+            // GL rejects empty buffers on some drivers, thus at least one byte is allocated.
+            gl.buffer_data_size(
+                glow::ARRAY_BUFFER,
+                vertex_bytes.max(1) as i32,
+                glow::DYNAMIC_COPY,
+            );
+            let stride = (VERTEX_COMPONENTS * std::mem::size_of::<f32>()) as i32;
+            gl.vertex_attrib_pointer_f32(0, 4, glow::FLOAT, false, stride, 0);
+            gl.enable_vertex_attrib_array(0);
+            gl.vertex_attrib_pointer_f32(1, 4, glow::FLOAT, false, stride, stride / 2);
+            gl.enable_vertex_attrib_array(1);
+            gl.bind_vertex_array(None);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+
+            let mut renderer = Self {
+                expansion,
+                render_program,
+                vertices,
+                vertex_array,
+                segment_count,
+                line_width: 0.01,
+                expanded: false,
+                mesh_program: None,
+                models: Vec::new(),
+            };
+            if let Err(error) = renderer.upload_models(gl, geometry, header) {
+                renderer.destroy(gl);
+                return Err(error);
+            }
+            Ok(renderer)
+        }
+    }
+
+    /// Compiles the mesh program and uploads every model of `geometry` with the attributes of its instances.
+    unsafe fn upload_models(
+        &mut self,
+        gl: &glow::Context,
+        geometry: &Geometry,
+        header: &str,
+    ) -> Result<(), RenderError> {
+        let placed = geometry
+            .models
+            .iter()
+            .map(|(name, mesh)| (mesh, instance_attributes(&geometry.instances, name)))
+            .filter(|(mesh, instances)| !mesh.is_empty() && !instances.is_empty())
+            .collect::<Vec<_>>();
+        if placed.is_empty() {
+            return Ok(());
+        }
+
+        self.mesh_program = Some(compile_program(
+            gl,
+            &[
+                (
+                    glow::VERTEX_SHADER,
+                    &format!("{header}{}", include_str!("shaders/mesh.vert")),
+                ),
+                (
+                    glow::FRAGMENT_SHADER,
+                    &format!("{header}{}", include_str!("shaders/lines.frag")),
+                ),
+            ],
+        )?);
+        for (mesh, instances) in placed {
+            self.models
+                .push(create_instanced_model(gl, mesh, &instances)?);
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the segments are expanded by a compute shader, `false` if they are expanded on the CPU.
+    pub fn uses_compute(&self) -> bool {
+        matches!(self.expansion, Expansion::Gpu { .. })
+    }
+
+    /// Sets the width in drawing units of a segment with width `1.0`, the segments are expanded again on the next paint.
+    pub fn set_line_width(&mut self, line_width: f32) {
+        self.line_width = line_width;
+        self.expanded = false;
+    }
+
+    pub fn line_width(&self) -> f32 {
+        self.line_width
+    }
+
+    /// Draws the segments and the instances as seen by `camera` into the bound framebuffer.
+    pub fn paint(&mut self, gl: &glow::Context, camera: &Camera) {
+        unsafe {
+            self.paint_models(gl, camera);
+        }
+        if self.segment_count == 0 {
+            return;
+        }
+
+        unsafe {
+            if !self.expanded {
+                self.expand(gl);
+            }
+
+            gl.use_program(Some(self.render_program));
+            let location = gl.get_uniform_location(self.render_program, "view_projection");
+            gl.uniform_matrix_4_f32_slice(
+                location.as_ref(),
+                false,
+                &camera.view_projection.to_cols_array(),
+            );
+
+            gl.bind_vertex_array(Some(self.vertex_array));
+            gl.draw_arrays(
+                glow::TRIANGLES,
+                0,
+                (self.segment_count * VERTICES_PER_SEGMENT) as i32,
+            );
+            gl.bind_vertex_array(None);
+            gl.use_program(None);
+        }
+    }
+
+    /// Frees all GL objects of the renderer.
+    pub fn destroy(self, gl: &glow::Context) {
+        unsafe {
+            for model in self.models {
+                gl.delete_vertex_array(model.vertex_array);
+                for buffer in model.buffers {
+                    gl.delete_buffer(buffer);
+                }
+            }
+            if let Some(program) = self.mesh_program {
+                gl.delete_program(program);
+            }
+            if let Expansion::Gpu { program, segments } = self.expansion {
+                gl.delete_program(program);
+                gl.delete_buffer(segments);
+            }
+            gl.delete_program(self.render_program);
+            gl.delete_buffer(self.vertices);
+            gl.delete_vertex_array(self.vertex_array);
+        }
+    }
+
+    /// Draws every model once per instance.
+    unsafe fn paint_models(&self, gl: &glow::Context, camera: &Camera) {
+        let Some(program) = self.mesh_program else {
+            return;
+        };
+
+        gl.use_program(Some(program));
+        let location = gl.get_uniform_location(program, "view_projection");
+        gl.uniform_matrix_4_f32_slice(
+            location.as_ref(),
+            false,
+            &camera.view_projection.to_cols_array(),
+        );
+        for model in &self.models {
+            gl.bind_vertex_array(Some(model.vertex_array));
+            gl.draw_elements_instanced(
+                glow::TRIANGLES,
+                model.index_count as i32,
+                glow::UNSIGNED_INT,
+                0,
+                model.instance_count as i32,
+            );
+        }
+        gl.bind_vertex_array(None);
+        gl.use_program(None);
+    }
+
+    /// Writes the quads of the segments into the vertex buffer.
+    unsafe fn expand(&mut self, gl: &glow::Context) {
+        match &self.expansion {
+            Expansion::Gpu { program, segments } => {
+                gl.use_program(Some(*program));
+                let location = gl.get_uniform_location(*program, "line_width");
+                gl.uniform_1_f32(location.as_ref(), self.line_width);
+
+                gl.bind_buffer_base(glow::SHADER_STORAGE_BUFFER, 0, Some(*segments));
+                gl.bind_buffer_base(glow::SHADER_STORAGE_BUFFER, 1, Some(self.vertices));
+                gl.dispatch_compute(workgroups(self.segment_count, LOCAL_SIZE), 1, 1);
+                // The vertices are read as vertex attributes by the render program.
+                gl.memory_barrier(glow::VERTEX_ATTRIB_ARRAY_BARRIER_BIT);
+
+                gl.bind_buffer_base(glow::SHADER_STORAGE_BUFFER, 0, None);
+                gl.bind_buffer_base(glow::SHADER_STORAGE_BUFFER, 1, None);
+                gl.use_program(None);
+            }
+            Expansion::Cpu { polylines } => {
+                let vertices = expand_lines_to_quads(polylines, self.line_width);
+                gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vertices));
+                gl.buffer_sub_data_u8_slice(glow::ARRAY_BUFFER, 0, vertices.as_bytes());
+                gl.bind_buffer(glow::ARRAY_BUFFER, None);
+            }
+        }
+        self.expanded = true;
+    }
+}
+
+/// Returns `true` if the context runs compute shaders, they need OpenGL 4.3 and are not part of WebGL.
+fn supports_compute(gl: &glow::Context) -> bool {
+    let version = gl.version();
+    !version.is_embedded && (version.major, version.minor) >= (4, 3)
+}
+
+/// Returns the version line of the render shaders, GLSL ES on embedded contexts like WebGL.
+fn render_header(gl: &glow::Context) -> &'static str {
+    if gl.version().is_embedded {
+        "#version 300 es\nprecision mediump float;\n"
+    } else {
+        "#version 330\n"
+    }
+}
+
+/// Compiles the compute shader and uploads the segments it expands.
+unsafe fn create_gpu_expansion(
+    gl: &glow::Context,
+    geometry: &Geometry,
+) -> Result<Expansion, RenderError> {
+    let mut segments = GpuVec::<f32>::new(SEGMENT_COMPONENTS);
+    for segment in &geometry.segments {
+        let color = segment.color;
+        segments.push(&[
+            segment.a.x,
+            segment.a.y,
+            segment.a.z,
+            segment.width,
+            segment.b.x,
+            segment.b.y,
+            segment.b.z,
+            0.0,
+            color.r,
+            color.g,
+            color.b,
+            color.a,
+        ]);
+    }
+
+    let program = compile_program(
+        gl,
+        &[(glow::COMPUTE_SHADER, include_str!("shaders/lines.comp"))],
+    )?;
+    let buffer = gl.create_buffer().map_err(|error| {
+        gl.delete_program(program);
+        RenderError::Create(error)
+    })?;
+    gl.bind_buffer(glow::SHADER_STORAGE_BUFFER, Some(buffer));
+    gl.buffer_data_u8_slice(
+        glow::SHADER_STORAGE_BUFFER,
+        segments.as_bytes(),
+        glow::STATIC_DRAW,
+    );
+    gl.bind_buffer(glow::SHADER_STORAGE_BUFFER, None);
+
+    Ok(Expansion::Gpu {
+        program,
+        segments: buffer,
+    })
+}
+
+/// Creates the vertex buffer and vertex array, nothing is left behind if one can not be created.
+unsafe fn create_vertex_objects(
+    gl: &glow::Context,
+) -> Result<(glow::Buffer, glow::VertexArray), RenderError> {
+    let vertices = gl.create_buffer().map_err(RenderError::Create)?;
+    let vertex_array = gl.create_vertex_array().map_err(|error| {
+        gl.delete_buffer(vertices);
+        RenderError::Create(error)
+    })?;
+    Ok((vertices, vertex_array))
+}
+
+/// Uploads the positions and indices of `mesh` and the attributes of its `instances` into a vertex array,
+/// nothing is left behind if a GL object can not be created.
+unsafe fn create_instanced_model(
+    gl: &glow::Context,
+    mesh: &Mesh,
+    instances: &GpuVec<f32>,
+) -> Result<InstancedModel, RenderError> {
+    let vertex_array = gl.create_vertex_array().map_err(RenderError::Create)?;
+    let mut buffers = Vec::with_capacity(3);
+    for _ in 0..3 {
+        match gl.create_buffer() {
+            Ok(buffer) => buffers.push(buffer),
+            Err(error) => {
+                for buffer in buffers {
+                    gl.delete_buffer(buffer);
+                }
+                gl.delete_vertex_array(vertex_array);
+                return Err(RenderError::Create(error));
+            }
+        }
+    }
+    let [positions, indices, attributes] = [buffers[0], buffers[1], buffers[2]];
+
+    gl.bind_vertex_array(Some(vertex_array));
+
+    let vertices = GpuVec::from_vec(
+        mesh.positions.iter().flat_map(|p| p.to_array()).collect(),
+        3,
+    );
+    gl.bind_buffer(glow::ARRAY_BUFFER, Some(positions));
+    gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, vertices.as_bytes(), glow::STATIC_DRAW);
+    gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, vertices.stride() as i32, 0);
+    gl.enable_vertex_attrib_array(0);
+
+    // The four columns of the transform and the color, `vec4`s advancing once per instance.
+    gl.bind_buffer(glow::ARRAY_BUFFER, Some(attributes));
+    gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, instances.as_bytes(), glow::STATIC_DRAW);
+    let column = (4 * std::mem::size_of::<f32>()) as i32;
+    for location in 1..=5 {
+        let offset = (location - 1) as i32 * column;
+        gl.vertex_attrib_pointer_f32(
+            location,
+            4,
+            glow::FLOAT,
+            false,
+            instances.stride() as i32,
+            offset,
+        );
+        gl.vertex_attrib_divisor(location, 1);
+        gl.enable_vertex_attrib_array(location);
+    }
+
+    gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(indices));
+    let index_bytes = mesh
+        .indices
+        .iter()
+        .flat_map(|index| index.to_ne_bytes())
+        .collect::<Vec<_>>();
+    gl.buffer_data_u8_slice(glow::ELEMENT_ARRAY_BUFFER, &index_bytes, glow::STATIC_DRAW);
+
+    // The element buffer stays bound to the vertex array.
+    gl.bind_vertex_array(None);
+    gl.bind_buffer(glow::ARRAY_BUFFER, None);
+    gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, None);
+
+    Ok(InstancedModel {
+        vertex_array,
+        buffers: [positions, indices, attributes],
+        index_count: mesh.indices.len(),
+        instance_count: instances.len(),
+    })
+}
+
+/// Compiles and links the `(shader type, source)` pairs into a program.
+unsafe fn compile_program(
+    gl: &glow::Context,
+    sources: &[(u32, &str)],
+) -> Result<glow::Program, RenderError> {
+    let program = gl.create_program().map_err(RenderError::Create)?;
+
+    let mut shaders = Vec::with_capacity(sources.len());
+    let mut result = Ok(());
+    for &(shader_type, source) in sources {
+        let shader = match gl.create_shader(shader_type) {
+            Ok(shader) => shader,
+            Err(error) => {
+                result = Err(RenderError::Create(error));
+                break;
+            }
+        };
+        shaders.push(shader);
+
+        gl.shader_source(shader, source);
+        gl.compile_shader(shader);
+        if !gl.get_shader_compile_status(shader) {
+            result = Err(RenderError::Compile {
+                stage: stage_name(shader_type),
+                log: gl.get_shader_info_log(shader),
+            });
+            break;
+        }
+        gl.attach_shader(program, shader);
+    }
+
+    if result.is_ok() {
+        gl.link_program(program);
+        if !gl.get_program_link_status(program) {
+            result = Err(RenderError::Link(gl.get_program_info_log(program)));
+        }
+    }
+
+    // The shaders are part of the linked program, they are no longer needed on their own.
+    for shader in shaders {
+        if result.is_ok() {
+            gl.detach_shader(program, shader);
+        }
+        gl.delete_shader(shader);
+    }
+
+    match result {
+        Ok(()) => Ok(program),
+        Err(error) => {
+            gl.delete_program(program);
+            Err(error)
+        }
+    }
+}
+
+fn stage_name(shader_type: u32) -> &'static str {
+    match shader_type {
+        glow::VERTEX_SHADER => "vertex",
+        glow::FRAGMENT_SHADER => "fragment",
+        glow::COMPUTE_SHADER => "compute",
+        _ => "unknown",
+    }
+}
@@ -0,0 +1,8 @@
+// The version line is prepended by the renderer, GLSL 330 or GLSL ES 300 on WebGL.
+in vec4 color;
+
+out vec4 out_color;
+
+void main() {
+    out_color = color;
+}
@@ -0,0 +1,12 @@
+// The version line is prepended by the renderer, GLSL 330 or GLSL ES 300 on WebGL.
+layout(location = 0) in vec4 in_position;
+layout(location = 1) in vec4 in_color;
+
+uniform mat4 view_projection;
+
+out vec4 color;
+
+void main() {
+    color = in_color;
+    gl_Position = view_projection * in_position;
+}
@@ -0,0 +1,18 @@
+// The version line is prepended by the renderer, GLSL 330 or GLSL ES 300 on WebGL.
+layout(location = 0) in vec3 in_position;
+// The transform of the instance as four columns, advancing once per instance.
+layout(location = 1) in vec4 in_transform_x;
+layout(location = 2) in vec4 in_transform_y;
+layout(location = 3) in vec4 in_transform_z;
+layout(location = 4) in vec4 in_transform_w;
+layout(location = 5) in vec4 in_color;
+
+uniform mat4 view_projection;
+
+out vec4 color;
+
+void main() {
+    mat4 transform = mat4(in_transform_x, in_transform_y, in_transform_z, in_transform_w);
+    color = in_color;
+    gl_Position = view_projection * transform * vec4(in_position, 1.0);
+}
@@ -0,0 +1,219 @@
+//! An interactive session to prototype grammars line by line, see `lsys repl`.
+use core::fmt;
+
+use crate::{
+    prelude::*, Alphabet, DefaultAlphabetSymbolDefiner, GenerateError, GenerateOptions, LSystem,
+};
+
+const HELP: &str = "\
+axiom <symbols>             sets the axiom and restarts at generation 0
+rule <symbol> -> <symbols>  adds a rule, replacing the rule of the same symbol
+remove <symbol>             removes the rule of the symbol
+rules                       lists the axiom and the rules
+step [n]                    derives n more generations, 1 by default
+reset                       restarts at generation 0
+show                        prints the current string
+stats                       prints statistics of the current string
+help                        prints this message";
+
+/// Derives a grammar that is edited one command at a time, e.g. `rule F -> F+F` followed by `step 2`.
+///
+/// Every command is answered by `eval`, the current string is derived again after every change,
+/// thus edited rules apply to all generations.
+pub struct Repl {
+    axiom: String,
+    rules: Vec<(String, String)>,
+    generation: u8,
+    generate_options: GenerateOptions,
+    /// Longer strings are cut off when printed.
+    max_print: usize,
+}
+
+/// A command `Repl::eval` could not execute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplError {
+    UnknownCommand(String),
+    /// The arguments of `command` do not match `usage`.
+    InvalidArguments {
+        command: String,
+        usage: &'static str,
+    },
+    /// The rule to remove does not exist.
+    UnknownRule(String),
+    Generate(GenerateError),
+}
+
+impl fmt::Display for ReplError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplError::UnknownCommand(command) => {
+                write!(f, "unknown command `{command}`, try `help`")
+            }
+            ReplError::InvalidArguments { command, usage } => {
+                write!(f, "invalid arguments for `{command}`, expected `{usage}`")
+            }
+            ReplError::UnknownRule(predecessor) => write!(f, "`{predecessor}` has no rule"),
+            ReplError::Generate(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl core::error::Error for ReplError {}
+
+impl From<GenerateError> for ReplError {
+    fn from(error: GenerateError) -> Self {
+        ReplError::Generate(error)
+    }
+}
+
+impl Repl {
+    /// Creates a session with the axiom `F`, no rules and a budget of one million symbols.
+    pub fn new() -> Self {
+        Self {
+            axiom: "F".to_string(),
+            rules: Vec::new(),
+            generation: 0,
+            generate_options: GenerateOptions::new().with_max_symbols(1_000_000),
+            max_print: 1000,
+        }
+    }
+
+    pub fn with_generate_options(mut self, generate_options: GenerateOptions) -> Self {
+        self.generate_options = generate_options;
+        self
+    }
+
+    /// Sets the number of symbols printed by `show` and `step`, `1000` by default.
+    pub fn with_max_print(mut self, max_print: usize) -> Self {
+        self.max_print = max_print;
+        self
+    }
+
+    pub fn generation(&self) -> u8 {
+        self.generation
+    }
+
+    /// Returns the lsystem of the axiom and rules entered so far.
+    pub fn lsystem(&self) -> LSystem {
+        let mut lsystem = LSystem::new(&self.axiom, DefaultAlphabetSymbolDefiner);
+        for (predecessor, successor) in &self.rules {
+            lsystem.add_rule(predecessor, successor);
+        }
+        lsystem
+    }
+
+    /// Derives the current generation.
+    pub fn current(&self) -> Result<Alphabet, GenerateError> {
+        self.lsystem()
+            .generate_with(self.generation, &self.generate_options)
+    }
+
+    /// Executes the command `line` and returns the text to print, empty lines return an empty text.
+    ///
+    /// A failed `step` keeps the previous generation.
+    pub fn eval(&mut self, line: &str) -> Result<String, ReplError> {
+        let line = line.trim();
+        let (command, args) = line
+            .split_once(char::is_whitespace)
+            .map(|(command, args)| (command, args.trim()))
+            .unwrap_or((line, ""));
+        let invalid = |usage| ReplError::InvalidArguments {
+            command: command.to_string(),
+            usage,
+        };
+
+        match command {
+            "" => Ok(String::new()),
+            "axiom" if !args.is_empty() => {
+                self.axiom = args.split_whitespace().collect();
+                self.generation = 0;
+                self.show()
+            }
+            "axiom" => Err(invalid("axiom <symbols>")),
+            "rule" => {
+                let (predecessor, successor) = args
+                    .split_once("->")
+                    .map(|(predecessor, successor)| (predecessor.trim(), successor.trim()))
+                    .filter(|(predecessor, _)| predecessor.chars().count() == 1)
+                    .ok_or_else(|| invalid("rule <symbol> -> <symbols>"))?;
+                let successor = successor.split_whitespace().collect::<String>();
+
+                match self.rules.iter_mut().find(|(p, _)| p == predecessor) {
+                    Some(rule) => rule.1 = successor,
+                    None => self.rules.push((predecessor.to_string(), successor)),
+                }
+                Ok(self.format_rules())
+            }
+            "remove" => {
+                let index = self
+                    .rules
+                    .iter()
+                    .position(|(predecessor, _)| predecessor == args)
+                    .ok_or_else(|| ReplError::UnknownRule(args.to_string()))?;
+                self.rules.remove(index);
+                Ok(self.format_rules())
+            }
+            "rules" => Ok(self.format_rules()),
+            "step" => {
+                let steps = match args {
+                    "" => 1,
+                    steps => steps.parse::<u8>().map_err(|_| invalid("step [n]"))?,
+                };
+                let previous = self.generation;
+                self.generation = previous.saturating_add(steps);
+                self.show().inspect_err(|_| self.generation = previous)
+            }
+            "reset" => {
+                self.generation = 0;
+                self.show()
+            }
+            "show" => self.show(),
+            "stats" => self.stats(),
+            "help" => Ok(HELP.to_string()),
+            _ => Err(ReplError::UnknownCommand(command.to_string())),
+        }
+    }
+
+    fn show(&self) -> Result<String, ReplError> {
+        let current = self.current()?.to_string();
+        let length = current.chars().count();
+
+        let mut text = format!("generation {}: ", self.generation);
+        text.extend(current.chars().take(self.max_print));
+        if length > self.max_print {
+            text.push_str(&format!("... ({} more)", length - self.max_print));
+        }
+        Ok(text)
+    }
+
+    fn stats(&self) -> Result<String, ReplError> {
+        let stats = self.current()?.stats();
+        let counts = stats
+            .counts
+            .iter()
+            .map(|(symbol, count)| format!("{symbol}: {count}"))
+            .collect::<Vec<_>>();
+
+        Ok(format!(
+            "generation {}: {} symbols, nesting {}\n{}",
+            self.generation,
+            stats.len(),
+            stats.max_nesting,
+            counts.join(", ")
+        ))
+    }
+
+    fn format_rules(&self) -> String {
+        let mut text = format!("axiom {}", self.axiom);
+        for (predecessor, successor) in &self.rules {
+            text.push_str(&format!("\nrule {predecessor} -> {successor}"));
+        }
+        text
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
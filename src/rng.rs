@@ -0,0 +1,80 @@
+//! Random numbers for stochastic rules, actions and random parameters like `r(1..2)`.
+//!
+//! Every `LSystem` draws from its own generator seeded with `LSystem::set_seed`,
+//! thus generating and running the same lsystem twice gives the same result.
+use core::ops::Range;
+
+#[cfg(feature = "std")]
+use perchance::PerchanceContext;
+
+/// The seed of an `LSystem` unless `LSystem::set_seed` is called.
+pub const DEFAULT_SEED: u64 = 32132132151651;
+
+/// A source of uniformly distributed random numbers.
+///
+/// Implement this to use another generator than the default `DefaultRng`,
+/// e.g. one replaying a fixed sequence in tests.
+pub trait Rng {
+    fn next_u32(&mut self) -> u32;
+
+    /// Returns a number in `0.0..1.0`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Returns a number in `range`.
+    fn range_f32(&mut self, range: Range<f32>) -> f32 {
+        range.start + (range.end - range.start) * self.next_f32()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Rng for PerchanceContext {
+    fn next_u32(&mut self) -> u32 {
+        self.get_u32()
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.uniform_f32()
+    }
+}
+
+/// The PCG XSL RR 128/64 generator of `perchance`, also available without `std`.
+///
+/// Draws the same numbers as a `PerchanceContext` with the same seed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefaultRng(u128);
+
+impl DefaultRng {
+    const MULTIPLIER: u128 = 0x2360_ED05_1FC6_5DA4_4385_DF64_9FCC_F645;
+
+    pub const fn new(seed: u128) -> Self {
+        // The state must be odd.
+        Self((seed << 1) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(Self::MULTIPLIER);
+
+        // Xorshifts the low bits and rotates them by the highest 6 bits.
+        let rotation = (self.0 >> 122) as u32;
+        let xorshifted = ((self.0 >> 64) as u64) ^ (self.0 as u64);
+        xorshifted.rotate_right(rotation)
+    }
+}
+
+impl Rng for DefaultRng {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        // A number in `1.0..2.0` built from the bits of the mantissa.
+        f32::from_bits(0x3F80_0000 | (self.next_u32() >> 9)) - 1.0
+    }
+}
+
+/// Returns the default generator seeded with `seed`.
+pub fn seeded_rng(seed: u64) -> DefaultRng {
+    DefaultRng::new(seed as u128)
+}
@@ -0,0 +1,66 @@
+//! Errors raised while running an lsystem with `LSystem::run`.
+use core::fmt;
+
+use crate::{prelude::*, ParamError};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuntimeError {
+    /// `symbol` is interpreted as `action` but the `ActionResolver` has no action with that name for `symbol`.
+    UnknownAction { symbol: char, action: String },
+    /// The `]` at `index` of the alphabet closes a branch that was never opened.
+    UnbalancedBrackets { index: usize },
+    /// `action` could not be created from its parameters,
+    /// `index` is the index of the first parameter it was not given.
+    MissingParameter { action: String, index: usize },
+    /// A parameter of an action is not of the expected type.
+    InvalidParameter(ParamError),
+    /// A parameter of `action` uses the variable `name` that is neither set by a `let` statement nor `LSystem::set_variable`.
+    UnknownVariable { action: String, name: String },
+    /// The run was aborted before the symbol at `index` because the `CancellationToken` was cancelled.
+    Cancelled { index: usize },
+    /// The symbol at `index` selects `turtle` that was never spawned, see `ExecuteContext::select_turtle`.
+    UnknownTurtle { turtle: usize, index: usize },
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::UnknownAction { symbol, action } => {
+                write!(f, "`{symbol}` is interpreted as unknown action `{action}`")
+            }
+            RuntimeError::UnbalancedBrackets { index } => {
+                write!(
+                    f,
+                    "`]` at index {index} closes a branch that was never opened"
+                )
+            }
+            RuntimeError::MissingParameter { action, index } => {
+                write!(f, "action `{action}` is missing parameter {index}")
+            }
+            RuntimeError::InvalidParameter(error) => error.fmt(f),
+            RuntimeError::UnknownVariable { action, name } => {
+                write!(f, "action `{action}` uses unknown variable `{name}`")
+            }
+            RuntimeError::Cancelled { index } => {
+                write!(f, "the run was cancelled at index {index}")
+            }
+            RuntimeError::UnknownTurtle { turtle, index } => {
+                write!(f, "symbol at index {index} selects unknown turtle {turtle}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for RuntimeError {}
+
+impl From<ParamError> for RuntimeError {
+    fn from(error: ParamError) -> Self {
+        match error.found {
+            None => RuntimeError::MissingParameter {
+                action: error.action,
+                index: error.index,
+            },
+            Some(_) => RuntimeError::InvalidParameter(error),
+        }
+    }
+}
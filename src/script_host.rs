@@ -0,0 +1,112 @@
+//! Reloads scripts while they are edited, for hosts like editors and game engines.
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    time::Duration,
+};
+
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+
+use crate::{CompileError, CompiledLSystem, LSystem};
+
+/// Changes within this time are reloaded once, editors often write a file in multiple steps.
+pub const DEFAULT_RELOAD_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// An event of a `ScriptHost`.
+// Events are rare, boxing the lsystem would only complicate matching them.
+#[allow(clippy::large_enum_variant)]
+pub enum ScriptEvent {
+    /// The script was compiled because it changed, or because watching started.
+    Reloaded(Result<LSystem, CompileError>),
+    /// The watcher reported an error, e.g. because the directory of the script was removed.
+    WatchFailed(String),
+}
+
+/// Watches a script file and compiles it whenever it changes.
+///
+/// The compiled lsystems are sent as `ScriptEvent`s, poll them with `try_next` once per frame
+/// or block on `events`. The script is compiled on the thread of the watcher.
+/// The directory of the script is watched because editors often replace a file instead of writing to it.
+pub struct ScriptHost {
+    path: PathBuf,
+    events: Receiver<ScriptEvent>,
+    /// Stops watching when dropped.
+    _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+impl ScriptHost {
+    /// Watches the script at `path`, its current content is compiled and sent as first event.
+    pub fn watch(path: impl AsRef<Path>) -> notify::Result<Self> {
+        Self::watch_with_debounce(path, DEFAULT_RELOAD_DEBOUNCE)
+    }
+
+    /// Same as `watch` but reloads once no change happened for `debounce`.
+    pub fn watch_with_debounce(path: impl AsRef<Path>, debounce: Duration) -> notify::Result<Self> {
+        let path = path.as_ref().canonicalize().map_err(notify::Error::io)?;
+        let (sender, events) = mpsc::channel();
+
+        let script = path.clone();
+        let reload_sender = sender.clone();
+        let mut debouncer =
+            new_debouncer(debounce, move |result: DebounceEventResult| match result {
+                Ok(events) if events.iter().any(|event| event.path == script) => {
+                    reload(&script, &reload_sender)
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    let _ = reload_sender.send(ScriptEvent::WatchFailed(error.to_string()));
+                }
+            })?;
+
+        let directory = path.parent().unwrap_or(Path::new("."));
+        debouncer
+            .watcher()
+            .watch(directory, RecursiveMode::NonRecursive)?;
+
+        reload(&path, &sender);
+        Ok(Self {
+            path,
+            events,
+            _debouncer: debouncer,
+        })
+    }
+
+    /// Returns the canonical path of the watched script.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn events(&self) -> &Receiver<ScriptEvent> {
+        &self.events
+    }
+
+    /// Returns the next event without blocking, `None` if the script did not change.
+    pub fn try_next(&self) -> Option<ScriptEvent> {
+        self.events.try_recv().ok()
+    }
+
+    /// Reads the current source of the script.
+    pub fn read(&self) -> io::Result<String> {
+        fs::read_to_string(&self.path)
+    }
+
+    /// Writes `source` to the script, which is then reloaded like any other change.
+    pub fn save(&self, source: &str) -> io::Result<()> {
+        fs::write(&self.path, source)
+    }
+}
+
+fn reload(path: &Path, sender: &Sender<ScriptEvent>) {
+    let lsystem = fs::read_to_string(path)
+        .map_err(|error| CompileError::Read {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        })
+        .and_then(|source| CompiledLSystem::try_compile(&source))
+        .map(|compiled| compiled.lsystem);
+
+    // The host may have been dropped while compiling.
+    let _ = sender.send(ScriptEvent::Reloaded(lsystem));
+}
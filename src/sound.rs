@@ -0,0 +1,370 @@
+//! Musical interpretation of lsystems, the symbols play notes instead of drawing.
+//!
+//! Run an lsystem with `ActionResolver::with_sound_actions` to collect `SoundEvent`s in `ExecuteContext::elements`,
+//! and write them with `export::midi::MidiExporter`. Times are measured in beats.
+//! Branches are voices: `[` starts a voice at the time, pitch and velocity of the current one
+//! and `]` returns to them, thus branches play at the same time.
+// Only used if `std` is not linked, see `Float`.
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use crate::prelude::Float;
+use crate::{
+    action::LSystemAction, action::ParamsResolver, prelude::*, ActionResolver, ExecuteContext,
+    ParamError, RuntimeError, Symbol,
+};
+
+/// The pitch of the first note, the middle C.
+pub const DEFAULT_PITCH: u8 = 60;
+
+/// The velocity notes are played with until `SetVelocityAction` changes it.
+pub const DEFAULT_VELOCITY: u8 = 100;
+
+/// An event of the notes played by an lsystem, at a time in beats.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SoundEvent {
+    NoteOn { time: f32, pitch: u8, velocity: u8 },
+    NoteOff { time: f32, pitch: u8 },
+}
+
+impl SoundEvent {
+    pub fn time(&self) -> f32 {
+        match self {
+            SoundEvent::NoteOn { time, .. } | SoundEvent::NoteOff { time, .. } => *time,
+        }
+    }
+}
+
+/// The time, pitch and velocity of the playing voice.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Voice {
+    pub time: f32,
+    /// The MIDI note number, `60` is the middle C.
+    pub pitch: u8,
+    pub velocity: u8,
+}
+
+impl Default for Voice {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            pitch: DEFAULT_PITCH,
+            velocity: DEFAULT_VELOCITY,
+        }
+    }
+}
+
+/// The voices of a run, kept in the state of the context, see `ExecuteContext::state`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Voices {
+    pub voice: Voice,
+    /// The voices saved by `PushVoiceAction`, the innermost last.
+    pub stack: Vec<Voice>,
+    /// The notes started by `NoteOnAction` that are still playing.
+    pub held: Vec<u8>,
+}
+
+impl ActionResolver<SoundEvent> {
+    /// Creates a resolver with the sound actions registered under these symbols:
+    /// `F G` play a note, `f g` rest, `+ -` transpose, `[ ]` push and pop the voice and `{ }` start and stop a note.
+    ///
+    /// `SetPitchAction` and `SetVelocityAction` are not bound, register them with `ActionResolverBuilder::with`.
+    pub fn with_sound_actions() -> Self {
+        let mut resolver = Self::default();
+        for symbol in ['F', 'G'] {
+            resolver.add_action_resolver::<NoteAction>(Symbol::Constant(symbol));
+        }
+        for symbol in ['f', 'g'] {
+            resolver.add_action_resolver::<RestAction>(Symbol::Constant(symbol));
+        }
+        for symbol in ['+', '-'] {
+            resolver.add_action_resolver::<TransposeAction>(Symbol::Constant(symbol));
+        }
+        resolver.add_action_resolver::<PushVoiceAction>(Symbol::Constant('['));
+        resolver.add_action_resolver::<PopVoiceAction>(Symbol::Constant(']'));
+        resolver.add_action_resolver::<NoteOnAction>(Symbol::Constant('{'));
+        resolver.add_action_resolver::<NoteOffAction>(Symbol::Constant('}'));
+        resolver
+    }
+}
+
+/// Plays the pitch of the voice for the duration in beats and advances the voice past it.
+pub struct NoteAction(pub f32);
+
+impl LSystemAction<SoundEvent> for NoteAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('F')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<SoundEvent>) {
+        let voice = context.state::<Voices>().voice;
+        let end = voice.time + self.0;
+        context.elements.push(SoundEvent::NoteOn {
+            time: voice.time,
+            pitch: voice.pitch,
+            velocity: voice.velocity,
+        });
+        context.elements.push(SoundEvent::NoteOff {
+            time: end,
+            pitch: voice.pitch,
+        });
+        context.state::<Voices>().voice.time = end;
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        Self::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(NoteAction(params.require(0)?.max(0.0)))
+    }
+
+    fn name() -> &'static str {
+        "NoteAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Advances the voice by the duration in beats without playing.
+pub struct RestAction(pub f32);
+
+impl LSystemAction<SoundEvent> for RestAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('f')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<SoundEvent>) {
+        context.state::<Voices>().voice.time += self.0;
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        Self::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(RestAction(params.require(0)?.max(0.0)))
+    }
+
+    fn name() -> &'static str {
+        "RestAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Changes the pitch of the voice by the number of semitones, down for negative numbers.
+/// The pitch stays within the MIDI note numbers `0..=127`.
+pub struct TransposeAction(pub i32);
+
+impl LSystemAction<SoundEvent> for TransposeAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('+')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<SoundEvent>) {
+        let voice = &mut context.state::<Voices>().voice;
+        voice.pitch = (voice.pitch as i32 + self.0).clamp(0, 127) as u8;
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        Self::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(TransposeAction(params.require(0)?.round() as i32))
+    }
+
+    fn name() -> &'static str {
+        "TransposeAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Sets the pitch of the voice to the MIDI note number, `60` is the middle C.
+pub struct SetPitchAction(pub u8);
+
+impl LSystemAction<SoundEvent> for SetPitchAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('=')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<SoundEvent>) {
+        context.state::<Voices>().voice.pitch = self.0;
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        Self::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(SetPitchAction(
+            params.require(0)?.round().clamp(0.0, 127.0) as u8
+        ))
+    }
+
+    fn name() -> &'static str {
+        "SetPitchAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Sets the velocity, the loudness, of the notes played by the voice within `1..=127`.
+pub struct SetVelocityAction(pub u8);
+
+impl LSystemAction<SoundEvent> for SetVelocityAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('!')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<SoundEvent>) {
+        context.state::<Voices>().voice.velocity = self.0;
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        Self::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(SetVelocityAction(
+            params.require(0)?.round().clamp(1.0, 127.0) as u8,
+        ))
+    }
+
+    fn name() -> &'static str {
+        "SetVelocityAction"
+    }
+
+    fn arity() -> usize {
+        1
+    }
+}
+
+/// Starts playing the pitch of the voice until `NoteOffAction`, without advancing the voice.
+pub struct NoteOnAction;
+
+impl LSystemAction<SoundEvent> for NoteOnAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('{')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<SoundEvent>) {
+        let voices = context.state::<Voices>();
+        let voice = voices.voice;
+        voices.held.push(voice.pitch);
+        context.elements.push(SoundEvent::NoteOn {
+            time: voice.time,
+            pitch: voice.pitch,
+            velocity: voice.velocity,
+        });
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        Self::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, _params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(NoteOnAction)
+    }
+
+    fn name() -> &'static str {
+        "NoteOnAction"
+    }
+}
+
+/// Stops the note started last by `NoteOnAction`, nothing if no note is held.
+pub struct NoteOffAction;
+
+impl LSystemAction<SoundEvent> for NoteOffAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('}')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<SoundEvent>) {
+        let voices = context.state::<Voices>();
+        let time = voices.voice.time;
+        if let Some(pitch) = voices.held.pop() {
+            context.elements.push(SoundEvent::NoteOff { time, pitch });
+        }
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        Self::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, _params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(NoteOffAction)
+    }
+
+    fn name() -> &'static str {
+        "NoteOffAction"
+    }
+}
+
+/// Saves the voice, the following notes play at the same time as those after the matching `PopVoiceAction`.
+pub struct PushVoiceAction;
+
+impl LSystemAction<SoundEvent> for PushVoiceAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('[')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<SoundEvent>) {
+        let voices = context.state::<Voices>();
+        voices.stack.push(voices.voice);
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        Self::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, _params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(PushVoiceAction)
+    }
+
+    fn name() -> &'static str {
+        "PushVoiceAction"
+    }
+}
+
+/// Returns to the voice saved last, the run fails with `RuntimeError::UnbalancedBrackets` if no voice was saved.
+pub struct PopVoiceAction;
+
+impl LSystemAction<SoundEvent> for PopVoiceAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant(']')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<SoundEvent>) {
+        let voices = context.state::<Voices>();
+        match voices.stack.pop() {
+            Some(voice) => voices.voice = voice,
+            None => {
+                let index = context.index;
+                context.fail(RuntimeError::UnbalancedBrackets { index });
+            }
+        }
+    }
+
+    fn from_params(symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        Self::try_from_params(symbol, params).ok()
+    }
+
+    fn try_from_params(_symbol: Symbol, _params: &ParamsResolver) -> Result<Self, ParamError> {
+        Ok(PopVoiceAction)
+    }
+
+    fn name() -> &'static str {
+        "PopVoiceAction"
+    }
+}
@@ -0,0 +1,61 @@
+//! Statistics of the symbols of an alphabet, useful for tuning lsystems and pre-allocating buffers.
+use alloc::collections::BTreeMap;
+
+use crate::{prelude::*, Alphabet};
+
+/// The result of `Alphabet::stats`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AlphabetStats {
+    /// Number of occurrences of every symbol char.
+    pub counts: BTreeMap<char, usize>,
+    /// Number of symbols per branch depth, `depths[0]` counts the symbols of the main branch.
+    /// Brackets are counted at the depth of the branch containing them.
+    pub depths: Vec<usize>,
+    /// The deepest nesting of branches, `0` if the alphabet has no branches.
+    pub max_nesting: usize,
+}
+
+impl AlphabetStats {
+    /// Returns the number of occurrences of `symbol`.
+    pub fn count(&self, symbol: char) -> usize {
+        self.counts.get(&symbol).copied().unwrap_or(0)
+    }
+
+    /// Returns the total number of symbols.
+    pub fn len(&self) -> usize {
+        self.depths.iter().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Alphabet {
+    /// Counts the symbols of the alphabet per char and per branch depth.
+    pub fn stats(&self) -> AlphabetStats {
+        let mut stats = AlphabetStats::default();
+        let mut depth = 0usize;
+
+        for symbol in &self.symbols {
+            let char = symbol.char();
+            *stats.counts.entry(char).or_default() += 1;
+
+            if char == ']' {
+                depth = depth.saturating_sub(1);
+            }
+
+            if stats.depths.len() <= depth {
+                stats.depths.resize(depth + 1, 0);
+            }
+            stats.depths[depth] += 1;
+
+            if char == '[' {
+                depth += 1;
+                stats.max_nesting = stats.max_nesting.max(depth);
+            }
+        }
+
+        stats
+    }
+}
@@ -0,0 +1,187 @@
+//! Running an lsystem one symbol at a time to inspect the actions and the turtle, see `ExecutionStepper`.
+use crate::{
+    action::ActionCache, prelude::*, seeded_rng, timing::Stopwatch, Action, ActionResolver,
+    Alphabet, DefaultAlphabetSymbolDefiner, ExecuteContext, GeometryElement, LSystem, RunOptions,
+    RuntimeError, Symbol, SymbolDefiner, Turtle,
+};
+
+/// The execution of one symbol, see `ExecutionStepper::step`.
+#[derive(Clone, Debug)]
+pub struct ExecutionStep {
+    /// Index of the symbol in the alphabet.
+    pub index: usize,
+    pub symbol: Symbol,
+    /// The actions the symbol is interpreted as with their evaluated parameters, random parameters sampled,
+    /// in the order they were executed. Empty if the symbol has no actions.
+    pub actions: Vec<Action>,
+    /// The turtle before the actions were executed.
+    pub before: Turtle,
+    /// The turtle after the actions were executed.
+    pub after: Turtle,
+    /// The branch depth of the symbol, see `ExecuteContext::depth`: a `[` is in the branch it opens
+    /// and a `]` in the branch it returns to, thus a `]` reports the depth of the parent branch.
+    pub depth: usize,
+}
+
+/// Where `ExecutionStepper::continue_to_breakpoint` stops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stops after every symbol of this char, e.g. `'F'`.
+    Symbol(char),
+    /// Stops after every symbol at least this deep in branches.
+    Depth(usize),
+}
+
+impl Breakpoint {
+    /// Returns whether the breakpoint stops after `step`.
+    pub fn matches(&self, step: &ExecutionStep) -> bool {
+        match *self {
+            Breakpoint::Symbol(char) => step.symbol.char() == char,
+            Breakpoint::Depth(depth) => step.depth >= depth,
+        }
+    }
+}
+
+/// Runs an lsystem one symbol at a time, exposing the actions of every symbol and the turtle before and after them,
+/// e.g. for the "step" button of an editor.
+///
+/// The stepper runs like `LSystem::run_with_options`, thus stepping through all symbols draws the same geometry.
+/// Parameter lists and the branches skipped by the cut symbol are run through without stopping at them.
+pub struct ExecutionStepper<
+    'a,
+    A: SymbolDefiner = DefaultAlphabetSymbolDefiner,
+    E = GeometryElement,
+> {
+    lsystem: &'a LSystem<A>,
+    action_resolver: &'a ActionResolver<E>,
+    alphabet: &'a Alphabet,
+    /// The chars of the alphabet and the actions resolved so far, kept for all steps as by a single run.
+    chars: Vec<char>,
+    cache: ActionCache<E>,
+    context: ExecuteContext<E>,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl<'a, A: SymbolDefiner, E> ExecutionStepper<'a, A, E> {
+    /// Prepares running `alphabet` with the actions of `action_resolver`, the turtle starts with the pose and pen from `options`.
+    pub fn new(
+        lsystem: &'a LSystem<A>,
+        action_resolver: &'a ActionResolver<E>,
+        alphabet: &'a Alphabet,
+        options: &RunOptions,
+    ) -> Self {
+        let mut context =
+            ExecuteContext::with_options(options).with_rng(seeded_rng(lsystem.seed()));
+        lsystem.prepare_context(alphabet, &mut context);
+        context.record();
+
+        Self {
+            lsystem,
+            action_resolver,
+            alphabet,
+            chars: alphabet.iter().map(Symbol::char).collect(),
+            cache: ActionCache::new(),
+            context,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    pub fn remove_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.retain(|known| *known != breakpoint);
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Returns the context of the run, holding the turtle and the geometry drawn so far.
+    pub fn context(&self) -> &ExecuteContext<E> {
+        &self.context
+    }
+
+    /// Returns the index of the symbol the next step continues with.
+    pub fn position(&self) -> usize {
+        self.context.position
+    }
+
+    /// Returns whether every symbol has been run.
+    pub fn is_finished(&self) -> bool {
+        self.context.position >= self.alphabet.symbols.len()
+    }
+
+    /// Runs the next symbol, returns `None` once every symbol has been run.
+    pub fn step(&mut self) -> Result<Option<ExecutionStep>, RuntimeError> {
+        while !self.is_finished()
+            && self
+                .context
+                .skipped_until
+                .is_some_and(|end| self.context.position <= end)
+        {
+            self.execute_symbol()?;
+        }
+        if self.is_finished() {
+            return Ok(None);
+        }
+
+        let index = self.context.position;
+        let before = self.context.turtle;
+        self.context.executed_actions = Some(Vec::new());
+        let result = self.execute_symbol();
+        let actions = self.context.executed_actions.take().unwrap_or_default();
+        result?;
+
+        Ok(Some(ExecutionStep {
+            index,
+            symbol: self.alphabet.symbols[index].clone(),
+            actions,
+            before,
+            after: self.context.turtle,
+            depth: self.context.depth(),
+        }))
+    }
+
+    /// Runs symbols until one of the breakpoints matches, returns the step of that symbol,
+    /// `None` if the run finished without hitting a breakpoint.
+    pub fn continue_to_breakpoint(&mut self) -> Result<Option<ExecutionStep>, RuntimeError> {
+        while let Some(step) = self.step()? {
+            if self
+                .breakpoints
+                .iter()
+                .any(|breakpoint| breakpoint.matches(&step))
+            {
+                return Ok(Some(step));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Runs the remaining symbols without stopping and returns the context, see `LSystem::run_with_context`.
+    pub fn finish(mut self) -> Result<ExecuteContext<E>, RuntimeError> {
+        self.lsystem.run_until(
+            self.action_resolver,
+            self.alphabet,
+            &mut self.context,
+            self.alphabet.symbols.len(),
+        )?;
+        Ok(self.context)
+    }
+
+    /// Runs the symbol at the position of the context without recollecting the chars or resolving cached actions again.
+    fn execute_symbol(&mut self) -> Result<(), RuntimeError> {
+        let stopwatch = Stopwatch::start();
+        let result = self.lsystem.execute_symbol(
+            self.action_resolver,
+            self.alphabet,
+            &self.chars,
+            &mut self.cache,
+            &mut self.context,
+            &mut ExecuteContext::record,
+        );
+        self.context.stats.run += stopwatch.elapsed();
+        result
+    }
+}
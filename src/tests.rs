@@ -1,3 +1,62 @@
+use crate::{presets::action, LSystem, SymbolDefiner};
+
+mod builder;
+#[cfg(feature = "std")]
+mod bundle;
+mod bvh;
+mod checkpoint;
+mod collision;
+mod color_map;
+mod commands;
+mod compile;
+mod context;
+mod debugger;
+#[cfg(feature = "derive")]
+mod derive;
+mod diff;
+#[cfg(feature = "disk-cache")]
+mod disk_cache;
+mod environment;
+#[cfg(feature = "std")]
+mod export;
+mod fractint;
+mod genetics;
+mod geometry;
+mod gpu;
 mod lexer;
+mod lod;
 mod lsystem;
+mod lsystem_cacher;
+#[cfg(feature = "macros")]
+mod macros;
 mod parser;
+mod planar;
+mod presets;
+mod projection;
+#[cfg(feature = "std")]
+mod render;
+mod repl;
+#[cfg(feature = "watch")]
+mod script_host;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "sound")]
+mod sound;
+mod stepper;
+mod timed;
+mod turtles;
+
+/// Creates an lsystem of `axiom` whose `F` draws forward by `length`, `+` turns by `angle` and `[ ]` branch.
+fn drawing_lsystem<A: SymbolDefiner>(
+    axiom: &str,
+    definer: A,
+    length: f32,
+    angle: f32,
+) -> LSystem<A> {
+    let mut lsystem = LSystem::new(axiom, definer);
+    lsystem.add_action_rule("F", action("DrawForwardAction", &[length]));
+    lsystem.add_action_rule("+", action("RotateZAction", &[angle]));
+    lsystem.add_action_rule("[", action("PushTranformToStackAction", &[]));
+    lsystem.add_action_rule("]", action("PopTransformFromStackAction", &[]));
+    lsystem
+}
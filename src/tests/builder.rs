@@ -0,0 +1,119 @@
+use crate::{
+    Action, ActionParam, ActionResolver, GrammarWarning, InferredSymbolDefiner, LSystemBuilder,
+    RuleKind, ValidationError,
+};
+
+#[test]
+fn build_lsystem_with_actions() {
+    let lsystem = LSystemBuilder::new()
+        .name("Koch")
+        .axiom(String::from("F"))
+        .rule("F", "F+F-F-F+F")
+        .interpret(
+            "F",
+            Action::new("DrawForwardAction".into(), vec![ActionParam::Number(1.0)]),
+        )
+        .interpret(
+            "+",
+            Action::new(
+                "RotateZAction".into(),
+                vec![ActionParam::Constant("angle".into())],
+            ),
+        )
+        .variable("angle", 90.0)
+        .seed(3)
+        .build()
+        .unwrap();
+
+    assert_eq!(lsystem.name, "Koch");
+    assert_eq!(lsystem.seed(), 3);
+    assert_eq!(lsystem.generate(1).to_string(), "F+F-F-F+F");
+
+    let resolver: ActionResolver = ActionResolver::with_default_actions();
+    let context = lsystem.run(&resolver, &lsystem.generate(1)).unwrap();
+    assert_eq!(context.geometry().segments.len(), 5);
+}
+
+#[test]
+fn build_stochastic_and_parametric_rules() {
+    let lsystem = LSystemBuilder::with_definer(InferredSymbolDefiner::new(['A', 'B']))
+        .axiom("A")
+        .stochastic_rule("A", 0.5, "AB")
+        .stochastic_rule("A", 0.5, "BA")
+        .parametric_rule("B", |_, _| Some("C".to_string()))
+        .build()
+        .unwrap();
+
+    let alphabet = lsystem.generate(1).to_string();
+    assert!(alphabet == "AB" || alphabet == "BA", "{alphabet}");
+}
+
+#[test]
+fn build_rejects_invalid_lsystems() {
+    assert_eq!(
+        LSystemBuilder::new().rule("F", "FF").build().err(),
+        Some(ValidationError::EmptyAxiom)
+    );
+
+    assert_eq!(
+        LSystemBuilder::new()
+            .axiom("A")
+            .stochastic_rule("A", 1.5, "B")
+            .build()
+            .err(),
+        Some(ValidationError::InvalidProbability {
+            predecessor: "A".to_string(),
+            probability: 1.5,
+        })
+    );
+
+    assert_eq!(
+        LSystemBuilder::new()
+            .axiom("A")
+            .stochastic_rule("A", 0.75, "B")
+            .stochastic_rule("A", 0.5, "AB")
+            .build()
+            .err(),
+        Some(ValidationError::ProbabilitiesExceedOne {
+            predecessor: "A".to_string(),
+            total: 1.25,
+        })
+    );
+
+    let error = LSystemBuilder::new()
+        .axiom("F")
+        .rule("F", "F[+F")
+        .rule("F", "FF")
+        .rule("FF", "F")
+        .build()
+        .err();
+    assert_eq!(
+        error,
+        Some(ValidationError::Grammar(vec![
+            GrammarWarning::DuplicateRule {
+                predecessor: "F".to_string(),
+                rule: RuleKind::Generic,
+            },
+            GrammarWarning::UnreachableRule {
+                predecessor: "FF".to_string(),
+            },
+        ]))
+    );
+}
+
+#[test]
+fn build_allows_warnings_that_are_no_errors() {
+    let lsystem = LSystemBuilder::new()
+        .axiom("F")
+        .rule("F", "F+F")
+        .rule("X", "F")
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        lsystem.validate(),
+        vec![GrammarWarning::UnusedRule {
+            predecessor: "X".to_string()
+        }]
+    );
+}
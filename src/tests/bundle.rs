@@ -0,0 +1,103 @@
+use std::fs;
+
+use macaw::Vec3;
+
+use crate::{export::raster::Raster, Color, Geometry, Polygon, Segment, SpeciesBundle};
+
+fn bundle_dir(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("species-bundle-{name}-{}", std::process::id()))
+}
+
+fn leaf() -> Geometry {
+    let mut geometry = Geometry::new();
+    geometry.segments = vec![
+        Segment::new(Vec3::ZERO, Vec3::Y),
+        Segment::new(Vec3::Y, Vec3::new(0.5, 2.0, 0.0)),
+    ];
+    geometry.polygons = vec![Polygon::new(vec![Vec3::ZERO, Vec3::X, Vec3::Y])];
+    geometry
+}
+
+#[test]
+fn color_from_hex() {
+    assert_eq!(Color::from_hex("#ff0000"), Some(Color::rgb(1.0, 0.0, 0.0)));
+    assert_eq!(
+        Color::from_hex("#00000000"),
+        Some(Color::new(0.0, 0.0, 0.0, 0.0))
+    );
+    assert_eq!(Color::from_hex("ff0000"), None);
+    assert_eq!(Color::from_hex("#ff00"), None);
+    assert_eq!(Color::from_hex("#gg0000"), None);
+}
+
+#[test]
+fn bundle_round_trip() {
+    let mut bundle = SpeciesBundle::new("lsystem Plant {\n    axiom F;\n    replace F by FF;\n}\n");
+    bundle.palette = vec![Color::BLACK, Color::rgb(1.0, 0.0, 0.0)];
+    bundle.leaves.insert("leaf".to_string(), leaf());
+    bundle.settings.generations = 6;
+    bundle.settings.formats = vec!["svg".to_string(), "obj".to_string()];
+    bundle.settings.preview.width = 4;
+    bundle.settings.preview.line_width = 1.5;
+    bundle.preview = Some(Raster::new(4, 2, Color::rgb(0.0, 1.0, 0.0)));
+
+    let dir = bundle_dir("round-trip");
+    bundle.save(&dir).unwrap();
+    let loaded = SpeciesBundle::load(&dir);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(loaded.unwrap(), bundle);
+}
+
+#[test]
+fn bundle_optional_files() {
+    let dir = bundle_dir("optional");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("script.ls"), "lsystem Empty {}").unwrap();
+
+    let loaded = SpeciesBundle::load(&dir);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(loaded.unwrap(), SpeciesBundle::new("lsystem Empty {}"));
+}
+
+#[test]
+fn bundle_invalid_settings() {
+    let dir = bundle_dir("invalid");
+    SpeciesBundle::new("").save(&dir).unwrap();
+    fs::write(dir.join("settings.txt"), "generations = many").unwrap();
+
+    let loaded = SpeciesBundle::load(&dir);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(loaded.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn bundle_rejects_leaf_paths() {
+    let dir = bundle_dir("leaf-paths");
+    for name in ["../escaped", "nested/leaf", "..", ""] {
+        let mut bundle = SpeciesBundle::new("");
+        bundle.leaves.insert(name.to_string(), leaf());
+
+        let error = bundle.save(&dir).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+    assert!(!dir.exists());
+}
+
+#[test]
+fn bundle_preview_size_overflow() {
+    let dir = bundle_dir("preview-overflow");
+    SpeciesBundle::new("").save(&dir).unwrap();
+    fs::write(
+        dir.join("preview.pam"),
+        "P7\nWIDTH 4294967295\nHEIGHT 4294967295\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+    )
+    .unwrap();
+
+    let loaded = SpeciesBundle::load(&dir);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(loaded.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
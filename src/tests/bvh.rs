@@ -0,0 +1,80 @@
+use macaw::Vec3;
+
+use crate::{ActionResolver, Bvh, DefaultAlphabetSymbolDefiner, Geometry, Segment};
+
+use super::drawing_lsystem;
+
+/// A row of 20 unit lines along y, one at every x from 0 to 19.
+fn fence() -> Geometry {
+    Geometry {
+        segments: (0..20)
+            .map(|x| {
+                let x = x as f32;
+                Segment::new(Vec3::new(x, 0.0, 0.0), Vec3::new(x, 1.0, 0.0))
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn raycast_segments() {
+    let bvh = fence().build_bvh().with_radius(0.1);
+    assert_eq!(bvh.len(), 20);
+
+    let hit = bvh
+        .raycast(Vec3::new(5.05, 0.5, 10.0), -Vec3::Z)
+        .unwrap();
+    assert_eq!(hit.segment, 5);
+    assert!((hit.distance - 10.0).abs() < 1e-5);
+    assert!(hit.point.abs_diff_eq(Vec3::new(5.05, 0.5, 0.0), 1e-5));
+
+    // Along the row the closest line is hit, lines behind the origin are not.
+    let hit = bvh.raycast(Vec3::new(7.5, 0.5, 0.0), Vec3::X).unwrap();
+    assert_eq!((hit.segment, hit.point), (8, Vec3::new(8.0, 0.5, 0.0)));
+    assert_eq!(
+        bvh.raycast(Vec3::new(20.5, 0.5, 0.0), Vec3::X)
+            .map(|hit| hit.segment),
+        None
+    );
+
+    // Between the lines and above them the ray misses.
+    assert_eq!(bvh.raycast(Vec3::new(5.5, 0.5, 10.0), -Vec3::Z), None);
+    assert_eq!(bvh.raycast(Vec3::new(5.0, 1.5, 10.0), -Vec3::Z), None);
+    assert_eq!(Bvh::new(vec![]).raycast(Vec3::ZERO, Vec3::X), None);
+}
+
+#[test]
+fn segments_within_distance() {
+    let bvh = fence().build_bvh();
+
+    assert_eq!(bvh.segments_within(Vec3::new(4.5, 0.5, 0.0), 1.0), [4, 5]);
+    assert_eq!(
+        bvh.segments_within(Vec3::new(4.5, 0.5, 0.0), 2.0),
+        [3, 4, 5, 6]
+    );
+    assert!(bvh.is_occupied(Vec3::new(19.0, 1.5, 0.0), 0.6));
+    assert!(!bvh.is_occupied(Vec3::new(10.5, 0.5, 1.0), 1.0));
+}
+
+#[test]
+fn pick_symbol_of_segment() {
+    let lsystem = drawing_lsystem("F[+F]F", DefaultAlphabetSymbolDefiner, 1.0, 0.5);
+    let alphabet = lsystem.generate(0);
+    let context = lsystem
+        .run(&ActionResolver::with_default_actions(), &alphabet)
+        .unwrap();
+
+    // The segments are drawn by the first, the bracketed and the last `F`.
+    let symbols = context.segment_symbols();
+    assert_eq!(symbols, [0, 3, 5]);
+
+    // Clicking the side branch picks the `F` inside the brackets.
+    let geometry = context.geometry();
+    let branch = &geometry.segments[1];
+    let hit = geometry
+        .build_bvh()
+        .raycast((branch.a + branch.b) * 0.5 + Vec3::Z, -Vec3::Z)
+        .unwrap();
+    assert_eq!(symbols[hit.segment], 3);
+}
@@ -0,0 +1,88 @@
+use crate::{ActionResolver, ExecuteContext, InferredSymbolDefiner, LSystem};
+
+use super::{action, drawing_lsystem};
+
+fn plant() -> LSystem<InferredSymbolDefiner> {
+    let mut lsystem = drawing_lsystem("X", InferredSymbolDefiner::new(['F', 'X']), 1.0, 0.4);
+    lsystem.add_rule("X", "F(2)[+X]F[-X%F]X");
+    lsystem.add_action_rule("-", action("RotateZAction", &[-0.6]));
+    lsystem
+}
+
+#[test]
+fn checkpoint_resumes_run() {
+    let lsystem = plant();
+    let alphabet = lsystem.generate(3);
+    let resolver = ActionResolver::with_default_actions();
+    let run = lsystem.run(&resolver, &alphabet).unwrap();
+    let expected = run.geometry();
+
+    // Stop inside a cut branch and inside the parameter list of a module.
+    let cut = alphabet
+        .iter()
+        .position(|symbol| symbol.char() == '%')
+        .unwrap();
+    let params = alphabet
+        .iter()
+        .position(|symbol| symbol.char() == '(')
+        .unwrap();
+    for end in [cut + 1, params + 1, alphabet.symbols.len() / 2] {
+        let mut context = ExecuteContext::new();
+        lsystem
+            .run_until(&resolver, &alphabet, &mut context, end)
+            .unwrap();
+        let state = context.save_state();
+        assert_eq!(state.position(), end);
+
+        let context = lsystem
+            .run_with_context(&resolver, &alphabet, context)
+            .unwrap();
+        assert_eq!(context.geometry(), expected);
+
+        // Rewinding removes the drawing after the checkpoint, running again draws it again.
+        let mut context = context;
+        context.restore_state(&state);
+        assert!(context.geometry().segments.len() < expected.segments.len());
+        let context = lsystem
+            .run_with_context(&resolver, &alphabet, context)
+            .unwrap();
+        assert_eq!(context.geometry(), expected);
+        // The symbols run after the checkpoint are not counted twice.
+        assert_eq!(context.stats.symbols, run.stats.symbols);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn checkpoint_serde() {
+    use crate::ExecutionState;
+
+    let lsystem = plant();
+    let alphabet = lsystem.generate(2);
+    let resolver = ActionResolver::with_default_actions();
+
+    let mut context = ExecuteContext::new();
+    lsystem
+        .run_until(&resolver, &alphabet, &mut context, 7)
+        .unwrap();
+    let state = context.save_state();
+    let json = serde_json::to_string(&state).unwrap();
+    let restored = serde_json::from_str::<ExecutionState>(&json).unwrap();
+    assert_eq!(restored.position(), 7);
+    assert_eq!(restored.stack_depth(), state.stack_depth());
+    assert_eq!(restored.turtle().origin(), state.turtle().origin());
+
+    let expected = lsystem
+        .run_with_context(&resolver, &alphabet, context)
+        .unwrap()
+        .geometry();
+    let mut context = ExecuteContext::new();
+    lsystem
+        .run_until(&resolver, &alphabet, &mut context, 7)
+        .unwrap();
+    context.restore_state(&restored);
+    let context = lsystem
+        .run_with_context(&resolver, &alphabet, context)
+        .unwrap();
+    assert_eq!(context.geometry(), expected);
+}
@@ -0,0 +1,116 @@
+use macaw::Vec3;
+
+use crate::{
+    ActionResolver, Collision, CollisionResponse, DefaultAlphabetSymbolDefiner, ExecuteContext,
+    Geometry, RunOptions, Segment,
+};
+
+use super::{action, drawing_lsystem};
+
+#[test]
+fn segment_distance() {
+    let segment = Segment::new(Vec3::ZERO, Vec3::X * 2.0);
+    let crossing = Segment::new(Vec3::new(1.0, -1.0, 0.0), Vec3::new(1.0, 1.0, 0.0));
+    let above = Segment::new(Vec3::new(1.0, 1.0, 1.0), Vec3::new(1.0, -1.0, 1.0));
+    let parallel = Segment::new(Vec3::new(3.0, 1.0, 0.0), Vec3::new(5.0, 1.0, 0.0));
+
+    assert_eq!(segment.distance_to_segment(&crossing), 0.0);
+    assert_eq!(segment.distance_to_segment(&above), 1.0);
+    assert!((segment.distance_to_segment(&parallel) - 2f32.sqrt()).abs() < 1e-6);
+    assert_eq!(
+        parallel.distance_to_segment(&segment),
+        segment.distance_to_segment(&parallel)
+    );
+}
+
+#[test]
+fn find_self_intersections() {
+    let geometry = Geometry {
+        segments: vec![
+            Segment::new(Vec3::ZERO, Vec3::new(2.0, 0.0, 0.0)),
+            Segment::new(Vec3::new(1.0, -1.0, 0.0), Vec3::new(1.0, 1.0, 0.0)),
+            // Connected to the first segment.
+            Segment::new(Vec3::new(2.0, 0.0, 0.0), Vec3::new(2.0, 2.0, 0.0)),
+            Segment::new(Vec3::new(0.0, 3.0, 0.0), Vec3::new(2.0, 3.0, 0.0)),
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(geometry.self_intersections(0.0), [(0, 1)]);
+    assert_eq!(geometry.self_intersections(1.0), [(0, 1), (1, 2), (2, 3)]);
+    assert!(Geometry::new().self_intersections(1.0).is_empty());
+}
+
+#[test]
+fn find_self_intersections_of_long_segments() {
+    // The short segments make the cells small, the diagonal is walked through the cells along it.
+    let mut segments = (0..100)
+        .map(|index| {
+            let start = Vec3::new(index as f32 * 0.01, 0.0, 5.0);
+            Segment::new(start, start + Vec3::new(0.005, 0.0, 0.0))
+        })
+        .collect::<Vec<_>>();
+    segments.push(Segment::new(
+        Vec3::new(-100.0, -100.0, 0.0),
+        Vec3::new(100.0, 100.0, 0.0),
+    ));
+    segments.push(Segment::new(
+        Vec3::new(-50.0, 50.0, 0.0),
+        Vec3::new(50.0, -50.0, 0.0),
+    ));
+    let geometry = Geometry {
+        segments,
+        ..Default::default()
+    };
+
+    assert_eq!(geometry.self_intersections(0.0), [(100, 101)]);
+    assert_eq!(geometry.self_intersections(0.004), [(100, 101)]);
+}
+
+/// Runs a trunk with a branch turning back into it, followed by the rest of the trunk.
+fn run_hook(collision: Option<Collision>) -> ExecuteContext {
+    let mut lsystem = drawing_lsystem("FF[+G+G+GG]F", DefaultAlphabetSymbolDefiner, 1.0, 1.5707964);
+    lsystem.add_action_rule("G", action("DrawForwardAction", &[0.75]));
+
+    let options = RunOptions {
+        collision,
+        ..Default::default()
+    };
+    lsystem
+        .run_with_options(
+            &ActionResolver::with_default_actions(),
+            &lsystem.generate(0),
+            &options,
+        )
+        .unwrap()
+}
+
+#[test]
+fn collisions_terminate_branches() {
+    let context = run_hook(None);
+    assert_eq!(context.turtle_geometry().segments.len(), 7);
+    assert!(!context.turtle_geometry().self_intersections(0.0).is_empty());
+    assert_eq!(context.stats.collisions, 0);
+
+    let context = run_hook(Some(Collision::new(0.1, CollisionResponse::Terminate)));
+    let geometry = context.turtle_geometry();
+    // The third line of the branch hits the trunk, the trunk above the branch is still drawn.
+    assert_eq!(geometry.segments.len(), 5);
+    assert!(geometry.self_intersections(0.0).is_empty());
+    assert_eq!(geometry.segments[4].b, Vec3::new(0.0, 2.5, 0.0));
+    assert_eq!(context.stats.collisions, 1);
+}
+
+#[test]
+fn collisions_reject_branches() {
+    let context = run_hook(Some(Collision::new(0.1, CollisionResponse::Reject)));
+    let geometry = context.turtle_geometry();
+
+    assert_eq!(geometry.segments.len(), 3);
+    assert!(geometry
+        .segments
+        .iter()
+        .all(|segment| segment.a.x == 0.0 && segment.b.x == 0.0));
+    assert_eq!(context.bounds(), geometry.bounds());
+    assert_eq!(context.stats.collisions, 1);
+}
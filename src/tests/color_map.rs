@@ -0,0 +1,105 @@
+use macaw::Vec3;
+
+use crate::{
+    ActionResolver, Color, ColorKey, ColorMap, DefaultAlphabetSymbolDefiner, Geometry, Polygon,
+    RunOptions, Segment,
+};
+
+use super::drawing_lsystem;
+
+const BROWN: Color = Color::rgb(0.4, 0.2, 0.0);
+const GREEN: Color = Color::rgb(0.0, 0.8, 0.0);
+
+#[test]
+fn color_map_interpolates_stops() {
+    let map = ColorMap::gradient(ColorKey::Depth, BROWN, GREEN);
+
+    assert_eq!(map.color_at(0.0), Some(BROWN));
+    assert_eq!(map.color_at(1.0), Some(GREEN));
+    assert_eq!(map.color_at(-1.0), Some(BROWN));
+    assert_eq!(map.color_at(2.0), Some(GREEN));
+    let middle = map.color_at(0.5).unwrap();
+    assert!((middle.r - 0.2).abs() < 1e-6 && (middle.g - 0.5).abs() < 1e-6);
+
+    // Stops are sorted by their position.
+    let map = ColorMap::new(ColorKey::Depth)
+        .with_stop(1.0, GREEN)
+        .with_stop(0.0, BROWN);
+    assert_eq!(map.stops, [(0.0, BROWN), (1.0, GREEN)]);
+    assert_eq!(ColorMap::new(ColorKey::Depth).color_at(0.5), None);
+}
+
+#[test]
+fn color_map_by_depth() {
+    let segment = |depth: usize| Segment {
+        depth,
+        ..Segment::new(Vec3::ZERO, Vec3::Y)
+    };
+    let mut geometry = Geometry {
+        segments: vec![segment(0), segment(1), segment(2)],
+        polygons: vec![Polygon {
+            depth: 2,
+            ..Polygon::new(vec![Vec3::ZERO, Vec3::X, Vec3::Y])
+        }],
+        ..Default::default()
+    };
+    geometry.apply_color_map(&ColorMap::gradient(ColorKey::Depth, BROWN, GREEN));
+
+    assert_eq!(geometry.segments[0].color, BROWN);
+    assert_eq!(geometry.segments[1].color, BROWN.lerp(GREEN, 0.5));
+    assert_eq!(geometry.segments[2].color, GREEN);
+    assert_eq!(geometry.polygons[0].color, GREEN);
+}
+
+#[test]
+fn color_map_by_path_length() {
+    // A trunk of two lines with a branch starting at its end, and a leaf at the end of the branch.
+    let mut geometry = Geometry {
+        segments: vec![
+            Segment::new(Vec3::ZERO, Vec3::Y),
+            Segment::new(Vec3::Y, Vec3::Y * 2.0),
+            Segment::new(Vec3::Y * 2.0, Vec3::new(2.0, 2.0, 0.0)),
+        ],
+        polygons: vec![Polygon::new(vec![
+            Vec3::new(2.0, 2.0, 0.0),
+            Vec3::new(3.0, 2.0, 0.0),
+            Vec3::new(3.0, 3.0, 0.0),
+        ])],
+        ..Default::default()
+    };
+    geometry.apply_color_map(&ColorMap::gradient(ColorKey::PathLength, BROWN, GREEN));
+
+    // The middles of the lines are at path lengths 0.5, 1.5 and 3 of 4.
+    assert_eq!(geometry.segments[0].color, BROWN.lerp(GREEN, 0.125));
+    assert_eq!(geometry.segments[1].color, BROWN.lerp(GREEN, 0.375));
+    assert_eq!(geometry.segments[2].color, BROWN.lerp(GREEN, 0.75));
+    assert_eq!(geometry.polygons[0].color, GREEN);
+}
+
+#[test]
+fn run_applies_color_map() {
+    let lsystem = drawing_lsystem("F[+F]F", DefaultAlphabetSymbolDefiner, 1.0, 0.5);
+
+    let options = RunOptions {
+        color_map: Some(ColorMap::gradient(ColorKey::Depth, BROWN, GREEN)),
+        ..Default::default()
+    };
+    let alphabet = lsystem.generate(0);
+    let context = lsystem
+        .run_with_options(&ActionResolver::with_default_actions(), &alphabet, &options)
+        .unwrap();
+    let colors = |segments: &[Segment]| {
+        segments
+            .iter()
+            .map(|segment| (segment.depth, segment.color))
+            .collect::<Vec<_>>()
+    };
+    let expected = [(0, BROWN), (1, GREEN), (0, BROWN)];
+    assert_eq!(colors(&context.geometry().segments), expected);
+    assert_eq!(colors(&context.turtle_geometry().segments), expected);
+
+    let planar = lsystem
+        .run_planar(&ActionResolver::with_default_actions(), &alphabet, &options)
+        .unwrap();
+    assert_eq!(colors(&planar.to_geometry().segments), expected);
+}
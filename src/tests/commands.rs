@@ -0,0 +1,67 @@
+use std::f32::consts::{FRAC_PI_4, FRAC_PI_6};
+
+use crate::{
+    format_commands, parse_commands, replay, ActionResolver, DefaultAlphabetSymbolDefiner,
+    DrawForwardAction, LSystem, RunOptions, TurtleCommand,
+};
+
+use super::action;
+
+#[test]
+fn export_and_replay_turtle_commands() {
+    let mut lsystem = LSystem::new("A+A[-A]A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule("A", action("DrawForwardAction", &[1.0]));
+    lsystem.add_action_rule("+", action("RotateZAction", &[FRAC_PI_4]));
+    lsystem.add_action_rule("-", action("RotateZAction", &[-FRAC_PI_6]));
+    lsystem.add_action_rule("[", action("PushTranformToStackAction", &[]));
+    lsystem.add_action_rule("]", action("PopTransformFromStackAction", &[]));
+
+    let resolver: ActionResolver = ActionResolver::builder()
+        .with_defaults()
+        .with::<DrawForwardAction>('A'.into())
+        .build();
+    let options = RunOptions {
+        record_commands: true,
+        ..Default::default()
+    };
+    let context = lsystem
+        .run_with_options(&resolver, &lsystem.generate(0), &options)
+        .unwrap();
+
+    // The commands use the conventional turtle symbols, not the symbols of the lsystem.
+    let text = format_commands(&context.commands);
+    assert_eq!(
+        text,
+        format!(
+            "F1 +{} F1 [ -{} F1 ] F1",
+            FRAC_PI_4.to_degrees(),
+            FRAC_PI_6.to_degrees()
+        )
+    );
+
+    // The arguments are written in full precision.
+    let commands = parse_commands(&text).unwrap();
+    assert_eq!(commands, context.commands);
+    assert_eq!(
+        commands[4],
+        TurtleCommand::new('-', vec![FRAC_PI_6.to_degrees()])
+    );
+
+    let replayed = replay(&commands, &RunOptions::default()).unwrap();
+    let expected = context.geometry().segments;
+    let segments = replayed.geometry().segments;
+    assert_eq!(segments.len(), expected.len());
+    for (segment, expected) in segments.iter().zip(&expected) {
+        assert!(segment.a.abs_diff_eq(expected.a, 1e-5));
+        assert!(segment.b.abs_diff_eq(expected.b, 1e-5));
+    }
+}
+
+#[test]
+fn invalid_turtle_commands() {
+    let error = parse_commands("F1 +x").unwrap_err();
+    assert_eq!(error.command, "+x");
+
+    let commands = parse_commands("F ] F").unwrap();
+    assert!(replay(&commands, &RunOptions::default()).is_err());
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+#[cfg(feature = "std")]
+use crate::CompileError;
+use crate::{source_hash, CompileCache, CompiledLSystem};
+
+const KOCH: &str = "lsystem KochCurve {
+    axiom F;
+    replace F by F+F-F-F+F;
+}";
+
+#[test]
+fn source_hash_ignores_trivia() {
+    let reformatted = "lsystem   KochCurve{axiom F;
+        replace F by F + F - F - F + F ;
+    }";
+
+    assert_eq!(source_hash(KOCH), source_hash(reformatted));
+}
+
+#[test]
+fn source_hash_keeps_separating_whitespace() {
+    assert_ne!(
+        source_hash("replace A B by B;"),
+        source_hash("replace AB by B;")
+    );
+    assert_ne!(source_hash(KOCH), source_hash(&KOCH.replace("F+F", "F-F")));
+}
+
+#[test]
+fn compile_cache_reuses_compiled_script() {
+    let mut cache = CompileCache::new();
+
+    let compiled = cache.get_or_compile(KOCH);
+    assert_eq!(compiled.lsystem.name, "KochCurve");
+    assert_eq!(compiled.lsystem.generate(1).to_string(), "F+F-F-F+F");
+
+    let whitespace_edit = cache.get_or_compile(&KOCH.replace(';', " ;"));
+    assert!(Arc::ptr_eq(&compiled, &whitespace_edit));
+    assert_eq!(cache.len(), 1);
+
+    cache.get_or_compile(&KOCH.replace("F+F", "F-F"));
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn compile_cache_compares_source_on_hash_collision() {
+    let mut cache = CompileCache::new();
+    let koch = cache.get_or_compile(KOCH);
+
+    // Files the Koch curve under the hash of `other`, as if their hashes collided.
+    let other = KOCH.replace("KochCurve", "Other");
+    let entry = cache.entries.remove(&source_hash(KOCH)).unwrap();
+    cache.entries.insert(source_hash(&other), entry);
+
+    assert!(!cache.contains(&other));
+    let compiled = cache.get_or_compile(&other);
+    assert_eq!(compiled.lsystem.name, "Other");
+    assert!(!Arc::ptr_eq(&koch, &compiled));
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn compile_cache_evicts_oldest() {
+    let mut cache = CompileCache::with_capacity(1);
+
+    cache.get_or_compile(KOCH);
+    let other = KOCH.replace("KochCurve", "Other");
+    cache.get_or_compile(&other);
+
+    assert_eq!(cache.len(), 1);
+    assert!(!cache.contains(KOCH));
+    assert!(cache.contains(&other));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn try_compile_reports_invalid_scripts() {
+    let compiled = CompiledLSystem::try_compile(KOCH).unwrap();
+    assert_eq!(compiled.lsystem.name, "KochCurve");
+
+    let error = CompiledLSystem::try_compile("lsystem KochCurve { axiom F; replace F by").err();
+    assert!(matches!(error, Some(CompileError::Parse(message)) if message.contains("replace")));
+
+    let nested = format!(
+        "lsystem Deep {{ axiom F; let x = {}1{}; }}",
+        "(".repeat(1_000),
+        ")".repeat(1_000)
+    );
+    let error = CompiledLSystem::try_compile(&nested).err();
+    assert!(
+        matches!(error, Some(CompileError::Parse(message)) if message.contains("maximum recursion depth"))
+    );
+}
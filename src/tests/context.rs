@@ -0,0 +1,66 @@
+use crate::{left_context, right_context, Alphabet, InferredSymbolDefiner, Symbol};
+
+fn chars(string: &str) -> Vec<char> {
+    string.chars().collect()
+}
+
+#[test]
+fn context_skips_branches() {
+    let symbols = chars("AB[CD]E[F]G");
+
+    // `E` follows `B` in the main branch.
+    assert_eq!(left_context(&symbols, 6, 2), ['A', 'B']);
+    assert_eq!(right_context(&symbols, 1, 2), ['E', 'G']);
+    // The branch `CD` continues from `B`.
+    assert_eq!(left_context(&symbols, 3, 2), ['A', 'B']);
+    assert_eq!(left_context(&symbols, 4, 2), ['B', 'C']);
+    // The right context ends with the branch.
+    assert_eq!(right_context(&symbols, 3, 2), ['D']);
+    assert!(right_context(&symbols, 4, 2).is_empty());
+}
+
+#[test]
+fn context_at_boundaries() {
+    let symbols = chars("ABC");
+
+    assert!(left_context(&symbols, 0, 3).is_empty());
+    assert!(right_context(&symbols, 2, 3).is_empty());
+    assert_eq!(left_context(&symbols, 2, 5), ['A', 'B']);
+    assert!(left_context(&[], 0, 1).is_empty());
+    assert!(right_context(&[], 0, 1).is_empty());
+
+    // Unbalanced brackets end the context.
+    assert!(right_context(&chars("A[B"), 0, 1).is_empty());
+    assert!(left_context(&chars("A]B"), 2, 1).is_empty());
+}
+
+#[test]
+fn context_skips_parameters() {
+    let symbols = chars("A(1,2)B(3)C");
+
+    assert_eq!(right_context(&symbols, 0, 2), ['B', 'C']);
+    assert_eq!(left_context(&symbols, 10, 2), ['A', 'B']);
+}
+
+#[test]
+fn alphabet_iter_with_context() {
+    let definer = InferredSymbolDefiner::new(['A', 'B', 'C']);
+    let alphabet = Alphabet::from_string("A[B]C".into(), 0, &definer);
+
+    let contexts = alphabet.iter_with_context(1, 1).collect::<Vec<_>>();
+    assert_eq!(contexts.len(), 5);
+
+    let a = &contexts[0];
+    assert!(a.left.is_empty());
+    assert_eq!(a.right, [&Symbol::Variable('C')]);
+
+    let b = &contexts[2];
+    assert_eq!(b.index, 2);
+    assert_eq!(b.symbol, &Symbol::Variable('B'));
+    assert_eq!(b.left, [&Symbol::Variable('A')]);
+    assert!(b.right.is_empty());
+
+    let c = &contexts[4];
+    assert_eq!(c.left, [&Symbol::Variable('A')]);
+    assert!(c.right.is_empty());
+}
@@ -0,0 +1,75 @@
+use crate::{DefaultAlphabetSymbolDefiner, LSystem, RuleKind};
+
+#[test]
+fn debugger_steps_context_sensitive_rule() {
+    let mut lsystem = LSystem::new("BA[F]C", DefaultAlphabetSymbolDefiner);
+    lsystem.add_context_sensitive_rule("A", |_symbol, index, chars| {
+        (chars[index - 1] == 'B').then_some("AA")
+    });
+    let mut debugger = lsystem.debugger();
+
+    let step = debugger.step().unwrap();
+    assert_eq!((step.predecessor.as_str(), step.rule), ("B", None));
+
+    // The branch is skipped by the right context.
+    let step = debugger.step().unwrap();
+    assert_eq!(step.index, 1);
+    assert_eq!(
+        (step.predecessor.as_str(), step.successor.as_str()),
+        ("A", "AA")
+    );
+    assert_eq!(step.rule, Some(RuleKind::ContextSensitive));
+    assert_eq!(
+        (step.left_context, step.right_context),
+        (vec!['B'], vec!['C'])
+    );
+    assert_eq!(debugger.string(), "BAA[F]C");
+    assert_eq!(debugger.position(), 2);
+
+    // The next generation starts after the last symbol.
+    assert_eq!(debugger.finish_generation().len(), 4);
+    assert_eq!(debugger.generation(), 1);
+    assert_eq!(
+        (debugger.current(), debugger.position()),
+        ("BAA[F]C".to_string(), 0)
+    );
+}
+
+#[test]
+fn debugger_derives_generations() {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_stochastic_rule("F", 0.4, "F[+F]F");
+    lsystem.add_stochastic_rule("F", 0.4, "F[-F]F");
+    lsystem.add_rule("F", "FF");
+    lsystem.set_seed(7);
+    let mut debugger = lsystem.debugger();
+
+    for generation in 1..=3 {
+        let steps = debugger.finish_generation();
+        assert!(steps.iter().all(|step| step.generation == generation - 1));
+        assert_eq!(debugger.current(), lsystem.generate(generation).to_string());
+    }
+
+    // Every rewritten `F` reports the stochastic rule that fired, `None` for the generic rule.
+    let step = debugger
+        .continue_until(|step| step.predecessor == "F" && step.stochastic.is_none())
+        .unwrap();
+    assert_eq!(
+        (step.rule, step.successor.as_str()),
+        (Some(RuleKind::Generic), "FF")
+    );
+    let step = debugger
+        .continue_until(|step| step.stochastic == Some(1))
+        .unwrap();
+    assert_eq!(step.successor, "F[-F]F");
+}
+
+#[test]
+fn debugger_stops_on_empty_generation() {
+    let lsystem = LSystem::new("", DefaultAlphabetSymbolDefiner);
+    let mut debugger = lsystem.debugger();
+
+    assert_eq!(debugger.step(), None);
+    assert!(debugger.finish_generation().is_empty());
+    assert_eq!(debugger.continue_until(|_| true), None);
+}
@@ -0,0 +1,85 @@
+use crate::{
+    Action, ActionParam, ActionResolver, ExecuteContext, LSystemAction, ParamError, ParamsResolver,
+    Symbol,
+};
+
+#[derive(LSystemAction, Debug, PartialEq)]
+#[action(name = "Length", trigger = 'F', execute = Self::push, element = f32)]
+struct Length {
+    length: f32,
+    #[action(default = 1.0)]
+    scale: f32,
+}
+
+impl Length {
+    fn push(&self, _symbol: &Symbol, context: &mut ExecuteContext<f32>) {
+        context.elements.push(self.length * self.scale);
+    }
+}
+
+#[derive(LSystemAction)]
+#[action(execute = Self::rotate)]
+struct Turn(#[action(trigger)] Symbol, f32);
+
+impl Turn {
+    fn rotate<E>(&self, _symbol: &Symbol, context: &mut ExecuteContext<E>) {
+        context.turtle.rotate_z(self.1);
+    }
+}
+
+fn params(numbers: &[f32]) -> ParamsResolver {
+    ParamsResolver {
+        params: numbers.iter().copied().map(ActionParam::Number).collect(),
+    }
+}
+
+#[test]
+fn derive_from_params() {
+    let symbol = Symbol::Variable('F');
+    assert_eq!(
+        Length::from_params(symbol.clone(), &params(&[2.0, 3.0])),
+        Some(Length {
+            length: 2.0,
+            scale: 3.0
+        })
+    );
+    assert_eq!(
+        Length::from_params(symbol.clone(), &params(&[2.0])),
+        Some(Length {
+            length: 2.0,
+            scale: 1.0
+        })
+    );
+    assert_eq!(Length::from_params(symbol.clone(), &params(&[])), None);
+    assert_eq!(
+        Length::try_from_params(symbol, &params(&[])),
+        Err(ParamError::missing(0))
+    );
+    assert_eq!(<Length as LSystemAction<f32>>::name(), "Length");
+    assert_eq!(<Length as LSystemAction<f32>>::arity(), 1);
+
+    let turn =
+        <Turn as LSystemAction>::from_params(Symbol::Constant('+'), &params(&[0.5])).unwrap();
+    assert_eq!(LSystemAction::<()>::trigger(&turn), Symbol::Constant('+'));
+    assert_eq!(turn.1, 0.5);
+    assert_eq!(<Turn as LSystemAction>::name(), "Turn");
+}
+
+#[test]
+fn derive_resolves() {
+    let resolver = ActionResolver::<f32>::builder()
+        .with::<Length>(Symbol::Variable('F'))
+        .with::<Turn>(Symbol::Constant('+'))
+        .build();
+
+    let action = Action::new(
+        "Length".into(),
+        vec![ActionParam::Number(2.0), ActionParam::Number(0.5)],
+    );
+    let length = resolver.resolve(&Symbol::Variable('F'), &action).unwrap();
+    assert_eq!(length.trigger(), Symbol::Constant('F'));
+
+    let mut context = ExecuteContext::<f32>::default();
+    length.execute(&Symbol::Variable('F'), &mut context);
+    assert_eq!(context.elements, [1.0]);
+}
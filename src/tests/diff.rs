@@ -0,0 +1,96 @@
+use crate::{Alphabet, AlphabetDiff, DefaultAlphabetSymbolDefiner, LSystem, Rng};
+
+fn alphabet(symbols: &str) -> Alphabet {
+    Alphabet::from_string(symbols.to_string(), 0, &DefaultAlphabetSymbolDefiner)
+}
+
+/// Applies `diff` to `old` by taking the symbols of `new` that were inserted.
+fn apply(old: &str, new: &str, diff: &AlphabetDiff) -> String {
+    let old = old.chars().collect::<Vec<_>>();
+    let new = new.chars().collect::<Vec<_>>();
+    let is_in =
+        |runs: &[std::ops::Range<usize>], index| runs.iter().any(|run| run.contains(&index));
+
+    let mut kept = (0..old.len()).filter(|index| !is_in(&diff.removed, *index));
+    let mut result = String::new();
+    for (index, symbol) in new.iter().enumerate() {
+        if is_in(&diff.inserted, index) {
+            result.push(*symbol);
+        } else {
+            let kept = kept.next().expect("More kept symbols in new than in old");
+            assert_eq!(old[kept], *symbol);
+            result.push(old[kept]);
+        }
+    }
+    assert_eq!(kept.next(), None);
+    result
+}
+
+fn longest_common_subsequence(old: &[char], new: &[char]) -> usize {
+    let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for (i, a) in old.iter().enumerate() {
+        for (j, b) in new.iter().enumerate() {
+            lengths[i + 1][j + 1] = if a == b {
+                lengths[i][j] + 1
+            } else {
+                lengths[i][j + 1].max(lengths[i + 1][j])
+            };
+        }
+    }
+    lengths[old.len()][new.len()]
+}
+
+#[test]
+fn diff_equal() {
+    assert!(alphabet("F+F").diff(&alphabet("F+F")).is_empty());
+    assert!(alphabet("").diff(&alphabet("")).is_empty());
+}
+
+#[test]
+fn diff_runs() {
+    let diff = alphabet("F+F").diff(&alphabet("F+FF-F"));
+    assert_eq!(diff.inserted, vec![(3..6)]);
+    assert!(diff.removed.is_empty());
+
+    let diff = alphabet("F[+F]F").diff(&alphabet("FF"));
+    assert_eq!(diff.removed, vec![(1..5)]);
+    assert!(diff.inserted.is_empty());
+
+    let diff = alphabet("AB").diff(&alphabet("BA"));
+    assert_eq!(diff.inserted_len() + diff.removed_len(), 2);
+}
+
+#[test]
+fn diff_generations() {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "F+F-F");
+
+    let diff = lsystem.generate(2).diff(&lsystem.generate(3));
+    // Every generation only adds symbols to the previous one.
+    assert!(diff.removed.is_empty());
+    assert_eq!(diff.inserted_len(), 53 - 17);
+}
+
+#[test]
+fn diff_is_minimal() {
+    let mut rng = crate::seeded_rng(7);
+    let mut random = |len: u32| {
+        (0..rng.next_u32() % len)
+            .map(|_| ['A', 'B', 'C'][(rng.next_u32() % 3) as usize])
+            .collect::<String>()
+    };
+
+    for _ in 0..500 {
+        let old = random(12);
+        let new = random(12);
+        let diff = alphabet(&old).diff(&alphabet(&new));
+
+        assert_eq!(apply(&old, &new, &diff), new, "{old} -> {new}");
+
+        let old = old.chars().collect::<Vec<_>>();
+        let new = new.chars().collect::<Vec<_>>();
+        let common = longest_common_subsequence(&old, &new);
+        assert_eq!(diff.removed_len(), old.len() - common);
+        assert_eq!(diff.inserted_len(), new.len() - common);
+    }
+}
@@ -0,0 +1,66 @@
+use std::fs;
+
+use crate::{
+    ActionResolver, Alphabet, CompiledLSystem, DefaultAlphabetSymbolDefiner, DiskCache,
+    DiskCacheKey, GenerateOptions,
+};
+
+const KOCH: &str = "lsystem KochCurve {
+    axiom F;
+    replace F by F+F-F-F+F;
+    interpret F as DrawForwardAction(1.0);
+}";
+
+#[test]
+fn disk_cache_stores_derivations_and_geometry() {
+    let directory = std::env::temp_dir().join(format!("lsystem-disk-cache-{}", std::process::id()));
+    let cache = DiskCache::new(&directory).unwrap();
+    cache.clear().unwrap();
+
+    let mut lsystem = CompiledLSystem::compile(KOCH).lsystem;
+    let resolver: ActionResolver = ActionResolver::with_default_actions();
+    let options = GenerateOptions::default();
+    let key = DiskCacheKey::new(&lsystem, 2).unwrap();
+    assert!(cache.load_alphabet(&key).is_none());
+
+    let geometry = cache.geometry(&lsystem, &resolver, 2, &options).unwrap();
+    assert_eq!(geometry.segments.len(), 25);
+    assert_eq!(cache.load_geometry(&key), Some(geometry));
+    let alphabet = lsystem.generate(2);
+    assert_eq!(cache.load_alphabet(&key), Some(alphabet));
+
+    // Cached results are loaded instead of generated.
+    let cached = Alphabet::from_string("F".to_string(), 2, &DefaultAlphabetSymbolDefiner);
+    cache.store_alphabet(&key, &cached).unwrap();
+    assert_eq!(cache.generate(&lsystem, 2, &options).unwrap(), cached);
+
+    // A changed lsystem has a key of its own, though it was compiled from the same script.
+    lsystem.set_variable("length", 2.0);
+    let changed = DiskCacheKey::new(&lsystem, 2).unwrap();
+    assert_ne!(changed, key);
+    assert!(cache.load_alphabet(&changed).is_none());
+
+    cache.remove(&key).unwrap();
+    assert!(cache.load_alphabet(&key).is_none());
+    assert!(cache.load_geometry(&key).is_none());
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn disk_cache_skips_lsystems_with_functions() {
+    let directory = std::env::temp_dir().join(format!(
+        "lsystem-disk-cache-functions-{}",
+        std::process::id()
+    ));
+    let cache = DiskCache::new(&directory).unwrap();
+
+    let mut lsystem = CompiledLSystem::compile(KOCH).lsystem;
+    lsystem.set_growth_function('F', |age, terminal_age| age / terminal_age);
+    assert_eq!(DiskCacheKey::new(&lsystem, 2), None);
+
+    let options = GenerateOptions::default();
+    let alphabet = cache.generate(&lsystem, 2, &options).unwrap();
+    assert_eq!(alphabet, lsystem.generate(2));
+    assert_eq!(fs::read_dir(&directory).unwrap().count(), 0);
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,99 @@
+use crate::{
+    Action, ActionResolver, Environment, ExecuteContext, InferredSymbolDefiner, LSystem,
+    LSystemAction, ParamsResolver, PositionEnvironment, Query, RunOptions, Symbol,
+};
+
+struct ForwardAction;
+
+impl LSystemAction for ForwardAction {
+    fn from_params(_symbol: Symbol, _params: &ParamsResolver) -> Option<Self> {
+        Some(ForwardAction)
+    }
+
+    fn name() -> &'static str {
+        "Forward"
+    }
+
+    fn trigger(&self) -> Symbol {
+        Symbol::Variable('F')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext) {
+        context.turtle.forward(1.0);
+    }
+}
+
+/// Environment with light above `y = 1`.
+struct Light;
+
+impl Environment for Light {
+    fn query(&mut self, query: &Query) -> Vec<f32> {
+        vec![if query.position.y > 1.0 { 1.0 } else { 0.0 }]
+    }
+}
+
+fn lsystem(axiom: &str) -> (LSystem<InferredSymbolDefiner>, ActionResolver) {
+    let mut lsystem = LSystem::new(axiom, InferredSymbolDefiner::new(['A', 'F', 'L', 'P']));
+    lsystem.add_action_rule("F", Action::new("Forward".into(), vec![]));
+
+    let resolver = ActionResolver::builder()
+        .with::<ForwardAction>(Symbol::Variable('F'))
+        .build();
+
+    (lsystem, resolver)
+}
+
+#[test]
+fn run_records_queries() {
+    let (lsystem, resolver) = lsystem("F?P(1,-2)F");
+    let alphabet = lsystem.generate(0);
+
+    let context = lsystem.run(&resolver, &alphabet).unwrap();
+
+    assert_eq!(context.queries.len(), 1);
+    let query = context.queries[0];
+    assert_eq!(query.symbol, 'P');
+    assert_eq!(query.index, 1);
+    assert_eq!(query.position, macaw::Vec3::new(0.0, 0.5, 0.0));
+    assert_eq!(query.heading, macaw::Vec3::Y);
+    // Parameter lists do not execute actions, thus they are not counted.
+    assert_eq!(context.stats.symbols, 4);
+}
+
+#[test]
+fn query_modules_are_filled_with_the_position() {
+    let (lsystem, resolver) = lsystem("F?P(1,1,1)F?P");
+
+    let alphabet = lsystem
+        .generate_with_environment(
+            0,
+            &resolver,
+            &RunOptions::default(),
+            &mut PositionEnvironment,
+        )
+        .unwrap();
+
+    assert_eq!(alphabet.to_string(), "F?P(0,0.5,0)F?P(0,1.5,0)");
+}
+
+#[test]
+fn environment_feeds_back_into_derivation() {
+    let (mut lsystem, resolver) = lsystem("AF?P");
+    lsystem.add_rule('A', "AF");
+    lsystem.add_parametic_production_rule('P', |_symbol, params| {
+        (params.get(0)? == 1.0).then(|| "L".to_string())
+    });
+
+    let options = RunOptions::default();
+    let generate = |generations| {
+        lsystem
+            .generate_with_environment(generations, &resolver, &options, &mut Light)
+            .unwrap()
+            .to_string()
+    };
+
+    assert_eq!(generate(0), "AF?P(0)");
+    assert_eq!(generate(1), "AFF?P(1)");
+    // The query of the previous generation saw light, thus `P` turned into `L`.
+    assert_eq!(generate(2), "AFFF?L(1)");
+}
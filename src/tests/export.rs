@@ -0,0 +1,479 @@
+use std::io::{self, Write};
+
+use macaw::Vec3;
+
+use crate::{
+    export::{
+        obj::ObjExporter,
+        raster::{Raster, RasterStyle},
+        svg::{SvgExporter, SvgLayers},
+        Exporter, ExporterRegistry,
+    },
+    ExecuteContext, Geometry, GeometryElement, Mesh, Polygon, Segment,
+};
+
+fn branched_context() -> ExecuteContext {
+    let mut context = ExecuteContext::new();
+    context.record();
+
+    context.turtle.forward(1.0);
+    context.record();
+
+    context.push(context.turtle);
+    context.record();
+    context.turtle.rotate_z(1.0);
+    context.turtle.forward(1.0);
+    context.record();
+    context.turtle = context.pop().unwrap();
+    context.record();
+
+    context.turtle.forward(1.0);
+    context.record();
+    context
+}
+
+#[test]
+fn svg_single_layer() {
+    let svg = SvgExporter::new().to_string(&branched_context());
+
+    assert_eq!(svg.matches("<g ").count(), 1);
+    assert_eq!(svg.matches("<line ").count(), 3);
+    assert!(svg.contains(r#"id="lsystem""#));
+}
+
+#[test]
+fn svg_layers_by_depth() {
+    let svg = SvgExporter::new()
+        .with_layers(SvgLayers::Depth)
+        .to_string(&branched_context());
+
+    assert_eq!(svg.matches("<g ").count(), 2);
+    assert!(svg.contains(r#"id="depth-0""#));
+    assert!(svg.contains(r#"id="depth-1""#));
+}
+
+#[test]
+fn svg_named_layers() {
+    let svg = SvgExporter::new()
+        .with_layers(SvgLayers::Named(Box::new(|depth| {
+            if depth == 0 { "trunk" } else { "twigs" }.to_string()
+        })))
+        .to_string(&branched_context());
+
+    assert!(svg.contains(r#"class="trunk""#));
+    assert!(svg.contains(r#"class="twigs""#));
+}
+
+#[test]
+fn svg_escapes_layer_names() {
+    let svg = SvgExporter::new()
+        .with_layers(SvgLayers::Named(Box::new(|_| r#"a<b & "c""#.to_string())))
+        .to_string(&branched_context());
+
+    assert!(svg.contains(r#"inkscape:label="a&lt;b &amp; &quot;c&quot;""#));
+}
+
+#[test]
+fn obj_polylines() {
+    let mut obj = Vec::new();
+    ObjExporter
+        .export_geometry(&branched_context().geometry(), &mut obj)
+        .unwrap();
+    let obj = String::from_utf8(obj).unwrap();
+
+    // Every segment is a polyline of its own, the trunk is interrupted by the branch.
+    assert_eq!(obj.matches("l ").count(), 3);
+    assert_eq!(obj.matches("v ").count(), 6);
+    assert!(obj.contains("l 1 2\nv"));
+    assert!(obj.ends_with("l 5 6\n"));
+}
+
+#[test]
+fn obj_meshes() {
+    let mut geometry = Geometry::new();
+    geometry.polygons = vec![Polygon::new(vec![Vec3::ZERO, Vec3::X, Vec3::Y])];
+    geometry.meshes = vec![Mesh::new(
+        vec![Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::ONE],
+        vec![0, 1, 2, 1, 3, 2],
+    )];
+
+    let mut obj = Vec::new();
+    ObjExporter.export_geometry(&geometry, &mut obj).unwrap();
+    let obj = String::from_utf8(obj).unwrap();
+
+    // The mesh indices continue after the vertices of the polygon.
+    assert_eq!(obj.matches("v ").count(), 7);
+    assert!(obj.ends_with("f 4 5 6\nf 5 7 6\n"));
+}
+
+#[test]
+fn obj_texture_coordinates() {
+    let mut geometry = Geometry::new();
+    geometry.meshes = vec![Mesh::tube(&[Segment::new(Vec3::ZERO, Vec3::Y)], 3, 0.1)];
+
+    let mut obj = Vec::new();
+    ObjExporter.export_geometry(&geometry, &mut obj).unwrap();
+    let obj = String::from_utf8(obj).unwrap();
+
+    // Two rings of 4 vertices, the faces refer to the vertex, texture coordinate and normal.
+    assert_eq!(obj.matches("vt ").count(), 8);
+    assert_eq!(obj.matches("vn ").count(), 8);
+    assert!(obj.contains("\nf 1/1/1 2/2/2 5/5/5\n"));
+}
+
+#[test]
+fn obj_instances() {
+    let mut context = ExecuteContext::new();
+    context.add_model(
+        "leaf",
+        Mesh::new(vec![Vec3::ZERO, Vec3::X, Vec3::Y], vec![0, 1, 2]),
+    );
+    context.place_model("leaf");
+    context.turtle.forward(1.0);
+    context.place_model("leaf");
+
+    let mut obj = Vec::new();
+    ObjExporter
+        .export_geometry(&context.geometry(), &mut obj)
+        .unwrap();
+    let obj = String::from_utf8(obj).unwrap();
+
+    // OBJ has no instancing, every instance is a copy of the leaf placed at the turtle.
+    assert_eq!(obj.matches("v ").count(), 6);
+    assert!(obj.contains("v 0 -0.5 0\nv 1 -0.5 0\nv 0 0.5 0\nf 1 2 3\n"));
+    assert!(obj.ends_with("v 0 1.5 0\nf 4 5 6\n"));
+}
+
+#[test]
+fn obj_materials() {
+    let mut context = branched_context();
+    context.begin_polygon();
+    for vertex in [Vec3::ZERO, Vec3::X, Vec3::Y] {
+        context.open_polygons.last_mut().unwrap().push(vertex);
+    }
+    context.set_material(Some("leaf".to_string()));
+    context.end_polygon();
+    context.emit_tagged(
+        "bark",
+        GeometryElement::Mesh(Mesh::new(vec![Vec3::ZERO, Vec3::X, Vec3::Z], vec![0, 1, 2])),
+    );
+    context.emit_tagged(
+        "leaf",
+        GeometryElement::Polygon(Polygon::new(vec![Vec3::ZERO, Vec3::Y, Vec3::Z])),
+    );
+    context
+        .elements
+        .push(GeometryElement::Polygon(Polygon::new(vec![
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::Z,
+        ])));
+    let geometry = context.geometry();
+    assert_eq!(geometry.polygons[0].material.as_deref(), Some("leaf"));
+
+    let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+    ObjExporter
+        .export_with_materials(&geometry, "tree.mtl", &mut obj, &mut mtl)
+        .unwrap();
+    let (obj, mtl) = (
+        String::from_utf8(obj).unwrap(),
+        String::from_utf8(mtl).unwrap(),
+    );
+
+    // The untagged polygon comes first, the tagged elements follow grouped by material.
+    assert!(obj.starts_with("mtllib tree.mtl\n"));
+    assert!(obj.contains("l 5 6\nv 0 0 0\nv 1 0 0\nv 0 0 1\nf 7 8 9\ng leaf\nusemtl leaf\n"));
+    assert!(
+        obj.ends_with("f 13 14 15\ng bark\nusemtl bark\nv 0 0 0\nv 1 0 0\nv 0 0 1\nf 16 17 18\n")
+    );
+    assert_eq!(
+        mtl,
+        "newmtl leaf\nKd 0 0 0\nd 1\nnewmtl bark\nKd 0 0 0\nd 1\n"
+    );
+}
+
+#[test]
+fn obj_segment_materials() {
+    let mut context = ExecuteContext::new();
+    context.record();
+    context.turtle.forward(1.0);
+    context.record();
+    context.set_material(Some("tree bark".to_string()));
+    context.turtle.forward(1.0);
+    context.record();
+    context.emit_tagged(
+        "tree bark",
+        GeometryElement::Segment(Segment::new(Vec3::X, Vec3::Y)),
+    );
+    let geometry = context.geometry();
+    assert_eq!(geometry.segments[0].material, None);
+    assert_eq!(geometry.segments[2].material.as_deref(), Some("tree bark"));
+
+    let (mut obj, mut mtl) = (Vec::new(), Vec::new());
+    ObjExporter
+        .export_with_materials(&geometry, "tree.mtl", &mut obj, &mut mtl)
+        .unwrap();
+    let (obj, mtl) = (
+        String::from_utf8(obj).unwrap(),
+        String::from_utf8(mtl).unwrap(),
+    );
+
+    // The lines are grouped by material, whitespace would end the material name.
+    assert_eq!(
+        obj,
+        "mtllib tree.mtl\nv 0 -0.5 0\nv 0 0.5 0\nl 1 2\ng tree_bark\nusemtl tree_bark\n\
+         v 0 0.5 0\nv 0 1.5 0\nl 3 4\nv 1 0 0\nv 0 1 0\nl 5 6\n"
+    );
+    assert_eq!(mtl, "newmtl tree_bark\nKd 0 0 0\nd 1\n");
+}
+
+struct SegmentCountExporter;
+
+impl Exporter for SegmentCountExporter {
+    fn format(&self) -> &'static str {
+        "count"
+    }
+
+    fn extension(&self) -> &'static str {
+        "txt"
+    }
+
+    fn export(&self, context: &ExecuteContext, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, "{}", context.geometry().segments.len())
+    }
+}
+
+#[test]
+fn exporter_registry() {
+    let mut registry = ExporterRegistry::with_defaults();
+    let mut formats = vec!["obj", "svg", "turtle"];
+    if cfg!(feature = "gltf") {
+        formats.insert(0, "gltf");
+    }
+    if cfg!(feature = "png") {
+        formats.push("png");
+    }
+    formats.sort_unstable();
+    assert_eq!(registry.formats().collect::<Vec<_>>(), formats);
+
+    registry.register(SegmentCountExporter);
+    assert_eq!(registry.get("count").unwrap().extension(), "txt");
+
+    let mut output = Vec::new();
+    registry
+        .export("count", &branched_context(), &mut output)
+        .unwrap();
+    assert_eq!(output, b"3");
+
+    let mut svg = Vec::new();
+    registry
+        .export("svg", &branched_context(), &mut svg)
+        .unwrap();
+    assert_eq!(
+        svg,
+        SvgExporter::new()
+            .to_string(&branched_context())
+            .into_bytes()
+    );
+
+    let error = registry
+        .export("dxf", &branched_context(), &mut Vec::new())
+        .unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+}
+
+#[test]
+fn raster_lines() {
+    let mut geometry = Geometry::new();
+    geometry.segments.push(Segment::new(Vec3::ZERO, Vec3::Y));
+
+    let style = RasterStyle {
+        width: 64,
+        height: 64,
+        line_width: 2.0,
+        ..Default::default()
+    };
+    let raster = Raster::render_geometry(&geometry, &style);
+
+    assert_eq!(raster.pixels.len(), 64 * 64 * 4);
+    // The segment is centered vertically in the image.
+    assert_eq!(raster.pixel(32, 20), [0, 0, 0, 255]);
+    assert_eq!(raster.pixel(32, 2), [255, 255, 255, 255]);
+    assert_eq!(raster.pixel(20, 32), [255, 255, 255, 255]);
+
+    let branched = Raster::render(&branched_context(), &style);
+    assert!(branched
+        .pixels
+        .chunks(4)
+        .any(|pixel| pixel == [0, 0, 0, 255]));
+}
+
+#[cfg(feature = "gltf")]
+#[test]
+fn gltf_branch_hierarchy() {
+    use crate::export::gltf::GltfExporter;
+
+    let mut context = branched_context();
+    context
+        .polygons
+        .push(Polygon::new(vec![Vec3::ZERO, Vec3::X, Vec3::Y]));
+    let gltf = GltfExporter::new().with_sides(4).to_string(&context);
+
+    assert!(gltf.contains(r#""asset":{"version":"2.0","generator":"scebpl-system"}"#));
+    // The side branch is a child of the main branch, the polygons are a node of their own.
+    assert!(gltf.contains(
+        r#""nodes":[{"name":"branch-1","mesh":0},{"name":"branch-0","mesh":1,"children":[0]},{"name":"surfaces","mesh":2}]"#
+    ));
+    assert!(gltf.contains(r#""scenes":[{"nodes":[1,2]}]"#));
+    assert_eq!(gltf.matches(r#""COLOR_0""#).count(), 3);
+
+    // Per segment 10 vertices of 48 bytes and 24 indices, the polygon has 3 vertices of 28 bytes and 3 indices.
+    let length = 3 * (10 * 48 + 24 * 4) + 3 * 28 + 3 * 4;
+    assert!(gltf.contains(&format!(
+        r#""byteLength":{length},"uri":"data:application/octet-stream;base64,"#
+    )));
+}
+
+#[cfg(feature = "gltf")]
+#[test]
+fn gltf_materials() {
+    use crate::export::gltf::GltfExporter;
+
+    let mut context = branched_context();
+    let triangle = || Polygon::new(vec![Vec3::ZERO, Vec3::X, Vec3::Y]);
+    context.emit_tagged("leaf", GeometryElement::Polygon(triangle()));
+    context.emit_tagged("flower \"red\"", GeometryElement::Polygon(triangle()));
+    context.emit_tagged("leaf", GeometryElement::Polygon(triangle()));
+    let gltf = GltfExporter::new().to_string(&context);
+
+    // The surfaces are one mesh with a primitive per material.
+    assert!(gltf.contains(r#""materials":[{"name":"leaf"},{"name":"flower \"red\""}]"#));
+    assert_eq!(gltf.matches(r#""material":0"#).count(), 1);
+    assert_eq!(gltf.matches(r#""material":1"#).count(), 1);
+    assert_eq!(gltf.matches(r#""COLOR_0""#).count(), 4);
+}
+
+#[cfg(feature = "gltf")]
+#[test]
+fn gltf_segment_materials() {
+    use crate::export::gltf::GltfExporter;
+
+    let mut context = branched_context();
+    context.set_material(Some("bark".to_string()));
+    context.turtle.forward(1.0);
+    context.record();
+    context.emit_tagged(
+        "bark",
+        GeometryElement::Segment(Segment::new(Vec3::ZERO, Vec3::Y)),
+    );
+    let gltf = GltfExporter::new().to_string(&context);
+
+    // The tube of the main branch and the tube of the elements have a primitive per material.
+    assert!(gltf.contains(r#""materials":[{"name":"bark"}]"#));
+    assert_eq!(gltf.matches(r#""material":0"#).count(), 2);
+    assert_eq!(gltf.matches(r#""COLOR_0""#).count(), 4);
+}
+
+#[cfg(feature = "gltf")]
+#[test]
+fn gltf_empty_drawing() {
+    use crate::export::gltf::GltfExporter;
+
+    let gltf = GltfExporter::new().to_string(&ExecuteContext::new());
+
+    assert!(gltf.contains(r#""nodes":[{"name":"branch-0"}]"#));
+    for property in ["meshes", "accessors", "bufferViews", "buffers"] {
+        assert!(!gltf.contains(property), "{property} in {gltf}");
+    }
+}
+
+#[cfg(feature = "gltf")]
+#[test]
+fn gltf_segment_elements() {
+    use crate::export::gltf::GltfExporter;
+
+    let mut context = ExecuteContext::new();
+    context
+        .elements
+        .push(GeometryElement::Segment(Segment::new(Vec3::ZERO, Vec3::Y)));
+    let gltf = GltfExporter::new().with_sides(4).to_string(&context);
+
+    assert!(gltf.contains(r#""nodes":[{"name":"branch-0"},{"name":"surfaces","mesh":0}]"#));
+    // The tube has 10 vertices of 48 bytes and 24 indices.
+    let length = 10 * 48 + 24 * 4;
+    assert!(gltf.contains(&format!(r#""buffers":[{{"byteLength":{length},"#)));
+}
+
+#[cfg(feature = "gltf")]
+#[test]
+fn gltf_instances() {
+    use crate::{export::gltf::GltfExporter, Color};
+
+    let mut context = ExecuteContext::new();
+    context.add_model(
+        "leaf",
+        Mesh::new(vec![Vec3::ZERO, Vec3::X, Vec3::Y], vec![0, 1, 2]),
+    );
+    context.place_model("leaf");
+    context.turtle.forward(1.0);
+    context.place_model("leaf");
+    context.turtle.set_color(Color::rgb(1.0, 0.0, 0.0));
+    context.place_model("leaf");
+    let gltf = GltfExporter::new().to_string(&context);
+
+    // The instances of the same color share the mesh of the leaf, the red leaf has a mesh of its own.
+    assert_eq!(gltf.matches(r#""name":"leaf""#).count(), 3);
+    assert_eq!(gltf.matches(r#""matrix":["#).count(), 3);
+    assert_eq!(gltf.matches(r#""mesh":0}"#).count(), 2);
+    assert_eq!(gltf.matches(r#""mesh":1}"#).count(), 1);
+    assert!(gltf.contains(r#"{"name":"instances","children":[1,2,3]}"#));
+    assert!(gltf.contains(r#"0,0.5,0,1],"mesh":0}"#));
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn png_thumbnail() {
+    use crate::render::raster::render_png;
+
+    let png = render_png(&branched_context().geometry(), 48, 32);
+
+    let decoder = png::Decoder::new(png.as_slice());
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+
+    assert_eq!((info.width, info.height), (48, 32));
+    assert_eq!(info.color_type, png::ColorType::Rgba);
+    let style = RasterStyle {
+        width: 48,
+        height: 32,
+        ..Default::default()
+    };
+    assert_eq!(pixels, Raster::render(&branched_context(), &style).pixels);
+}
+
+#[cfg(feature = "gif")]
+#[test]
+fn growth_animation_gif() {
+    use crate::{export::animation::*, ActionResolver, DefaultAlphabetSymbolDefiner, LSystem};
+
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "F[F]F");
+
+    let resolver = ActionResolver::new();
+    let style = AnimationStyle {
+        raster: RasterStyle {
+            width: 32,
+            height: 32,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let mut gif = Vec::new();
+    write_gif(&lsystem, &resolver, 3, &style, &mut gif).unwrap();
+
+    assert!(gif.starts_with(b"GIF89a"));
+    // One graphic control extension per generation.
+    assert_eq!(gif.windows(2).filter(|w| w == &[0x21, 0xF9]).count(), 4);
+}
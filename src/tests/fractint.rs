@@ -0,0 +1,75 @@
+use crate::{parse_fractint, ActionResolver, FractintError, LSystemParser};
+
+const KOCH: &str = "
+; From the Fractint distribution.
+Koch1 {        ; Koch snowflake
+  Angle 6
+  Axiom F--F--F
+  F=F+F--F+F
+}
+
+Sierpinski { ; moves with g
+  angle 3
+  axiom f
+  f = g+f+g
+  g -> f-g-f
+}
+";
+
+#[test]
+fn fractint_entries() {
+    let items = parse_fractint(KOCH).unwrap();
+    assert_eq!(items.len(), 2);
+
+    let koch = LSystemParser::parse(items[0].clone());
+    assert_eq!(koch.name, "Koch1");
+    assert_eq!(koch.generate(1).to_string(), "F+F--F+F--F+F--F+F--F+F--F+F");
+
+    let resolver = ActionResolver::with_default_actions();
+    let context = koch.run(&resolver, &koch.generate(1)).unwrap();
+    assert_eq!(context.geometry().segments.len(), 12);
+    // The snowflake is closed, the turtle ends where it started.
+    let start = context.geometry().segments[0].a;
+    assert!(context.turtle.origin().abs_diff_eq(start, 1e-4));
+
+    // `G` moves without drawing.
+    let sierpinski = LSystemParser::parse(items[1].clone());
+    assert_eq!(sierpinski.generate(1).to_string(), "f+F+f");
+    let context = sierpinski.run(&resolver, &sierpinski.generate(1)).unwrap();
+    assert_eq!(context.geometry().segments.len(), 1);
+}
+
+#[test]
+fn abop_style_rules() {
+    let items = parse_fractint("axiom X\nangle 4\nrule X=F[+X]F\n").unwrap();
+    let lsystem = LSystemParser::parse_inferred(items[0].clone());
+    assert_eq!(lsystem.generate(2).to_string(), "F[+F[+X]F]F");
+}
+
+#[test]
+fn fractint_errors() {
+    assert_eq!(
+        parse_fractint("Dragon {\n  Angle 8\n  Axiom FX\n  X=!X\n}").unwrap_err(),
+        FractintError::UnsupportedCommand {
+            name: "Dragon".to_string(),
+            command: '!',
+        }
+    );
+    assert_eq!(
+        parse_fractint("Color {\n  Axiom C12F\n}").unwrap_err(),
+        FractintError::UnsupportedCommand {
+            name: "Color".to_string(),
+            command: 'C',
+        }
+    );
+    assert_eq!(
+        parse_fractint("Turn {\n  Axiom F+F\n}").unwrap_err(),
+        FractintError::MissingAngle {
+            name: "Turn".to_string()
+        }
+    );
+    assert!(matches!(
+        parse_fractint("Broken {\n  Axiom F\n  F\n}"),
+        Err(FractintError::InvalidLine { line: 3, .. })
+    ));
+}
@@ -0,0 +1,119 @@
+use crate::{
+    genetics::{crossover, mutate, MutationConfig},
+    seeded_rng, Action, ActionParam, DefaultAlphabetSymbolDefiner, GrammarWarning, LSystem,
+};
+
+fn plant() -> LSystem {
+    let mut lsystem = LSystem::new("X", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule("X", "F+[[X]-X]-F[-FX]+X");
+    lsystem.add_rule("F", "FF");
+    lsystem.add_stochastic_rule("G", 0.5, "G[+G]");
+    lsystem.add_stochastic_rule("G", 0.25, "GG");
+    lsystem.add_action_rule(
+        "+",
+        Action::new("RotateZAction".into(), vec![ActionParam::Number(0.4)]),
+    );
+    lsystem.set_variable("length", 1.0);
+    lsystem
+}
+
+#[test]
+fn mutate_keeps_lsystems_valid() {
+    let lsystem = plant();
+    let config = MutationConfig {
+        symbol_rate: 0.5,
+        param_rate: 1.0,
+        weight_rate: 1.0,
+        ..Default::default()
+    };
+
+    for seed in 0..32 {
+        let mutant = mutate(&lsystem, &config, &mut seeded_rng(seed));
+
+        assert!(!mutant
+            .validate()
+            .iter()
+            .any(|warning| matches!(warning, GrammarWarning::UnbalancedBrackets { .. })));
+        for (_, successor) in mutant.generic_rules() {
+            assert!(successor.len() <= config.max_successor_len);
+            assert!(successor.chars().all(|symbol| "FGX+-[]".contains(symbol)));
+        }
+        let (_, rules) = mutant.stochastic_rule_sets().remove(0);
+        let total = rules
+            .iter()
+            .map(|(probability, _)| probability)
+            .sum::<f32>();
+        assert!((total - 0.75).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn mutate_ignores_brackets_of_the_symbols() {
+    let lsystem = plant();
+    let config = MutationConfig {
+        symbol_rate: 1.0,
+        symbols: vec!['F', '[', ']', '(', ')'],
+        ..Default::default()
+    };
+
+    for seed in 0..32 {
+        let mutant = mutate(&lsystem, &config, &mut seeded_rng(seed));
+
+        assert!(!mutant
+            .validate()
+            .iter()
+            .any(|warning| matches!(warning, GrammarWarning::UnbalancedBrackets { .. })));
+        for (_, successor) in mutant.generic_rules() {
+            assert!(!successor.contains(['(', ')']));
+        }
+    }
+}
+
+#[test]
+fn mutate_is_deterministic() {
+    let lsystem = plant();
+    let config = MutationConfig {
+        symbol_rate: 0.5,
+        ..Default::default()
+    };
+
+    let a = mutate(&lsystem, &config, &mut seeded_rng(3));
+    let b = mutate(&lsystem, &config, &mut seeded_rng(3));
+    assert_eq!(a.content_hash(), b.content_hash());
+    assert_ne!(a.content_hash(), lsystem.content_hash());
+
+    let unchanged = MutationConfig {
+        symbol_rate: 0.0,
+        weight_rate: 0.0,
+        param_rate: 0.0,
+        ..Default::default()
+    };
+    let copy = mutate(&lsystem, &unchanged, &mut seeded_rng(3));
+    assert_eq!(copy.content_hash(), lsystem.content_hash());
+}
+
+#[test]
+fn crossover_inherits_from_both_parents() {
+    let a = plant();
+    let mut b = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    b.add_rule("X", "X");
+    b.add_rule("F", "F+F");
+
+    assert_eq!(
+        crossover(&a, &a, &mut seeded_rng(0)).content_hash(),
+        a.content_hash()
+    );
+
+    let mut rules = vec![];
+    for seed in 0..16 {
+        let child = crossover(&a, &b, &mut seeded_rng(seed));
+        for (predecessor, successor) in child.generic_rules() {
+            rules.push(format!("{predecessor}{successor}"));
+        }
+        // Rules `b` does not have are inherited from `a`.
+        assert_eq!(child.stochastic_rule_sets().len(), 1);
+    }
+    for rule in ["XF+[[X]-X]-F[-FX]+X", "XX", "FFF", "FF+F"] {
+        assert!(rules.iter().any(|inherited| inherited == rule));
+    }
+}
@@ -0,0 +1,332 @@
+use macaw::{BoundingBox, Mat4, Vec3};
+
+use crate::{
+    expand_lines_to_quads, instance_attributes, Color, ExecuteContext, Geometry, GeometryElement,
+    Instance, Mesh, Polygon, Polyline, Segment, Turtle, INSTANCE_COMPONENTS,
+};
+
+#[test]
+fn merge_connected_segments_into_polylines() {
+    let segments = [
+        Segment::new(Vec3::ZERO, Vec3::X),
+        Segment::new(Vec3::X, Vec3::new(1.0, 1.0, 0.0)),
+        // Not connected to the previous segment.
+        Segment::new(Vec3::Y, Vec3::ZERO),
+        // Connected but with another color.
+        Segment {
+            color: Color::WHITE,
+            ..Segment::new(Vec3::ZERO, Vec3::X)
+        },
+    ];
+
+    let polylines = Polyline::from_segments(&segments);
+
+    assert_eq!(polylines.len(), 3);
+    assert_eq!(polylines[0].points.len(), 3);
+    assert_eq!(polylines[0].length(), 2.0);
+    assert_eq!(polylines[0].segments().collect::<Vec<_>>(), segments[..2]);
+}
+
+#[test]
+fn transform_geometry() {
+    let mut geometry = Geometry {
+        segments: vec![Segment::new(Vec3::ZERO, Vec3::X)],
+        polygons: vec![Polygon::new(vec![Vec3::ZERO, Vec3::X, Vec3::Y])],
+        ..Default::default()
+    };
+
+    geometry.scale(2.0);
+    geometry.transform(&Mat4::from_translation(Vec3::Z));
+
+    assert_eq!(geometry.segments[0].b, Vec3::new(2.0, 0.0, 1.0));
+    assert_eq!(geometry.polygons[0].vertices[2], Vec3::new(0.0, 2.0, 1.0));
+
+    let bounds = geometry.bounds().unwrap();
+    assert_eq!(bounds.min, Vec3::new(0.0, 0.0, 1.0));
+    assert_eq!(bounds.max, Vec3::new(2.0, 2.0, 1.0));
+    assert!(Geometry::new().bounds().is_none());
+}
+
+#[test]
+fn fit_geometry() {
+    let mut geometry = Geometry {
+        segments: vec![Segment::new(
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(5.0, 3.0, 0.0),
+        )],
+        ..Default::default()
+    };
+
+    geometry.normalize_to_unit_box();
+    // The longest side fits, the proportions are kept.
+    let bounds = geometry.bounds().unwrap();
+    assert!(bounds.min.abs_diff_eq(Vec3::new(-0.5, -0.25, 0.0), 1e-6));
+    assert!(bounds.max.abs_diff_eq(Vec3::new(0.5, 0.25, 0.0), 1e-6));
+
+    let target = BoundingBox::from_min_max(Vec3::new(0.0, 0.0, -1.0), Vec3::new(10.0, 1.0, 1.0));
+    geometry.fit_into(target);
+    let bounds = geometry.bounds().unwrap();
+    assert!(bounds.min.abs_diff_eq(Vec3::new(4.0, 0.0, 0.0), 1e-5));
+    assert!(bounds.max.abs_diff_eq(Vec3::new(6.0, 1.0, 0.0), 1e-5));
+
+    let mut empty = Geometry::new();
+    empty.normalize_to_unit_box();
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn simplify_geometry() {
+    let point = |x, y| Vec3::new(x, y, 0.0);
+    let line = |points: &[Vec3]| {
+        points
+            .windows(2)
+            .map(|pair| Segment::new(pair[0], pair[1]))
+            .collect::<Vec<_>>()
+    };
+    // Three collinear segments, a corner, a slight bend and a segment doubling back.
+    let mut segments = line(&[
+        point(0.0, 0.0),
+        point(1.0, 0.0),
+        point(2.0, 0.0),
+        point(3.0, 0.0),
+        point(3.0, 1.0),
+        point(3.1, 2.0),
+        point(3.0, 3.0),
+        point(3.0, 2.0),
+    ]);
+    // A collinear segment of another color is not merged.
+    segments.push(Segment {
+        color: Color::WHITE,
+        ..Segment::new(point(3.0, 2.0), point(3.0, 1.0))
+    });
+    let mut geometry = Geometry {
+        segments,
+        ..Default::default()
+    };
+
+    let mut merged = geometry.clone();
+    merged.simplify(0.0);
+    let mut expected = line(&[
+        point(0.0, 0.0),
+        point(3.0, 0.0),
+        point(3.0, 1.0),
+        point(3.1, 2.0),
+        point(3.0, 3.0),
+        point(3.0, 2.0),
+    ]);
+    expected.push(geometry.segments[7].clone());
+    assert_eq!(merged.segments, expected);
+
+    geometry.simplify(0.2);
+    assert_eq!(geometry.segments.len(), 4);
+    assert_eq!(
+        geometry.segments[1],
+        Segment::new(point(3.0, 0.0), point(3.0, 3.0))
+    );
+}
+
+#[test]
+fn tube_mesh() {
+    let segments = [
+        Segment {
+            width: 2.0,
+            color: Color::WHITE,
+            ..Segment::new(Vec3::ZERO, Vec3::Y)
+        },
+        Segment::new(Vec3::Y, Vec3::Y),
+    ];
+    let tube = Mesh::tube(&segments, 5, 0.5);
+
+    // The empty segment is skipped, every ring has a seam vertex.
+    assert_eq!(tube.positions.len(), 12);
+    assert_eq!(tube.triangle_count(), 10);
+    assert_eq!(tube.colors, vec![Color::WHITE; 12]);
+    for (position, normal) in tube.positions.iter().zip(&tube.normals) {
+        assert!((Vec3::new(position.x, 0.0, position.z).length() - 1.0).abs() < 1e-6);
+        assert!(normal.y.abs() < 1e-6);
+    }
+    // The faces point outwards.
+    for [a, b, c] in tube.triangles() {
+        let center = (a + b + c) / 3.0;
+        assert!((b - a).cross(c - a).dot(Vec3::new(center.x, 0.0, center.z)) > 0.0);
+    }
+}
+
+#[test]
+fn tube_mesh_joints() {
+    // A branch bending to the right halfway.
+    let segments = [
+        Segment::new(Vec3::ZERO, Vec3::Y),
+        Segment::new(Vec3::Y, Vec3::new(1.0, 2.0, 0.0)),
+    ];
+    let radius = 1.0 / std::f32::consts::TAU;
+    let tube = Mesh::tube(&segments, 4, radius);
+    assert_eq!(tube.uvs.len(), tube.positions.len());
+
+    // The rings at the joint match, tilted halfway between both segments.
+    let ring = 5;
+    let tilt = (Vec3::Y + Vec3::new(1.0, 1.0, 0.0).normalize()).normalize();
+    for side in 0..ring {
+        let (end, start) = (ring + side, 2 * ring + side);
+        assert!(tube.positions[end].abs_diff_eq(tube.positions[start], 1e-6));
+        assert!(tube.normals[end].abs_diff_eq(tube.normals[start], 1e-6));
+        assert!(tube.normals[end].dot(tilt).abs() < 1e-6);
+        assert_eq!(tube.uvs[end], tube.uvs[start]);
+    }
+
+    // `u` goes around the ring, `v` along the branch in circumferences.
+    let us = tube.uvs[..ring].iter().map(|uv| uv.x).collect::<Vec<_>>();
+    assert_eq!(us, [0.0, 0.25, 0.5, 0.75, 1.0]);
+    assert!(tube.positions[0].abs_diff_eq(tube.positions[ring - 1], 1e-6));
+    let v = |vertex: usize| tube.uvs[vertex].y;
+    assert_eq!(v(0), 0.0);
+    assert!((v(ring) - 1.0).abs() < 1e-5);
+    assert!((v(3 * ring) - (1.0 + 2f32.sqrt())).abs() < 1e-5);
+}
+
+#[test]
+fn expand_polylines_to_quads() {
+    let polyline = Polyline {
+        points: vec![Vec3::ZERO, Vec3::X, Vec3::X],
+        width: 2.0,
+        color: Color::WHITE,
+        depth: 0,
+        material: None,
+    };
+    let vertices = expand_lines_to_quads(&[polyline], 0.5);
+
+    assert_eq!(vertices.len(), 12);
+    assert_eq!(vertices.components(), 8);
+    assert_eq!(
+        vertices.vertex(0),
+        Some(&[0.0, 0.5, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0][..])
+    );
+    assert_eq!(
+        vertices.vertex(4).map(|v| &v[..3]),
+        Some(&[1.0, -0.5, 0.0][..])
+    );
+    // Both triangles face the z axis.
+    for triangle in vertices.as_slice().chunks(3 * 8) {
+        let corner = |i: usize| Vec3::from_slice(&triangle[i * 8..i * 8 + 3]);
+        let normal = (corner(1) - corner(0)).cross(corner(2) - corner(0));
+        assert!(normal.z >= 0.0);
+    }
+    // The zero length segment collapses into a point.
+    for index in 6..12 {
+        assert_eq!(
+            vertices.vertex(index).map(|v| &v[..3]),
+            Some(&[1.0, 0.0, 0.0][..])
+        );
+    }
+}
+
+#[test]
+fn meshes_and_instances() {
+    let mut mesh = Mesh::new(
+        vec![Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::ONE],
+        vec![0, 1, 2, 1, 3, 2],
+    );
+    mesh.normals = vec![Vec3::Z; 4];
+    assert_eq!(mesh.triangle_count(), 2);
+    assert_eq!(mesh.triangles().nth(1), Some([Vec3::X, Vec3::ONE, Vec3::Y]));
+
+    // The instance is placed at the turtle, its model `+Y` along the heading.
+    let mut turtle = Turtle::with_pose(Vec3::new(1.0, 2.0, 3.0), Vec3::X, Vec3::Z);
+    turtle.scale(2.0);
+    let instance = Instance::at_turtle("leaf", &turtle);
+    assert_eq!(instance.position(), Vec3::new(1.0, 2.0, 3.0));
+    assert!(instance
+        .transform
+        .transform_point3(Vec3::Y)
+        .abs_diff_eq(Vec3::new(3.0, 2.0, 3.0), 1e-6));
+
+    let mut context = ExecuteContext::new();
+    context.elements.push(GeometryElement::Mesh(mesh));
+    context.elements.push(GeometryElement::Instance(instance));
+    let mut geometry = context.geometry();
+    assert_eq!((geometry.meshes.len(), geometry.instances.len()), (1, 1));
+    let bounds = geometry.bounds().unwrap();
+    assert_eq!(
+        (bounds.min, bounds.max),
+        (Vec3::ZERO, Vec3::new(1.0, 2.0, 3.0))
+    );
+
+    geometry.transform(&Mat4::from_rotation_x(std::f32::consts::FRAC_PI_2));
+    assert!(geometry.meshes[0].positions[2].abs_diff_eq(Vec3::Z, 1e-6));
+    assert!(geometry.meshes[0].normals[0].abs_diff_eq(-Vec3::Y, 1e-6));
+    assert!(geometry.instances[0]
+        .position()
+        .abs_diff_eq(Vec3::new(1.0, -3.0, 2.0), 1e-6));
+}
+
+#[test]
+fn instanced_models() {
+    let leaf = Mesh::new(vec![Vec3::ZERO, Vec3::X, Vec3::Y], vec![0, 1, 2]);
+    let mut context = ExecuteContext::new();
+    context.add_model("leaf", leaf.clone());
+    context.place_model("leaf");
+    context.turtle.forward(2.0);
+    context.set_material(Some("green".to_string()));
+    context.place_model("leaf");
+    context.place_model("flower");
+
+    // The model is stored once, the instances only place it.
+    let mut geometry = context.geometry();
+    assert_eq!(geometry.models.len(), 1);
+    assert_eq!(geometry.instances.len(), 3);
+    assert!(geometry.meshes.is_empty());
+    assert!(geometry.instances[1]
+        .position()
+        .abs_diff_eq(context.turtle.origin(), 1e-6));
+    assert_eq!(geometry.instances[1].material.as_deref(), Some("green"));
+
+    let attributes = instance_attributes(&geometry.instances, "leaf");
+    assert_eq!(attributes.len(), 2);
+    assert_eq!(attributes.components(), INSTANCE_COMPONENTS);
+    let vertex = attributes.vertex(1).unwrap();
+    assert_eq!(
+        vertex[..16],
+        geometry.instances[1].transform.to_cols_array()
+    );
+    assert_eq!(vertex[16..], [0.0, 0.0, 0.0, 1.0]);
+
+    // Only the instances of known models are replaced by meshes.
+    geometry.expand_instances();
+    assert_eq!(geometry.meshes.len(), 2);
+    assert_eq!(geometry.instances.len(), 1);
+    assert_eq!(geometry.instances[0].name, "flower");
+    let expanded = &geometry.meshes[1];
+    assert_eq!(expanded.material.as_deref(), Some("green"));
+    assert!(expanded.positions[1].abs_diff_eq(
+        context.turtle.to_mat4().transform_point3(leaf.positions[1]),
+        1e-6
+    ));
+}
+
+#[test]
+fn color_to_hex() {
+    assert_eq!(Color::rgb(1.0, 0.5, 0.0).to_hex(), "#ff8000");
+}
+
+#[test]
+fn geometry_to_json() {
+    let mut geometry = Geometry::new();
+    assert_eq!(
+        geometry.to_json(),
+        r#"{"segments":[],"polygons":[],"meshes":[],"instances":[],"bounds":null}"#
+    );
+
+    geometry.segments.push(Segment {
+        color: Color::new(1.0, 0.5, 0.0, 1.0),
+        ..Segment::new(Vec3::ZERO, Vec3::new(0.0, 2.0, f32::NAN))
+    });
+    geometry
+        .instances
+        .push(Instance::new("leaf \"a\"", Mat4::from_translation(Vec3::X)));
+
+    let json = geometry.to_json();
+    assert!(json.starts_with(
+        r#"{"segments":[{"a":[0,0,0],"b":[0,2,null],"width":1,"color":[1,0.5,0,1],"depth":0,"material":null}],"#
+    ));
+    assert!(json.contains(r#""instances":[{"name":"leaf \"a\"","position":[1,0,0],"#));
+}
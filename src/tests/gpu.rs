@@ -0,0 +1,42 @@
+use crate::{workgroups, GpuVec};
+
+#[test]
+fn gpu_vec_sizes() {
+    let mut positions = GpuVec::<f32>::new(4);
+    positions.push(&[0.0, 1.0, 2.0, 0.0]);
+    positions.push(&[3.0, 4.0, 5.0, 1.0]);
+
+    assert_eq!(positions.len(), 2);
+    assert_eq!(positions.stride(), 16);
+    assert_eq!(positions.byte_len(), 32);
+    assert_eq!(positions.as_bytes().len(), 32);
+    assert_eq!(positions.vertex(1), Some(&[3.0, 4.0, 5.0, 1.0][..]));
+    assert_eq!(positions.vertex(2), None);
+}
+
+#[test]
+#[should_panic]
+fn gpu_vec_partial_vertex() {
+    GpuVec::from_vec(vec![0.0f32; 6], 4);
+}
+
+#[test]
+fn gpu_vec_sub_data() {
+    let mut vertices = GpuVec::<f32>::zeroed(3, 4);
+
+    let (offset, bytes) = vertices.sub_data_mut(1, 2).unwrap();
+    assert_eq!(offset, 16);
+    assert_eq!(bytes.len(), 32);
+    bytes[..4].copy_from_slice(&1.5f32.to_ne_bytes());
+    assert_eq!(vertices.vertex(1), Some(&[1.5, 0.0, 0.0, 0.0][..]));
+
+    assert!(vertices.sub_data_mut(2, 2).is_none());
+    assert!(vertices.sub_data_mut(usize::MAX, 1).is_none());
+}
+
+#[test]
+fn workgroup_count() {
+    assert_eq!(workgroups(0, 64), 0);
+    assert_eq!(workgroups(64, 64), 1);
+    assert_eq!(workgroups(65, 64), 2);
+}
@@ -100,6 +100,14 @@ fn symbol_lexer() {
     assert!(tokens.next().is_none());
 }
 
+#[test]
+fn cut_symbol_lexer() {
+    let lexer = Lexer::new();
+    let tokens = lexer.lex(String::from("F[%]"));
+
+    assert_eq!(tokens[2], Token::Symbol('%'));
+}
+
 #[test]
 fn break_lexer() {
     let lexer = Lexer::new();
@@ -150,3 +158,10 @@ fn lsystem_lexer() {
     assert_eq!(tokens.next().unwrap(), Token::Parentesis('}'));
     assert!(tokens.next().is_none());
 }
+
+#[test]
+fn empty_lexer() {
+    let lexer = Lexer::new();
+
+    assert!(lexer.lex(String::new()).is_empty());
+}
@@ -0,0 +1,49 @@
+use crate::{DefaultAlphabetSymbolDefiner, LSystem, LodSet, RunOptions};
+
+use super::drawing_lsystem;
+
+fn plant() -> LSystem {
+    let mut lsystem = drawing_lsystem("F", DefaultAlphabetSymbolDefiner, 1.0, 0.5);
+    lsystem.add_rule("F", "F[+F]F");
+    lsystem
+}
+
+#[test]
+fn lod_levels_from_generations() {
+    let lod = LodSet::build(&plant(), &[1, 3, 2, 3]).unwrap();
+
+    // The levels go from the finest to the coarsest, every generation count is drawn once.
+    let generations = lod.levels.iter().map(|level| level.generations);
+    assert_eq!(generations.collect::<Vec<_>>(), [3, 2, 1]);
+    let segments = lod.levels.iter().map(|level| level.geometry.segments.len());
+    assert_eq!(segments.collect::<Vec<_>>(), [27, 9, 3]);
+
+    // The levels grow from the same root and are as large as the finest one.
+    let root = RunOptions::default().start_position;
+    let finest = lod.levels[0].geometry.bounds().unwrap().size();
+    for level in &lod.levels {
+        assert!(level.geometry.segments[0].a.abs_diff_eq(root, 1e-5));
+        let size = level.geometry.bounds().unwrap().size();
+        assert!((size.length() - finest.length()).abs() < 1e-4);
+    }
+
+    // The same seed draws the same levels.
+    assert_eq!(lod, LodSet::build(&plant(), &[3, 2, 1]).unwrap());
+}
+
+#[test]
+fn lod_switch_distances() {
+    let lod = LodSet::build(&plant(), &[1, 2, 3]).unwrap();
+
+    // Coarser levels have larger details and are switched to farther away.
+    assert_eq!(lod.levels[0].switch_distance, 0.0);
+    assert!(lod.levels[1].detail > lod.levels[0].detail);
+    assert!(lod.levels[1].switch_distance > 0.0);
+    assert!(lod.levels[2].switch_distance > lod.levels[1].switch_distance);
+
+    let at = |distance: f32| lod.level_at(distance).unwrap().generations;
+    assert_eq!(at(0.0), 3);
+    assert_eq!(at(lod.levels[1].switch_distance), 2);
+    assert_eq!(at(f32::MAX), 1);
+    assert!(LodSet::default().level_at(1.0).is_none());
+}
@@ -1,11 +1,23 @@
-use macaw::Quat;
+use std::sync::{Arc, Mutex};
+
+use macaw::{Quat, Vec3};
 
 use crate::action::ActionResolver;
-use crate::default_actions::RotateXAction;
+use crate::default_actions::{
+    PopTransformFromStackAction, PushTranformToStackAction, RotateXAction, RotateZAction,
+    SetMaterialAction,
+};
 use crate::lexer::Lexer;
+use crate::TurtleTransformStack;
 use crate::{action::*, parser::*};
-use crate::{DefaultAlphabetSymbolDefiner, SymbolDefiner};
-use crate::{LSystem, Symbol};
+use crate::{Action, ActionParam, Alphabet, ExprKind, ModuleParams, Rng};
+use crate::{Budget, GenerateError, GenerateOptions, LSystem, Symbol, SymbolId, SymbolInterner};
+use crate::{CancellationToken, ExecutionHook, Progress};
+use crate::{Color, ExecuteContext, GrammarWarning, RuleKind, RunOptions, Taper, Turtle};
+use crate::{DefaultAlphabetSymbolDefiner, InferredSymbolDefiner, SymbolDefiner, TracedSymbol};
+use crate::{ExecutionEvent, GeometryElement, ParamError, Polygon, RuntimeError, TurtleEvent};
+
+use super::action;
 
 struct DefaultAlphabet;
 
@@ -23,7 +35,7 @@ impl SymbolDefiner for DefaultAlphabet {
             'B' => Symbol::Constant(char),
             '[' => Symbol::Constant(char),
             ']' => Symbol::Constant(char),
-            'a' => Symbol::Module(char, vec![]),
+            'a' => Symbol::Module(char, ModuleParams::new()),
             '(' => Symbol::Constant(char),
             ')' => Symbol::Constant(char),
             ',' => Symbol::Constant(char),
@@ -36,7 +48,7 @@ struct ParameticAction;
 
 impl LSystemAction for ParameticAction {
     fn trigger(&self) -> Symbol {
-        Symbol::Module('a', vec!['x', 'y', 'z'])
+        Symbol::Module('a', ModuleParams::from(vec![0.0, 1.0, 2.0]))
     }
 
     fn execute(&self, symbol: &Symbol, _context: &mut crate::ExecuteContext) {
@@ -45,7 +57,7 @@ impl LSystemAction for ParameticAction {
         }
     }
 
-    fn from_params(symbol: Symbol,_params: &ParamsResolver) -> Option<Self>
+    fn from_params(symbol: Symbol, _params: &ParamsResolver) -> Option<Self>
     where
         Self: Sized,
     {
@@ -230,15 +242,1808 @@ fn parse_lsystem_from_script_and_action() {
     let mut lsystem = LSystemParser::parse(item);
     let alphabet = lsystem.generate(2);
 
-    let mut resolver = ActionResolver {
-        actions: Default::default(),
-    };
+    let mut resolver = ActionResolver::new();
     resolver.add_action_resolver::<RotateXAction>('F'.into());
 
-    let context = lsystem.run(&resolver, &alphabet);
+    let context = lsystem.run(&resolver, &alphabet).unwrap();
 
     assert_eq!(
         context.turtle.rotation(),
         Quat::from_mat4(&macaw::Mat4::from_rotation_x(10.0))
     );
 }
+
+#[test]
+fn parse_lsystem_with_inferred_alphabet() {
+    let definition = format!(
+        "lsystem Algae {{
+            axiom a;
+
+            replace a by ab;
+            replace b by a;
+        }}
+    ",
+    );
+
+    let lexer = Lexer::new();
+
+    let lex = lexer.lex(definition);
+    let tokens = LexedTokens::new(lex);
+
+    let item = parse(tokens);
+
+    let lsystem = LSystemParser::parse_inferred(item);
+    let alphabet = lsystem.generate(3);
+
+    assert_eq!(alphabet.to_string(), "abaab");
+    assert_eq!(alphabet.symbols[0], Symbol::Variable('a'));
+    assert_eq!(alphabet.symbols[1], Symbol::Variable('b'));
+}
+
+#[test]
+fn suggest_generations_within_budget() {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "F+F-F-F+F");
+
+    // Segments per generation: 1, 5, 25, 125, 625.
+    assert_eq!(lsystem.suggest_generations(0), 0);
+    assert_eq!(lsystem.suggest_generations(100), 2);
+    assert_eq!(lsystem.suggest_generations(125), 3);
+
+    let alphabet = lsystem.generate(3);
+    let segments = alphabet
+        .iter()
+        .filter(|symbol| **symbol == Symbol::Variable('F'))
+        .count();
+    assert_eq!(segments, 125);
+}
+
+#[test]
+fn suggest_generations_stops_when_not_growing() {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "G");
+
+    assert_eq!(lsystem.suggest_generations(10), 1);
+}
+
+#[test]
+fn suggest_generations_with_unbalanced_successors() {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "F(2.5)F)[F(x");
+
+    // Segments per generation: 1, 3, 9, the parameters and unknown chars are not counted.
+    assert_eq!(lsystem.suggest_generations(8), 1);
+    assert_eq!(lsystem.suggest_generations(9), 2);
+}
+
+#[test]
+fn symbol_interner() {
+    let mut interner = SymbolInterner::new();
+
+    let f = interner.intern("F");
+    let name = interner.intern("Apex");
+
+    assert_eq!(f, SymbolId::from('F'));
+    assert_eq!(f.as_char(), Some('F'));
+    assert_eq!(name.as_char(), None);
+    assert_eq!(interner.intern("Apex"), name);
+    assert_ne!(interner.intern("Leaf"), name);
+    assert_eq!(interner.resolve(name), Some("Apex".to_string()));
+    assert_eq!(interner.resolve(f), Some("F".to_string()));
+    assert_eq!(interner.get("Unknown"), None);
+}
+
+#[test]
+fn empty_lsystem() {
+    let definition = format!(
+        "lsystem Empty {{
+            axiom ;
+        }}
+    ",
+    );
+
+    let lexer = Lexer::new();
+
+    let lex = lexer.lex(definition);
+    let tokens = LexedTokens::new(lex);
+
+    let item = parse(tokens);
+
+    let mut lsystem = LSystemParser::parse(item);
+    let alphabet = lsystem.generate(3);
+
+    assert_eq!(alphabet.to_string(), "");
+    assert_eq!(lsystem.suggest_generations(10), 0);
+
+    let resolver = ActionResolver::new();
+    let context = lsystem.run(&resolver, &alphabet).unwrap();
+    assert!(context.events.is_empty());
+
+    #[cfg(feature = "std")]
+    {
+        let svg = crate::export::svg::SvgExporter::new().to_string(&context);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
+
+#[test]
+fn axiom_only_lsystem() {
+    let mut lsystem = LSystem::new("F+F", DefaultAlphabetSymbolDefiner);
+
+    let alphabet = lsystem.generate(4);
+    assert_eq!(alphabet.to_string(), "F+F");
+
+    let resolver = ActionResolver::new();
+    let context = lsystem.run(&resolver, &alphabet).unwrap();
+    assert_eq!(context.stats.symbols, 3);
+    // Without actions the turtle never moves.
+    assert!(context.events.is_empty());
+
+    let empty = LSystem::new("", DefaultAlphabetSymbolDefiner);
+    assert!(empty.generate(0).symbols.is_empty());
+    assert!(empty.generate(2).symbols.is_empty());
+}
+
+#[test]
+fn context_sensitive_rule_sees_whole_generation() {
+    let mut lsystem = LSystem::new("BAC", DefaultAlphabetSymbolDefiner);
+    lsystem.add_context_sensitive_rule("A", |_symbol, index, chars| {
+        if chars[index - 1] == 'B' && chars[index + 1] == 'C' {
+            Some("AA")
+        } else {
+            None
+        }
+    });
+
+    // In the second generation neither `A` is surrounded by `B` and `C` anymore.
+    let alphabet = lsystem.generate(2);
+    assert_eq!(alphabet.to_string(), "BAAC");
+}
+
+#[test]
+fn deep_generation() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "AB");
+    lsystem.add_rule('B', "A");
+
+    // Fibonacci number of symbols.
+    let alphabet = lsystem.generate(25);
+    assert_eq!(alphabet.symbols.len(), 196418);
+}
+
+#[test]
+fn generate_within_budget() {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "FF");
+
+    let options = GenerateOptions::new().with_max_symbols(8);
+    let alphabet = lsystem.generate_with(3, &options).unwrap();
+    assert_eq!(alphabet.symbols.len(), 8);
+}
+
+#[test]
+fn generate_symbol_budget_exceeded() {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "FFFFFFFF");
+
+    let options = GenerateOptions::new().with_max_symbols(10_000);
+    assert_eq!(
+        lsystem.generate_with(12, &options).unwrap_err(),
+        GenerateError::BudgetExceeded {
+            budget: Budget::Symbols,
+            generation: 5
+        }
+    );
+}
+
+#[test]
+fn generate_cancelled() {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "FF");
+
+    let cancellation = CancellationToken::new();
+    let options = GenerateOptions::new().with_cancellation(cancellation.clone());
+    assert!(lsystem.generate_with(3, &options).is_ok());
+
+    cancellation.cancel();
+    assert_eq!(
+        lsystem.generate_with(3, &options).unwrap_err(),
+        GenerateError::Cancelled { generation: 1 }
+    );
+}
+
+#[test]
+fn generate_progress() {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "FF");
+
+    let reports = Arc::new(Mutex::new(vec![]));
+    let progress = reports.clone();
+    let options = GenerateOptions::new()
+        .with_progress(move |done, total| progress.lock().unwrap().push((done, total)));
+    lsystem.generate_with(3, &options).unwrap();
+
+    assert_eq!(*reports.lock().unwrap(), [(1, 3), (2, 3), (3, 3)]);
+}
+
+#[test]
+fn run_cancelled_and_progress() {
+    let lsystem = LSystem::new("F+F", DefaultAlphabetSymbolDefiner);
+    let alphabet = lsystem.generate(0);
+    let resolver = ActionResolver::new();
+
+    let reports = Arc::new(Mutex::new(vec![]));
+    let progress = reports.clone();
+    let cancellation = CancellationToken::new();
+    let options = RunOptions {
+        cancellation: Some(cancellation.clone()),
+        progress: Some(Progress::new(move |done, total| {
+            progress.lock().unwrap().push((done, total))
+        })),
+        ..Default::default()
+    };
+
+    lsystem
+        .run_with_options(&resolver, &alphabet, &options)
+        .unwrap();
+    assert_eq!(*reports.lock().unwrap(), [(0, 3), (3, 3)]);
+
+    cancellation.cancel();
+    assert_eq!(
+        lsystem
+            .run_with_options(&resolver, &alphabet, &options)
+            .err(),
+        Some(RuntimeError::Cancelled { index: 0 })
+    );
+}
+
+#[test]
+fn generate_memory_budget_exceeded() {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "FFFFFFFF");
+
+    let options = GenerateOptions::new().with_max_memory(1024);
+    let error = lsystem.generate_with(12, &options).unwrap_err();
+    assert!(matches!(
+        error,
+        GenerateError::BudgetExceeded {
+            budget: Budget::Memory,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn generate_memoized() {
+    let mut lsystem = LSystem::new("F-G-G", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "F-G+F+G-F");
+    lsystem.add_rule('G', "GG");
+    lsystem.add_interpretation_rule('G', "F");
+    assert!(lsystem.is_memoizable());
+
+    let options = GenerateOptions::new().with_memoization();
+    for generations in 0..6 {
+        assert_eq!(
+            lsystem.generate_with(generations, &options).unwrap(),
+            lsystem.generate(generations)
+        );
+    }
+
+    let options = options.with_max_symbols(10_000);
+    assert_eq!(
+        lsystem.generate_with(12, &options).unwrap_err(),
+        GenerateError::BudgetExceeded {
+            budget: Budget::Symbols,
+            generation: 12
+        }
+    );
+
+    lsystem.add_stochastic_rule('G', 0.5, "G");
+    assert!(!lsystem.is_memoizable());
+}
+
+#[test]
+fn generate_all_generations() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "AB");
+    lsystem.add_rule('B', "A");
+
+    let generations = lsystem.generate_all(4);
+    let symbols = generations
+        .iter()
+        .map(|alphabet| alphabet.to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(symbols, ["A", "AB", "ABA", "ABAAB", "ABAABABA"]);
+    assert_eq!(generations[3].generation, 3);
+    assert_eq!(generations[4].to_string(), lsystem.generate(4).to_string());
+}
+
+#[test]
+fn generations_iterator() {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "FF");
+
+    let lengths = lsystem
+        .generations()
+        .map(|alphabet| alphabet.symbols.len())
+        .take(5)
+        .collect::<Vec<_>>();
+    assert_eq!(lengths, [1, 2, 4, 8, 16]);
+
+    let axiom_only = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    assert_eq!(axiom_only.generations().count(), 256);
+}
+
+#[test]
+fn rule_tables_per_generation() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "X");
+    lsystem.add_rule_table(0..2, [("A", "AA")]);
+    lsystem.add_rule_table(2.., [("A", "B")]);
+
+    let generations = lsystem
+        .generate_all(3)
+        .iter()
+        .map(|alphabet| alphabet.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(generations, ["A", "AA", "AAAA", "BBBB"]);
+}
+
+#[test]
+fn rule_tables_fall_back_to_rules() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "AB");
+    lsystem.add_rule_table(1..=1, [("A", "AA")]);
+
+    assert_eq!(lsystem.generate(3).to_string(), "ABABB");
+    // The symbol counts per generation are 1, 2, 3 and 5.
+    assert_eq!(lsystem.suggest_generations(3), 2);
+}
+
+#[test]
+fn run_options_start_pose() {
+    let options = RunOptions {
+        start_position: Vec3::new(1.0, 2.0, 0.0),
+        start_heading: Vec3::X,
+        step: 2.0,
+        width: 3.0,
+        color: Color::WHITE,
+        ..Default::default()
+    };
+
+    let mut context = ExecuteContext::with_options(&options);
+    context.record();
+    context.turtle.forward(1.0);
+    context.record();
+    // Turning left around the up axis heads the turtle towards +Y.
+    context.turtle.rotate_z(std::f32::consts::FRAC_PI_2);
+    context.turtle.forward(1.0);
+    context.record();
+
+    let segments = context.geometry().segments;
+    assert!(segments[0].a.abs_diff_eq(Vec3::new(1.0, 2.0, 0.0), 1e-5));
+    assert!(segments[0].b.abs_diff_eq(Vec3::new(3.0, 2.0, 0.0), 1e-5));
+    assert!(segments[1].b.abs_diff_eq(Vec3::new(3.0, 4.0, 0.0), 1e-5));
+    assert_eq!(segments[0].width, 3.0);
+    assert_eq!(segments[0].color, Color::WHITE);
+}
+
+#[test]
+fn palette_colors() {
+    let mut lsystem = LSystem::new("F'F['F]F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule(
+        "F",
+        Action::new("DrawForwardAction".into(), vec![ActionParam::Number(1.0)]),
+    );
+    lsystem.add_action_rule("'", Action::new("IncColorAction".into(), vec![]));
+    lsystem.add_action_rule("[", Action::new("PushTranformToStackAction".into(), vec![]));
+    lsystem.add_action_rule(
+        "]",
+        Action::new("PopTransformFromStackAction".into(), vec![]),
+    );
+    let (red, green, blue) = (
+        Color::rgb(1.0, 0.0, 0.0),
+        Color::rgb(0.0, 1.0, 0.0),
+        Color::rgb(0.0, 0.0, 1.0),
+    );
+    let options = RunOptions {
+        palette: vec![red, green, blue],
+        ..Default::default()
+    };
+
+    let context = lsystem
+        .run_with_options(
+            &ActionResolver::with_default_actions(),
+            &lsystem.generate(0),
+            &options,
+        )
+        .unwrap();
+    let colors = context
+        .geometry()
+        .segments
+        .iter()
+        .map(|segment| segment.color)
+        .collect::<Vec<_>>();
+    // Closing the branch restores the color it was opened with.
+    assert_eq!(colors, [Color::BLACK, green, blue, green]);
+
+    let mut context: ExecuteContext = ExecuteContext::with_options(&options);
+    context.set_color_index(4);
+    assert_eq!(context.turtle.color(), green);
+    assert_eq!(context.turtle.color_index(), 4);
+}
+
+#[test]
+fn material_action() {
+    let mut lsystem = LSystem::new("FAF[BF]F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule("F", action("DrawForwardAction", &[1.0]));
+    lsystem.add_action_rule("A", action("SetMaterialAction", &[0.0]));
+    lsystem.add_action_rule("B", action("SetMaterialAction", &[1.0]));
+    lsystem.add_action_rule("[", action("PushTranformToStackAction", &[]));
+    lsystem.add_action_rule("]", action("PopTransformFromStackAction", &[]));
+    let resolver = ActionResolver::builder()
+        .with_defaults()
+        .with::<SetMaterialAction>(Symbol::Constant('A'))
+        .with::<SetMaterialAction>(Symbol::Constant('B'))
+        .build();
+    let options = RunOptions {
+        materials: vec!["bark".to_string()],
+        ..Default::default()
+    };
+
+    let context = lsystem
+        .run_with_options(&resolver, &lsystem.generate(0), &options)
+        .unwrap();
+    // A material without name is named by its index, closing the branch keeps the material.
+    let materials = context
+        .geometry()
+        .segments
+        .into_iter()
+        .map(|segment| segment.material)
+        .collect::<Vec<_>>();
+    let material = |name: &str| Some(name.to_string());
+    assert_eq!(
+        materials,
+        [None, material("bark"), material("1"), material("1")]
+    );
+}
+
+#[test]
+fn turtle_width() {
+    let mut lsystem = LSystem::new("F!F[!F]F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule("F", action("DrawForwardAction", &[1.0]));
+    lsystem.add_action_rule("!", action("MultiplyWidthAction", &[0.5]));
+    lsystem.add_action_rule("[", action("PushTranformToStackAction", &[]));
+    lsystem.add_action_rule("]", action("PopTransformFromStackAction", &[]));
+    let options = RunOptions {
+        width: 4.0,
+        ..Default::default()
+    };
+
+    let context = lsystem
+        .run_with_options(
+            &ActionResolver::with_default_actions(),
+            &lsystem.generate(0),
+            &options,
+        )
+        .unwrap();
+    let widths = context
+        .geometry()
+        .segments
+        .iter()
+        .map(|segment| segment.width)
+        .collect::<Vec<_>>();
+    // Closing the branch restores the width it was opened with.
+    assert_eq!(widths, [4.0, 2.0, 1.0, 2.0]);
+}
+
+#[test]
+fn turtle_orientation_3d() {
+    let angle = || vec![ActionParam::Number(std::f32::consts::FRAC_PI_2)];
+    let position = |symbols: &str| {
+        let mut lsystem = LSystem::new(symbols, DefaultAlphabetSymbolDefiner);
+        for (symbol, action, params) in [
+            ("&", "PitchDownAction", angle()),
+            ("^", "PitchUpAction", angle()),
+            ("\\", "RollLeftAction", angle()),
+            ("/", "RollRightAction", angle()),
+            ("|", "TurnAroundAction", vec![]),
+            ("F", "DrawForwardAction", vec![ActionParam::Number(1.0)]),
+        ] {
+            lsystem.add_action_rule(symbol, Action::new(action.into(), params));
+        }
+        let options = RunOptions {
+            start_position: Vec3::ZERO,
+            ..Default::default()
+        };
+        let resolver = ActionResolver::with_default_actions();
+        let context = lsystem
+            .run_with_options(&resolver, &lsystem.generate(0), &options)
+            .unwrap();
+        context.turtle.origin()
+    };
+
+    // The turtle starts heading +Y with up +Z, its right is +X.
+    assert!(position("&F").abs_diff_eq(-Vec3::Z, 1e-5));
+    assert!(position("^F").abs_diff_eq(Vec3::Z, 1e-5));
+    assert!(position("|F").abs_diff_eq(-Vec3::Y, 1e-5));
+    // Rolling keeps the heading but turns the up direction the turtle pitches towards.
+    assert!(position("\\F").abs_diff_eq(Vec3::Y, 1e-5));
+    assert!(position("\\^F").abs_diff_eq(Vec3::X, 1e-5));
+    assert!(position("/^F").abs_diff_eq(-Vec3::X, 1e-5));
+}
+
+#[test]
+fn turtle_tropism() {
+    let mut turtle = Turtle::with_pose(Vec3::ZERO, Vec3::Y, Vec3::Z);
+    turtle.set_tropism(-Vec3::Z, 0.2);
+    assert_eq!(turtle.tropism(), (-Vec3::Z, 0.2));
+
+    // Every move bends the heading further down, but never past the tropism direction.
+    let mut heights = vec![];
+    for _ in 0..50 {
+        turtle.forward(1.0);
+        heights.push(turtle.origin().z);
+    }
+    assert!(heights.windows(2).all(|pair| pair[1] < pair[0]));
+    assert!(turtle.transform(Vec3::Y).abs_diff_eq(-Vec3::Z, 1e-2));
+
+    // Without elasticity the turtle moves straight.
+    let mut turtle = Turtle::with_pose(Vec3::ZERO, Vec3::Y, Vec3::Z);
+    turtle.set_tropism(-Vec3::Z, 0.0);
+    turtle.forward(1.0);
+    turtle.forward(1.0);
+    assert!(turtle.origin().abs_diff_eq(Vec3::new(0.0, 2.0, 0.0), 1e-6));
+}
+
+#[test]
+fn turtle_frame() {
+    let mut turtle = Turtle::new();
+    assert_eq!(
+        (turtle.heading(), turtle.left(), turtle.up()),
+        (Vec3::Y, -Vec3::X, Vec3::Z)
+    );
+
+    turtle.rotate_z(std::f32::consts::FRAC_PI_2);
+    assert!(turtle.heading().abs_diff_eq(-Vec3::X, 1e-6));
+    assert!(turtle.left().abs_diff_eq(-Vec3::Y, 1e-6));
+    assert!(turtle
+        .rotation()
+        .mul_vec3(Vec3::Y)
+        .abs_diff_eq(turtle.heading(), 1e-6));
+
+    // Many rotations keep the frame orthonormal.
+    for step in 0..10_000 {
+        turtle.rotate_x(0.1 + step as f32 * 1e-4);
+        turtle.rotate_y(0.3);
+        turtle.rotate_z(0.7);
+    }
+    let (heading, left, up) = (turtle.heading(), turtle.left(), turtle.up());
+    for axis in [heading, left, up] {
+        assert!((axis.length() - 1.0).abs() < 1e-5);
+    }
+    assert!(heading.dot(left).abs() < 1e-5);
+    assert!(heading.dot(up).abs() < 1e-5);
+    assert!(up.abs_diff_eq(heading.cross(left), 1e-5));
+}
+
+#[test]
+fn default_run_options_match_default_turtle() {
+    let turtle = RunOptions::default().turtle();
+
+    assert_eq!(turtle.origin(), Turtle::new().origin());
+    assert!(turtle.transform(Vec3::Y).abs_diff_eq(Vec3::Y, 1e-6));
+}
+
+#[test]
+fn rule_priority() {
+    let mut lsystem = LSystem::new("BA", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "F");
+    lsystem.add_context_sensitive_rule("A", |_symbol, index, chars| {
+        (index > 0 && chars[index - 1] == 'B').then_some("AA")
+    });
+
+    // Context sensitive rules are tried before generic rules.
+    assert_eq!(lsystem.generate(1).to_string(), "BAA");
+    assert!(lsystem.validate().is_empty());
+
+    lsystem.set_rule_priority('A', RuleKind::Generic, 1);
+    assert_eq!(lsystem.generate(1).to_string(), "BF");
+    assert_eq!(
+        lsystem.validate(),
+        [GrammarWarning::ShadowedRule {
+            predecessor: "A".into(),
+            rule: RuleKind::ContextSensitive,
+            by: RuleKind::Generic
+        }]
+    );
+}
+
+#[test]
+fn validate_rules() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule("AB", "B");
+    lsystem.add_parametic_production_rule("A", |_symbol, _params| Some("B".into()));
+    lsystem.add_context_sensitive_rule("A", |_symbol, _index, _chars| None);
+
+    assert_eq!(
+        lsystem.validate(),
+        [
+            GrammarWarning::AmbiguousRules {
+                predecessor: "A".into(),
+                rules: vec![RuleKind::ContextSensitive, RuleKind::Parametric]
+            },
+            GrammarWarning::UnreachableRule {
+                predecessor: "AB".into()
+            }
+        ]
+    );
+
+    lsystem.set_rule_priority("A", RuleKind::Parametric, 1);
+    assert_eq!(lsystem.validate().len(), 1);
+}
+
+#[test]
+fn interpretation_rules() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "AB");
+    lsystem.add_interpretation_rule('A', "F[+F][-F]");
+
+    // The interpretation does not take part in the derivation.
+    assert_eq!(lsystem.generate(0).to_string(), "F[+F][-F]");
+    assert_eq!(lsystem.generate(2).to_string(), "F[+F][-F]BB");
+
+    let generations = lsystem
+        .generate_all(1)
+        .iter()
+        .map(|alphabet| alphabet.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(generations, ["F[+F][-F]", "F[+F][-F]B"]);
+}
+
+#[test]
+fn interpretation_rules_budget() {
+    let mut lsystem = LSystem::new("AAAA", DefaultAlphabetSymbolDefiner);
+    lsystem.add_interpretation_rule('A', "FFFF");
+
+    let options = GenerateOptions::new().with_max_symbols(8);
+    assert_eq!(
+        lsystem.generate_with(0, &options).unwrap_err(),
+        GenerateError::BudgetExceeded {
+            budget: Budget::Symbols,
+            generation: 0
+        }
+    );
+}
+
+#[test]
+fn duplicate_rules() {
+    let mut lsystem = LSystem::new("AB", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "AB");
+    lsystem.add_rule('A', "BA");
+    lsystem.add_rule('B', "A");
+
+    assert_eq!(lsystem.generate(1).to_string(), "BAA");
+    let duplicate = GrammarWarning::DuplicateRule {
+        predecessor: "A".into(),
+        rule: RuleKind::Generic,
+    };
+    assert_eq!(lsystem.warnings(), std::slice::from_ref(&duplicate));
+    assert_eq!(lsystem.validate(), [duplicate]);
+}
+
+#[test]
+fn override_rules() {
+    let mut lsystem = LSystem::new("AB", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "AB");
+    lsystem.override_rule('A', "BA");
+    lsystem.override_rule('B', "A");
+
+    assert_eq!(lsystem.generate(1).to_string(), "BAA");
+    assert!(lsystem.validate().is_empty());
+}
+
+#[test]
+fn validate_successors() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "F[+A");
+    lsystem.add_rule('B', "A");
+    lsystem.add_interpretation_rule('F', "F]");
+
+    assert_eq!(
+        lsystem.validate(),
+        [
+            GrammarWarning::UnbalancedBrackets {
+                predecessor: "A".into(),
+                successor: "F[+A".into()
+            },
+            GrammarWarning::UnusedRule {
+                predecessor: "B".into()
+            },
+            GrammarWarning::UnbalancedBrackets {
+                predecessor: "F".into(),
+                successor: "F]".into()
+            },
+        ]
+    );
+
+    // The successors of parametric rules are unknown, thus `B` might be derived.
+    lsystem.add_parametic_production_rule('A', |_symbol, _params| None);
+    assert_eq!(lsystem.validate().len(), 2);
+}
+
+#[test]
+fn validate_with_actions() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "F+A(1,2)X");
+    lsystem.add_action_rule("+", Action::new("RotateXAction".into(), vec![]));
+    lsystem.add_action_rule("F", Action::new("Forward".into(), vec![]));
+
+    let mut resolver = ActionResolver::new();
+    resolver.add_action_resolver::<RotateXAction>(Symbol::Constant('+'));
+
+    assert!(lsystem.validate().is_empty());
+    assert_eq!(
+        lsystem.validate_with_actions(&resolver),
+        [
+            GrammarWarning::UnknownAction {
+                symbol: "F".into(),
+                action: "Forward".into()
+            },
+            GrammarWarning::InertSymbol { symbol: 'X' },
+        ]
+    );
+}
+
+/// A user defined element recording where a leaf grows.
+#[derive(Debug, PartialEq)]
+struct Leaf(Vec3);
+
+struct LeafAction;
+
+impl LSystemAction<Leaf> for LeafAction {
+    fn from_params(_symbol: Symbol, _params: &ParamsResolver) -> Option<Self> {
+        Some(LeafAction)
+    }
+
+    fn name() -> &'static str {
+        "Leaf"
+    }
+
+    fn trigger(&self) -> Symbol {
+        Symbol::Variable('L')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<Leaf>) {
+        context.turtle.forward(1.0);
+        context.elements.push(Leaf(context.turtle.origin()));
+    }
+}
+
+#[test]
+fn run_with_custom_elements() {
+    let mut lsystem = LSystem::new("L[L]", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule("L", Action::new("Leaf".into(), vec![]));
+    lsystem.add_action_rule("[", Action::new("PushTranformToStackAction".into(), vec![]));
+    lsystem.add_action_rule(
+        "]",
+        Action::new("PopTransformFromStackAction".into(), vec![]),
+    );
+
+    let mut resolver = ActionResolver::<Leaf>::default();
+    resolver.add_action_resolver::<LeafAction>(Symbol::Variable('L'));
+    // The default actions work with any element type.
+    resolver.add_action_resolver::<PushTranformToStackAction>(Symbol::Constant('['));
+    resolver.add_action_resolver::<PopTransformFromStackAction>(Symbol::Constant(']'));
+
+    let context = lsystem.run(&resolver, &lsystem.generate(0)).unwrap();
+    assert_eq!(context.elements.len(), 2);
+    assert!(context.elements[1].0.y > context.elements[0].0.y);
+}
+
+#[test]
+fn run_with_events() {
+    let mut lsystem = LSystem::new("L[L]L", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule("L", Action::new("Leaf".into(), vec![]));
+    lsystem.add_action_rule("[", Action::new("PushTranformToStackAction".into(), vec![]));
+    lsystem.add_action_rule(
+        "]",
+        Action::new("PopTransformFromStackAction".into(), vec![]),
+    );
+
+    let mut resolver = ActionResolver::<Leaf>::default();
+    resolver.add_action_resolver::<LeafAction>(Symbol::Variable('L'));
+    resolver.add_action_resolver::<PushTranformToStackAction>(Symbol::Constant('['));
+    resolver.add_action_resolver::<PopTransformFromStackAction>(Symbol::Constant(']'));
+
+    let alphabet = lsystem.generate(0);
+    let mut events = vec![];
+    let context = lsystem
+        .run_with(&resolver, &alphabet, |event| events.push(event))
+        .unwrap();
+    assert!(context.events.is_empty());
+    assert!(context.elements.is_empty());
+
+    let kinds = events
+        .iter()
+        .map(|event| match event {
+            ExecutionEvent::MoveTo(_) => "move",
+            ExecutionEvent::LineTo { .. } => "line",
+            ExecutionEvent::Push => "push",
+            ExecutionEvent::Pop => "pop",
+            ExecutionEvent::Polygon(_) => "polygon",
+            ExecutionEvent::EmitElement(_) => "element",
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        ["move", "line", "element", "push", "line", "element", "pop", "move", "line", "element"]
+    );
+
+    // The lines are the segments recorded by `run`.
+    let lines = events
+        .iter()
+        .filter_map(|event| match event {
+            ExecutionEvent::LineTo { to, depth, .. } => Some((*to, *depth)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let segments = lsystem
+        .run(&resolver, &alphabet)
+        .unwrap()
+        .turtle_geometry()
+        .segments
+        .iter()
+        .map(|segment| (segment.b, segment.depth))
+        .collect::<Vec<_>>();
+    assert_eq!(lines, segments);
+}
+
+#[test]
+fn context_bounds() {
+    let mut context = ExecuteContext::with_options(&RunOptions {
+        start_position: Vec3::ZERO,
+        ..Default::default()
+    });
+    assert_eq!(context.bounds(), None);
+    assert_eq!(context.center(), None);
+
+    context.record();
+    context.draw_forward(2.0);
+    context.turtle.rotate_z(std::f32::consts::FRAC_PI_2);
+    context.move_forward(4.0);
+    // Moving without drawing does not grow the bounds.
+    assert_eq!(context.bounds(), context.geometry().bounds());
+
+    context.begin_polygon();
+    for _ in 0..3 {
+        context.add_vertex();
+        context.move_forward(1.0);
+        context.turtle.rotate_z(2.0);
+    }
+    context.end_polygon();
+    let bounds = context.bounds().unwrap();
+    assert_eq!(Some(bounds), context.geometry().bounds());
+    assert!(bounds.max.abs_diff_eq(Vec3::new(0.0, 2.0, 0.0), 1e-5));
+    assert!((bounds.min.x + 5.0).abs() < 1e-5);
+    assert!(context.center().unwrap().abs_diff_eq(bounds.center(), 1e-6));
+}
+
+#[test]
+fn branch_tree() {
+    let mut context = ExecuteContext::new();
+    context.record();
+    context.turtle.forward(1.0);
+    context.record();
+    for _ in 0..2 {
+        context.push(context.turtle);
+        context.turtle.rotate_z(0.5);
+        context.turtle.forward(1.0);
+        context.record();
+    }
+    for _ in 0..2 {
+        context.turtle = context.pop().unwrap();
+        context.record();
+    }
+    context.turtle.forward(1.0);
+    context.record();
+
+    let tree = context.branch_tree();
+    assert_eq!(tree.depth, 0);
+    assert_eq!(tree.segments.len(), 2);
+    assert_eq!(tree.children.len(), 1);
+    assert_eq!(tree.children[0].segments.len(), 1);
+    assert_eq!(tree.children[0].children[0].segments[0].depth, 2);
+    assert_eq!(tree.segment_count(), 4);
+
+    let depths = tree.iter().map(|branch| branch.depth).collect::<Vec<_>>();
+    assert_eq!(depths, [0, 1, 2]);
+}
+
+#[test]
+fn geometry_elements() {
+    let mut context = ExecuteContext::new();
+    context.record();
+    context.turtle.forward(1.0);
+    context.record();
+    context
+        .elements
+        .push(GeometryElement::Polygon(Polygon::new(vec![
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::Y,
+        ])));
+
+    assert!(context.turtle_geometry().polygons.is_empty());
+    let geometry = context.geometry();
+    assert_eq!(geometry.segments.len(), 1);
+    assert_eq!(geometry.polygons.len(), 1);
+}
+
+/// Pushes its first parameter as element.
+struct LengthAction(f32);
+
+impl LSystemAction<f32> for LengthAction {
+    fn from_params(_symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        Some(LengthAction(params.get(0)?))
+    }
+
+    fn name() -> &'static str {
+        "Length"
+    }
+
+    fn trigger(&self) -> Symbol {
+        Symbol::Variable('F')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext<f32>) {
+        context.elements.push(self.0);
+    }
+}
+
+#[test]
+fn run_modules() {
+    let mut lsystem = LSystem::new("F(2)FF(0.5,3)", InferredSymbolDefiner::new(['F']));
+    lsystem.add_action_rule(
+        "F",
+        Action::new("Length".into(), vec![ActionParam::Number(1.0)]),
+    );
+
+    let mut resolver = ActionResolver::<f32>::default();
+    resolver.add_action_resolver::<LengthAction>(Symbol::Variable('F'));
+
+    let context = lsystem.run(&resolver, &lsystem.generate(0)).unwrap();
+    assert_eq!(context.elements, vec![2.0, 1.0, 0.5]);
+
+    let modules = Alphabet {
+        symbols: vec![
+            Symbol::Module('F', ModuleParams::from(vec![4.0])),
+            Symbol::Module('F', ModuleParams::new()),
+        ],
+        generation: 0,
+    };
+    let context = lsystem.run(&resolver, &modules).unwrap();
+    assert_eq!(context.elements, vec![4.0, 1.0]);
+}
+
+#[test]
+fn implicit_depth_and_generation() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "F[A]");
+    for name in ["depth", "gen"] {
+        lsystem.add_action_rule(
+            "F",
+            Action::new("Length".into(), vec![ActionParam::Constant(name.into())]),
+        );
+    }
+    let mut resolver = ActionResolver::<f32>::default();
+    resolver.add_action_resolver::<LengthAction>(Symbol::Variable('F'));
+
+    let report = lsystem
+        .generate_with_report(2, &GenerateOptions::new().with_trace())
+        .unwrap();
+    assert_eq!(report.alphabet.to_string(), "F[F[A]]");
+
+    // Without origins every symbol is of the generation of the alphabet.
+    let context = lsystem.run(&resolver, &report.alphabet).unwrap();
+    assert_eq!(context.elements, [0.0, 2.0, 1.0, 2.0]);
+
+    let origins = report.trace.unwrap().origins();
+    let context = lsystem
+        .run_with_context(
+            &resolver,
+            &report.alphabet,
+            ExecuteContext::default().with_origins(origins),
+        )
+        .unwrap();
+    assert_eq!(context.elements, [0.0, 1.0, 1.0, 2.0]);
+
+    // Variables of the lsystem shadow the implicit ones.
+    lsystem.set_variable("depth", 5.0);
+    let context = lsystem.run(&resolver, &report.alphabet).unwrap();
+    assert_eq!(context.elements, [5.0, 2.0, 5.0, 2.0]);
+}
+
+#[test]
+fn taper_scales_branches() {
+    let mut lsystem = LSystem::new("F[F[F]]", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule(
+        "F",
+        Action::new("DrawForwardAction".into(), vec![ActionParam::Number(1.0)]),
+    );
+    lsystem.add_action_rule("[", Action::new("PushTranformToStackAction".into(), vec![]));
+    lsystem.add_action_rule(
+        "]",
+        Action::new("PopTransformFromStackAction".into(), vec![]),
+    );
+    let resolver = ActionResolver::with_default_actions();
+    let alphabet = lsystem.generate(0);
+    let segments = |lsystem: &LSystem, options: &RunOptions| {
+        lsystem
+            .run_with_options(&resolver, &alphabet, options)
+            .unwrap()
+            .geometry()
+            .segments
+            .iter()
+            .map(|segment| (segment.length(), segment.width))
+            .collect::<Vec<_>>()
+    };
+
+    let options = RunOptions {
+        taper: Some(Taper::new(0.5).with_width(0.75)),
+        ..Default::default()
+    };
+    assert_eq!(
+        segments(&lsystem, &options),
+        [(1.0, 1.0), (0.5, 0.75), (0.25, 0.5625)]
+    );
+
+    // The taper of the run options replaces the taper of the lsystem.
+    lsystem.set_taper(Some(Taper::new(0.5)));
+    let options = RunOptions {
+        taper: Some(Taper::default()),
+        ..Default::default()
+    };
+    assert_eq!(segments(&lsystem, &options), [(1.0, 1.0); 3]);
+}
+
+#[test]
+fn module_params_compare_bits() {
+    let nan = ModuleParams::from(vec![f32::NAN, 1.0]);
+    assert_eq!(nan, nan.clone());
+    assert_ne!(
+        ModuleParams::from(vec![0.0]),
+        ModuleParams::from(vec![-0.0])
+    );
+
+    let params = [1.0, 2.0].into_iter().collect::<ModuleParams>();
+    assert_eq!(
+        Symbol::Module('F', params.clone()),
+        Symbol::Module('F', ModuleParams::from(vec![1.0, 2.0]))
+    );
+    assert_eq!(params.get(1), Some(&2.0));
+}
+
+fn run_script(script: &str) -> ExecuteContext {
+    let item = parse(LexedTokens::new(Lexer::new().lex(script.to_string())));
+    let lsystem = LSystemParser::parse(item);
+
+    let mut resolver = ActionResolver::new();
+    resolver.add_action_resolver::<PushTranformToStackAction>(Symbol::Constant('['));
+    resolver.add_action_resolver::<PopTransformFromStackAction>(Symbol::Constant(']'));
+
+    lsystem.run(&resolver, &lsystem.generate(0)).unwrap()
+}
+
+#[test]
+fn polygon_mode() {
+    let script = "lsystem Leaf {
+        axiom {.f+.f+.f+.}F{.};
+        interpret { as BeginPolygonAction();
+        interpret . as AddVertexAction();
+        interpret } as EndPolygonAction();
+        interpret f as MoveForwardAction(1);
+        interpret F as DrawForwardAction(1);
+        interpret + as RotateZAction(1.5707964);
+    }";
+    let item = parse(LexedTokens::new(Lexer::new().lex(script.to_string())));
+    let lsystem = LSystemParser::parse(item);
+    let resolver = ActionResolver::with_default_actions();
+
+    let geometry = lsystem
+        .run(&resolver, &lsystem.generate(0))
+        .unwrap()
+        .geometry();
+    // The polygon with a single vertex encloses no area and is dropped.
+    assert_eq!(geometry.polygons.len(), 1);
+    let expected = [(0.0, -0.5), (0.0, 0.5), (-1.0, 0.5), (-1.0, -0.5)];
+    let vertices = &geometry.polygons[0].vertices;
+    assert_eq!(vertices.len(), expected.len());
+    for (vertex, (x, y)) in vertices.iter().zip(expected) {
+        assert!(vertex.abs_diff_eq(Vec3::new(x, y, 0.0), 1e-5));
+    }
+    // Moves inside the polygon do not draw, only `F` does.
+    assert_eq!(geometry.segments.len(), 1);
+
+    let mut polygons = 0;
+    lsystem
+        .run_with(&resolver, &lsystem.generate(0), |event| {
+            polygons += matches!(event, ExecutionEvent::Polygon(_)) as usize
+        })
+        .unwrap();
+    assert_eq!(polygons, 1);
+}
+
+#[test]
+fn cut_symbol_skips_rest_of_branch() {
+    let context = run_script(
+        "lsystem Cut {
+            axiom F[F%FF[F]F]F;
+            interpret [ as PushTranformToStackAction();
+            interpret ] as PopTransformFromStackAction();
+        }",
+    );
+
+    // `FF[F]F` after the cut is skipped, the closing `]` still pops the branch.
+    assert_eq!(context.stats.symbols, 6);
+    assert!(matches!(
+        context.events[..],
+        [
+            TurtleEvent::Branch { depth: 1, .. },
+            TurtleEvent::BranchEnd { depth: 1 }
+        ]
+    ));
+}
+
+#[test]
+fn cut_symbol_in_main_branch() {
+    let context = run_script(
+        "lsystem Cut {
+            axiom F%F[F];
+            interpret [ as PushTranformToStackAction();
+            interpret ] as PopTransformFromStackAction();
+        }",
+    );
+
+    assert_eq!(context.stats.symbols, 2);
+    assert!(context.events.is_empty());
+}
+
+#[test]
+fn run_with_variables() {
+    let item = parse(LexedTokens::new(Lexer::new().lex(String::from(
+        "lsystem Variables {
+            let angle = 2.5 / 2;
+            let double = angle * 2;
+            axiom +-;
+            interpret + as RotateZAction(double);
+            interpret - as RotateZAction(unknown);
+        }",
+    ))));
+    let mut lsystem = LSystemParser::parse(item);
+    assert_eq!(lsystem.variables().get("angle"), Some(1.25));
+    assert_eq!(lsystem.variables().get("double"), Some(2.5));
+
+    let resolver = ActionResolver::with_default_actions();
+    let alphabet = lsystem.generate(0);
+    assert_eq!(
+        lsystem.run(&resolver, &alphabet).err(),
+        Some(RuntimeError::UnknownVariable {
+            action: "RotateZAction".to_string(),
+            name: "unknown".to_string(),
+        })
+    );
+
+    lsystem.set_variable("unknown", 0.0);
+    let context = lsystem.run(&resolver, &alphabet).unwrap();
+    let mut turtle = Turtle::new();
+    turtle.rotate_z(2.5);
+    assert_eq!(context.turtle.rotation(), turtle.rotation());
+
+    lsystem.set_variable("double", 1.0);
+    let context = lsystem.run(&resolver, &alphabet).unwrap();
+    let mut turtle = Turtle::new();
+    turtle.rotate_z(1.0);
+    assert_eq!(context.turtle.rotation(), turtle.rotation());
+}
+
+#[test]
+fn run_errors() {
+    let mut lsystem = LSystem::new("F[+F]]", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule("+", Action::new("RotateZAction".into(), vec![]));
+    lsystem.add_action_rule("F", Action::new("Forward".into(), vec![]));
+    let alphabet = lsystem.generate(0);
+
+    let mut resolver = ActionResolver::new();
+    assert_eq!(
+        lsystem.run(&resolver, &alphabet).err(),
+        Some(RuntimeError::UnknownAction {
+            symbol: 'F',
+            action: "Forward".to_string(),
+        })
+    );
+
+    lsystem.remove_action_rules("F");
+    resolver.add_action_resolver::<RotateZAction>(Symbol::Constant('+'));
+    assert_eq!(
+        lsystem.run(&resolver, &alphabet).err(),
+        Some(RuntimeError::MissingParameter {
+            action: "RotateZAction".to_string(),
+            index: 0,
+        })
+    );
+
+    lsystem.clear_action_rules();
+    assert_eq!(
+        lsystem.run(&resolver, &alphabet).err(),
+        Some(RuntimeError::UnbalancedBrackets { index: 5 })
+    );
+}
+
+#[test]
+fn pop_without_push() {
+    assert!(TurtleTransformStack::new().pop().is_none());
+    assert_eq!(
+        ExecuteContext::new().pop().err(),
+        Some(RuntimeError::UnbalancedBrackets { index: 0 })
+    );
+
+    // An action popping more transforms than were pushed fails the run at its symbol.
+    let mut lsystem = LSystem::new("F[F]X", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule("[", Action::new("PushTranformToStackAction".into(), vec![]));
+    lsystem.add_action_rule(
+        "]",
+        Action::new("PopTransformFromStackAction".into(), vec![]),
+    );
+    lsystem.add_action_rule(
+        "X",
+        Action::new("PopTransformFromStackAction".into(), vec![]),
+    );
+    let resolver: ActionResolver = ActionResolver::builder()
+        .with_defaults()
+        .with::<PopTransformFromStackAction>(Symbol::Variable('X'))
+        .build();
+    assert_eq!(
+        lsystem.run(&resolver, &lsystem.generate(0)).err(),
+        Some(RuntimeError::UnbalancedBrackets { index: 4 })
+    );
+}
+
+#[test]
+fn param_errors() {
+    let params = ParamsResolver {
+        params: vec![
+            ActionParam::Number(1.0),
+            ActionParam::Constant("x".to_string()),
+        ],
+    };
+    assert_eq!(params.len(), 2);
+    assert_eq!(params.require(0), Ok(1.0));
+    assert_eq!(
+        params.require(1),
+        Err(ParamError {
+            action: String::new(),
+            index: 1,
+            expected: "number",
+            found: Some("x".to_string()),
+        })
+    );
+    assert_eq!(params.require(2), Err(ParamError::missing(2)));
+    assert_eq!(params.validate_arity(2), Ok(()));
+    assert_eq!(params.validate_arity(3), Err(ParamError::missing(2)));
+
+    // The resolver checks the arity and names the action.
+    let resolver = ActionResolver::with_default_actions();
+    let rotate = Action::new("RotateZAction".into(), vec![]);
+    let error = resolver
+        .try_resolve(&Symbol::Constant('+'), &rotate)
+        .unwrap()
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "action `RotateZAction` is missing parameter 0, expected a number"
+    );
+    assert!(resolver
+        .try_resolve(&Symbol::Constant('F'), &rotate)
+        .is_none());
+
+    // The default actions report the parameter that is not a number.
+    let rotate = Action::new(
+        "RotateZAction".into(),
+        vec![ActionParam::Constant("x".to_string())],
+    );
+    assert_eq!(
+        resolver
+            .try_resolve(&Symbol::Constant('+'), &rotate)
+            .unwrap()
+            .err(),
+        Some(ParamError {
+            action: "RotateZAction".to_string(),
+            index: 0,
+            expected: "number",
+            found: Some("x".to_string()),
+        })
+    );
+}
+
+/// Only implements `from_params`, requires two numbers.
+struct PairAction;
+
+impl LSystemAction for PairAction {
+    fn trigger(&self) -> Symbol {
+        Symbol::Constant('P')
+    }
+
+    fn execute(&self, _symbol: &Symbol, _context: &mut ExecuteContext) {}
+
+    fn from_params(_symbol: Symbol, params: &ParamsResolver) -> Option<Self> {
+        params.get(0)?;
+        params.get(1)?;
+        Some(PairAction)
+    }
+
+    fn name() -> &'static str {
+        "PairAction"
+    }
+}
+
+#[test]
+fn default_param_errors() {
+    let params = |params: Vec<ActionParam>| ParamsResolver { params };
+    let symbol = Symbol::Constant('P');
+
+    assert_eq!(
+        PairAction::try_from_params(
+            symbol.clone(),
+            &params(vec![
+                ActionParam::Constant("x".to_string()),
+                ActionParam::Number(1.0)
+            ])
+        )
+        .err()
+        .map(|error| (error.index, error.found)),
+        Some((0, Some("x".to_string())))
+    );
+    assert_eq!(
+        PairAction::try_from_params(symbol.clone(), &params(vec![ActionParam::Number(1.0)])).err(),
+        Some(ParamError::missing(1))
+    );
+    assert!(PairAction::try_from_params(
+        symbol,
+        &params(vec![ActionParam::Number(1.0), ActionParam::Number(2.0)])
+    )
+    .is_ok());
+}
+
+#[test]
+fn generation_report() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "AB");
+    lsystem.add_rule('B', "A");
+
+    let report = lsystem
+        .generate_with_report(4, &GenerateOptions::default())
+        .unwrap();
+    assert_eq!(report.alphabet, lsystem.generate(4));
+
+    #[cfg(not(feature = "timing"))]
+    {
+        assert_eq!(report.rewrite, std::time::Duration::ZERO);
+        assert_eq!(report.interpretation_rules, std::time::Duration::ZERO);
+    }
+}
+
+#[test]
+fn derivation_trace() {
+    let mut lsystem = LSystem::new("AC", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "AB");
+    lsystem.add_rule('B', "A");
+
+    let report = lsystem
+        .generate_with_report(2, &GenerateOptions::default())
+        .unwrap();
+    assert_eq!(report.trace, None);
+
+    let report = lsystem
+        .generate_with_report(2, &GenerateOptions::new().with_trace())
+        .unwrap();
+    assert_eq!(report.alphabet.to_string(), "ABAC");
+
+    let trace = report.trace.unwrap();
+    let traced = |parent, rule| TracedSymbol { parent, rule };
+    let generic = Some(RuleKind::Generic);
+    assert_eq!(trace.generations(), 2);
+    assert_eq!(
+        trace.steps[0],
+        [traced(0, generic), traced(0, generic), traced(1, None)]
+    );
+    assert_eq!(trace.parent(2, 2), Some(traced(1, generic)));
+    assert_eq!(trace.parent(0, 0), None);
+    assert_eq!(trace.ancestors(2, 2), [1, 0]);
+    assert_eq!(trace.ancestors(2, 3), [2, 1]);
+    assert_eq!(trace.derived_index(3), Some(3));
+    // `C` is copied from the axiom, the other symbols are rewritten in the last generation.
+    assert_eq!(trace.origins(), [2, 2, 2, 0]);
+}
+
+#[test]
+fn derivation_trace_with_interpretation_rules() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('A', "AB");
+    lsystem.add_interpretation_rule('B', "FF");
+
+    let report = lsystem
+        .generate_with_report(1, &GenerateOptions::new().with_trace())
+        .unwrap();
+    assert_eq!(report.alphabet.to_string(), "AFF");
+
+    let trace = report.trace.unwrap();
+    assert_eq!(trace.interpretation, Some(vec![0, 1, 1]));
+    assert_eq!(trace.derived_index(2), Some(1));
+    assert_eq!(trace.derived_index(3), None);
+    assert_eq!(trace.origins(), [1, 1, 1]);
+}
+
+#[test]
+fn alphabet_stats() {
+    let lsystem = LSystem::new("F[+F[F]]F", DefaultAlphabetSymbolDefiner);
+    let stats = lsystem.generate(0).stats();
+
+    assert_eq!(stats.count('F'), 4);
+    assert_eq!(stats.count('['), 2);
+    assert_eq!(stats.count('X'), 0);
+    assert_eq!(stats.depths, [4, 4, 1]);
+    assert_eq!(stats.max_nesting, 2);
+    assert_eq!(stats.len(), 9);
+}
+
+#[test]
+fn growth_estimate() {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule('F', "F+F-F-F+F");
+
+    let estimate = lsystem.growth_estimate(3);
+    assert_eq!(estimate, [1.0, 9.0, 49.0, 249.0]);
+    assert_eq!(estimate[3] as usize, lsystem.generate(3).symbols.len());
+
+    // Half of the symbols are expected to double each generation.
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_stochastic_rule('F', 0.5, "FF");
+    assert_eq!(lsystem.growth_estimate(2), [1.0, 1.5, 2.25]);
+}
+
+#[test]
+fn growth_estimate_of_modules() {
+    let mut lsystem = LSystem::new("F(1)+G", InferredSymbolDefiner::new(['F', 'G']));
+    lsystem.add_rule('F', "FF");
+    lsystem.add_rule('G', "G[+F(0.5)]");
+
+    // Modules are not rewritten by generic rules, though every char of them is a symbol of the alphabet.
+    let estimate = lsystem.growth_estimate(3);
+    assert_eq!(estimate, [6.0, 15.0, 24.0, 33.0]);
+    for (generation, estimate) in estimate.into_iter().enumerate() {
+        let stats = lsystem.generate(generation as u8).stats();
+        assert_eq!(estimate as usize, stats.len());
+    }
+}
+
+#[test]
+fn run_stats() {
+    let mut context = run_script(
+        "lsystem Cut {
+            axiom F%F[F];
+            interpret [ as PushTranformToStackAction();
+            interpret ] as PopTransformFromStackAction();
+        }",
+    );
+
+    // The symbols after the cut are not run.
+    assert_eq!(context.stats.symbols, 2);
+
+    context.build_geometry();
+    #[cfg(not(feature = "timing"))]
+    assert_eq!(
+        context.stats,
+        crate::RunStats {
+            symbols: 2,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn resolved_actions_are_cached() {
+    let mut lsystem = LSystem::new("+-+-++", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule(
+        "+",
+        Action::new("RotateZAction".into(), vec![ActionParam::Number(0.5)]),
+    );
+    lsystem.add_action_rule(
+        "-",
+        Action::new(
+            "RotateZAction".into(),
+            vec![ActionParam::Expression(ExprKind::Random(0.0..1.0))],
+        ),
+    );
+
+    let mut resolver = ActionResolver::new();
+    resolver.add_action_resolver::<RotateZAction>(Symbol::Constant('+'));
+    resolver.add_action_resolver::<RotateZAction>(Symbol::Constant('-'));
+
+    let context = lsystem.run(&resolver, &lsystem.generate(0)).unwrap();
+    // Every `-` draws another angle, all `+` share one action.
+    assert_eq!(context.stats.resolved_actions, 3);
+}
+
+/// Counts its invocations in the state of the context.
+struct CountAction;
+
+#[derive(Default)]
+struct Count(usize);
+
+impl LSystemAction for CountAction {
+    fn from_params(_symbol: Symbol, _params: &ParamsResolver) -> Option<Self> {
+        Some(CountAction)
+    }
+
+    fn name() -> &'static str {
+        "Count"
+    }
+
+    fn trigger(&self) -> Symbol {
+        Symbol::Variable('F')
+    }
+
+    fn execute(&self, _symbol: &Symbol, context: &mut ExecuteContext) {
+        context.state::<Count>().0 += 1;
+    }
+}
+
+#[test]
+fn action_state() {
+    let mut lsystem = LSystem::new("FF[F]", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule("F", Action::new("Count".into(), vec![]));
+    let resolver = ActionResolver::builder()
+        .with::<CountAction>(Symbol::Variable('F'))
+        .build();
+
+    let context = lsystem.run(&resolver, &lsystem.generate(0)).unwrap();
+    assert_eq!(context.get_state::<Count>().map(|count| count.0), Some(3));
+    assert!(context.get_state::<f32>().is_none());
+
+    // Every run starts with a fresh state.
+    let context = lsystem.run(&resolver, &lsystem.generate(0)).unwrap();
+    assert_eq!(context.get_state::<Count>().map(|count| count.0), Some(3));
+}
+
+/// Records the symbols around which it was called.
+struct TraceHook(Arc<Mutex<Vec<String>>>);
+
+impl ExecutionHook for TraceHook {
+    fn before(&self, symbol: &Symbol, context: &mut ExecuteContext) {
+        let count = context.get_state::<Count>().map_or(0, |count| count.0);
+        let symbol = symbol.char();
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("before {symbol} {count}"));
+    }
+
+    fn after(&self, symbol: &Symbol, context: &mut ExecuteContext) {
+        let count = context.get_state::<Count>().map_or(0, |count| count.0);
+        let symbol = symbol.char();
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("after {symbol} {count}"));
+    }
+}
+
+#[test]
+fn execution_hooks() {
+    let mut lsystem = LSystem::new("FF", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule("F", Action::new("Count".into(), vec![]));
+    let trace = Arc::new(Mutex::new(vec![]));
+    let resolver = ActionResolver::builder()
+        .with::<CountAction>(Symbol::Variable('F'))
+        .with_hook(TraceHook(trace.clone()))
+        .build();
+
+    lsystem.run(&resolver, &lsystem.generate(0)).unwrap();
+    assert_eq!(
+        *trace.lock().unwrap(),
+        ["before F 0", "after F 1", "before F 1", "after F 2"]
+    );
+}
+
+#[test]
+fn action_rules_by_symbol() {
+    let mut lsystem = LSystem::new("++", DefaultAlphabetSymbolDefiner);
+    let rotate = |angle| Action::new("RotateZAction".into(), vec![ActionParam::Number(angle)]);
+    lsystem.add_action_rule("+", rotate(0.5));
+    lsystem.add_action_rule("+", rotate(0.25));
+    assert_eq!(lsystem.action_rules("+"), [rotate(0.5), rotate(0.25)]);
+    assert!(lsystem.action_rules("-").is_empty());
+
+    let mut resolver = ActionResolver::new();
+    resolver.add_action_resolver::<RotateZAction>(Symbol::Constant('+'));
+
+    // Both actions run for every `+`.
+    let context = lsystem.run(&resolver, &lsystem.generate(0)).unwrap();
+    assert_eq!(context.stats.resolved_actions, 2);
+
+    assert_eq!(lsystem.remove_action_rules("+").len(), 2);
+    assert!(lsystem.action_rules("+").is_empty());
+}
+
+#[test]
+fn draw_and_move_forward() {
+    let mut lsystem = LSystem::new("FfGF", DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule(
+        "F",
+        Action::new("DrawForwardAction".into(), vec![ActionParam::Number(1.0)]),
+    );
+    lsystem.add_action_rule(
+        "f",
+        Action::new("MoveForwardAction".into(), vec![ActionParam::Number(2.0)]),
+    );
+    // Both lines of `G` are recorded.
+    for _ in 0..2 {
+        lsystem.add_action_rule(
+            "G",
+            Action::new("DrawForwardAction".into(), vec![ActionParam::Number(0.5)]),
+        );
+    }
+    let resolver = ActionResolver::with_default_actions();
+    let options = RunOptions {
+        start_position: Vec3::ZERO,
+        ..Default::default()
+    };
+
+    let context = lsystem
+        .run_with_options(&resolver, &lsystem.generate(0), &options)
+        .unwrap();
+    let lines = context
+        .turtle_geometry()
+        .segments
+        .iter()
+        .map(|segment| (segment.a.y, segment.b.y))
+        .collect::<Vec<_>>();
+    assert_eq!(lines, [(0.0, 1.0), (3.0, 3.5), (3.5, 4.0), (4.0, 5.0)]);
+
+    // The pen up move is streamed as a move.
+    let mut events = vec![];
+    lsystem
+        .run_with(&resolver, &lsystem.generate(0), |event| events.push(event))
+        .unwrap();
+    assert!(events.contains(&ExecutionEvent::MoveTo(Vec3::new(0.0, 2.5, 0.0))));
+}
+
+#[test]
+fn resolver_builder() {
+    let resolver = ActionResolver::with_default_actions();
+    for (symbol, name) in [
+        ('+', "RotateZAction"),
+        ('-', "RotateZAction"),
+        ('&', "RotateXAction"),
+        ('^', "RotateXAction"),
+        ('/', "RotateYAction"),
+        ('\\', "RotateYAction"),
+        ('F', "DrawForwardAction"),
+        ('G', "DrawForwardAction"),
+        ('f', "MoveForwardAction"),
+        ('g', "MoveForwardAction"),
+        ('\'', "IncColorAction"),
+        ('!', "MultiplyWidthAction"),
+        ('&', "PitchDownAction"),
+        ('^', "PitchUpAction"),
+        ('\\', "RollLeftAction"),
+        ('/', "RollRightAction"),
+        ('|', "TurnAroundAction"),
+        ('{', "BeginPolygonAction"),
+        ('.', "AddVertexAction"),
+        ('}', "EndPolygonAction"),
+        ('[', "PushTranformToStackAction"),
+        (']', "PopTransformFromStackAction"),
+    ] {
+        assert!(resolver.contains(&Symbol::Constant(symbol), name));
+    }
+    assert_eq!(resolver.actions.len(), 22);
+
+    let resolver = ActionResolver::<f32>::builder()
+        .with_defaults()
+        .with::<LengthAction>(Symbol::Variable('F'))
+        .build();
+    assert!(resolver.contains(&Symbol::Variable('F'), "Length"));
+    assert_eq!(resolver.actions.len(), 23);
+}
+
+/// Replays the same number, useful to test random parameters.
+struct FixedRng(u32);
+
+impl Rng for FixedRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0
+    }
+}
+
+#[test]
+fn stochastic_rules_are_seeded() {
+    let mut lsystem = LSystem::new("F".repeat(64), DefaultAlphabetSymbolDefiner);
+    lsystem.add_stochastic_rule('F', 0.5, "G");
+
+    let first = lsystem.generate(1).to_string();
+    assert_eq!(lsystem.generate(1).to_string(), first);
+    assert!(first.contains('F') && first.contains('G'));
+
+    lsystem.set_seed(7);
+    assert_ne!(lsystem.generate(1).to_string(), first);
+}
+
+#[test]
+fn stochastic_rules_are_alternatives() {
+    let mut lsystem = LSystem::new("FFFFFFFF", DefaultAlphabetSymbolDefiner);
+    lsystem.add_stochastic_rule('F', 0.5, "A");
+    lsystem.add_stochastic_rule('F', 0.5, "B");
+    let alphabet = lsystem.generate(1).to_string();
+    assert!(alphabet.chars().all(|symbol| matches!(symbol, 'A' | 'B')));
+
+    // The generic rule applies if no stochastic rule is picked.
+    let mut lsystem = LSystem::new("FFFFFFFF", DefaultAlphabetSymbolDefiner);
+    lsystem.add_stochastic_rule('F', 0.0, "A");
+    lsystem.add_rule('F', "B");
+    assert_eq!(lsystem.generate(1).to_string(), "BBBBBBBB");
+}
+
+#[test]
+fn stochastic_rule_script() {
+    let item = parse(LexedTokens::new(Lexer::new().lex(String::from(
+        "lsystem Stochastic {
+            axiom FFFF;
+            replace F(1) by G;
+        }",
+    ))));
+    let lsystem = LSystemParser::parse(item);
+
+    assert_eq!(lsystem.generate(1).to_string(), "GGGG");
+}
+
+/// Seeds draw the same numbers as with `perchance`, thus with and without `std`.
+#[cfg(feature = "std")]
+#[test]
+fn default_rng_matches_perchance() {
+    let mut rng = crate::seeded_rng(crate::DEFAULT_SEED);
+    let mut perchance = perchance::PerchanceContext::new(crate::DEFAULT_SEED as u128);
+    for _ in 0..64 {
+        assert_eq!(rng.next_u32(), perchance.next_u32());
+        assert_eq!(rng.next_f32(), perchance.next_f32());
+    }
+}
+
+#[test]
+fn random_params_use_context_rng() {
+    let mut context = ExecuteContext::new().with_rng(FixedRng(u32::MAX));
+    assert!(context.random_float() > 0.99);
+
+    let params = ParamsResolver {
+        params: vec![
+            ActionParam::Expression(ExprKind::Random(1.0..3.0)),
+            ActionParam::Number(5.0),
+        ],
+    };
+    let sampled = params.sample(&mut FixedRng(0));
+    assert_eq!(sampled.get(0), Some(1.0));
+    assert_eq!(sampled.get(1), Some(5.0));
+
+    // Unsampled random parameters are drawn from the given generator or one seeded with `DEFAULT_SEED`.
+    let variables = crate::Variables::default();
+    assert_eq!(
+        params.get_with_rng(0, &variables, &mut FixedRng(0)),
+        Some(1.0)
+    );
+    let seeded = params.get_with_rng(0, &variables, &mut crate::seeded_rng(crate::DEFAULT_SEED));
+    assert_eq!(params.get(0), seeded);
+    assert_eq!(params.require(0).ok(), seeded);
+}
@@ -0,0 +1,103 @@
+use crate::{
+    Action, ActionParam, ActionResolver, DefaultAlphabetSymbolDefiner, LSystem, LSystemDefinition,
+    LSystemFactory, LSystemKey,
+};
+
+fn koch() -> LSystem {
+    let mut lsystem = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    lsystem.add_rule("F", "F+F-F-F+F");
+    lsystem.add_action_rule(
+        "F",
+        Action::new("DrawForwardAction".into(), vec![ActionParam::Number(1.0)]),
+    );
+    lsystem
+}
+
+#[test]
+fn content_hash_ignores_name_and_rule_order() {
+    let mut a = koch();
+    a.add_rule("G", "GG");
+    a.name = "Koch".to_string();
+    let mut b = LSystem::new("F", DefaultAlphabetSymbolDefiner);
+    b.add_rule("G", "GG");
+    b.add_rule("F", "F+F-F-F+F");
+    b.add_action_rule(
+        "F",
+        Action::new("DrawForwardAction".into(), vec![ActionParam::Number(1.0)]),
+    );
+    assert_eq!(a.content_hash(), b.content_hash());
+
+    b.set_seed(7);
+    assert_ne!(a.content_hash(), b.content_hash());
+    b.set_seed(a.seed());
+    b.set_variable("angle", 90.0);
+    assert_ne!(a.content_hash(), b.content_hash());
+}
+
+#[test]
+fn factory_caches_identical_lsystems() {
+    let resolver: ActionResolver = ActionResolver::with_default_actions();
+    let mut factory = LSystemFactory::new().with_max_entries(2);
+
+    let symbols = factory.generate(&koch(), 2).unwrap().symbols.len();
+    assert_eq!(symbols, 49);
+    let segments = factory
+        .run(&koch(), &resolver, 2)
+        .unwrap()
+        .geometry()
+        .segments
+        .len();
+    assert_eq!(segments, 25);
+    assert_eq!(factory.len(), 1);
+
+    // An edited lsystem gets its own entry, the least recently used entry is evicted.
+    let mut edited = koch();
+    edited.add_rule("F", "FF");
+    factory.generate(&edited, 2).unwrap();
+    factory.generate(&koch(), 3).unwrap();
+    assert_eq!(factory.len(), 2);
+    assert!(!factory.contains(&LSystemKey::new(&koch(), 2)));
+    assert!(factory.contains(&LSystemKey::new(&edited, 2)));
+
+    factory.invalidate(&edited);
+    assert_eq!(factory.len(), 1);
+    assert!(factory.contains(&LSystemKey::new(&koch(), 3)));
+}
+
+struct Koch;
+
+impl LSystemDefinition for Koch {
+    type Definer = DefaultAlphabetSymbolDefiner;
+
+    fn name(&self) -> &str {
+        "Koch"
+    }
+
+    fn build(&self) -> LSystem {
+        koch()
+    }
+
+    fn default_generations(&self) -> u8 {
+        2
+    }
+}
+
+#[test]
+fn factory_runs_definitions() {
+    let registry: Vec<Box<dyn LSystemDefinition<Definer = DefaultAlphabetSymbolDefiner>>> =
+        vec![Box::new(Koch)];
+    let mut factory = LSystemFactory::new();
+
+    let definition = registry
+        .iter()
+        .find(|definition| definition.name() == "Koch")
+        .unwrap();
+    let segments = factory
+        .run_definition(definition.as_ref())
+        .unwrap()
+        .geometry()
+        .segments
+        .len();
+    assert_eq!(segments, 25);
+    assert!(factory.contains(&LSystemKey::new(&koch(), 2)));
+}
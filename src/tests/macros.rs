@@ -0,0 +1,111 @@
+use crate::{lsystem, CompiledLSystem};
+
+#[test]
+fn macro_equals_compiled_script() {
+    let koch = lsystem! {
+        lsystem KochCurve {
+            axiom F;
+            replace F by F+F-F-F+F;
+        }
+    };
+    let compiled = CompiledLSystem::compile(
+        "lsystem KochCurve {
+            axiom F;
+            replace F by F+F-F-F+F;
+        }",
+    );
+
+    assert_eq!(koch.name, "KochCurve");
+    assert_eq!(koch.content_hash(), compiled.lsystem.content_hash());
+    assert_eq!(
+        koch.generate(2).to_string(),
+        compiled.lsystem.generate(2).to_string()
+    );
+}
+
+#[test]
+fn macro_reads_every_statement() {
+    let tree = lsystem! {
+        lsystem FractalBinaryTree {
+            let angle = 0.8;
+            let length = angle * 2;
+            alias X = A;
+            axiom X;
+
+            replace A by B[A]A;
+            replace B(0.5) by BB;
+            replace B(0.5) by B;
+            override B by BB;
+            taper angle, 0.5;
+
+            interpret A as DrawLeaf(length = 0.05, width = length / 2);
+            interpret B as DrawLine(0.1);
+            interpret '[' as PushStack(angle);
+            interpret ']' as PopStack(0..1);
+        }
+    };
+    let compiled = CompiledLSystem::compile(
+        "lsystem FractalBinaryTree {
+            let angle = 0.8;
+            let length = angle * 2;
+            alias X = A;
+            axiom X;
+
+            replace A by B[A]A;
+            replace B(0.5) by BB;
+            replace B(0.5) by B;
+            override B by BB;
+            taper angle, 0.5;
+
+            interpret A as DrawLeaf(length = 0.05, width = length / 2);
+            interpret B as DrawLine(0.1);
+            interpret [ as PushStack(angle);
+            interpret ] as PopStack(0..1);
+        }",
+    );
+
+    assert_eq!(tree.content_hash(), compiled.lsystem.content_hash());
+    assert_eq!(tree.variables(), compiled.lsystem.variables());
+    assert_eq!(tree.taper(), compiled.lsystem.taper());
+}
+
+#[test]
+fn macro_parses_operators_like_the_runtime() {
+    let guard = lsystem! {
+        lsystem Guard {
+            let x = 3 > 2 && 0 || 1 + 2 * 3 == 7;
+            let y = -x * 2;
+            axiom A;
+            interpret A as Guard(3 > 2 && 0, 0 && 1 || 1, 8 - 2 - 1, x);
+        }
+    };
+    let compiled = CompiledLSystem::compile(
+        "lsystem Guard {
+            let x = 3 > 2 && 0 || 1 + 2 * 3 == 7;
+            let y = -x * 2;
+            axiom A;
+            interpret A as Guard(3 > 2 && 0, 0 && 1 || 1, 8 - 2 - 1, x);
+        }",
+    )
+    .lsystem;
+
+    assert_eq!(guard.variables(), compiled.variables());
+    assert_eq!(guard.action_rules("A"), compiled.action_rules("A"));
+
+    let params = &guard.action_rules("A")[0].params;
+    let values = (0..3)
+        .map(|index| params.get(index).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(values, [0.0, 1.0, 5.0]);
+    assert_eq!(
+        params.get_with(3, guard.variables()),
+        Some(1.0),
+        "`x` is `((3 > 2) && 0) || ((1 + 2 * 3) == 7)`"
+    );
+    assert_eq!(guard.variables().get("y"), Some(-2.0));
+}
+
+#[test]
+fn macro_fails_on_unknown_variables() {
+    trybuild::TestCases::new().compile_fail("src/tests/ui/*.rs");
+}
@@ -1,6 +1,9 @@
 use std::vec;
 
-use crate::{abs::*, lexer::*, parser::*, LSystem};
+use crate::{
+    abs::*, lexer::*, parser::*, DefaultAlphabetSymbolDefiner, GrammarWarning, LSystem, RuleKind,
+    Taper, Variables,
+};
 
 #[test]
 fn interpret_simple_action() {
@@ -178,6 +181,154 @@ fn interpret_action_decimal_division() {
     );
 }
 
+#[test]
+fn interpret_action_named_parameters() {
+    let lexer = Lexer::new();
+    let string = String::from(
+        "lsystem LSystemName {
+            interpret A as DrawForward(length = 1.0, width = 0.1 * 2);
+        }",
+    );
+
+    let item = parse(LexedTokens::new(lexer.lex(string)));
+
+    let length = ActionParam::Named("length".into(), P::new(ActionParam::Number(1.0)));
+    let width = ActionParam::Named(
+        "width".into(),
+        P::new(ActionParam::Expression(ExprKind::Binary(
+            BinOpKind::Mul,
+            P::new(ActionParam::Number(0.1)),
+            P::new(ActionParam::Number(2.0)),
+        ))),
+    );
+
+    assert_eq!(
+        item,
+        Item {
+            item_kind: ItemKind::LSystem(
+                "LSystemName".into(),
+                vec![StatementKind::Interpret(
+                    "A".into(),
+                    Action::new("DrawForward".into(), vec![length, width])
+                )]
+            )
+        }
+    );
+
+    let ItemKind::LSystem(_, statements) = &item.item_kind;
+    let StatementKind::Interpret(_, action) = &statements[0] else {
+        panic!("Expected an interpret statement.");
+    };
+    assert_eq!(action.params.get_named("length"), Some(1.0));
+    assert_eq!(action.params.get_named("width"), Some(0.2));
+    assert_eq!(action.params.get_named("color"), None);
+    // Named parameters are found at their position as well.
+    assert_eq!(action.params.get(1), Some(0.2));
+
+    // Replacing a named parameter keeps its name.
+    let instance = crate::ParamsResolver::from_string("3".into()).or(&action.params);
+    assert_eq!(instance.get_named("length"), Some(3.0));
+    assert_eq!(instance.get_named("width"), Some(0.2));
+}
+
+#[test]
+fn interpret_action_operators() {
+    let lexer = Lexer::new();
+    let string = String::from(
+        "lsystem LSystemName {
+            interpret A as Guard(7 % 4, 2 < 3, 2 <= 1, 0 || 3 == 3, 1 >= 2, 1 && 0, 5 != 5, 6 & 3, 6 | 1, 5 ^ 1, 3 > 2);
+        }",
+    );
+
+    let item = parse(LexedTokens::new(lexer.lex(string)));
+    let ItemKind::LSystem(_, statements) = &item.item_kind;
+    let StatementKind::Interpret(_, action) = &statements[0] else {
+        panic!("Expected an interpret statement.");
+    };
+
+    let values = (0..action.params.params.len())
+        .map(|index| action.params.get(index).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        [3.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 2.0, 7.0, 4.0, 1.0]
+    );
+}
+
+#[test]
+fn interpret_action_operator_precedence() {
+    let lexer = Lexer::new();
+    let string = String::from(
+        "lsystem LSystemName {
+            interpret A as Guard(3 > 2 && 0, 0 && 1 || 1, 1 || 0 && 0, 2 * 3 + 1, 1 + 2 * 3, 8 - 2 - 1, 8 / 4 / 2, 1 + 1 == 2, 2 < 3 == 1, 4 - 1 > 2 && 6 & 3 == 2, (1 + 2) * 3, 10 % 4 * 2);
+        }",
+    );
+
+    let item = parse(LexedTokens::new(lexer.lex(string)));
+    let ItemKind::LSystem(_, statements) = &item.item_kind;
+    let StatementKind::Interpret(_, action) = &statements[0] else {
+        panic!("Expected an interpret statement.");
+    };
+
+    let values = (0..action.params.params.len())
+        .map(|index| action.params.get(index).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        [0.0, 1.0, 1.0, 7.0, 7.0, 5.0, 1.0, 1.0, 1.0, 1.0, 9.0, 4.0]
+    );
+}
+
+#[test]
+fn interpret_action_negation() {
+    let lexer = Lexer::new();
+    let string = String::from(
+        "lsystem LSystemName {
+            interpret A as Guard(-5, -(a + 1), a * -2);
+        }",
+    );
+
+    let item = parse(LexedTokens::new(lexer.lex(string)));
+    let ItemKind::LSystem(_, statements) = &item.item_kind;
+    let StatementKind::Interpret(_, action) = &statements[0] else {
+        panic!("Expected an interpret statement.");
+    };
+
+    let negate = |param: ActionParam| {
+        ActionParam::Expression(ExprKind::Binary(
+            BinOpKind::Sub,
+            P::new(ActionParam::Number(0.0)),
+            P::new(param),
+        ))
+    };
+    let a_plus_one = ActionParam::Expression(ExprKind::Binary(
+        BinOpKind::Add,
+        P::new(ActionParam::Constant("a".into())),
+        P::new(ActionParam::Number(1.0)),
+    ));
+    let a_times_minus_two = ActionParam::Expression(ExprKind::Binary(
+        BinOpKind::Mul,
+        P::new(ActionParam::Constant("a".into())),
+        P::new(negate(ActionParam::Number(2.0))),
+    ));
+    assert_eq!(
+        action.params.params,
+        vec![
+            negate(ActionParam::Number(5.0)),
+            negate(a_plus_one),
+            a_times_minus_two
+        ]
+    );
+
+    let mut variables = Variables::new();
+    variables.set("a", 3.0);
+    let values = (0..action.params.params.len())
+        .map(|index| action.params.get_with(index, &variables).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(values, [-5.0, -4.0, -6.0]);
+    assert_eq!(action.params.get(0), Some(-5.0));
+}
+
 #[test]
 fn replace_single_const() {
     let lexer = Lexer::new();
@@ -233,6 +384,142 @@ fn replace_multi_const() {
     );
 }
 
+#[test]
+fn override_rule() {
+    let lexer = Lexer::new();
+    let string = String::from(
+        "lsystem LSystemName {
+            axiom F;
+            replace F by F+F;
+            override F by FF;
+        }",
+    );
+
+    let item = parse(LexedTokens::new(lexer.lex(string)));
+
+    assert_eq!(
+        item,
+        Item {
+            item_kind: ItemKind::LSystem(
+                "LSystemName".into(),
+                vec![
+                    StatementKind::Axiom(String::from("F")),
+                    StatementKind::Replace(String::from("F"), String::from("F+F")),
+                    StatementKind::Override(String::from("F"), String::from("FF"))
+                ]
+            )
+        }
+    );
+
+    let lsystem = LSystemParser::parse(item);
+    assert_eq!(lsystem.generate(1).to_string(), "FF");
+    assert!(lsystem.validate().is_empty());
+}
+
+#[test]
+fn duplicate_replace_statements() {
+    let lexer = Lexer::new();
+    let string = String::from(
+        "lsystem LSystemName {
+            axiom F;
+            replace F by F+F;
+            replace F by FF;
+        }",
+    );
+
+    let item = parse(LexedTokens::new(lexer.lex(string)));
+    let lsystem = LSystemParser::parse(item);
+
+    // The last rule wins, but the duplicate is reported.
+    assert_eq!(lsystem.generate(1).to_string(), "FF");
+    assert_eq!(
+        lsystem.validate(),
+        [GrammarWarning::DuplicateRule {
+            predecessor: "F".into(),
+            rule: RuleKind::Generic
+        }]
+    );
+}
+
+#[test]
+fn alias() {
+    let lexer = Lexer::new();
+    let string = String::from(
+        "lsystem LSystemName {
+            alias X = F;
+            alias p = +;
+            axiom X;
+            replace X by XpX;
+        }",
+    );
+
+    let item = parse(LexedTokens::new(lexer.lex(string)));
+
+    let ItemKind::LSystem(_, statements) = &item.item_kind;
+    assert_eq!(statements[0], StatementKind::Alias('X', 'F'));
+    assert_eq!(statements[1], StatementKind::Alias('p', '+'));
+
+    let lsystem = LSystemParser::parse(item);
+    assert_eq!(lsystem.axiom, "F");
+    assert_eq!(lsystem.generate(2).to_string(), "F+F+F+F");
+}
+
+#[test]
+fn taper() {
+    let lexer = Lexer::new();
+    let string = String::from(
+        "lsystem LSystemName {
+            let decay = 0.5;
+            axiom F;
+            taper decay + 0.25, 0.5;
+        }",
+    );
+
+    let item = parse(LexedTokens::new(lexer.lex(string)));
+    let ItemKind::LSystem(_, statements) = &item.item_kind;
+    assert!(matches!(&statements[2], StatementKind::Taper(factors) if factors.len() == 2));
+
+    let lsystem = LSystemParser::parse(item);
+    assert_eq!(lsystem.taper(), Some(Taper::new(0.75).with_width(0.5)));
+
+    let string = String::from("lsystem LSystemName { axiom F; taper 0.8; }");
+    let lsystem = LSystemParser::parse(parse(LexedTokens::new(lexer.lex(string))));
+    assert_eq!(lsystem.taper(), Some(Taper::new(0.8)));
+}
+
+#[test]
+fn case_policy() {
+    let lexer = Lexer::new();
+    let string = String::from(
+        "lsystem LSystemName {
+            axiom f;
+            replace F by fF;
+        }",
+    );
+    let item = parse(LexedTokens::new(lexer.lex(string)));
+
+    let sensitive = LSystemParser::parse(item.clone());
+    assert_eq!(sensitive.generate(1).to_string(), "f");
+
+    let insensitive = LSystemParser::parse_with_case_policy(
+        item,
+        DefaultAlphabetSymbolDefiner,
+        CasePolicy::Insensitive,
+    );
+    assert_eq!(insensitive.generate(1).to_string(), "FF");
+}
+
+#[test]
+fn aliases_keep_parameters() {
+    let mut aliases = SymbolAliases::new(CasePolicy::Insensitive);
+    aliases.alias('a', 'B');
+
+    assert_eq!(aliases.resolve('a'), 'B');
+    assert_eq!(aliases.resolve('f'), 'F');
+    assert_eq!(aliases.resolve('+'), '+');
+    assert_eq!(aliases.resolve_str("af(x,0.5)[a]"), "BF(x,0.5)[B]");
+}
+
 #[test]
 fn axiom() {
     let lexer = Lexer::new();
@@ -383,7 +670,7 @@ fn parse_parameter_integer_number() {
         Token::Param(')'),
     ]);
 
-    let parsed = parse_module_parameters(&mut tokens);
+    let parsed = parse_module_parameters(&mut tokens).unwrap();
 
     assert_eq!(parsed[0], ActionParam::Number(1.0));
     assert_eq!(parsed[1], ActionParam::Number(20.0));
@@ -401,7 +688,7 @@ fn parse_parameter_integer_flaot_1() {
     ]);
 
     println!("{:?}", tokens.tokens[2]);
-    let parsed = parse_module_parameters(&mut tokens);
+    let parsed = parse_module_parameters(&mut tokens).unwrap();
 
     assert_eq!(parsed[0], ActionParam::Number(0.01));
     assert_eq!(parsed.get(1), None);
@@ -417,7 +704,7 @@ fn parse_parameter_range() {
     ]);
 
     println!("{:?}", tokens.tokens[2]);
-    let parsed = parse_module_parameters(&mut tokens);
+    let parsed = parse_module_parameters(&mut tokens).unwrap();
 
     assert_eq!(
         parsed[0],
@@ -429,3 +716,126 @@ fn parse_parameter_range() {
     );
     assert_eq!(parsed.get(2), None);
 }
+
+#[test]
+fn empty_token_stream() {
+    let tokens = LexedTokens::new(vec![]);
+
+    assert!(tokens.finished());
+}
+
+fn nested_parameter_tokens(depth: usize) -> Vec<Token> {
+    let mut tokens = vec![Token::Param('('); depth + 1];
+    tokens.push(Token::Number(1.0));
+    tokens.extend(vec![Token::Param(')'); depth + 1]);
+    tokens
+}
+
+#[test]
+fn parse_parameter_nested() {
+    let mut tokens = LexedTokens::new(nested_parameter_tokens(10));
+
+    let parsed = parse_module_parameters(&mut tokens).unwrap();
+
+    assert_eq!(parsed, vec![ActionParam::Number(1.0)]);
+    assert!(tokens.max_depth_reached() > 10);
+}
+
+#[test]
+fn parse_parameter_nesting_limit() {
+    let mut tokens = LexedTokens::new(nested_parameter_tokens(100)).with_max_depth(16);
+
+    assert_eq!(
+        parse_module_parameters(&mut tokens),
+        Err(ParseError::TooDeep { max_depth: 16 })
+    );
+}
+
+#[test]
+fn parse_parameter_default_nesting_limit() {
+    let mut tokens = LexedTokens::new(nested_parameter_tokens(10_000));
+
+    assert_eq!(
+        parse_module_parameters(&mut tokens),
+        Err(ParseError::TooDeep {
+            max_depth: DEFAULT_MAX_EXPRESSION_DEPTH
+        })
+    );
+}
+
+#[test]
+fn parse_script_too_deep() {
+    let script = format!(
+        "lsystem Deep {{ axiom F; interpret F as DrawForwardAction({}1{}); }}",
+        "(".repeat(1_000),
+        ")".repeat(1_000)
+    );
+
+    let tokens = LexedTokens::new(Lexer::new().lex(script));
+    assert_eq!(
+        try_parse(tokens),
+        Err(ParseError::TooDeep {
+            max_depth: DEFAULT_MAX_EXPRESSION_DEPTH
+        })
+    );
+}
+
+fn try_parse_script(script: &str) -> Result<Item, ParseError> {
+    try_parse(LexedTokens::new(Lexer::new().lex(script.to_string())))
+}
+
+#[test]
+fn parse_script_unclosed_parentheses() {
+    assert_eq!(
+        try_parse_script("lsystem L { axiom F; interpret F as DrawForwardAction((1 + 2); }"),
+        Err(ParseError::UnclosedParenthesis)
+    );
+}
+
+#[test]
+fn parse_script_stray_operators() {
+    let found = |script: &str| match try_parse_script(script) {
+        Err(ParseError::UnexpectedToken { found, .. }) => found,
+        result => panic!("Expected an unexpected token, found {result:?}"),
+    };
+
+    assert_eq!(
+        found("lsystem L { interpret F as DrawForwardAction(1 +); }"),
+        Some(")".to_string())
+    );
+    assert_eq!(
+        found("lsystem L { interpret F as DrawForwardAction(* 2); }"),
+        Some("*".to_string())
+    );
+    assert_eq!(
+        found("lsystem L { interpret F as DrawForwardAction(1, -); }"),
+        Some(")".to_string())
+    );
+}
+
+#[test]
+fn parse_script_invalid_statements() {
+    assert_eq!(
+        try_parse_script("lsystem L { axiom F; grow F; }"),
+        Err(ParseError::UnexpectedToken {
+            found: Some("grow".to_string()),
+            expected:
+                "a 'let', 'interpret', 'replace', 'override', 'axiom', 'alias' or 'taper' statement",
+        })
+    );
+    assert_eq!(
+        try_parse_script("lsystem L { let x = 1, 2; }"),
+        Err(ParseError::UnexpectedValues {
+            found: 2,
+            expected: "a single value in the let statement. Expected: 'let x = 1.0;'",
+        })
+    );
+    assert!(matches!(
+        try_parse_script("lsystem L { replace F by FF"),
+        Err(ParseError::UnexpectedToken { found: None, .. })
+    ));
+    assert!(matches!(
+        try_parse_script("L { axiom F; }"),
+        Err(ParseError::UnexpectedToken { .. })
+    ));
+}
@@ -0,0 +1,86 @@
+use macaw::Vec2;
+
+use crate::{
+    ActionResolver, DefaultAlphabetSymbolDefiner, LSystem, RunOptions, RuntimeError, Taper,
+    Turtle2D,
+};
+
+use super::{action, drawing_lsystem};
+
+fn plant(axiom: &str) -> LSystem {
+    let mut lsystem = drawing_lsystem(axiom, DefaultAlphabetSymbolDefiner, 1.0, 0.4);
+    lsystem.add_rule("X", "F[+X]F[-X!X]%FF");
+    lsystem.add_action_rule("f", action("MoveForwardAction", &[0.5]));
+    lsystem.add_action_rule("-", action("RotateZAction", &[-0.6]));
+    lsystem.add_action_rule("&", action("PitchDownAction", &[0.5]));
+    lsystem.add_action_rule("!", action("MultiplyWidthAction", &[0.5]));
+    lsystem
+}
+
+#[test]
+fn planar_run_matches_run() {
+    let lsystem = plant("fX");
+    let alphabet = lsystem.generate(3);
+    let options = RunOptions {
+        step: 2.0,
+        taper: Some(Taper::new(0.8)),
+        ..Default::default()
+    };
+
+    let resolver = ActionResolver::with_default_actions();
+    let planar = lsystem.run_planar(&resolver, &alphabet, &options).unwrap();
+    let expected = lsystem
+        .run_with_options(&resolver, &alphabet, &options)
+        .unwrap()
+        .geometry()
+        .to_planar();
+
+    assert!(!planar.is_empty());
+    assert_eq!(planar.segments.len(), expected.segments.len());
+    for (segment, expected) in planar.segments.iter().zip(&expected.segments) {
+        assert!(segment.a.distance(expected.a) < 1e-4);
+        assert!(segment.b.distance(expected.b) < 1e-4);
+        assert!((segment.width - expected.width).abs() < 1e-6);
+        assert_eq!(segment.depth, expected.depth);
+    }
+}
+
+#[test]
+fn planar_turtle() {
+    let mut turtle = Turtle2D::new(Vec2::ZERO);
+    turtle.forward(2.0);
+    turtle.turn(core::f32::consts::FRAC_PI_2);
+    turtle.forward(1.0);
+    assert!(turtle.position.distance(Vec2::new(-1.0, 2.0)) < 1e-6);
+
+    let resolver = ActionResolver::with_default_actions();
+    let lsystem = plant("F&F");
+    let geometry = lsystem
+        .run_planar(&resolver, &lsystem.generate(0), &RunOptions::default())
+        .unwrap();
+    // Pitching leaves the plane and is skipped.
+    assert_eq!(geometry.segments.len(), 2);
+    let (min, max) = geometry.bounds().unwrap();
+    assert!(min.distance(Vec2::new(0.0, -0.5)) < 1e-6);
+    assert!(max.distance(Vec2::new(0.0, 1.5)) < 1e-6);
+
+    let lsystem = plant("F]");
+    let error = lsystem
+        .run_planar(&resolver, &lsystem.generate(0), &RunOptions::default())
+        .err();
+    assert_eq!(error, Some(RuntimeError::UnbalancedBrackets { index: 1 }));
+
+    // Actions are looked up in the resolver, as by `run`.
+    let mut lsystem = plant("FG");
+    lsystem.add_action_rule("G", action("GrowAction", &[]));
+    let error = lsystem
+        .run_planar(&resolver, &lsystem.generate(0), &RunOptions::default())
+        .err();
+    assert_eq!(
+        error,
+        Some(RuntimeError::UnknownAction {
+            symbol: 'G',
+            action: "GrowAction".to_string(),
+        })
+    );
+}
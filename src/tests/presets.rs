@@ -0,0 +1,36 @@
+use crate::presets::{self, Preset};
+
+fn segments((lsystem, resolver, generations): Preset) -> usize {
+    let alphabet = lsystem.generate(generations);
+    let context = lsystem.run(&resolver, &alphabet).unwrap();
+    context.geometry().segments.len()
+}
+
+#[test]
+fn presets_draw() {
+    // Every `F` of the 4th generation of the Koch curve is a segment.
+    assert_eq!(segments(presets::koch_curve()), 5usize.pow(4));
+    // The Hilbert curve of order n visits every cell of a 2^n grid once.
+    assert_eq!(segments(presets::hilbert_curve()), 4usize.pow(5) - 1);
+    assert_eq!(segments(presets::hilbert_curve_3d()), 8usize.pow(3) - 1);
+
+    for preset in [
+        presets::dragon_curve(),
+        presets::sierpinski_triangle(),
+        presets::fractal_plant(),
+        presets::stochastic_bush(0),
+    ] {
+        assert!(segments(preset) > 0);
+    }
+}
+
+#[test]
+fn stochastic_bush_depends_on_seed() {
+    let bush = |seed| {
+        let (lsystem, _, generations) = presets::stochastic_bush(seed);
+        lsystem.generate(generations).to_string()
+    };
+
+    assert_eq!(bush(1), bush(1));
+    assert_ne!(bush(1), bush(2));
+}
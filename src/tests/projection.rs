@@ -0,0 +1,67 @@
+use macaw::{Vec2, Vec3};
+
+use crate::{Geometry, Polygon, Projection, Segment};
+
+fn geometry(segments: &[(Vec3, Vec3)]) -> Geometry {
+    Geometry {
+        segments: segments.iter().map(|&(a, b)| Segment::new(a, b)).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn orthographic_projection() {
+    let geometry = Geometry {
+        polygons: vec![Polygon::new(vec![
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::new(1.0, 2.0, 3.0),
+        ])],
+        ..geometry(&[(Vec3::ZERO, Vec3::new(1.0, 2.0, 3.0))])
+    };
+
+    // The default drops the z coordinates.
+    assert_eq!(
+        Projection::default().project(&geometry),
+        geometry.to_planar()
+    );
+
+    // Seen from the front the z axis points up.
+    let front = Projection::orthographic()
+        .with_direction(Vec3::Y, Vec3::Z)
+        .project(&geometry);
+    assert_eq!(front.segments[0].b, Vec2::new(1.0, 3.0));
+    assert_eq!(front.polygons[0].vertices[2], Vec2::new(1.0, 3.0));
+}
+
+#[test]
+fn perspective_projection() {
+    // Two equally long lines, the second one closer to the viewer looking along -z.
+    let geometry = geometry(&[
+        (Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)),
+        (Vec3::new(-1.0, 1.0, 1.0), Vec3::new(1.0, 1.0, 1.0)),
+    ]);
+
+    let orthographic = Projection::orthographic().project(&geometry);
+    assert_eq!(
+        orthographic.segments[0].length(),
+        orthographic.segments[1].length()
+    );
+
+    let perspective = Projection::perspective(1.0).project(&geometry);
+    assert!(perspective.segments[1].length() > perspective.segments[0].length());
+}
+
+#[test]
+fn projection_fits_drawing() {
+    let geometry = geometry(&[(Vec3::new(-2.0, -2.0, 0.0), Vec3::new(2.0, 2.0, 0.0))]);
+    let fitted = Projection::default()
+        .with_fit(Vec2::new(100.0, 50.0))
+        .project(&geometry);
+
+    // The square keeps its aspect ratio and is centered horizontally.
+    assert_eq!(
+        fitted.bounds(),
+        Some((Vec2::new(25.0, 0.0), Vec2::new(75.0, 50.0)))
+    );
+}
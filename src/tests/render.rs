@@ -0,0 +1,42 @@
+use macaw::{BoundingBox, Vec3, Vec4Swizzles};
+
+use crate::{
+    render::{ascii, Camera},
+    Geometry, Polygon, Segment,
+};
+
+#[test]
+fn camera_fits_bounds() {
+    let bounds = BoundingBox::from_min_max(Vec3::new(1.0, 0.0, 0.0), Vec3::new(3.0, 4.0, 0.0));
+    let camera = Camera::fit(bounds, 2.0);
+    let project = |point: Vec3| (camera.view_projection * point.extend(1.0)).xyz();
+
+    assert!(project(Vec3::new(2.0, 2.0, 0.0)).abs_diff_eq(Vec3::ZERO, 1e-6));
+    // The drawing is taller than wide, thus it fills the height and keeps its aspect ratio.
+    assert!(project(Vec3::new(2.0, 4.0, 0.0)).abs_diff_eq(Vec3::Y, 1e-6));
+    assert!(project(Vec3::new(3.0, 0.0, 0.0)).abs_diff_eq(Vec3::new(0.25, -1.0, 0.0), 1e-6));
+}
+
+#[test]
+fn ascii_draws_lines() {
+    let mut geometry = Geometry::new();
+    geometry.segments.push(Segment::new(Vec3::ZERO, Vec3::X));
+    assert_eq!(ascii::render(&geometry, 4, 1), "⠤⠤⠤⠤");
+    assert_eq!(
+        ascii::render_with(&geometry, 4, 1, ascii::Charset::Blocks),
+        "▄▄▄▄"
+    );
+
+    let mut geometry = Geometry::new();
+    geometry.polygons.push(Polygon::new(vec![
+        Vec3::ZERO,
+        Vec3::X,
+        Vec3::new(1.0, 1.0, 0.0),
+        Vec3::Y,
+    ]));
+    assert_eq!(
+        ascii::render_with(&geometry, 3, 2, ascii::Charset::Blocks),
+        "▄▄▄\n█▄█"
+    );
+    assert_eq!(ascii::render(&Geometry::new(), 2, 2), "⠀⠀\n⠀⠀");
+}
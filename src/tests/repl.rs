@@ -0,0 +1,66 @@
+use crate::{GenerateError, GenerateOptions, Repl, ReplError};
+
+#[test]
+fn derive_grammar_interactively() {
+    let mut repl = Repl::new();
+
+    assert_eq!(repl.eval("axiom F X").unwrap(), "generation 0: FX");
+    assert_eq!(
+        repl.eval("rule X -> X+Y F").unwrap(),
+        "axiom FX\nrule X -> X+YF"
+    );
+    repl.eval("rule Y -> F X-Y").unwrap();
+    assert_eq!(repl.eval("step").unwrap(), "generation 1: FX+YF");
+    assert_eq!(repl.eval("step 1").unwrap(), "generation 2: FX+YF+FX-YF");
+
+    // Edited rules apply to all generations.
+    assert_eq!(
+        repl.eval("rule X -> X").unwrap(),
+        "axiom FX\nrule X -> X\nrule Y -> FX-Y"
+    );
+    assert_eq!(repl.eval("show").unwrap(), "generation 2: FX");
+    assert_eq!(
+        repl.eval("stats").unwrap(),
+        "generation 2: 2 symbols, nesting 0\nF: 1, X: 1"
+    );
+
+    assert_eq!(repl.eval("remove Y").unwrap(), "axiom FX\nrule X -> X");
+    assert_eq!(repl.eval("reset").unwrap(), "generation 0: FX");
+    assert_eq!(repl.eval("  ").unwrap(), "");
+}
+
+#[test]
+fn invalid_repl_commands() {
+    let mut repl = Repl::new()
+        .with_max_print(4)
+        .with_generate_options(GenerateOptions::new().with_max_symbols(100));
+
+    assert_eq!(
+        repl.eval("grow"),
+        Err(ReplError::UnknownCommand("grow".to_string()))
+    );
+    assert!(matches!(
+        repl.eval("rule FF -> F"),
+        Err(ReplError::InvalidArguments { .. })
+    ));
+    assert!(matches!(
+        repl.eval("step many"),
+        Err(ReplError::InvalidArguments { .. })
+    ));
+    assert_eq!(
+        repl.eval("remove G"),
+        Err(ReplError::UnknownRule("G".to_string()))
+    );
+
+    repl.eval("rule F -> FF").unwrap();
+    assert_eq!(
+        repl.eval("step 3").unwrap(),
+        "generation 3: FFFF... (4 more)"
+    );
+    // A failed step keeps the previous generation.
+    assert!(matches!(
+        repl.eval("step 5"),
+        Err(ReplError::Generate(GenerateError::BudgetExceeded { .. }))
+    ));
+    assert_eq!(repl.generation(), 3);
+}
@@ -0,0 +1,38 @@
+use std::{fs, time::Duration};
+
+use crate::{CompileError, ScriptEvent, ScriptHost};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn next_lsystem(host: &ScriptHost) -> Result<crate::LSystem, CompileError> {
+    match host
+        .events()
+        .recv_timeout(TIMEOUT)
+        .expect("The script is reloaded.")
+    {
+        ScriptEvent::Reloaded(lsystem) => lsystem,
+        ScriptEvent::WatchFailed(error) => panic!("Watching failed: {error}"),
+    }
+}
+
+#[test]
+fn script_host_reloads_changed_script() {
+    let directory =
+        std::env::temp_dir().join(format!("lsystem-script-host-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("plant.ls");
+    fs::write(&path, "lsystem Plant { axiom F; replace F by FF; }").unwrap();
+
+    let host = ScriptHost::watch_with_debounce(&path, Duration::from_millis(50)).unwrap();
+    assert_eq!(next_lsystem(&host).unwrap().name, "Plant");
+
+    host.save("lsystem Plant { axiom F; replace F by").unwrap();
+    assert!(matches!(next_lsystem(&host), Err(CompileError::Parse(_))));
+
+    host.save("lsystem Tree { axiom F; }").unwrap();
+    assert_eq!(next_lsystem(&host).unwrap().name, "Tree");
+    assert_eq!(host.read().unwrap(), "lsystem Tree { axiom F; }");
+
+    drop(host);
+    fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,62 @@
+use crate::{
+    ActionResolver, CompiledLSystem, DefaultAlphabetSymbolDefiner, LSystem, RuleKind, Turtle,
+};
+
+const PLANT: &str = "lsystem Plant {
+    axiom X;
+    let angle = 25.0;
+    replace X by F+[[X]-X]-F[-FX]+X;
+    replace F by FF;
+    interpret F as DrawForwardAction(1.0);
+    interpret + as RotateZAction(angle);
+    interpret - as RotateZAction(10.0);
+    interpret [ as PushTranformToStackAction();
+    interpret ] as PopTransformFromStackAction();
+}";
+
+#[test]
+fn lsystem_round_trips_through_json() {
+    let mut lsystem = CompiledLSystem::compile(PLANT).lsystem;
+    lsystem.set_seed(7);
+    lsystem.add_stochastic_rule('Y', 0.5, "FY");
+    lsystem.add_rule_table(2.., [("X", "F")]);
+    lsystem.add_interpretation_rule("Y", "F");
+    lsystem.set_rule_priority("X", RuleKind::Generic, 2);
+
+    let json = serde_json::to_string(&lsystem).unwrap();
+    let deserialized: LSystem = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(deserialized.content_hash(), lsystem.content_hash());
+
+    let resolver: ActionResolver = ActionResolver::with_default_actions();
+    let alphabet = lsystem.generate(3);
+    assert_eq!(deserialized.generate(3), alphabet);
+    assert_eq!(
+        deserialized.run(&resolver, &alphabet).unwrap().geometry(),
+        lsystem.run(&resolver, &alphabet).unwrap().geometry()
+    );
+}
+
+#[test]
+fn callback_rules_fail_to_serialize() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem.add_context_sensitive_rule('A', |_, _, _| Some("AB"));
+
+    let error = serde_json::to_string(&lsystem).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("1 context sensitive rules can not be serialized"));
+}
+
+#[test]
+fn turtle_round_trips_through_json() {
+    let mut turtle = Turtle::new();
+    turtle.rotate_z(30.0);
+    turtle.forward(2.0);
+
+    let json = serde_json::to_string(&turtle).unwrap();
+    let deserialized: Turtle = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(deserialized.origin(), turtle.origin());
+    assert_eq!(deserialized.heading(), turtle.heading());
+}
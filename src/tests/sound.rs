@@ -0,0 +1,121 @@
+use crate::{
+    ActionResolver, DefaultAlphabetSymbolDefiner, LSystem, RuntimeError, SoundEvent, Voices,
+};
+
+use super::action;
+
+fn melody(axiom: &str) -> LSystem {
+    let mut lsystem = LSystem::new(axiom, DefaultAlphabetSymbolDefiner);
+    lsystem.add_action_rule("F", action("NoteAction", &[1.0]));
+    lsystem.add_action_rule("f", action("RestAction", &[0.5]));
+    lsystem.add_action_rule("+", action("TransposeAction", &[2.0]));
+    lsystem.add_action_rule("-", action("TransposeAction", &[-2.0]));
+    lsystem.add_action_rule("[", action("PushVoiceAction", &[]));
+    lsystem.add_action_rule("]", action("PopVoiceAction", &[]));
+    lsystem
+}
+
+#[test]
+fn sound_actions_play_notes() {
+    let lsystem = melody("F+F[+F]f-F");
+    let context = lsystem
+        .run(&ActionResolver::with_sound_actions(), &lsystem.generate(0))
+        .unwrap();
+
+    let notes = context
+        .elements
+        .iter()
+        .filter_map(|event| match event {
+            SoundEvent::NoteOn { time, pitch, .. } => Some((*time, *pitch)),
+            SoundEvent::NoteOff { .. } => None,
+        })
+        .collect::<Vec<_>>();
+    // The branch plays at the same time as the rest and the note after it.
+    assert_eq!(notes, [(0.0, 60), (1.0, 62), (2.0, 64), (2.5, 60)]);
+    assert_eq!(context.get_state::<Voices>().unwrap().voice.time, 3.5);
+}
+
+#[test]
+fn sound_unbalanced_voices() {
+    let lsystem = melody("F]");
+    let error = lsystem
+        .run(&ActionResolver::with_sound_actions(), &lsystem.generate(0))
+        .err();
+    assert_eq!(error, Some(RuntimeError::UnbalancedBrackets { index: 1 }));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn export_midi() {
+    use crate::export::midi::MidiExporter;
+
+    let events = [
+        SoundEvent::NoteOn {
+            time: 0.0,
+            pitch: 60,
+            velocity: 100,
+        },
+        SoundEvent::NoteOff {
+            time: 1.0,
+            pitch: 60,
+        },
+        SoundEvent::NoteOn {
+            time: 1.0,
+            pitch: 62,
+            velocity: 100,
+        },
+        SoundEvent::NoteOff {
+            time: 2.0,
+            pitch: 62,
+        },
+    ];
+    let mut midi = vec![];
+    MidiExporter::new()
+        .export_events(&events, &mut midi)
+        .unwrap();
+
+    assert_eq!(&midi[..14], b"MThd\0\0\0\x06\0\0\0\x01\x01\xe0");
+    assert_eq!(&midi[14..22], b"MTrk\0\0\0\x1d");
+    assert_eq!(
+        &midi[22..],
+        [
+            0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20, // 120 beats per minute
+            0x00, 0x90, 60, 100, // note on
+            0x83, 0x60, 0x80, 60, 0, // note off after 480 ticks
+            0x00, 0x90, 62, 100, // note on
+            0x83, 0x60, 0x80, 62, 0, // note off
+            0x00, 0xff, 0x2f, 0x00, // end of track
+        ]
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn export_midi_zero_duration_note() {
+    use crate::export::midi::MidiExporter;
+
+    let events = [
+        SoundEvent::NoteOn {
+            time: 1.0,
+            pitch: 60,
+            velocity: 100,
+        },
+        SoundEvent::NoteOff {
+            time: 1.0,
+            pitch: 60,
+        },
+    ];
+    let mut midi = vec![];
+    MidiExporter::new()
+        .export_events(&events, &mut midi)
+        .unwrap();
+
+    assert_eq!(
+        &midi[29..],
+        [
+            0x83, 0x60, 0x90, 60, 100, // note on after 480 ticks
+            0x01, 0x80, 60, 0, // note off one tick later
+            0x00, 0xff, 0x2f, 0x00, // end of track
+        ]
+    );
+}
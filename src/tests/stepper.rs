@@ -0,0 +1,98 @@
+use macaw::Vec3;
+
+use crate::{
+    ActionResolver, Breakpoint, DefaultAlphabetSymbolDefiner, ExecutionStepper, LSystem, RunOptions,
+};
+
+use super::drawing_lsystem;
+
+fn branched() -> LSystem {
+    drawing_lsystem("F[+F]F", DefaultAlphabetSymbolDefiner, 2.0, 0.5)
+}
+
+#[test]
+fn step_exposes_actions_and_turtle() {
+    let lsystem = branched();
+    let resolver = ActionResolver::with_default_actions();
+    let alphabet = lsystem.generate(0);
+    let options = RunOptions::default();
+    let mut stepper = ExecutionStepper::new(&lsystem, &resolver, &alphabet, &options);
+
+    let step = stepper.step().unwrap().unwrap();
+    assert_eq!((step.index, step.symbol.char(), step.depth), (0, 'F', 0));
+    assert_eq!(step.actions.len(), 1);
+    assert_eq!(step.actions[0].name, "DrawForwardAction");
+    assert_eq!(step.actions[0].params.get(0), Some(2.0));
+    assert_eq!(step.before.origin(), options.start_position);
+    assert!(step
+        .after
+        .origin()
+        .abs_diff_eq(options.start_position + Vec3::Y * 2.0, 1e-6));
+    assert_eq!(stepper.position(), 1);
+    assert_eq!(stepper.context().turtle_geometry().segments.len(), 1);
+}
+
+#[test]
+fn continue_to_breakpoints() {
+    let lsystem = branched();
+    let resolver = ActionResolver::with_default_actions();
+    let alphabet = lsystem.generate(0);
+    let mut stepper = ExecutionStepper::new(&lsystem, &resolver, &alphabet, &RunOptions::default());
+    stepper.add_breakpoint(Breakpoint::Depth(1));
+
+    // The `[` is in the branch it opens.
+    let step = stepper.continue_to_breakpoint().unwrap().unwrap();
+    assert_eq!((step.index, step.depth), (1, 1));
+    let step = stepper.continue_to_breakpoint().unwrap().unwrap();
+    assert_eq!(step.symbol.char(), '+');
+    assert!(step.before.heading().abs_diff_eq(Vec3::Y, 1e-6));
+    assert!(!step.after.heading().abs_diff_eq(Vec3::Y, 1e-6));
+
+    stepper.remove_breakpoint(Breakpoint::Depth(1));
+    stepper.add_breakpoint(Breakpoint::Symbol('F'));
+    assert_eq!(stepper.breakpoints(), [Breakpoint::Symbol('F')]);
+    let step = stepper.continue_to_breakpoint().unwrap().unwrap();
+    assert_eq!((step.index, step.depth), (3, 1));
+    let step = stepper.continue_to_breakpoint().unwrap().unwrap();
+    assert_eq!((step.index, step.depth), (5, 0));
+    assert!(stepper.continue_to_breakpoint().unwrap().is_none());
+    assert!(stepper.is_finished());
+}
+
+#[test]
+fn stepping_draws_like_run() {
+    let lsystem = branched();
+    let resolver = ActionResolver::with_default_actions();
+    let alphabet = lsystem.generate(0);
+    let mut stepper = ExecutionStepper::new(&lsystem, &resolver, &alphabet, &RunOptions::default());
+
+    stepper.step().unwrap();
+    stepper.step().unwrap();
+    let stepped = stepper.finish().unwrap().geometry();
+    let run = lsystem.run(&resolver, &alphabet).unwrap().geometry();
+    assert_eq!(stepped, run);
+}
+
+#[test]
+fn stepping_resolves_actions_once() {
+    let lsystem = branched();
+    let resolver = ActionResolver::with_default_actions();
+    let alphabet = lsystem.generate(0);
+    let mut stepper = ExecutionStepper::new(&lsystem, &resolver, &alphabet, &RunOptions::default());
+
+    let mut depths = Vec::new();
+    while let Some(step) = stepper.step().unwrap() {
+        depths.push(step.depth);
+    }
+    // The `]` is in the branch it returns to.
+    assert_eq!(depths, [0, 1, 1, 1, 0, 0]);
+
+    // The actions resolved by earlier steps are reused, as by a single run.
+    let run = lsystem.run(&resolver, &alphabet).unwrap();
+    assert_eq!(stepper.context().stats.symbols, run.stats.symbols);
+    assert_eq!(
+        stepper.context().stats.resolved_actions,
+        run.stats.resolved_actions
+    );
+    assert_eq!(stepper.context().geometry(), run.geometry());
+}
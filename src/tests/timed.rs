@@ -0,0 +1,90 @@
+use crate::{
+    Action, ActionParam, ActionResolver, DefaultAlphabetSymbolDefiner, LSystem, ModuleParams,
+    Symbol, TimedRule, TimedRuleError,
+};
+
+/// An apex `A` that grows a segment `F` every time unit, the segment grows to its full length within that unit.
+fn growing_stem() -> LSystem {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem
+        .add_timed_rule('A', TimedRule::new(1.0).with('F', 0.0).with('A', 0.0))
+        .unwrap();
+    lsystem.set_growth_function('F', |age, _| age.min(1.0));
+    lsystem.add_action_rule(
+        "F",
+        Action::new("DrawForwardAction".into(), vec![ActionParam::Number(1.0)]),
+    );
+    lsystem
+}
+
+#[test]
+fn simulate_independent_of_step_size() {
+    let lsystem = growing_stem();
+
+    let mut large = lsystem.timed_axiom();
+    lsystem.simulate(&mut large, 2.5);
+    let mut small = lsystem.timed_axiom();
+    for _ in 0..10 {
+        lsystem.simulate(&mut small, 0.25);
+    }
+
+    for timed in [&large, &small] {
+        assert_eq!(timed.time, 2.5);
+        let symbols = timed.modules.iter().map(|module| module.symbol);
+        assert_eq!(symbols.collect::<String>(), "FFA");
+        let ages = timed.modules.iter().map(|module| module.age);
+        for (age, expected) in ages.zip([1.5, 0.5, 0.5]) {
+            assert!((age - expected).abs() < 1e-5);
+        }
+    }
+}
+
+#[test]
+fn run_timed_grows_geometry() {
+    let lsystem = growing_stem();
+    let resolver: ActionResolver = ActionResolver::with_default_actions();
+
+    let mut timed = lsystem.timed_axiom();
+    lsystem.simulate(&mut timed, 1.5);
+    let alphabet = lsystem.timed_to_alphabet(&timed);
+    assert_eq!(
+        alphabet.symbols[0],
+        Symbol::Module('F', ModuleParams::from(vec![0.5]))
+    );
+    assert_eq!(alphabet.symbols[1], Symbol::Variable('A'));
+
+    lsystem.simulate(&mut timed, 1.0);
+    let segments = lsystem
+        .run_timed(&resolver, &timed)
+        .unwrap()
+        .geometry()
+        .segments;
+    assert_eq!(segments.len(), 2);
+    // The older segment is fully grown.
+    let lengths = segments.iter().map(|segment| segment.a.distance(segment.b));
+    for (length, expected) in lengths.zip([1.0, 0.5]) {
+        assert!((length - expected).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn reject_successors_starting_after_terminal_age() {
+    let mut lsystem = LSystem::new("A", DefaultAlphabetSymbolDefiner);
+    lsystem
+        .add_timed_rule('A', TimedRule::new(1.0).with('B', 2.0))
+        .unwrap();
+
+    let error = lsystem
+        .add_timed_rule('B', TimedRule::new(2.0).with('A', 0.0))
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TimedRuleError {
+            predecessor: 'A',
+            successor: 'B',
+            initial_age: 2.0,
+            terminal_age: 2.0,
+        }
+    );
+    assert!(lsystem.timed_rule('B').is_none());
+}
@@ -0,0 +1,87 @@
+use crate::{
+    ActionResolver, DefaultAlphabetSymbolDefiner, ExecutionEvent, LSystem, RuntimeError,
+    SelectTurtleAction, SpawnTurtleAction, Symbol, TurtleEvent,
+};
+
+use super::{action, drawing_lsystem};
+
+/// Interprets `S` as spawning a turtle and `A`, `B` and `C` as selecting the turtles `0`, `1` and `2`.
+fn swarm(axiom: &str) -> (LSystem, ActionResolver) {
+    let mut lsystem = drawing_lsystem(axiom, DefaultAlphabetSymbolDefiner, 1.0, 1.5707964);
+    lsystem.add_action_rule("-", action("RotateZAction", &[-1.5707964]));
+    lsystem.add_action_rule("S", action("SpawnTurtleAction", &[]));
+    for (symbol, turtle) in [("A", 0.0), ("B", 1.0), ("C", 2.0)] {
+        lsystem.add_action_rule(symbol, action("SelectTurtleAction", &[turtle]));
+    }
+
+    let mut builder = ActionResolver::builder()
+        .with_defaults()
+        .with::<SpawnTurtleAction>(Symbol::Constant('S'));
+    for symbol in ['A', 'B', 'C'] {
+        builder = builder.with::<SelectTurtleAction>(Symbol::Constant(symbol));
+    }
+    (lsystem, builder.build())
+}
+
+fn lines(events: &[TurtleEvent]) -> Vec<(macaw::Vec3, macaw::Vec3)> {
+    events
+        .iter()
+        .filter_map(|event| match *event {
+            TurtleEvent::Line { from, to, .. } => Some((from, to)),
+            TurtleEvent::Branch { .. } | TurtleEvent::BranchEnd { .. } => None,
+        })
+        .collect()
+}
+
+#[test]
+fn turtles_draw_independently() {
+    let (lsystem, resolver) = swarm("FS+FA-FBF");
+    let context = lsystem.run(&resolver, &lsystem.generate(0)).unwrap();
+
+    assert_eq!(context.turtle_count(), 2);
+    assert_eq!(context.active_turtle(), 1);
+    let first = lines(context.turtle_events(0).unwrap());
+    let spawned = lines(context.turtle_events(1).unwrap());
+    assert!(context.turtle_events(2).is_none());
+
+    assert_eq!(first.len(), 2);
+    assert_eq!(spawned.len(), 2);
+    // Both turtles continue where the first line ended, each in its own direction.
+    assert_eq!(spawned[0].0, first[0].1);
+    assert_eq!(first[1].0, first[0].1);
+    assert!((spawned[0].1 - first[1].1).length() > 1.0);
+    // The spawned turtle continues its own path after it is selected again.
+    assert_eq!(spawned[1].0, spawned[0].1);
+    assert_eq!(context.geometry().segments.len(), 4);
+
+    let mut streamed = 0;
+    lsystem
+        .run_with(&resolver, &lsystem.generate(0), |event| {
+            streamed += matches!(event, ExecutionEvent::LineTo { .. }) as usize
+        })
+        .unwrap();
+    assert_eq!(streamed, 4);
+}
+
+#[test]
+fn turtles_have_own_stacks() {
+    let (lsystem, resolver) = swarm("[SF]");
+    let error = lsystem.run(&resolver, &lsystem.generate(0)).err();
+    assert_eq!(error, Some(RuntimeError::UnbalancedBrackets { index: 3 }));
+
+    let (lsystem, resolver) = swarm("[SFA]");
+    assert!(lsystem.run(&resolver, &lsystem.generate(0)).is_ok());
+}
+
+#[test]
+fn select_unknown_turtle() {
+    let (lsystem, resolver) = swarm("FSFC");
+    let error = lsystem.run(&resolver, &lsystem.generate(0)).err();
+    assert_eq!(
+        error,
+        Some(RuntimeError::UnknownTurtle {
+            turtle: 2,
+            index: 3
+        })
+    );
+}
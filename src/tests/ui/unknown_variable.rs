@@ -0,0 +1,27 @@
+use scebpl_system::lsystem;
+
+fn main() {
+    let _ = lsystem! {
+        lsystem Unknown {
+            axiom F;
+            let a = b;
+        }
+    };
+
+    // A variable can only be used after its `let` statement.
+    let _ = lsystem! {
+        lsystem Later {
+            axiom F;
+            let a = b * 2;
+            let b = 1;
+        }
+    };
+
+    let _ = lsystem! {
+        lsystem Taper {
+            axiom F;
+            let width = 0.7;
+            taper factor, width;
+        }
+    };
+}
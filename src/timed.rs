@@ -0,0 +1,159 @@
+//! Timed lsystems, whose modules age continuously instead of being replaced once per generation.
+//!
+//! A module of a timed lsystem is replaced by the successor of its `TimedRule` once its age reaches the terminal age
+//! of the rule, the successor modules start at their initial ages. Growth functions map the age of a module onto the
+//! parameters of its actions, thus the drawing develops smoothly while `LSystem::simulate` advances the time.
+use core::fmt;
+
+use crate::prelude::*;
+
+/// Maps the age and the terminal age of a module onto the first parameter of its actions,
+/// e.g. the length drawn by `DrawForward`, see `LSystem::set_growth_function`.
+///
+/// The terminal age of a module without timed rule is infinite.
+pub type GrowthFunction = fn(f32, f32) -> f32;
+
+/// A module of a timed lsystem and its age.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimedModule {
+    pub symbol: char,
+    pub age: f32,
+}
+
+impl TimedModule {
+    pub fn new(symbol: char, age: f32) -> Self {
+        Self { symbol, age }
+    }
+}
+
+/// A rule replacing a module once it reached the terminal age, see `LSystem::add_timed_rule`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimedRule {
+    terminal_age: f32,
+    successor: Vec<TimedModule>,
+}
+
+impl TimedRule {
+    /// Creates a rule with an empty successor, which removes the module once it is `terminal_age` old.
+    ///
+    /// # Panics
+    /// If `terminal_age` is not positive, the module would be replaced forever.
+    pub fn new(terminal_age: f32) -> Self {
+        assert!(
+            terminal_age > 0.0,
+            "terminal age must be positive, got {terminal_age}"
+        );
+        Self {
+            terminal_age,
+            successor: vec![],
+        }
+    }
+
+    /// Appends `symbol` to the successor, it starts at `initial_age`.
+    pub fn with(mut self, symbol: char, initial_age: f32) -> Self {
+        self.successor.push(TimedModule::new(symbol, initial_age));
+        self
+    }
+
+    /// Appends every symbol of `symbols` to the successor, they start at `initial_age`, e.g. `with_symbols("[+A]", 0.0)`.
+    pub fn with_symbols(mut self, symbols: &str, initial_age: f32) -> Self {
+        self.successor.extend(
+            symbols
+                .chars()
+                .map(|symbol| TimedModule::new(symbol, initial_age)),
+        );
+        self
+    }
+
+    pub fn terminal_age(&self) -> f32 {
+        self.terminal_age
+    }
+
+    pub fn successor(&self) -> &[TimedModule] {
+        &self.successor
+    }
+}
+
+/// A successor module of a timed rule starts at or after the terminal age of its own rule,
+/// simulating would replace it over and over again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimedRuleError {
+    pub predecessor: char,
+    pub successor: char,
+    pub initial_age: f32,
+    pub terminal_age: f32,
+}
+
+impl fmt::Display for TimedRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` of the rule for `{}` starts at age {}, which is not below its terminal age {}",
+            self.successor, self.predecessor, self.initial_age, self.terminal_age
+        )
+    }
+}
+
+impl core::error::Error for TimedRuleError {}
+
+/// The modules of a timed lsystem at a point in time, see `LSystem::timed_axiom` and `LSystem::simulate`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimedAlphabet {
+    pub modules: Vec<TimedModule>,
+    /// The time simulated since the axiom.
+    pub time: f32,
+}
+
+impl TimedAlphabet {
+    /// Ages every module by `dt` and replaces the modules reaching the terminal age of their rule.
+    ///
+    /// The age a module exceeds its terminal age by is added to its successor modules,
+    /// thus the result doesn't depend on the size of the steps.
+    pub(crate) fn advance<'a>(&mut self, dt: f32, rule: impl Fn(char) -> Option<&'a TimedRule>) {
+        let mut modules = Vec::with_capacity(self.modules.len());
+        let mut pending = vec![];
+
+        for module in self.modules.drain(..) {
+            pending.push(TimedModule::new(module.symbol, module.age + dt));
+            while let Some(module) = pending.pop() {
+                match rule(module.symbol) {
+                    Some(rule) if module.age >= rule.terminal_age => {
+                        let overflow = module.age - rule.terminal_age;
+                        pending.extend(rule.successor.iter().rev().map(|successor| {
+                            TimedModule::new(successor.symbol, successor.age + overflow)
+                        }));
+                    }
+                    _ => modules.push(module),
+                }
+            }
+        }
+
+        self.modules = modules;
+        self.time += dt;
+    }
+}
+
+/// Checks that the `successor` modules of `rule` start before the terminal age of `successor_rule`.
+pub(crate) fn check_initial_ages(
+    predecessor: char,
+    rule: &TimedRule,
+    successor: char,
+    successor_rule: &TimedRule,
+) -> Result<(), TimedRuleError> {
+    match rule
+        .successor
+        .iter()
+        .find(|module| module.symbol == successor && module.age >= successor_rule.terminal_age)
+    {
+        Some(module) => Err(TimedRuleError {
+            predecessor,
+            successor,
+            initial_age: module.age,
+            terminal_age: successor_rule.terminal_age,
+        }),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,76 @@
+//! Timing of the phases of an lsystem: compiling, rewriting, running and building the geometry.
+//!
+//! Durations are only measured with the `timing` feature enabled.
+//! Without it every duration is zero and the clock is never queried,
+//! as on `wasm32-unknown-unknown` which has no clock.
+use core::time::Duration;
+#[cfg(all(
+    feature = "timing",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+use std::time::Instant;
+
+use crate::{Alphabet, DerivationTrace};
+
+/// Measures the duration of a phase, see the module documentation.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Stopwatch {
+    #[cfg(all(
+        feature = "timing",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    ))]
+    start: Instant,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(all(
+                feature = "timing",
+                not(all(target_arch = "wasm32", target_os = "unknown"))
+            ))]
+            start: Instant::now(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(all(
+            feature = "timing",
+            not(all(target_arch = "wasm32", target_os = "unknown"))
+        ))]
+        return self.start.elapsed();
+        #[cfg(not(all(
+            feature = "timing",
+            not(all(target_arch = "wasm32", target_os = "unknown"))
+        )))]
+        return Duration::ZERO;
+    }
+}
+
+/// The result of `LSystem::generate_with_report`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GenerationReport {
+    pub alphabet: Alphabet,
+    /// Time spent rewriting all generations.
+    pub rewrite: Duration,
+    /// Time spent applying the interpretation rules and defining the symbols of the final generation.
+    pub interpretation_rules: Duration,
+    /// How every symbol was derived if `GenerateOptions::trace` is enabled.
+    pub trace: Option<DerivationTrace>,
+}
+
+/// Statistics of running an lsystem, see `ExecuteContext::stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RunStats {
+    /// Number of symbols that were run, skipped symbols are not counted.
+    pub symbols: usize,
+    /// Number of actions created by the `ActionResolver`,
+    /// symbols with equal actions and parameters share the action created for the first of them.
+    pub resolved_actions: usize,
+    /// Number of lines that entered occupied space and were not drawn, see `RunOptions::collision`.
+    pub collisions: usize,
+    /// Time spent running the actions of the symbols.
+    pub run: Duration,
+    /// Time spent building the geometry with `ExecuteContext::build_geometry`.
+    pub geometry: Duration,
+}
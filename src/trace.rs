@@ -0,0 +1,94 @@
+//! Maps the symbols of a generation back to the symbols and rules they were produced by.
+use crate::{prelude::*, RuleKind};
+
+/// The symbol of the previous generation a symbol was produced from, see `DerivationTrace`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TracedSymbol {
+    /// Index of the parent symbol in the previous generation.
+    pub parent: usize,
+    /// The kind of rule that rewrote the parent, `None` if the parent was copied unchanged.
+    pub rule: Option<RuleKind>,
+}
+
+/// Records how every symbol was derived, enable it with `GenerateOptions::with_trace`.
+///
+/// Editors use this to answer which rule produced a branch by walking from a symbol to its ancestors.
+/// Parameters of a module, e.g. `(1,2)` in `A(1,2)`, have the same parent as the module.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DerivationTrace {
+    /// The parents of the symbols of every rewritten generation,
+    /// `steps[0]` maps the symbols of generation 1 onto the axiom.
+    pub steps: Vec<Vec<TracedSymbol>>,
+    /// Maps the symbols of the final alphabet onto the symbols of the last generation
+    /// if interpretation rules were applied.
+    pub interpretation: Option<Vec<usize>>,
+}
+
+impl DerivationTrace {
+    /// Returns the number of rewritten generations.
+    pub fn generations(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns the parent of the symbol at `index` of `generation`, `None` for the axiom.
+    pub fn parent(&self, generation: usize, index: usize) -> Option<TracedSymbol> {
+        self.steps
+            .get(generation.checked_sub(1)?)?
+            .get(index)
+            .copied()
+    }
+
+    /// Returns the indices of the ancestors of the symbol at `index` of `generation`,
+    /// starting with its parent and ending with a symbol of the axiom.
+    pub fn ancestors(&self, generation: usize, index: usize) -> Vec<usize> {
+        let mut ancestors = Vec::with_capacity(generation);
+        let mut index = index;
+
+        for generation in (1..=generation).rev() {
+            match self.parent(generation, index) {
+                Some(parent) => {
+                    index = parent.parent;
+                    ancestors.push(index);
+                }
+                None => break,
+            }
+        }
+
+        ancestors
+    }
+
+    /// Returns the generation every symbol of the final alphabet was produced in, `0` for symbols of the axiom.
+    ///
+    /// Symbols copied unchanged keep the generation of their parent, symbols written by interpretation rules
+    /// the generation of the symbol they interpret. Run the alphabet with `ExecuteContext::with_origins` to
+    /// make them available to actions.
+    pub fn origins(&self) -> Vec<u8> {
+        let mut origins = Vec::<u8>::new();
+        for (step, symbols) in self.steps.iter().enumerate() {
+            let generation = u8::try_from(step + 1).unwrap_or(u8::MAX);
+            origins = symbols
+                .iter()
+                .map(|symbol| match symbol.rule {
+                    Some(_) => generation,
+                    None => origins.get(symbol.parent).copied().unwrap_or(0),
+                })
+                .collect();
+        }
+
+        match &self.interpretation {
+            Some(interpretation) => interpretation
+                .iter()
+                .map(|&index| origins.get(index).copied().unwrap_or(0))
+                .collect(),
+            None => origins,
+        }
+    }
+
+    /// Returns the index in the last generation of the symbol at `index` of the final alphabet.
+    pub fn derived_index(&self, index: usize) -> Option<usize> {
+        match &self.interpretation {
+            Some(interpretation) => interpretation.get(index).copied(),
+            None => Some(index),
+        }
+    }
+}
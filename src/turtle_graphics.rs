@@ -1,30 +1,111 @@
-use std::collections::VecDeque;
+use alloc::collections::VecDeque;
 
-use macaw::{Mat4, Quat, Vec3};
+use macaw::{Mat3, Mat4, Quat, Vec3};
+
+// Only used if `std` is not linked, see `Float`.
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use crate::prelude::Float;
+use crate::Color;
+
+/// The number of rotations after which the frame of the turtle is made orthonormal again.
+const ORTHONORMALIZE_INTERVAL: u32 = 16;
 
 /// A turtle that can be moved arround..
 /// A turtle is an entity moving relative to it self.
 /// Google 'turtle graphics' for more information.
+///
+/// The orientation is the orthonormal frame of ABOP: the `heading` the turtle moves towards,
+/// its `left` and its `up`, pointing out of the drawing plane.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Turtle {
-    /// The turtle's rotation/orientation.
-    rotation: Mat4,
-    scale: Mat4,
+    heading: Vec3,
+    left: Vec3,
+    up: Vec3,
+    /// Scale of every forward move.
+    scale: f32,
     origin: Vec3,
+    /// The number of rotations since the frame was last made orthonormal, see `orthonormalize`.
+    rotations: u32,
+    /// The width of the lines drawn by the turtle.
+    width: f32,
+    /// The color of the lines drawn by the turtle.
+    color: Color,
+    /// The index of `color` in the palette of the context, see `ExecuteContext::increment_color`.
+    color_index: usize,
+    /// The world space direction the heading bends towards after every move, see `set_tropism`.
+    tropism: Vec3,
+    elasticity: f32,
 }
 
 impl Turtle {
+    /// Creates a turtle at `(0, -0.5, 0)` heading `+Y` with up `+Z`.
     pub fn new() -> Self {
+        Self::with_pose(Vec3::new(0.0, -0.5, 0.0), Vec3::Y, Vec3::Z)
+    }
+
+    /// Creates a turtle at `position` moving towards `heading`, with `up` pointing out of its drawing plane.
+    /// `up` is made perpendicular to `heading`, the default turtle has heading `+Y` and up `+Z`.
+    pub fn with_pose(position: Vec3, heading: Vec3, up: Vec3) -> Self {
+        let heading = heading.try_normalize().unwrap_or(Vec3::Y);
+        let up = (up - heading * up.dot(heading))
+            .try_normalize()
+            .unwrap_or_else(|| heading.any_orthonormal_vector());
+
         Self {
-            rotation: Mat4::IDENTITY,
-            scale: Mat4::IDENTITY,
-            origin: Vec3::new(0.0, -0.5, 0.0),
+            heading,
+            left: up.cross(heading),
+            up,
+            scale: 1.0,
+            origin: position,
+            rotations: 0,
+            width: 1.0,
+            color: Color::BLACK,
+            color_index: 0,
+            tropism: Vec3::ZERO,
+            elasticity: 0.0,
         }
     }
 
-    /// Moves the turtle forward for its relative orientation.
+    /// Moves the turtle forward along its heading.
+    ///
+    /// Afterwards the heading bends towards the tropism direction, see `set_tropism`.
     pub fn forward(&mut self, len: f32) {
-        self.origin += self.transform(Vec3::new(0.0, len, 0.0));
+        self.origin += self.heading * len * self.scale;
+        self.bend();
+    }
+
+    /// Bends the heading towards `direction` after every move, e.g. `-Z` for gravity or a light source.
+    ///
+    /// Like the tropism of ABOP the heading `H` is rotated by `elasticity * |H x direction|`
+    /// around `H x direction`, thus a longer `direction` bends stronger.
+    /// An `elasticity` of `0.0` disables the tropism.
+    pub fn set_tropism(&mut self, direction: Vec3, elasticity: f32) {
+        self.tropism = direction;
+        self.elasticity = elasticity;
+    }
+
+    /// Returns the tropism direction and elasticity, see `set_tropism`.
+    pub fn tropism(&self) -> (Vec3, f32) {
+        (self.tropism, self.elasticity)
+    }
+
+    fn bend(&mut self) {
+        if self.elasticity == 0.0 {
+            return;
+        }
+
+        let axis = self.heading.cross(self.tropism);
+        let Some(normal) = axis.try_normalize() else {
+            // The heading is parallel to the tropism.
+            return;
+        };
+        let rotation = Quat::from_axis_angle(normal, self.elasticity * axis.length());
+        self.heading = rotation * self.heading;
+        self.left = rotation * self.left;
+        self.up = rotation * self.up;
+        self.rotated();
     }
 
     /// Sets the origin of the turtle.
@@ -33,22 +114,25 @@ impl Turtle {
     }
 
     pub fn scale(&mut self, scale: f32) {
-        self.scale *= Mat4::from_scale(Vec3::new(scale, scale, scale));
+        self.scale *= scale;
     }
 
-    /// Rotates the trurtle arround the z axis.
+    /// Rotates the trurtle arround the z axis, its up direction, turning left for positive angles.
     pub fn rotate_z(&mut self, rotation_angle: f32) {
-        self.rotation *= Mat4::from_rotation_z(rotation_angle);
+        (self.heading, self.left) = rotate(self.heading, self.left, rotation_angle);
+        self.rotated();
     }
 
-    /// Rotates the trurtle arround the x axis.
+    /// Rotates the trurtle arround the x axis, its right direction, pitching up for positive angles.
     pub fn rotate_x(&mut self, rotation_angle: f32) {
-        self.rotation *= Mat4::from_rotation_x(rotation_angle);
+        (self.heading, self.up) = rotate(self.heading, self.up, rotation_angle);
+        self.rotated();
     }
 
-    /// Rotates the trurtle arround the y axis.
+    /// Rotates the trurtle arround the y axis, its heading, rolling left for positive angles.
     pub fn rotate_y(&mut self, rotation_angle: f32) {
-        self.rotation *= Mat4::from_rotation_y(rotation_angle);
+        (self.left, self.up) = rotate(self.left, self.up, rotation_angle);
+        self.rotated();
     }
 
     /// Returns the origin position of the turret.
@@ -56,14 +140,95 @@ impl Turtle {
         self.origin
     }
 
+    /// Returns the direction the turtle moves forward in.
+    pub fn heading(&self) -> Vec3 {
+        self.heading
+    }
+
+    pub fn left(&self) -> Vec3 {
+        self.left
+    }
+
+    /// Returns the direction pointing out of the drawing plane of the turtle.
+    pub fn up(&self) -> Vec3 {
+        self.up
+    }
+
     /// Transform the given position by applying the rotation and scale.
+    ///
+    /// The axes of `position` are the right, the heading and the up direction of the turtle.
     pub fn transform(&self, position: Vec3) -> Vec3 {
-        self.rotation().mul_vec3(position)
+        (self.heading * position.y + self.up * position.z - self.left * position.x) * self.scale
     }
 
+    /// Returns the rotation from the default turtle frame, heading `+Y` and up `+Z`, to this frame.
     pub fn rotation(&self) -> Quat {
-        Quat::from_mat4(&self.rotation)
+        Quat::from_mat3(&Mat3::from_cols(-self.left, self.heading, self.up))
     }
+
+    /// Returns the transform from the default turtle frame at the origin to this turtle, including its scale.
+    pub fn to_mat4(&self) -> Mat4 {
+        Mat4::from_cols(
+            (-self.left * self.scale).extend(0.0),
+            (self.heading * self.scale).extend(0.0),
+            (self.up * self.scale).extend(0.0),
+            self.origin.extend(1.0),
+        )
+    }
+
+    /// Counts a rotation and makes the frame orthonormal again every few rotations,
+    /// the rounding errors of the rotations would skew it otherwise.
+    fn rotated(&mut self) {
+        self.rotations += 1;
+        if self.rotations >= ORTHONORMALIZE_INTERVAL {
+            self.orthonormalize();
+        }
+    }
+
+    fn orthonormalize(&mut self) {
+        self.rotations = 0;
+        self.heading = self.heading.normalize();
+        self.left = self.up.cross(self.heading).normalize();
+        self.up = self.heading.cross(self.left);
+    }
+
+    /// Returns the width of the lines drawn by the turtle.
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn set_width(&mut self, width: f32) {
+        self.width = width;
+    }
+
+    /// Returns the color of the lines drawn by the turtle.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Returns the palette index of the color, see `ExecuteContext::set_color_index`.
+    pub fn color_index(&self) -> usize {
+        self.color_index
+    }
+
+    /// Sets the palette index and the color at that index, the index wraps around the palette.
+    /// The color is kept if the palette is empty.
+    pub fn set_color_index(&mut self, index: usize, palette: &[Color]) {
+        self.color_index = index;
+        if !palette.is_empty() {
+            self.color = palette[index % palette.len()];
+        }
+    }
+}
+
+/// Rotates `a` towards `b` by `angle` in the plane of both, `b` is rotated away from `a` by the same angle.
+fn rotate(a: Vec3, b: Vec3, angle: f32) -> (Vec3, Vec3) {
+    let (sin, cos) = angle.sin_cos();
+    (a * cos + b * sin, b * cos - a * sin)
 }
 
 impl Default for Turtle {
@@ -74,6 +239,8 @@ impl Default for Turtle {
 
 /// The turtle transform stack stores turtle transforms for a given L-system.
 /// Many L-systems use a transform stack to reset to a stored transform at the end of a recursion path.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TurtleTransformStack {
     transforms: VecDeque<Turtle>,
 }
@@ -88,11 +255,16 @@ impl TurtleTransformStack {
         self.transforms.push_back(transform);
     }
 
-    pub fn pop(&mut self) -> Turtle {
-        self.transforms.pop_back().unwrap()
+    /// Returns the last pushed transform, `None` if the stack is empty.
+    pub fn pop(&mut self) -> Option<Turtle> {
+        self.transforms.pop_back()
     }
 
-    pub fn len(&mut self) -> usize {
+    pub fn len(&self) -> usize {
         self.transforms.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
 }
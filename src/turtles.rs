@@ -0,0 +1,121 @@
+//! Several turtles driven by the symbols of one run, e.g. for swarms or branches growing in parallel,
+//! see `ExecuteContext::spawn_turtle`.
+use macaw::Vec3;
+
+use crate::{prelude::*, ExecuteContext, RuntimeError, Turtle, TurtleEvent, TurtleTransformStack};
+
+/// The state of a turtle while another turtle is selected, see `ExecuteContext::select_turtle`.
+///
+/// The selected turtle keeps its state in the fields of the context, its slot is empty.
+pub(crate) struct ParkedTurtle {
+    pub(crate) turtle: Turtle,
+    pub(crate) transform_stack: TurtleTransformStack,
+    pub(crate) events: Vec<TurtleEvent>,
+    pub(crate) recorded: Option<(Vec3, usize)>,
+    /// The branches of the turtle opened while testing for collisions, see `CollisionState::branches`.
+    pub(crate) branches: Vec<(usize, usize)>,
+}
+
+impl ParkedTurtle {
+    pub(crate) fn new(turtle: Turtle, recorded: Option<(Vec3, usize)>) -> Self {
+        Self {
+            turtle,
+            transform_stack: TurtleTransformStack::new(),
+            events: vec![],
+            recorded,
+            branches: vec![],
+        }
+    }
+}
+
+impl<E> ExecuteContext<E> {
+    /// Adds a turtle with the pose and pen of the selected turtle and returns its index,
+    /// the first turtle of a run has index `0`.
+    ///
+    /// The new turtle has its own transform stack and `TurtleEvent`s, its first line starts where it was spawned.
+    /// It is not selected, see `select_turtle` and `SpawnTurtleAction`.
+    pub fn spawn_turtle(&mut self) -> usize {
+        self.record();
+        let origin = self.turtle.origin();
+        self.turtles
+            .push(ParkedTurtle::new(self.turtle, Some((origin, 0))));
+        self.turtles.len() - 1
+    }
+
+    /// Selects the turtle at `index`, the following actions move it and draw with it
+    /// until another turtle is selected. The other turtles keep their state.
+    ///
+    /// Fails with `RuntimeError::UnknownTurtle` if no turtle was spawned at `index`.
+    pub fn select_turtle(&mut self, index: usize) -> Result<(), RuntimeError> {
+        if index >= self.turtles.len() {
+            return Err(RuntimeError::UnknownTurtle {
+                turtle: index,
+                index: self.index,
+            });
+        }
+        if index != self.active_turtle {
+            self.record();
+            self.swap_turtle(self.active_turtle);
+            self.swap_turtle(index);
+            self.active_turtle = index;
+        }
+        Ok(())
+    }
+
+    /// Returns the index of the selected turtle.
+    pub fn active_turtle(&self) -> usize {
+        self.active_turtle
+    }
+
+    /// Returns the number of turtles, at least the first one.
+    pub fn turtle_count(&self) -> usize {
+        self.turtles.len()
+    }
+
+    /// Returns the events recorded by the turtle at `index`, `None` if there is no such turtle.
+    ///
+    /// The events of the selected turtle are `events`.
+    pub fn turtle_events(&self, index: usize) -> Option<&[TurtleEvent]> {
+        if index == self.active_turtle {
+            Some(&self.events)
+        } else {
+            self.turtles.get(index).map(|slot| slot.events.as_slice())
+        }
+    }
+
+    /// Returns the events recorded by all turtles, turtle by turtle.
+    pub(crate) fn all_turtle_events(&self) -> impl Iterator<Item = &TurtleEvent> {
+        (0..self.turtles.len()).flat_map(|index| {
+            self.turtle_events(index)
+                .expect("Every turtle has events")
+                .iter()
+        })
+    }
+
+    /// Moves the events recorded by all turtles out of the context, turtle by turtle.
+    pub(crate) fn take_turtle_events(&mut self) -> Vec<TurtleEvent> {
+        let mut events = vec![];
+        for index in 0..self.turtles.len() {
+            if index == self.active_turtle {
+                events.append(&mut self.events);
+            } else {
+                events.append(&mut self.turtles[index].events);
+            }
+        }
+        events
+    }
+
+    /// Exchanges the state of the selected turtle with the slot at `index`.
+    pub(crate) fn swap_turtle(&mut self, index: usize) {
+        let slot = &mut self.turtles[index];
+        core::mem::swap(&mut self.turtle, &mut slot.turtle);
+        core::mem::swap(&mut self.transform_stack, &mut slot.transform_stack);
+        core::mem::swap(&mut self.events, &mut slot.events);
+        core::mem::swap(&mut self.recorded, &mut slot.recorded);
+        if let Some(state) = &mut self.collision {
+            core::mem::swap(&mut state.branches, &mut slot.branches);
+            // The lines hashed so far may belong to other turtles, branches opened before must not remove them.
+            state.shared = state.lines.len();
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//! Widgets for egui apps that edit and preview lsystem scripts.
+use std::{ops::RangeInclusive, sync::Arc};
+
+use egui::mutex::Mutex;
+use macaw::BoundingBox;
+
+use crate::{
+    render::{glow::Renderer, Camera},
+    source_hash, ActionResolver, Alphabet, CompiledLSystem, GenerateOptions, LSystem,
+};
+
+/// A script editor next to a preview of the drawing, for apps built with eframe and its glow backend.
+///
+/// The script is recompiled whenever it effectively changes, see `source_hash`.
+/// Errors of compiling, generating and running are shown below the editor while the preview keeps the last drawing.
+/// A slider selects the number of generations and every `let` variable of the script gets its own slider,
+/// moving these only regenerates or reruns what changed.
+///
+/// GL objects are not freed on drop, call `destroy` from `eframe::App::on_exit`.
+pub struct LSystemEditorWidget {
+    gl: Arc<glow::Context>,
+    script: String,
+    resolver: ActionResolver,
+    generate_options: GenerateOptions,
+    generations: u8,
+    max_generations: u8,
+    /// Width of the editor column in `show`, the preview fills the rest.
+    editor_width: f32,
+    line_width: f32,
+    /// The script that compiled last, `None` until a script compiled.
+    compiled: Option<CompiledLSystem>,
+    alphabet: Option<Alphabet>,
+    parameters: Vec<Parameter>,
+    error: Option<String>,
+    /// Shared with the paint callback, which runs after `show` returned.
+    renderer: Arc<Mutex<Option<Renderer>>>,
+    bounds: Option<BoundingBox>,
+}
+
+/// A `let` variable of the script bound to a slider.
+#[derive(Clone)]
+struct Parameter {
+    name: String,
+    /// The value assigned in the script.
+    default: f32,
+    value: f32,
+    range: RangeInclusive<f32>,
+}
+
+impl Parameter {
+    /// The slider reaches from zero to twice the value of the script, on the side of its sign.
+    fn new(name: String, default: f32) -> Self {
+        let span = if default == 0.0 {
+            1.0
+        } else {
+            default.abs() * 2.0
+        };
+        let range = if default < 0.0 {
+            -span..=0.0
+        } else {
+            0.0..=span
+        };
+
+        Self {
+            name,
+            default,
+            value: default,
+            range,
+        }
+    }
+}
+
+impl LSystemEditorWidget {
+    /// Creates the editor for `script` and compiles it, the drawing uses the default actions.
+    pub fn new(gl: Arc<glow::Context>, script: impl Into<String>) -> Self {
+        let mut widget = Self {
+            gl,
+            script: script.into(),
+            resolver: ActionResolver::with_default_actions(),
+            generate_options: GenerateOptions::new().with_max_symbols(1_000_000),
+            generations: 3,
+            max_generations: 12,
+            editor_width: 300.0,
+            line_width: 0.01,
+            compiled: None,
+            alphabet: None,
+            parameters: Vec::new(),
+            error: None,
+            renderer: Arc::new(Mutex::new(None)),
+            bounds: None,
+        };
+        widget.recompile();
+        widget
+    }
+
+    /// Draws with the actions of `resolver` instead of the default actions.
+    pub fn with_resolver(mut self, resolver: ActionResolver) -> Self {
+        self.resolver = resolver;
+        self.redraw();
+        self
+    }
+
+    /// Limits generation so exponential scripts can not freeze the app, by default to one million symbols.
+    pub fn with_generate_options(mut self, generate_options: GenerateOptions) -> Self {
+        self.generate_options = generate_options;
+        self.regenerate();
+        self
+    }
+
+    pub fn with_generations(mut self, generations: u8) -> Self {
+        self.generations = generations.min(self.max_generations);
+        self.regenerate();
+        self
+    }
+
+    /// Sets the maximum of the generation slider, `12` by default.
+    pub fn with_max_generations(mut self, max_generations: u8) -> Self {
+        self.max_generations = max_generations;
+        if self.generations > max_generations {
+            self.generations = max_generations;
+            self.regenerate();
+        }
+        self
+    }
+
+    pub fn with_editor_width(mut self, editor_width: f32) -> Self {
+        self.editor_width = editor_width;
+        self
+    }
+
+    /// Sets the width of a segment with width `1.0` in the preview, see `Renderer::set_line_width`.
+    pub fn with_line_width(mut self, line_width: f32) -> Self {
+        self.line_width = line_width;
+        if let Some(renderer) = self.renderer.lock().as_mut() {
+            renderer.set_line_width(line_width);
+        }
+        self
+    }
+
+    pub fn script(&self) -> &str {
+        &self.script
+    }
+
+    /// Replaces the script and recompiles it.
+    pub fn set_script(&mut self, script: impl Into<String>) {
+        self.script = script.into();
+        self.recompile();
+    }
+
+    pub fn generations(&self) -> u8 {
+        self.generations
+    }
+
+    /// The error of the last compile, generate or run, `None` if the preview shows the current script.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// The last generated alphabet.
+    pub fn alphabet(&self) -> Option<&Alphabet> {
+        self.alphabet.as_ref()
+    }
+
+    /// The lsystem of the script that compiled last, with the values of the parameter sliders.
+    pub fn lsystem(&self) -> Option<&LSystem> {
+        self.compiled.as_ref().map(|compiled| &compiled.lsystem)
+    }
+
+    /// Shows the editor on the right of the preview.
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_top(|ui| {
+            let preview_width = (ui.available_width() - self.editor_width).max(0.0);
+            let size = egui::vec2(preview_width, ui.available_height());
+            ui.allocate_ui(size, |ui| self.show_preview(ui));
+
+            ui.vertical(|ui| {
+                ui.set_width(self.editor_width);
+                self.show_editor(ui);
+            });
+        });
+    }
+
+    /// Shows the script editor, the generation and parameter sliders and the last error.
+    pub fn show_editor(&mut self, ui: &mut egui::Ui) {
+        let edited = ui
+            .add(
+                egui::TextEdit::multiline(&mut self.script)
+                    .code_editor()
+                    .desired_width(f32::INFINITY),
+            )
+            .changed();
+        if edited {
+            self.recompile();
+        }
+
+        let generations = ui
+            .add(
+                egui::Slider::new(&mut self.generations, 0..=self.max_generations)
+                    .text("generations"),
+            )
+            .changed();
+        if generations {
+            self.regenerate();
+        }
+
+        let mut moved = false;
+        for parameter in &mut self.parameters {
+            let slider = egui::Slider::new(&mut parameter.value, parameter.range.clone())
+                .text(&parameter.name);
+            moved |= ui.add(slider).changed();
+        }
+        if moved {
+            self.redraw();
+        }
+
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+    }
+
+    /// Shows the drawing fitted into the available space.
+    pub fn show_preview(&mut self, ui: &mut egui::Ui) {
+        let (rect, _) = ui.allocate_exact_size(ui.available_size(), egui::Sense::hover());
+
+        let camera = self
+            .bounds
+            .map(|bounds| Camera::fit(bounds, rect.aspect_ratio()))
+            .unwrap_or_default();
+        let renderer = self.renderer.clone();
+
+        let callback = egui::PaintCallback {
+            rect,
+            callback: Arc::new(egui_glow::CallbackFn::new(move |_info, painter| {
+                if let Some(renderer) = renderer.lock().as_mut() {
+                    renderer.paint(painter.gl(), &camera);
+                }
+            })),
+        };
+        ui.painter().add(callback);
+    }
+
+    /// Frees the GL objects of the preview.
+    pub fn destroy(&mut self, gl: &glow::Context) {
+        if let Some(renderer) = self.renderer.lock().take() {
+            renderer.destroy(gl);
+        }
+    }
+
+    /// Compiles the script if it effectively changed and regenerates it.
+    pub fn recompile(&mut self) {
+        let hash = source_hash(&self.script);
+        if self
+            .compiled
+            .as_ref()
+            .is_some_and(|compiled| compiled.hash == hash)
+        {
+            // An edit reverted to the compiled script leaves the error of the invalid script behind,
+            // regenerating reports the errors of the compiled script again.
+            if self.error.take().is_some() {
+                self.regenerate();
+            }
+            return;
+        }
+
+        // Invalid scripts are common while typing.
+        let compiled = match CompiledLSystem::try_compile(&self.script) {
+            Ok(compiled) => compiled,
+            Err(error) => {
+                self.error = Some(error.to_string());
+                return;
+            }
+        };
+
+        // Keeps the slider of a variable whose value in the script did not change.
+        let mut variables = compiled
+            .lsystem
+            .variables()
+            .iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect::<Vec<_>>();
+        variables.sort_by(|a, b| a.0.cmp(&b.0));
+        let previous = std::mem::take(&mut self.parameters);
+        self.parameters = variables
+            .into_iter()
+            .map(|(name, default)| {
+                previous
+                    .iter()
+                    .find(|parameter| parameter.name == name && parameter.default == default)
+                    .cloned()
+                    .unwrap_or_else(|| Parameter::new(name, default))
+            })
+            .collect();
+
+        self.compiled = Some(compiled);
+        self.regenerate();
+    }
+
+    /// Generates the alphabet of the compiled script and redraws it.
+    fn regenerate(&mut self) {
+        let Some(compiled) = &self.compiled else {
+            return;
+        };
+
+        match compiled
+            .lsystem
+            .generate_with(self.generations, &self.generate_options)
+        {
+            Ok(alphabet) => {
+                self.alphabet = Some(alphabet);
+                self.redraw();
+            }
+            Err(error) => self.error = Some(error.to_string()),
+        }
+    }
+
+    /// Runs the actions of the alphabet with the values of the parameter sliders and uploads the drawing.
+    fn redraw(&mut self) {
+        let (Some(compiled), Some(alphabet)) = (&mut self.compiled, &self.alphabet) else {
+            return;
+        };
+
+        for parameter in &self.parameters {
+            compiled
+                .lsystem
+                .set_variable(parameter.name.clone(), parameter.value);
+        }
+
+        let geometry = match compiled.lsystem.run(&self.resolver, alphabet) {
+            Ok(context) => context.geometry(),
+            Err(error) => {
+                self.error = Some(error.to_string());
+                return;
+            }
+        };
+        let mut renderer = match Renderer::new(&self.gl, &geometry) {
+            Ok(renderer) => renderer,
+            Err(error) => {
+                self.error = Some(error.to_string());
+                return;
+            }
+        };
+        renderer.set_line_width(self.line_width);
+
+        self.error = None;
+        self.bounds = geometry.bounds();
+        if let Some(previous) = self.renderer.lock().replace(renderer) {
+            previous.destroy(&self.gl);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! The values of the variables used by action parameters, `interpret + as Rotate(angle);`.
+use crate::prelude::*;
+
+/// Maps variable names to their values, filled by `let` statements and `LSystem::set_variable`.
+///
+/// Action parameters also see the implicit variables `depth` and `gen` of the running symbol unless they are set here,
+/// see `ExecuteContext::depth` and `ExecuteContext::generation`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Variables {
+    values: HashMap<String, f32>,
+}
+
+impl Variables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of `name`, replacing its previous value.
+    pub fn set(&mut self, name: impl Into<String>, value: f32) {
+        self.values.insert(name.into(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<f32> {
+        self.values.get(name).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+    }
+}
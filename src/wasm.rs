@@ -0,0 +1,86 @@
+//! Bindings for JavaScript, e.g. to power an lsystem playground in the browser.
+//!
+//! The crate is a `rlib`, the module loaded by JavaScript is built as `cdylib` for wasm only:
+//!
+//! ```sh
+//! cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/scebpl_system.wasm
+//! ```
+//!
+//! Then:
+//!
+//! ```js
+//! compile("lsystem Koch { axiom F; replace F by F+F-F-F+F; interpret F as DrawForwardAction(1.0); }");
+//! generate(3);
+//! const geometry = JSON.parse(geometry_json());
+//! ```
+//!
+//! The bindings keep one compiled script, it is drawn with the default actions.
+//! The parser panics on invalid scripts, which aborts the module on `wasm32-unknown-unknown`,
+//! install a panic hook like `console_error_panic_hook` to see the message in the console.
+use std::cell::RefCell;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{ActionResolver, Alphabet, CompiledLSystem, GenerateOptions, LSystem};
+
+/// Generation is limited so exponential scripts can not freeze the page.
+/// There is no timeout because `wasm32-unknown-unknown` has no clock.
+const MAX_SYMBOLS: usize = 1_000_000;
+
+#[derive(Default)]
+struct Playground {
+    lsystem: Option<LSystem>,
+    alphabet: Option<Alphabet>,
+}
+
+thread_local! {
+    static PLAYGROUND: RefCell<Playground> = RefCell::new(Playground::default());
+}
+
+/// Compiles `script`, replacing the previous script and its generated alphabet.
+#[wasm_bindgen]
+pub fn compile(script: &str) {
+    let compiled = CompiledLSystem::compile(script);
+    PLAYGROUND.with(|playground| {
+        *playground.borrow_mut() = Playground {
+            lsystem: Some(compiled.lsystem),
+            alphabet: None,
+        };
+    });
+}
+
+/// Generates `generations` generations of the compiled script, returns the number of symbols.
+///
+/// Fails if no script is compiled or the result exceeds one million symbols.
+#[wasm_bindgen]
+pub fn generate(generations: u8) -> Result<usize, JsError> {
+    PLAYGROUND.with(|playground| {
+        let mut playground = playground.borrow_mut();
+        let lsystem = playground
+            .lsystem
+            .as_ref()
+            .ok_or_else(|| JsError::new("no script is compiled, call `compile` first"))?;
+
+        let options = GenerateOptions::new().with_max_symbols(MAX_SYMBOLS);
+        let alphabet = lsystem.generate_with(generations, &options)?;
+        let symbols = alphabet.iter().len();
+        playground.alphabet = Some(alphabet);
+        Ok(symbols)
+    })
+}
+
+/// Runs the last generated alphabet with the default actions and returns its geometry, see `Geometry::to_json`.
+#[wasm_bindgen]
+pub fn geometry_json() -> Result<String, JsError> {
+    PLAYGROUND.with(|playground| {
+        let playground = playground.borrow();
+        let (Some(lsystem), Some(alphabet)) = (&playground.lsystem, &playground.alphabet) else {
+            return Err(JsError::new("nothing is generated, call `generate` first"));
+        };
+
+        let resolver: ActionResolver = ActionResolver::with_default_actions();
+        let context = lsystem.run(&resolver, alphabet)?;
+        Ok(context.geometry().to_json())
+    })
+}